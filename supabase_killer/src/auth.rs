@@ -0,0 +1,266 @@
+//! # 🔐 ZIK_ZAK AUTH TOKENS
+//!
+//! Signed HS256 JWTs instead of "the user id is the token", and bcrypt password hashes.
+//!
+//! Refresh tokens are opaque: `{id}.{secret}`, both random. The server keeps only
+//! the id and a SHA-256 of the secret, so it can expire, rotate and revoke them,
+//! which a self-contained JWT would not allow.
+//!
+//! - `JWT_SECRET` - signing secret, REQUIRED when `ZIK_ZAK_ENV=production`
+//! - `JWT_EXPIRY_SECONDS` - access token lifetime (default 1 hour)
+//! - `JWT_REFRESH_EXPIRY_SECONDS` - refresh token lifetime (default 30 days)
+//! - `BCRYPT_COST` - cost factor for new password hashes, 4 to 31 (default 12)
+//!
+//! Outside production a missing secret falls back to a random one, so tokens
+//! simply stop working when the server restarts.
+//!
+//! Raising `BCRYPT_COST` needs no password resets: a login with a correct
+//! password whose hash has a lower cost gets back a new hash at the current cost.
+
+use anyhow::{anyhow, bail, Result};
+use bcrypt::HashParts;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use uuid::Uuid;
+
+const DEFAULT_EXPIRY_SECONDS: i64 = 60 * 60;
+const DEFAULT_REFRESH_EXPIRY_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+const MIN_BCRYPT_COST: u32 = 4;
+const MAX_BCRYPT_COST: u32 = 31;
+
+const ACCESS: &str = "access";
+
+/// What every token we sign carries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// The user id
+    pub sub: String,
+    pub iat: i64,
+    pub exp: i64,
+    /// Always `access`; tokens of any other type are refused
+    pub typ: String,
+}
+
+/// Outcome of checking a password against its stored hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordCheck {
+    Invalid,
+    Valid,
+    /// Correct, and this hash at the current cost should replace the stored one
+    Rehashed(String),
+}
+
+pub struct AuthService {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    expiry_seconds: i64,
+    refresh_expiry_seconds: i64,
+    bcrypt_cost: u32,
+}
+
+impl AuthService {
+    /// Configure from the environment (see the module docs)
+    pub fn new() -> Result<Self> {
+        let production = std::env::var("ZIK_ZAK_ENV").is_ok_and(|env| env == "production");
+
+        let secret = match std::env::var("JWT_SECRET") {
+            Ok(secret) if !secret.is_empty() => secret,
+            _ if production => bail!("JWT_SECRET must be set when ZIK_ZAK_ENV=production"),
+            _ => {
+                warn!("🔐 JWT_SECRET not set - using a random secret, tokens die with this process");
+                Uuid::new_v4().to_string()
+            }
+        };
+
+        Ok(Self::with_config(
+            &secret,
+            env_seconds("JWT_EXPIRY_SECONDS", DEFAULT_EXPIRY_SECONDS)?,
+            env_seconds("JWT_REFRESH_EXPIRY_SECONDS", DEFAULT_REFRESH_EXPIRY_SECONDS)?,
+        )
+        .with_bcrypt_cost(env_bcrypt_cost()?))
+    }
+
+    pub fn with_config(secret: &str, expiry_seconds: i64, refresh_expiry_seconds: i64) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            expiry_seconds,
+            refresh_expiry_seconds,
+            bcrypt_cost: bcrypt::DEFAULT_COST,
+        }
+    }
+
+    /// Hash new passwords at `cost` and upgrade weaker hashes on login
+    pub fn with_bcrypt_cost(mut self, cost: u32) -> Self {
+        self.bcrypt_cost = cost;
+        self
+    }
+
+    /// 🔒 bcrypt hash of `password` at the configured cost
+    pub fn hash_password(&self, password: &str) -> Result<String> {
+        Ok(bcrypt::hash(password, self.bcrypt_cost)?)
+    }
+
+    /// 🔑 Check `password` against `hash`, rehashing it if its cost is below the current one
+    pub fn verify_password(&self, password: &str, hash: &str) -> Result<PasswordCheck> {
+        if !bcrypt::verify(password, hash)? {
+            return Ok(PasswordCheck::Invalid);
+        }
+        let cost = hash.parse::<HashParts>()?.get_cost();
+        if cost < self.bcrypt_cost {
+            return Ok(PasswordCheck::Rehashed(self.hash_password(password)?));
+        }
+        Ok(PasswordCheck::Valid)
+    }
+
+    /// 🎟️ Short-lived access token for `user_id`
+    pub fn generate_jwt(&self, user_id: &str) -> Result<String> {
+        self.sign(user_id, ACCESS, self.expiry_seconds)
+    }
+
+    /// 🔄 New opaque refresh token and the unix time it expires at
+    pub fn generate_refresh_token(&self) -> (String, i64) {
+        let id = Uuid::new_v4().simple();
+        let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let expires_at = chrono::Utc::now().timestamp() + self.refresh_expiry_seconds;
+        (format!("{}.{}", id, secret), expires_at)
+    }
+
+    /// ✅ Claims of a valid, unexpired access token
+    pub fn validate_jwt(&self, token: &str) -> Result<Claims> {
+        self.verify(token, ACCESS)
+    }
+
+    fn sign(&self, user_id: &str, typ: &str, lifetime_seconds: i64) -> Result<String> {
+        let now = chrono::Utc::now().timestamp();
+        let claims = Claims {
+            sub: user_id.to_string(),
+            iat: now,
+            exp: now + lifetime_seconds,
+            typ: typ.to_string(),
+        };
+        Ok(encode(&Header::default(), &claims, &self.encoding_key)?)
+    }
+
+    fn verify(&self, token: &str, typ: &str) -> Result<Claims> {
+        let mut validation = Validation::default();
+        // Expiry is configured in seconds, so honour it to the second
+        validation.leeway = 0;
+
+        let claims = decode::<Claims>(token, &self.decoding_key, &validation)?.claims;
+        if claims.typ != typ {
+            return Err(anyhow!("Expected a {} token, got {}", typ, claims.typ));
+        }
+        Ok(claims)
+    }
+}
+
+/// 🔍 Id and secret hash of a refresh token, as the server keeps them
+pub fn refresh_token_parts(token: &str) -> Result<(String, String)> {
+    match token.split_once('.') {
+        Some((id, secret)) if !id.is_empty() && !secret.is_empty() => {
+            Ok((id.to_string(), format!("{:x}", Sha256::digest(secret.as_bytes()))))
+        }
+        _ => Err(anyhow!("Malformed refresh token")),
+    }
+}
+
+fn env_seconds(name: &str, default: i64) -> Result<i64> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|seconds| *seconds > 0)
+            .ok_or_else(|| anyhow!("{} must be a positive number of seconds, got {:?}", name, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+fn env_bcrypt_cost() -> Result<u32> {
+    match std::env::var("BCRYPT_COST") {
+        Ok(value) => value
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|cost| (MIN_BCRYPT_COST..=MAX_BCRYPT_COST).contains(cost))
+            .ok_or_else(|| {
+                anyhow!(
+                    "BCRYPT_COST must be between {} and {}, got {:?}",
+                    MIN_BCRYPT_COST,
+                    MAX_BCRYPT_COST,
+                    value
+                )
+            }),
+        Err(_) => Ok(bcrypt::DEFAULT_COST),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_access_token_round_trip() {
+        let auth = AuthService::with_config("test-secret", 60, 600);
+        let token = auth.generate_jwt("user_1").unwrap();
+
+        let claims = auth.validate_jwt(&token).unwrap();
+        assert_eq!(claims.sub, "user_1");
+        assert_eq!(claims.exp - claims.iat, 60);
+
+        let other = AuthService::with_config("other-secret", 60, 600);
+        assert!(other.validate_jwt(&token).is_err());
+    }
+
+    #[test]
+    fn test_refresh_tokens_are_opaque_and_unique() {
+        let auth = AuthService::with_config("test-secret", 60, 600);
+        let (refresh, expires_at) = auth.generate_refresh_token();
+        let (other, _) = auth.generate_refresh_token();
+        assert_ne!(refresh, other);
+        assert!((expires_at - chrono::Utc::now().timestamp() - 600).abs() <= 1);
+        assert!(auth.validate_jwt(&refresh).is_err());
+
+        let (id, hash) = refresh_token_parts(&refresh).unwrap();
+        assert!(refresh.starts_with(&format!("{}.", id)));
+        assert!(!refresh.contains(&hash));
+        assert_eq!(refresh_token_parts(&refresh).unwrap().1, hash);
+        assert_ne!(refresh_token_parts(&other).unwrap().1, hash);
+
+        let access = auth.generate_jwt("user_1").unwrap();
+        assert!(refresh_token_parts("no-secret").is_err());
+        assert!(refresh_token_parts(&format!("{}.", id)).is_err());
+        assert!(auth.validate_jwt(&access).is_ok());
+    }
+
+    #[test]
+    fn test_login_upgrades_low_cost_hash() {
+        let auth = AuthService::with_config("test-secret", 60, 600).with_bcrypt_cost(5);
+        let weak = bcrypt::hash("hunter2", 4).unwrap();
+
+        assert_eq!(auth.verify_password("wrong", &weak).unwrap(), PasswordCheck::Invalid);
+        let PasswordCheck::Rehashed(upgraded) = auth.verify_password("hunter2", &weak).unwrap() else {
+            panic!("a cost 4 hash should be upgraded to cost 5");
+        };
+        assert_eq!(upgraded.parse::<HashParts>().unwrap().get_cost(), 5);
+
+        // The upgraded hash is current, so the next login leaves it alone
+        assert_eq!(auth.verify_password("hunter2", &upgraded).unwrap(), PasswordCheck::Valid);
+        assert_eq!(auth.hash_password("hunter2").unwrap().parse::<HashParts>().unwrap().get_cost(), 5);
+    }
+
+    #[test]
+    fn test_short_expiry_is_rejected_once_passed() {
+        let auth = AuthService::with_config("test-secret", 1, 600);
+        let token = auth.generate_jwt("user_1").unwrap();
+        assert!(auth.validate_jwt(&token).is_ok());
+
+        std::thread::sleep(Duration::from_millis(2100));
+        assert!(auth.validate_jwt(&token).is_err());
+    }
+}
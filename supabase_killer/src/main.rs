@@ -16,7 +16,16 @@
 //! user:{user_id}:admin                    = 1 (super admin)
 //! resource:{id}:owner:{user_id}           = 1 (owns this)
 //! tenant:{tenant_id}:member:{user_id}     = 1 (tenant member)
+//! refresh:{token_id}:active               = 1 (live refresh token)
 //! ```
+//!
+//! ## Refresh Tokens:
+//! Every refresh rotates the token: the old one's balance goes to `system:void`
+//! and a new token of the same family is issued. Presenting a rotated token again
+//! means it leaked, so the whole family is revoked. `POST /auth/logout` revokes
+//! one token the same way.
+
+mod auth;
 
 use axum::{
     extract::{Path, Query, State, Request},
@@ -29,9 +38,11 @@ use serde_json::{json, Value};
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::Mutex;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
+use auth::{refresh_token_parts, AuthService, PasswordCheck};
+
 type SharedState = Arc<Mutex<ZikZakSecurityEngine>>;
 
 /// 🦖 The Revolutionary ZIK_ZAK Security Engine
@@ -40,6 +51,24 @@ struct ZikZakSecurityEngine {
     accounts: HashMap<String, i64>,
     // Transaction log for audit trails
     transactions: Vec<SecurityTransaction>,
+    // Signs and checks access/refresh tokens
+    auth: AuthService,
+    // Email -> (user id, bcrypt password hash)
+    credentials: HashMap<String, (String, String)>,
+    // Refresh token id -> who it was issued to; its balance says whether it is live
+    refresh_tokens: HashMap<String, RefreshSession>,
+}
+
+/// 🔄 What the server keeps of a refresh token
+#[derive(Debug, Clone)]
+struct RefreshSession {
+    user_id: String,
+    /// Id of the login's first token, shared by every token rotated from it
+    family: String,
+    secret_hash: String,
+    expires_at: i64,
+    /// Revoked by a refresh rather than a logout - seeing it again is theft
+    rotated: bool,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -54,10 +83,13 @@ struct SecurityTransaction {
 }
 
 impl ZikZakSecurityEngine {
-    fn new() -> Self {
+    fn new(auth: AuthService) -> Self {
         let mut engine = Self {
             accounts: HashMap::new(),
             transactions: Vec::new(),
+            auth,
+            credentials: HashMap::new(),
+            refresh_tokens: HashMap::new(),
         };
 
         // Initialize system accounts
@@ -101,21 +133,125 @@ impl ZikZakSecurityEngine {
         self.accounts.get(permission_account).copied().unwrap_or(0) > 0
     }
 
-    /// 🎯 Extract user ID from authorization header
-    fn extract_user_id(headers: &HeaderMap) -> Result<String, String> {
-        let auth_header = headers
+    /// 🔎 Does this entity exist? (`{entity}:existence` > 0)
+    ///
+    /// Soft-deleted entities have their existence moved to `system:void`, so they
+    /// read as gone here just like entities that were never created.
+    fn exists(&self, entity: &str) -> bool {
+        self.accounts.get(&format!("{}:existence", entity)).copied().unwrap_or(0) > 0
+    }
+
+    /// 🔎 Like `exists`, but as an error for `?` chains
+    fn ensure_exists(&self, entity: &str) -> Result<(), String> {
+        if self.exists(entity) {
+            Ok(())
+        } else {
+            Err(format!("{} not found", entity))
+        }
+    }
+
+    /// 🎯 Extract user ID from the bearer access token
+    fn extract_user_id(&self, headers: &HeaderMap) -> Result<String, String> {
+        let token = headers
             .get("authorization")
             .and_then(|h| h.to_str().ok())
             .and_then(|h| h.strip_prefix("Bearer "))
             .ok_or("Missing or invalid authorization header")?;
 
-        // In real implementation, decode JWT and extract user ID
-        // For demo, we'll parse a simple format: "user_123"
-        if auth_header.starts_with("user_") {
-            Ok(auth_header.to_string())
-        } else {
-            Err("Invalid token format".to_string())
+        self.auth.validate_jwt(token)
+            .map(|claims| claims.sub)
+            .map_err(|e| format!("Invalid token: {}", e))
+    }
+
+    /// 🎟️ Access + refresh token pair for a freshly authenticated user
+    fn issue_tokens(&mut self, user_id: &str) -> Result<(String, String), String> {
+        let access = self.auth.generate_jwt(user_id).map_err(|e| e.to_string())?;
+        let refresh = self.issue_refresh_token(user_id, None)?;
+        Ok((access, refresh))
+    }
+
+    /// 🔄 New refresh token for `user_id`, starting a family unless one is given
+    fn issue_refresh_token(&mut self, user_id: &str, family: Option<&str>) -> Result<String, String> {
+        let (token, expires_at) = self.auth.generate_refresh_token();
+        let (token_id, secret_hash) = refresh_token_parts(&token).map_err(|e| e.to_string())?;
+        let family = family.unwrap_or(&token_id).to_string();
+
+        let mut metadata = HashMap::new();
+        metadata.insert("user_id".to_string(), user_id.to_string());
+        metadata.insert("family".to_string(), family.clone());
+        metadata.insert("expires_at".to_string(), expires_at.to_string());
+        self.transfer("system:genesis", &format!("refresh:{}:active", token_id), 1, "issue_refresh_token", metadata)?;
+
+        self.refresh_tokens.insert(token_id, RefreshSession {
+            user_id: user_id.to_string(),
+            family,
+            secret_hash,
+            expires_at,
+            rotated: false,
+        });
+        Ok(token)
+    }
+
+    /// 🔍 Id and session of a presented refresh token, live or not
+    fn find_refresh_token(&self, token: &str) -> Result<(String, RefreshSession), String> {
+        let invalid = || "Invalid refresh token".to_string();
+        let (token_id, secret_hash) = refresh_token_parts(token).map_err(|_| invalid())?;
+        let session = self.refresh_tokens.get(&token_id).ok_or_else(invalid)?;
+        if session.secret_hash != secret_hash {
+            return Err(invalid());
+        }
+        Ok((token_id, session.clone()))
+    }
+
+    /// 🔁 Trade a live refresh token for `(user_id, its replacement)`
+    ///
+    /// A token that was already rotated revokes its whole family.
+    fn rotate_refresh_token(&mut self, token: &str) -> Result<(String, String), String> {
+        let (token_id, session) = self.find_refresh_token(token)?;
+        if !self.has_permission(&format!("refresh:{}:active", token_id)) {
+            if session.rotated {
+                let revoked = self.revoke_refresh_family(&session.family, "reuse_detected")?;
+                warn!("🚨 Rotated refresh token {} reused, revoked {} tokens of its family", token_id, revoked);
+            }
+            return Err("Refresh token revoked".to_string());
+        }
+        if session.expires_at <= chrono::Utc::now().timestamp() {
+            return Err("Refresh token expired".to_string());
+        }
+
+        self.revoke_refresh_token_id(&token_id, "rotate_refresh_token")?;
+        if let Some(session) = self.refresh_tokens.get_mut(&token_id) {
+            session.rotated = true;
+        }
+        let replacement = self.issue_refresh_token(&session.user_id, Some(&session.family))?;
+        Ok((session.user_id, replacement))
+    }
+
+    /// 🚪 Revoke a refresh token; revoking a revoked one does nothing
+    fn revoke_refresh_token(&mut self, token: &str) -> Result<(), String> {
+        let (token_id, _) = self.find_refresh_token(token)?;
+        self.revoke_refresh_token_id(&token_id, "revoke_refresh_token")
+    }
+
+    /// 💀 Zero the balance of every live token of `family`, returning how many there were
+    fn revoke_refresh_family(&mut self, family: &str, reason: &str) -> Result<usize, String> {
+        let live: Vec<String> = self.refresh_tokens.iter()
+            .filter(|(id, session)| session.family == family && self.has_permission(&format!("refresh:{}:active", id)))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for token_id in &live {
+            self.revoke_refresh_token_id(token_id, reason)?;
+        }
+        Ok(live.len())
+    }
+
+    fn revoke_refresh_token_id(&mut self, token_id: &str, operation: &str) -> Result<(), String> {
+        let account = format!("refresh:{}:active", token_id);
+        let balance = self.accounts.get(&account).copied().unwrap_or(0);
+        if balance > 0 {
+            self.transfer(&account, "system:void", balance, operation, HashMap::new())?;
         }
+        Ok(())
     }
 
     /// 🏗️ Create a new user with automatic permission setup
@@ -191,6 +327,27 @@ impl ZikZakSecurityEngine {
         Ok(resource_id)
     }
 
+    /// 🔥 Erase a resource's data for good (GDPR erasure)
+    ///
+    /// Resource data only lives in the `data` metadata of its transactions, so that
+    /// is stripped from every transaction on `{resource_type}:{id}:*`. A tombstone
+    /// transfer to `{resource_type}:{id}:erased` records who erased it and why.
+    fn purge_resource(&mut self, resource_type: &str, resource_id: &str, reason: &str, mut metadata: HashMap<String, String>) -> Result<usize, String> {
+        let prefix = format!("{}:{}:", resource_type, resource_id);
+        let mut scrubbed = 0;
+        for transaction in &mut self.transactions {
+            let touches = transaction.from_account.starts_with(&prefix) || transaction.to_account.starts_with(&prefix);
+            if touches && transaction.metadata.remove("data").is_some() {
+                scrubbed += 1;
+            }
+        }
+
+        metadata.insert("erasure_reason".to_string(), reason.to_string());
+        self.transfer("system:genesis", &format!("{}erased", prefix), 1, "erase_resource", metadata)?;
+
+        Ok(scrubbed)
+    }
+
     /// 🛡️ Check if user can access resource
     fn can_access_resource(&self, user_id: &str, resource_type: &str, resource_id: &str, action: &str) -> bool {
         // Admin override
@@ -231,17 +388,17 @@ async fn security_middleware(
     let path = request.uri().path();
 
     // Public endpoints that don't need auth
-    if path == "/health" || path == "/auth/signup" || path == "/auth/login" || path.starts_with("/public/") {
+    if path == "/health" || path == "/auth/signup" || path == "/auth/login" || path == "/auth/refresh" || path == "/auth/logout" || path.starts_with("/public/") {
         return Ok(next.run(request).await);
     }
 
     // Extract user ID from token
-    let user_id = ZikZakSecurityEngine::extract_user_id(&headers)
+    let state = state.lock().await;
+    let user_id = state.extract_user_id(&headers)
         .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
 
     // Check if user exists
-    let state = state.lock().await;
-    if !state.has_permission(&format!("user:{}:existence", user_id)) {
+    if !state.exists(&format!("user:{}", user_id)) {
         return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "User not found"}))));
     }
 
@@ -257,18 +414,30 @@ async fn auth_signup(
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     let email = payload["email"].as_str()
         .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "Email required"}))))?;
+    let password = payload["password"].as_str()
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "Password required"}))))?;
     let role = payload["role"].as_str().unwrap_or("customer");
     let tenant_id = payload["tenant_id"].as_str();
 
     let mut state = state.lock().await;
 
+    if state.credentials.contains_key(email) {
+        return Err((StatusCode::CONFLICT, Json(json!({"error": "Email already registered"}))));
+    }
+    let password_hash = state.auth.hash_password(password)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+
     // Create user with permissions
     let user_id = state.create_user(email, role, tenant_id)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e}))))?;
+    state.credentials.insert(email.to_string(), (user_id.clone(), password_hash));
+    let (access_token, refresh_token) = state.issue_tokens(&user_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e}))))?;
 
     Ok(Json(json!({
         "user_id": user_id,
-        "access_token": user_id, // Simplified token
+        "access_token": access_token,
+        "refresh_token": refresh_token,
         "email": email,
         "role": role,
         "tenant_id": tenant_id,
@@ -283,33 +452,95 @@ async fn auth_login(
     let email = payload["email"].as_str()
         .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "Email required"}))))?;
 
-    let state = state.lock().await;
+    let password = payload["password"].as_str()
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "Password required"}))))?;
 
-    // Find user by email (simplified lookup)
-    let user_id = format!("user_{}", email.replace("@", "_").replace(".", "_"));
+    let mut state = state.lock().await;
+    let invalid = || (StatusCode::UNAUTHORIZED, Json(json!({"error": "Invalid credentials"})));
+
+    let (user_id, password_hash) = state.credentials.get(email).cloned().ok_or_else(invalid)?;
+    match state.auth.verify_password(password, &password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?
+    {
+        PasswordCheck::Invalid => return Err(invalid()),
+        PasswordCheck::Valid => {}
+        // BCRYPT_COST went up since this password was stored
+        PasswordCheck::Rehashed(upgraded) => {
+            state.credentials.insert(email.to_string(), (user_id.clone(), upgraded));
+        }
+    }
 
-    if !state.has_permission(&format!("user:{}:existence", user_id)) {
-        return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "Invalid credentials"}))));
+    if !state.exists(&format!("user:{}", user_id)) {
+        return Err(invalid());
     }
+    let (access_token, refresh_token) = state.issue_tokens(&user_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e}))))?;
 
     Ok(Json(json!({
         "user_id": user_id,
-        "access_token": user_id,
+        "access_token": access_token,
+        "refresh_token": refresh_token,
         "email": email,
         "message": "🦖 Logged in with ZIK_ZAK security!"
     })))
 }
 
+/// 🔄 Trade a refresh token for a new access token and its replacement
+async fn auth_refresh(
+    State(state): State<SharedState>,
+    Json(payload): Json<Value>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let refresh_token = payload["refresh_token"].as_str()
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "refresh_token required"}))))?;
+
+    let mut state = state.lock().await;
+
+    let (user_id, refresh_token) = state.rotate_refresh_token(refresh_token)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
+
+    // Deleted users keep their refresh tokens but can't use them
+    if !state.exists(&format!("user:{}", user_id)) {
+        return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "User not found"}))));
+    }
+
+    let access_token = state.auth.generate_jwt(&user_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+
+    Ok(Json(json!({
+        "user_id": user_id,
+        "access_token": access_token,
+        "refresh_token": refresh_token,
+        "message": "🦖 Token refreshed with ZIK_ZAK security!"
+    })))
+}
+
+/// 🚪 Revoke a refresh token
+async fn auth_logout(
+    State(state): State<SharedState>,
+    Json(payload): Json<Value>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let refresh_token = payload["refresh_token"].as_str()
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "refresh_token required"}))))?;
+
+    let mut state = state.lock().await;
+    state.revoke_refresh_token(refresh_token)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
+
+    Ok(Json(json!({
+        "revoked": true,
+        "message": "🦖 Logged out with ZIK_ZAK security!"
+    })))
+}
+
 // 📊 SECURE RESOURCE ENDPOINTS
 async fn create_product(
     State(state): State<SharedState>,
     headers: HeaderMap,
     Json(payload): Json<Value>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let user_id = ZikZakSecurityEngine::extract_user_id(&headers)
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
-
     let mut state = state.lock().await;
+    let user_id = state.extract_user_id(&headers)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
 
     // Check if user can create products
     if !state.can_access_resource(&user_id, "products", "", "write") {
@@ -334,10 +565,9 @@ async fn get_product(
     headers: HeaderMap,
     Path(product_id): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let user_id = ZikZakSecurityEngine::extract_user_id(&headers)
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
-
     let state = state.lock().await;
+    let user_id = state.extract_user_id(&headers)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
 
     // Check if user can read this product
     if !state.can_access_resource(&user_id, "product", &product_id, "read") {
@@ -345,7 +575,7 @@ async fn get_product(
     }
 
     // Check if product exists
-    if !state.has_permission(&format!("product:{}:existence", product_id)) {
+    if !state.exists(&format!("product:{}", product_id)) {
         return Err((StatusCode::NOT_FOUND, Json(json!({"error": "Product not found"}))));
     }
 
@@ -362,31 +592,52 @@ async fn get_product(
     })))
 }
 
+/// `DELETE /products/:id` soft-deletes; `?purge=true` (with an optional `reason`)
+/// also erases the product's data
 async fn delete_product(
     State(state): State<SharedState>,
     headers: HeaderMap,
     Path(product_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let user_id = ZikZakSecurityEngine::extract_user_id(&headers)
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
-
     let mut state = state.lock().await;
+    let user_id = state.extract_user_id(&headers)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
 
     // Check if user can delete this product
     if !state.can_access_resource(&user_id, "product", &product_id, "delete") {
         return Err((StatusCode::FORBIDDEN, Json(json!({"error": "No permission to delete this product"}))));
     }
 
-    // Move to void (soft delete)
+    let purge = params.get("purge").is_some_and(|purge| purge == "true");
+    let entity = format!("product:{}", product_id);
     let mut metadata = HashMap::new();
     metadata.insert("deleted_by".to_string(), user_id.clone());
 
-    state.transfer(&format!("product:{}:existence", product_id), "system:void", 1, "delete_product", metadata)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e}))))?;
+    // Deleting something that is already gone is a 404, not a failed transfer -
+    // unless it is being purged, erasure requests can come after a soft delete
+    let was_created = state.accounts.contains_key(&format!("{}:existence", entity));
+    if !(purge && was_created) {
+        state.ensure_exists(&entity)
+            .map_err(|e| (StatusCode::NOT_FOUND, Json(json!({"error": e}))))?;
+    }
+
+    if state.exists(&entity) {
+        // Move to void (soft delete)
+        state.transfer(&format!("{}:existence", entity), "system:void", 1, "delete_product", metadata.clone())
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e}))))?;
+    }
+
+    if purge {
+        let reason = params.get("reason").map_or("erasure request", String::as_str);
+        state.purge_resource("product", &product_id, reason, metadata)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e}))))?;
+    }
 
     Ok(Json(json!({
         "product_id": product_id,
         "deleted_by": user_id,
+        "purged": purge,
         "message": "🦖 Product deleted with ZIK_ZAK security!"
     })))
 }
@@ -397,10 +648,9 @@ async fn grant_permission(
     headers: HeaderMap,
     Json(payload): Json<Value>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let admin_user_id = ZikZakSecurityEngine::extract_user_id(&headers)
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
-
     let mut state = state.lock().await;
+    let admin_user_id = state.extract_user_id(&headers)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
 
     // Only admins can grant permissions
     if !state.has_permission(&format!("user:{}:admin", admin_user_id)) {
@@ -432,10 +682,9 @@ async fn audit_trail(
     headers: HeaderMap,
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let user_id = ZikZakSecurityEngine::extract_user_id(&headers)
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
-
     let state = state.lock().await;
+    let user_id = state.extract_user_id(&headers)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e}))))?;
 
     // Only admins can view audit trails
     if !state.has_permission(&format!("user:{}:admin", user_id)) {
@@ -492,12 +741,15 @@ async fn main() -> anyhow::Result<()> {
 
     info!("🚀 Starting ZIK_ZAK REVOLUTIONARY SECURITY server...");
 
-    let state = Arc::new(Mutex::new(ZikZakSecurityEngine::new()));
+    let auth = AuthService::new()?;
+    let state = Arc::new(Mutex::new(ZikZakSecurityEngine::new(auth)));
 
     let app = Router::new()
         // 🔐 Auth endpoints (no middleware)
         .route("/auth/signup", post(auth_signup))
         .route("/auth/login", post(auth_login))
+        .route("/auth/refresh", post(auth_refresh))
+        .route("/auth/logout", post(auth_logout))
 
         // 📊 Secured resource endpoints
         .route("/products", post(create_product))
@@ -533,3 +785,41 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> ZikZakSecurityEngine {
+        ZikZakSecurityEngine::new(AuthService::with_config("test-secret", 60, 600))
+    }
+
+    #[test]
+    fn test_refresh_rotates_and_logout_revokes() {
+        let mut engine = engine();
+        let (_, first) = engine.issue_tokens("user_1").unwrap();
+
+        let (user_id, second) = engine.rotate_refresh_token(&first).unwrap();
+        assert_eq!(user_id, "user_1");
+        assert_ne!(first, second);
+
+        engine.revoke_refresh_token(&second).unwrap();
+        engine.revoke_refresh_token(&second).unwrap();
+        assert!(engine.rotate_refresh_token(&second).is_err());
+        assert!(engine.rotate_refresh_token("forged.token").is_err());
+    }
+
+    #[test]
+    fn test_reused_refresh_token_revokes_its_family() {
+        let mut engine = engine();
+        let (_, stolen) = engine.issue_tokens("user_1").unwrap();
+        let (_, other_login) = engine.issue_tokens("user_1").unwrap();
+        let (_, current) = engine.rotate_refresh_token(&stolen).unwrap();
+
+        // The thief replays the token its owner already rotated
+        assert!(engine.rotate_refresh_token(&stolen).is_err());
+        assert!(engine.rotate_refresh_token(&current).is_err());
+        // Other logins of the user are separate families
+        assert!(engine.rotate_refresh_token(&other_login).is_ok());
+    }
+}
@@ -0,0 +1,106 @@
+//! # 🏎️ Concurrent balance reads - Mutex vs RwLock
+//!
+//! Shares one engine between many tasks reading balances, once behind a
+//! `Mutex` (every read waits for the one before it) and once behind the
+//! `RwLock` the server uses (reads share the lock), and prints the throughput
+//! of each.
+//!
+//! Requires TigerBeetle running as described in
+//! `tests/tigerbeetle_integration_test.rs`:
+//!
+//! ```bash
+//! cargo bench --bench concurrent_reads
+//! ```
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+use zik_zak::ZikZakEngine;
+
+/// Tasks reading at the same time
+const READERS: usize = 32;
+
+/// Balance reads per task
+const READS_PER_READER: usize = 200;
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    // Fresh account per run - TigerBeetle keeps state between runs
+    let wallet = format!("bench:{}:balance", Uuid::new_v4().simple());
+    engine
+        .transfer("system:genesis", &wallet, 1000, HashMap::new())
+        .await?;
+
+    println!(
+        "🏎️ {} readers x {} balance reads",
+        READERS, READS_PER_READER
+    );
+
+    let engine = Arc::new(Mutex::new(engine));
+    let mutex = run_readers(&wallet, |wallet| {
+        let engine = Arc::clone(&engine);
+        async move { engine.lock().await.get_balance(&wallet).await }
+    })
+    .await?;
+    report("Mutex", mutex);
+
+    let engine = Arc::new(RwLock::new(
+        Arc::into_inner(engine)
+            .expect("readers are done")
+            .into_inner(),
+    ));
+    let rwlock = run_readers(&wallet, |wallet| {
+        let engine = Arc::clone(&engine);
+        async move { engine.read().await.get_balance(&wallet).await }
+    })
+    .await?;
+    report("RwLock", rwlock);
+
+    println!(
+        "⚡ RwLock reads {:.1}x faster",
+        mutex.as_secs_f64() / rwlock.as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Time `READERS` tasks each calling `read` `READS_PER_READER` times
+async fn run_readers<F, Fut>(wallet: &str, read: F) -> Result<Duration>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<i64>> + Send + 'static,
+{
+    let started = Instant::now();
+    let mut handles = Vec::with_capacity(READERS);
+    for _ in 0..READERS {
+        let reads: Vec<_> = (0..READS_PER_READER)
+            .map(|_| read(wallet.to_string()))
+            .collect();
+        handles.push(tokio::spawn(async move {
+            for read in reads {
+                assert_eq!(read.await?, 1000);
+            }
+            Ok::<_, anyhow::Error>(())
+        }));
+    }
+    for handle in handles {
+        handle.await??;
+    }
+    Ok(started.elapsed())
+}
+
+fn report(lock: &str, elapsed: Duration) {
+    let reads = (READERS * READS_PER_READER) as f64;
+    println!(
+        "🔒 {:<6} {:>8.1} ms  {:>10.0} reads/s",
+        lock,
+        elapsed.as_secs_f64() * 1000.0,
+        reads / elapsed.as_secs_f64()
+    );
+}
@@ -0,0 +1,74 @@
+//! # 📡 ZIK_ZAK Live Events Example - Watching Creation Happen
+//!
+//! Subscribes to every user account and prints what GENESIS creates while a
+//! few sparks ignite, the way a live admin console would.
+
+use anyhow::Result;
+use futures::StreamExt;
+use serde_json::json;
+use std::time::Duration;
+use tempfile::TempDir;
+use zik_zak::realtime::SparkEvent;
+use zik_zak::{zak, zik, Genesis, ZikZak};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("genesis.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "create_user".to_string(),
+        serde_json::from_value(json!({
+            "description": "Bring a user into existence with a starting balance",
+            "inputs": ["user_id"],
+            "operations": [
+                { "type": "transfer", "zik": "system:genesis", "zak": "user:{user_id}:existence", "amount": 1 },
+                { "type": "transfer", "zik": "system:genesis", "zak": "user:{user_id}:balance", "amount": 1000 }
+            ],
+            "return": { "user_id": "{user_id}" }
+        }))?,
+    );
+
+    // 👀 The console: print events for every user field as they happen
+    let mut events = Box::pin(genesis.subscribe("user:*:*")?);
+    let console = tokio::spawn(async move {
+        while let Some(event) = events.next().await {
+            match event {
+                SparkEvent::Ignited {
+                    spark,
+                    inputs,
+                    transfers,
+                    ..
+                } => println!(
+                    "⚡ {} ignited with {:?}, {} transfers",
+                    spark,
+                    inputs,
+                    transfers.len()
+                ),
+                SparkEvent::Transfer(transfer) => println!(
+                    "💸 {} -> {}: {}",
+                    transfer.from_account, transfer.to_account, transfer.amount
+                ),
+                SparkEvent::Lagged(missed) => println!("🐢 Console missed {} events", missed),
+            }
+        }
+    });
+
+    for user_id in 1..=3 {
+        genesis
+            .ignite_spark(
+                "create_user",
+                ZikZak {
+                    zik: zik! { user_id: user_id },
+                    zak: zak! {},
+                },
+            )
+            .await?;
+    }
+
+    // Give the console a moment to catch up, then end the stream by dropping GENESIS
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    drop(genesis);
+    console.await?;
+    Ok(())
+}
@@ -0,0 +1,234 @@
+//! # 🧾 ZIK_ZAK Account Names
+//!
+//! Every account is a `entity:id:field` string. Building those by hand with
+//! `format!` works until someone types `prodcut:123:price` and silently gets a
+//! brand new, empty account. The [`Account`] builder produces the canonical names:
+//!
+//! ```rust
+//! use zik_zak::Account;
+//!
+//! let product = Account::entity("product", 123);
+//! assert_eq!(product.price().as_str(), "product:123:price");
+//! assert_eq!(product.existence().as_str(), "product:123:existence");
+//!
+//! assert_eq!(Account::entity("user", 456).balance().as_str(), "user:456:balance");
+//! assert_eq!(Account::entity("order", 789).field("status").as_str(), "order:789:status");
+//!
+//! assert_eq!(Account::system("genesis").as_str(), "system:genesis");
+//! assert_eq!(Account::system("deleted").as_str(), "system:deleted");
+//! ```
+//!
+//! [`Account::entity`] and [`Account::field`] trust their input. When an id or
+//! field comes from a request body, use [`Account::try_entity`] and
+//! [`Account::try_field`], which refuse segments that are empty, contain `:`
+//! (`user:1:2` would name another account) or are the pattern wildcard `*`:
+//!
+//! ```rust
+//! use zik_zak::Account;
+//!
+//! let product = Account::try_entity("product", "laptop").unwrap();
+//! assert_eq!(product.try_field("price").unwrap().as_str(), "product:laptop:price");
+//! assert!(Account::try_entity("product", "laptop:price").is_err());
+//! assert!(product.try_field("").is_err());
+//! ```
+//!
+//! Engine methods take `impl Into<AccountId>`, so builder output and plain
+//! `&str` names are interchangeable:
+//!
+//! ```rust
+//! use zik_zak::{Account, ZikZakEngine};
+//!
+//! # async fn example(engine: &mut ZikZakEngine) -> anyhow::Result<()> {
+//! let product = Account::entity("product", 123);
+//! engine.transfer(Account::system("genesis"), product.price(), 2999, Default::default()).await?;
+//! let price = engine.get_balance("product:123:price").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::error::ZikZakError;
+
+/// Canonical account name as understood by the engine
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct AccountId(String);
+
+impl AccountId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for AccountId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for AccountId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for AccountId {
+    fn from(name: &str) -> Self {
+        Self(name.to_string())
+    }
+}
+
+impl From<String> for AccountId {
+    fn from(name: String) -> Self {
+        Self(name)
+    }
+}
+
+impl From<&String> for AccountId {
+    fn from(name: &String) -> Self {
+        Self(name.clone())
+    }
+}
+
+impl From<&AccountId> for AccountId {
+    fn from(id: &AccountId) -> Self {
+        id.clone()
+    }
+}
+
+/// Builder for the accounts belonging to one entity (`{entity}:{id}:*`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Account {
+    entity: String,
+    id: String,
+}
+
+impl Account {
+    /// Start building accounts for `{entity}:{id}`
+    pub fn entity(entity: &str, id: impl fmt::Display) -> Self {
+        Self {
+            entity: entity.to_string(),
+            id: id.to_string(),
+        }
+    }
+
+    /// [`entity`](Self::entity), refusing an `entity` or `id` that is not a valid segment
+    pub fn try_entity(entity: &str, id: impl fmt::Display) -> Result<Self, ZikZakError> {
+        let account = Self::entity(entity, id);
+        check_segment(&account.entity)?;
+        check_segment(&account.id)?;
+        Ok(account)
+    }
+
+    /// System account such as `system:genesis` or `system:deleted`
+    pub fn system(name: &str) -> AccountId {
+        AccountId(format!("system:{}", name))
+    }
+
+    /// The `{entity}:{id}` prefix shared by all field accounts
+    pub fn prefix(&self) -> String {
+        format!("{}:{}", self.entity, self.id)
+    }
+
+    /// Account holding one field of the entity: `{entity}:{id}:{field}`
+    pub fn field(&self, field: &str) -> AccountId {
+        AccountId(format!("{}:{}:{}", self.entity, self.id, field))
+    }
+
+    /// [`field`](Self::field), refusing a `field` that is not a valid segment
+    pub fn try_field(&self, field: &str) -> Result<AccountId, ZikZakError> {
+        check_segment(field)?;
+        Ok(self.field(field))
+    }
+
+    /// `{entity}:{id}:existence` - balance > 0 means the entity exists
+    pub fn existence(&self) -> AccountId {
+        self.field("existence")
+    }
+
+    /// `{entity}:{id}:balance`
+    pub fn balance(&self) -> AccountId {
+        self.field("balance")
+    }
+
+    /// `{entity}:{id}:price`
+    pub fn price(&self) -> AccountId {
+        self.field("price")
+    }
+}
+
+/// One `:`-separated part of an account name must be non-empty, colon-free and not `*`
+pub(crate) fn check_segment(segment: &str) -> Result<(), ZikZakError> {
+    let reason = if segment.is_empty() {
+        "empty segment"
+    } else if segment.contains(':') {
+        "contains ':'"
+    } else if segment == "*" {
+        "'*' is the pattern wildcard"
+    } else {
+        return Ok(());
+    };
+    Err(ZikZakError::InvalidAccountName {
+        segment: segment.to_string(),
+        reason: reason.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_fields() {
+        let product = Account::entity("product", "laptop");
+        assert_eq!(product.prefix(), "product:laptop");
+        assert_eq!(product.field("name").as_str(), "product:laptop:name");
+        assert_eq!(product.price().to_string(), "product:laptop:price");
+    }
+
+    #[test]
+    fn test_builder_and_str_convert_to_same_id() {
+        let built: AccountId = Account::entity("user", 456).balance();
+        let plain: AccountId = "user:456:balance".into();
+        assert_eq!(built, plain);
+        assert_eq!(
+            Account::system("genesis"),
+            AccountId::from("system:genesis")
+        );
+    }
+
+    #[test]
+    fn test_checked_builder_rejects_bad_segments() {
+        let order = Account::try_entity("order", 42).unwrap();
+        assert_eq!(order, Account::entity("order", 42));
+        assert_eq!(
+            order.try_field("status").unwrap().as_str(),
+            "order:42:status"
+        );
+
+        for (entity, id) in [
+            ("order", "42:status"),
+            ("", "42"),
+            ("order", ""),
+            ("order", "*"),
+        ] {
+            assert!(
+                matches!(
+                    Account::try_entity(entity, id),
+                    Err(ZikZakError::InvalidAccountName { .. })
+                ),
+                "{}:{}",
+                entity,
+                id
+            );
+        }
+        for field in ["", "status:2", "*"] {
+            assert!(order.try_field(field).is_err(), "{}", field);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Tamper-evident export of the transfer log
+//!
+//! Behind [`ZikZakEngine::export_audit`](crate::ZikZakEngine::export_audit). One
+//! JSON record per line, oldest first:
+//!
+//! ```text
+//! {"prev_hash":"00…00","hash":"9f…","transfer":{"id":"…","from_account":"…",…}}
+//! ```
+//!
+//! `hash` is SHA-256 over `prev_hash` and the transfer's fields, and each record's
+//! `prev_hash` is the `hash` before it, starting from [`GENESIS_HASH`]. Editing,
+//! dropping or reordering any record breaks every hash after it. Cutting records
+//! off the end does not, which is what checkpointing
+//! [`audit_head_hash`](crate::ZikZakEngine::audit_head_hash) elsewhere is for.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, Write};
+
+use crate::zik_zak::Transfer;
+
+/// `prev_hash` of the first record
+pub(crate) const GENESIS_HASH: [u8; 32] = [0; 32];
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    prev_hash: String,
+    hash: String,
+    transfer: Transfer,
+}
+
+/// Chain hash of `transfer` following `prev`
+///
+/// Strings are length-prefixed and metadata sorted by key, so the same transfer
+/// always hashes the same regardless of how it was serialized.
+pub(crate) fn link(prev: &[u8; 32], transfer: &Transfer) -> [u8; 32] {
+    fn field(hasher: &mut Sha256, value: &str) {
+        hasher.update((value.len() as u64).to_le_bytes());
+        hasher.update(value.as_bytes());
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(prev);
+    field(&mut hasher, &transfer.id);
+    field(&mut hasher, &transfer.from_account);
+    field(&mut hasher, &transfer.to_account);
+    hasher.update(transfer.amount.to_le_bytes());
+    hasher.update(transfer.timestamp.to_le_bytes());
+
+    let mut metadata: Vec<_> = transfer.metadata.iter().collect();
+    metadata.sort();
+    hasher.update((metadata.len() as u64).to_le_bytes());
+    for (key, value) in metadata {
+        field(&mut hasher, key);
+        field(&mut hasher, value);
+    }
+    hasher.finalize().into()
+}
+
+/// Write `transfers` as a hash chain, one record per line
+pub(crate) fn write<W: Write>(mut writer: W, transfers: &[Transfer]) -> Result<()> {
+    let mut prev = GENESIS_HASH;
+    for transfer in transfers {
+        let hash = link(&prev, transfer);
+        let record = Record {
+            prev_hash: hex::encode(prev),
+            hash: hex::encode(hash),
+            transfer: transfer.clone(),
+        };
+        serde_json::to_writer(&mut writer, &record)?;
+        writer.write_all(b"\n")?;
+        prev = hash;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Whether every record of a [`write`] chain still links up
+///
+/// A line that no longer parses counts as tampering; only read errors fail.
+pub(crate) fn verify<R: BufRead>(reader: R) -> Result<bool> {
+    let mut prev = GENESIS_HASH;
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(record) = serde_json::from_str::<Record>(&line) else {
+            return Ok(false);
+        };
+        let hash = link(&prev, &record.transfer);
+        if record.prev_hash != hex::encode(prev) || record.hash != hex::encode(hash) {
+            return Ok(false);
+        }
+        prev = hash;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn transfer(id: &str, amount: i64) -> Transfer {
+        Transfer {
+            id: id.to_string(),
+            from_account: "system:genesis".to_string(),
+            to_account: "user:1:balance".to_string(),
+            amount,
+            metadata: HashMap::from([
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "2".to_string()),
+            ]),
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    fn export(transfers: &[Transfer]) -> String {
+        let mut out = Vec::new();
+        write(&mut out, transfers).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_chain_detects_tampering() {
+        let transfers = vec![transfer("t1", 10), transfer("t2", 20), transfer("t3", 30)];
+        let exported = export(&transfers);
+        assert!(verify(exported.as_bytes()).unwrap());
+        assert!(verify(&b""[..]).unwrap());
+
+        let edited = exported.replacen("\"amount\":20", "\"amount\":2000", 1);
+        assert!(!verify(edited.as_bytes()).unwrap());
+
+        let lines: Vec<&str> = exported.lines().collect();
+        let dropped = format!("{}\n{}\n", lines[0], lines[2]);
+        assert!(!verify(dropped.as_bytes()).unwrap());
+
+        assert!(!verify(&b"not json\n"[..]).unwrap());
+    }
+}
@@ -0,0 +1,108 @@
+//! # 🔌 Accounting Backends
+//!
+//! [`TigerBeetleClient`](crate::TigerBeetleClient) keeps the ZIK/ZAK side of
+//! things - account names, ids, caches - and hands every request to an
+//! [`AccountingBackend`]: TigerBeetle itself, or a
+//! [`MemoryBackend`](crate::MemoryBackend) so an engine can run without a cluster.
+//!
+//! ```ignore
+//! let clock = Arc::new(MockClock::new(1_700_000_000_000));
+//! let backend = MemoryBackend::new().with_clock(clock.clone());
+//! let engine = ZikZakEngine::with_backend("", backend).await?.with_clock(clock);
+//! ```
+//!
+//! The methods mirror the TigerBeetle client's and take and return its types, so
+//! a backend has to answer with TigerBeetle's semantics: one result per event,
+//! linked chains that fail together, balance flags enforced on every transfer.
+
+use anyhow::{anyhow, Result};
+use std::future::Future;
+use std::pin::Pin;
+use tigerbeetle::{
+    Account, AccountBalance, AccountFilter, Client, CreateAccountResult, CreateTransferResult,
+    NotFound, PacketStatus, QueryFilter, Transfer,
+};
+
+/// Answer to one backend request; it owns its data, so it outlives the borrow it came from
+pub type BackendFuture<T> = Pin<Box<dyn Future<Output = Result<T>> + Send>>;
+
+/// Where a [`TigerBeetleClient`](crate::TigerBeetleClient) sends its requests
+///
+/// An `Err` means the request never got an answer - the cluster is unreachable -
+/// while refused events are reported in the results.
+pub trait AccountingBackend: Send + Sync {
+    /// One result per account, in order
+    fn create_accounts(&self, accounts: &[Account]) -> BackendFuture<Vec<CreateAccountResult>>;
+
+    /// One result per transfer, in order
+    fn create_transfers(&self, transfers: &[Transfer]) -> BackendFuture<Vec<CreateTransferResult>>;
+
+    fn lookup_accounts(&self, ids: &[u128]) -> BackendFuture<Vec<Result<Account, NotFound>>>;
+
+    fn lookup_transfers(&self, ids: &[u128]) -> BackendFuture<Vec<Result<Transfer, NotFound>>>;
+
+    fn get_account_transfers(&self, filter: AccountFilter) -> BackendFuture<Vec<Transfer>>;
+
+    /// Balances after each transfer matching `filter`, for accounts keeping history
+    fn get_account_balances(&self, filter: AccountFilter) -> BackendFuture<Vec<AccountBalance>>;
+
+    fn query_accounts(&self, filter: QueryFilter) -> BackendFuture<Vec<Account>>;
+
+    fn query_transfers(&self, filter: QueryFilter) -> BackendFuture<Vec<Transfer>>;
+}
+
+/// A TigerBeetle cluster, through the official client
+pub(crate) struct TigerBeetleBackend(Client);
+
+// SAFETY: the official client accepts concurrent requests from any thread
+unsafe impl Send for TigerBeetleBackend {}
+unsafe impl Sync for TigerBeetleBackend {}
+
+impl TigerBeetleBackend {
+    pub(crate) fn new(cluster_id: u128, addresses: &str) -> Result<Self> {
+        Client::new(cluster_id, addresses)
+            .map(Self)
+            .map_err(|e| anyhow!("Failed to initialize TigerBeetle client: {:?}", e))
+    }
+}
+
+/// `request` with TigerBeetle's packet status turned into an error
+fn packet<T>(
+    request: impl Future<Output = Result<T, PacketStatus>> + Send + 'static,
+) -> BackendFuture<T> {
+    Box::pin(async move { request.await.map_err(|status| anyhow!("{:?}", status)) })
+}
+
+impl AccountingBackend for TigerBeetleBackend {
+    fn create_accounts(&self, accounts: &[Account]) -> BackendFuture<Vec<CreateAccountResult>> {
+        packet(self.0.create_accounts(accounts))
+    }
+
+    fn create_transfers(&self, transfers: &[Transfer]) -> BackendFuture<Vec<CreateTransferResult>> {
+        packet(self.0.create_transfers(transfers))
+    }
+
+    fn lookup_accounts(&self, ids: &[u128]) -> BackendFuture<Vec<Result<Account, NotFound>>> {
+        packet(self.0.lookup_accounts(ids))
+    }
+
+    fn lookup_transfers(&self, ids: &[u128]) -> BackendFuture<Vec<Result<Transfer, NotFound>>> {
+        packet(self.0.lookup_transfers(ids))
+    }
+
+    fn get_account_transfers(&self, filter: AccountFilter) -> BackendFuture<Vec<Transfer>> {
+        packet(self.0.get_account_transfers(filter))
+    }
+
+    fn get_account_balances(&self, filter: AccountFilter) -> BackendFuture<Vec<AccountBalance>> {
+        packet(self.0.get_account_balances(filter))
+    }
+
+    fn query_accounts(&self, filter: QueryFilter) -> BackendFuture<Vec<Account>> {
+        packet(self.0.query_accounts(filter))
+    }
+
+    fn query_transfers(&self, filter: QueryFilter) -> BackendFuture<Vec<Transfer>> {
+        packet(self.0.query_transfers(filter))
+    }
+}
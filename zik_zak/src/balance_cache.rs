@@ -0,0 +1,227 @@
+//! # 🧊 Balance Cache
+//!
+//! A dashboard polling the same hot accounts need not hit TigerBeetle for every
+//! read. Once [`ZikZakEngine::with_balance_cache`](crate::ZikZakEngine::with_balance_cache)
+//! is set, [`get_balance`](crate::ZikZakEngine::get_balance) answers from memory
+//! for balances read less than `ttl` ago, keeping the `max_entries` most recently
+//! used accounts.
+//!
+//! Every transfer the engine records drops both of its accounts from the cache
+//! before hooks, webhooks or subscribers hear of it, so whatever reacts to a
+//! transfer reads the new balance. Changes the engine cannot see - another
+//! process writing to the same cluster - stay invisible until the entry expires,
+//! unless reported with [`invalidate_balances`](crate::ZikZakEngine::invalidate_balances).
+//!
+//! Off by default. [`ZikZakEngine::new`](crate::ZikZakEngine::new) turns it on
+//! when `ZIK_ZAK_BALANCE_CACHE_TTL_MS` is set, see [`BalanceCacheConfig::from_env`].
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::zik_zak::DEFAULT_BALANCE_CACHE_TTL;
+
+/// Accounts a cache keeps unless configured otherwise
+pub const DEFAULT_BALANCE_CACHE_SIZE: usize = 10_000;
+
+/// How long balances may be served from memory, and how many are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceCacheConfig {
+    pub ttl: Duration,
+    /// The least recently used account is dropped beyond this many
+    pub max_entries: usize,
+}
+
+impl Default for BalanceCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: DEFAULT_BALANCE_CACHE_TTL,
+            max_entries: DEFAULT_BALANCE_CACHE_SIZE,
+        }
+    }
+}
+
+impl BalanceCacheConfig {
+    /// `ZIK_ZAK_BALANCE_CACHE_TTL_MS` / `ZIK_ZAK_BALANCE_CACHE_SIZE`, or `None`
+    /// (no caching) while the TTL is unset
+    pub fn from_env() -> Result<Option<Self>> {
+        let env = |name: &str| -> Result<Option<u64>> {
+            match std::env::var(name) {
+                Ok(value) => value
+                    .trim()
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| anyhow!("{} must be a whole number, got {:?}", name, value)),
+                Err(_) => Ok(None),
+            }
+        };
+
+        let Some(ttl_ms) = env("ZIK_ZAK_BALANCE_CACHE_TTL_MS")? else {
+            return Ok(None);
+        };
+        let max_entries = match env("ZIK_ZAK_BALANCE_CACHE_SIZE")? {
+            Some(0) => return Err(anyhow!("ZIK_ZAK_BALANCE_CACHE_SIZE must be positive")),
+            Some(size) => size as usize,
+            None => DEFAULT_BALANCE_CACHE_SIZE,
+        };
+        Ok(Some(Self {
+            ttl: Duration::from_millis(ttl_ms),
+            max_entries,
+        }))
+    }
+}
+
+/// Lookups since the engine started, see [`ZikZakEngine::balance_cache_stats`](crate::ZikZakEngine::balance_cache_stats)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BalanceCacheStats {
+    /// Balances answered from memory
+    pub hits: u64,
+    /// Lookups that had to read TigerBeetle
+    pub misses: u64,
+    /// Accounts currently cached
+    pub entries: usize,
+}
+
+struct Entry {
+    balance: i64,
+    /// Millis on the engine clock when the balance was read
+    read_at: u64,
+    /// Key of the account in `Entries::by_use`
+    last_use: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_account: HashMap<String, Entry>,
+    /// Use counter -> account, least recently used first
+    by_use: BTreeMap<u64, String>,
+    next_use: u64,
+}
+
+impl Entries {
+    fn touch(&mut self, account: &str) {
+        let use_id = self.next_use;
+        self.next_use += 1;
+        if let Some(entry) = self.by_account.get_mut(account) {
+            self.by_use.remove(&entry.last_use);
+            entry.last_use = use_id;
+            self.by_use.insert(use_id, account.to_string());
+        }
+    }
+
+    fn remove(&mut self, account: &str) {
+        if let Some(entry) = self.by_account.remove(account) {
+            self.by_use.remove(&entry.last_use);
+        }
+    }
+}
+
+/// Balances by engine account name, least recently used evicted first
+pub(crate) struct BalanceCache {
+    entries: Mutex<Entries>,
+    max_entries: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl BalanceCache {
+    pub(crate) fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            max_entries: max_entries.max(1),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Balance of `account` read no more than `max_age` millis before `now`,
+    /// any age when `None`
+    pub(crate) fn get(&self, account: &str, now: u64, max_age: Option<u64>) -> Option<i64> {
+        let mut entries = self.entries.lock().unwrap();
+        let fresh = entries.by_account.get(account).and_then(|entry| {
+            let young = max_age.is_none_or(|max| now.saturating_sub(entry.read_at) <= max);
+            young.then_some(entry.balance)
+        });
+        match fresh {
+            Some(_) => {
+                entries.touch(account);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        fresh
+    }
+
+    pub(crate) fn insert(&self, account: &str, balance: i64, now: u64) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(account);
+        let last_use = entries.next_use;
+        entries.next_use += 1;
+        entries.by_use.insert(last_use, account.to_string());
+        entries.by_account.insert(
+            account.to_string(),
+            Entry {
+                balance,
+                read_at: now,
+                last_use,
+            },
+        );
+
+        while entries.by_account.len() > self.max_entries {
+            let Some((_, oldest)) = entries.by_use.pop_first() else {
+                break;
+            };
+            entries.by_account.remove(&oldest);
+        }
+    }
+
+    pub(crate) fn invalidate(&self, account: &str) {
+        self.entries.lock().unwrap().remove(account);
+    }
+
+    pub(crate) fn stats(&self) -> BalanceCacheStats {
+        BalanceCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().by_account.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction_and_ttl() {
+        let cache = BalanceCache::new(2);
+        cache.insert("a", 1, 0);
+        cache.insert("b", 2, 0);
+        // `a` was used last, so `b` goes when `c` arrives
+        assert_eq!(cache.get("a", 0, None), Some(1));
+        cache.insert("c", 3, 0);
+        assert_eq!(cache.get("b", 0, None), None);
+        assert_eq!(cache.get("a", 0, None), Some(1));
+        assert_eq!(cache.get("c", 0, None), Some(3));
+
+        assert_eq!(cache.get("c", 1_000, Some(1_000)), Some(3));
+        assert_eq!(cache.get("c", 1_001, Some(1_000)), None);
+
+        cache.invalidate("a");
+        assert_eq!(cache.get("a", 0, None), None);
+        assert_eq!(
+            cache.stats(),
+            BalanceCacheStats {
+                hits: 4,
+                misses: 3,
+                entries: 1
+            }
+        );
+    }
+}
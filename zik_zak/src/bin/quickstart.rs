@@ -1,6 +1,8 @@
 //! # 🦖 ZIK_ZAK + SLED Quick Start Guide
 //!
 //! The fastest way to replace your entire backend with pure accounting + varchar storage.
+//!
+//! Set `COMPACT_INTERVAL_SECS` to compact the SLED database in the background.
 
 use anyhow::Result;
 use std::collections::HashMap;
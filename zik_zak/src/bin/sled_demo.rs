@@ -1,11 +1,21 @@
 //! # 🦖 ZIK_ZAK SLED Integration Example
 //!
 //! Demonstrates the complete ZIK_ZAK + SLED solution for varchar fields
+//!
+//! Set `COMPACT_INTERVAL_SECS` to compact the SLED database in the background.
 
 use anyhow::Result;
 use serde_json::json;
 use std::collections::HashMap;
-use zik_zak::ZikZakSledEngine;
+use zik_zak::{state_enum, StateEnum, ZikZakSledEngine};
+
+state_enum! {
+    enum OrderStatus {
+        Pending = 1,
+        Shipped = 2,
+        Delivered = 3,
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -107,12 +117,7 @@ async fn main() -> Result<()> {
         ).await?;
 
         // Create order status
-        engine.accounting.transfer(
-            "system:genesis",
-            &format!("{}:status", order_id),
-            1, // 1 = pending, 2 = shipped, 3 = delivered
-            HashMap::new(),
-        ).await?;
+        engine.accounting.set_state(&format!("{}:status", order_id), OrderStatus::Pending).await?;
 
         // Store order details in SLED
         engine.varchar_store.store_varchar(order_id, "user_id", user_id, "text", HashMap::new()).await?;
@@ -175,7 +180,7 @@ async fn main() -> Result<()> {
 
     // Show order details
     println!("\n📦 Order Details:");
-    let order_status = engine.accounting.get_balance("order:001:status").await?;
+    let order_status: Option<OrderStatus> = engine.accounting.get_state("order:001:status").await?;
     let order_details = engine.varchar_store.get_account_varchars("order:001").await?;
     
     println!("  Order ID: order:001");
@@ -183,7 +188,7 @@ async fn main() -> Result<()> {
     println!("  Product: {}", order_details.get("product_id").unwrap_or(&"Unknown".to_string()));
     println!("  Quantity: {}", order_details.get("quantity").unwrap_or(&"0".to_string()));
     println!("  Notes: {}", order_details.get("notes").unwrap_or(&"None".to_string()));
-    println!("  Status: {} (1=pending, 2=shipped, 3=delivered)", order_status);
+    println!("  Status: {}", order_status.map_or("None", |s| s.name()));
 
     println!("\n🎉 ZIK_ZAK + SLED Integration Complete!");
     println!("✅ TigerBeetle handles all numeric accounting");
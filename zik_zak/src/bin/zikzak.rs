@@ -0,0 +1,301 @@
+//! # 🦖 zikzak - ZIK_ZAK from the command line
+//!
+//! One-liners for the admin work that would otherwise mean curling the server
+//! or writing throwaway Rust:
+//!
+//! ```bash
+//! zikzak balance user:123:balance
+//! zikzak transfer system:genesis user:123:balance 5000 --meta reason=refund
+//! zikzak spark create_user --input user_id=123 --input credits=100
+//! zikzak export --pattern 'user:*:balance' --format csv > balances.csv
+//! zikzak stats --json
+//! ```
+//!
+//! Reads the same environment as the server (`ZIK_ZAK_NAMESPACE`,
+//! `ZIK_ZAK_NAMES_DB`, `ZIK_ZAK_SPARKS`, `ZIK_ZAK_SPARKS_DB` and the TigerBeetle
+//! settings), so it sees the ledger exactly as the server does. `export --tags`
+//! also reads account tags from `ZIK_ZAK_TAGS_DB`.
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use zik_zak::{AccountNameStore, SparkEngine, TagStore, Zak, Zik, ZikZak, ZikZakEngine};
+
+#[derive(Parser)]
+#[command(name = "zikzak", version, about = "Operate a ZIK_ZAK ledger")]
+struct Cli {
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Net balance of an account
+    Balance { account: String },
+    /// Move `amount` from one account to another
+    Transfer {
+        from: String,
+        to: String,
+        amount: i64,
+        /// Transfer metadata, repeatable
+        #[arg(long = "meta", value_name = "KEY=VALUE", value_parser = parse_pair)]
+        metadata: Vec<(String, String)>,
+    },
+    /// Ignite a spark from `ZIK_ZAK_SPARKS`
+    #[command(alias = "recipe")]
+    Spark {
+        name: String,
+        /// Spark input, repeatable; values that parse as JSON are passed as JSON
+        #[arg(long = "input", value_name = "KEY=VALUE", value_parser = parse_pair)]
+        inputs: Vec<(String, String)>,
+    },
+    /// Accounts matching a pattern with their balances
+    Export {
+        /// Account pattern, `*` standing for one segment
+        #[arg(long)]
+        pattern: String,
+        #[arg(long, value_enum, default_value_t = Format::Csv)]
+        format: Format,
+        /// Add each account's tags from `ZIK_ZAK_TAGS_DB`
+        #[arg(long)]
+        tags: bool,
+    },
+    /// Ledger size and connection status
+    Stats,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut engine = connect().await?;
+
+    match cli.command {
+        Command::Balance { account } => {
+            let balance = engine.get_balance(account.as_str()).await?;
+            if cli.json {
+                print_json(&json!({ "account": account, "balance": balance }))?;
+            } else {
+                println!("{}  {}", account, engine.format_amount(balance));
+            }
+        }
+        Command::Transfer {
+            from,
+            to,
+            amount,
+            metadata,
+        } => {
+            let id = engine
+                .transfer(
+                    from.as_str(),
+                    to.as_str(),
+                    amount,
+                    metadata.into_iter().collect(),
+                )
+                .await?;
+            if cli.json {
+                print_json(&json!({ "id": id, "from": from, "to": to, "amount": amount }))?;
+            } else {
+                println!(
+                    "✅ {} -> {}  {}  ({})",
+                    from,
+                    to,
+                    engine.format_amount(amount),
+                    id
+                );
+            }
+        }
+        Command::Spark { name, inputs } => {
+            let sparks = load_sparks()?;
+            if sparks.get_spark(&name).is_none() {
+                return Err(anyhow!("Unknown spark {:?}", name));
+            }
+            let inputs = inputs
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+                    (key, value)
+                })
+                .collect();
+            let zikzak = ZikZak::new(Zik::new(inputs), Zak::new(HashMap::new()));
+
+            let from = engine.get_transfer_count().await?;
+            let result = sparks.ignite_spark(&name, zikzak, &mut engine).await?;
+            let transfer_ids: Vec<_> = engine
+                .transfers_since(from)
+                .iter()
+                .map(|t| t.id.clone())
+                .collect();
+            let result = Value::Object(result.into_map().into_iter().collect());
+
+            if cli.json {
+                print_json(&json!({ "result": result, "transfer_ids": transfer_ids }))?;
+            } else {
+                println!("⚡ {} ignited, {} transfers", name, transfer_ids.len());
+                if let Value::Object(fields) = &result {
+                    for (key, value) in fields {
+                        println!("{}: {}", key, value);
+                    }
+                }
+            }
+        }
+        Command::Export {
+            pattern,
+            format,
+            tags,
+        } => {
+            let matches = engine.find_accounts(&pattern).await?;
+            let mut account_tags = Vec::new();
+            if tags {
+                let tags_path = std::env::var("ZIK_ZAK_TAGS_DB")
+                    .unwrap_or_else(|_| "./zik_zak_tags.db".to_string());
+                engine.set_tag_store(TagStore::new(&tags_path)?);
+                for found in &matches {
+                    account_tags.push(engine.tags_of(&found.account).await?);
+                }
+            }
+            match (format, cli.json) {
+                (Format::Json, _) | (_, true) => {
+                    let mut rows = serde_json::to_value(&matches)?;
+                    if let Value::Array(rows) = &mut rows {
+                        for (row, tags) in rows.iter_mut().zip(&account_tags) {
+                            row["tags"] = json!(tags);
+                        }
+                    }
+                    print_json(&rows)?
+                }
+                (Format::Csv, false) => {
+                    println!(
+                        "account,balance,metadata{}",
+                        if tags { ",tags" } else { "" }
+                    );
+                    for (i, found) in matches.iter().enumerate() {
+                        let mut metadata: Vec<_> = found
+                            .metadata
+                            .iter()
+                            .map(|(key, value)| format!("{}={}", key, value))
+                            .collect();
+                        metadata.sort();
+                        let mut line = format!(
+                            "{},{},{}",
+                            csv_field(&found.account),
+                            found.balance,
+                            csv_field(&metadata.join(";"))
+                        );
+                        if let Some(tags) = account_tags.get(i) {
+                            line.push(',');
+                            line.push_str(&csv_field(&tags.join(";")));
+                        }
+                        println!("{}", line);
+                    }
+                }
+            }
+        }
+        Command::Stats => {
+            let accounts = engine.get_account_count().await?;
+            let connected = engine.ping().await;
+            if cli.json {
+                print_json(&json!({
+                    "namespace": engine.namespace(),
+                    "ledger": engine.ledger(),
+                    "accounts": accounts,
+                    "tigerbeetle_connected": connected,
+                }))?;
+            } else {
+                println!("📊 namespace: {:?}", engine.namespace());
+                println!("   ledger:    {}", engine.ledger());
+                println!("   accounts:  {}", accounts);
+                println!(
+                    "   tigerbeetle: {}",
+                    if connected {
+                        "connected"
+                    } else {
+                        "unreachable"
+                    }
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The engine the server would build from the same environment
+async fn connect() -> Result<ZikZakEngine> {
+    let namespace = std::env::var("ZIK_ZAK_NAMESPACE").unwrap_or_default();
+    let mut engine = ZikZakEngine::new(&namespace).await?;
+    let names_path =
+        std::env::var("ZIK_ZAK_NAMES_DB").unwrap_or_else(|_| "./zik_zak_names.db".to_string());
+    engine.set_account_name_store(AccountNameStore::new(&names_path)?)?;
+    Ok(engine)
+}
+
+fn load_sparks() -> Result<SparkEngine> {
+    let sparks_file = std::env::var("ZIK_ZAK_SPARKS")
+        .map_err(|_| anyhow!("Set ZIK_ZAK_SPARKS to the spark definitions file"))?;
+    let sparks_db =
+        std::env::var("ZIK_ZAK_SPARKS_DB").unwrap_or_else(|_| "./zik_zak_sparks.db".to_string());
+    SparkEngine::new(&sparks_file, &sparks_db)
+}
+
+fn print_json(value: &Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// `KEY=VALUE` -> `(KEY, VALUE)`
+fn parse_pair(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got {:?}", arg)),
+    }
+}
+
+/// `value` quoted for CSV when it holds a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pair_and_csv_field() {
+        assert_eq!(
+            parse_pair("note=a=b"),
+            Ok(("note".to_string(), "a=b".to_string()))
+        );
+        assert!(parse_pair("=x").is_err());
+        assert!(parse_pair("novalue").is_err());
+
+        assert_eq!(csv_field("user:1:balance"), "user:1:balance");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_recipe_is_an_alias_for_spark() {
+        let cli =
+            Cli::try_parse_from(["zikzak", "recipe", "create_user", "--input", "id=1"]).unwrap();
+        assert!(matches!(cli.command, Command::Spark { name, .. } if name == "create_user"));
+
+        let cli =
+            Cli::try_parse_from(["zikzak", "export", "--pattern", "user:*", "--json"]).unwrap();
+        assert!(cli.json);
+    }
+}
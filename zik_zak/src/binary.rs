@@ -0,0 +1,194 @@
+//! Compact binary encoding of the transfer log
+//!
+//! Behind [`ZikZakEngine::export_transfers_binary`](crate::ZikZakEngine::export_transfers_binary).
+//! A version byte, then bincode (varint integers) of a string table and the
+//! transfers: account names and metadata are written once and referenced by
+//! index, and UUID transfer ids shrink to their 16 bytes.
+
+use anyhow::{anyhow, Result};
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::zik_zak::Transfer;
+
+/// First byte of every payload
+const FORMAT_VERSION: u8 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Log {
+    strings: Vec<String>,
+    transfers: Vec<Record>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    id: Id,
+    from_account: u32,
+    to_account: u32,
+    amount: i64,
+    metadata: Vec<(u32, u32)>,
+    timestamp: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Id {
+    /// An id that prints back exactly as this UUID
+    Uuid([u8; 16]),
+    Text(String),
+}
+
+impl Id {
+    fn new(id: &str) -> Self {
+        match Uuid::parse_str(id) {
+            Ok(uuid) if uuid.to_string() == id => Id::Uuid(uuid.into_bytes()),
+            _ => Id::Text(id.to_string()),
+        }
+    }
+}
+
+/// Deduplicates strings into [`Log::strings`]
+#[derive(Default)]
+struct Strings<'a> {
+    strings: Vec<String>,
+    index: HashMap<&'a str, u32>,
+}
+
+impl<'a> Strings<'a> {
+    fn intern(&mut self, s: &'a str) -> u32 {
+        *self.index.entry(s).or_insert_with(|| {
+            self.strings.push(s.to_string());
+            self.strings.len() as u32 - 1
+        })
+    }
+}
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+pub(crate) fn encode(transfers: &[Transfer]) -> Result<Vec<u8>> {
+    let mut strings = Strings::default();
+    let transfers = transfers
+        .iter()
+        .map(|t| Record {
+            id: Id::new(&t.id),
+            from_account: strings.intern(&t.from_account),
+            to_account: strings.intern(&t.to_account),
+            amount: t.amount,
+            metadata: t
+                .metadata
+                .iter()
+                .map(|(key, value)| (strings.intern(key), strings.intern(value)))
+                .collect(),
+            timestamp: t.timestamp,
+        })
+        .collect();
+    let log = Log {
+        strings: strings.strings,
+        transfers,
+    };
+
+    let mut bytes = vec![FORMAT_VERSION];
+    options().serialize_into(&mut bytes, &log)?;
+    Ok(bytes)
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Result<Vec<Transfer>> {
+    let log: Log = match bytes.split_first() {
+        Some((&FORMAT_VERSION, body)) => options().deserialize(body)?,
+        Some((version, _)) => {
+            return Err(anyhow!(
+                "Unsupported binary transfer format version {}",
+                version
+            ))
+        }
+        None => return Err(anyhow!("Empty binary transfer export")),
+    };
+
+    let string = |i: u32| {
+        log.strings
+            .get(i as usize)
+            .cloned()
+            .ok_or_else(|| anyhow!("Binary transfer export references missing string {}", i))
+    };
+    log.transfers
+        .iter()
+        .map(|r| {
+            Ok(Transfer {
+                id: match &r.id {
+                    Id::Uuid(bytes) => Uuid::from_bytes(*bytes).to_string(),
+                    Id::Text(id) => id.clone(),
+                },
+                from_account: string(r.from_account)?,
+                to_account: string(r.to_account)?,
+                amount: r.amount,
+                metadata: r
+                    .metadata
+                    .iter()
+                    .map(|&(key, value)| Ok((string(key)?, string(value)?)))
+                    .collect::<Result<_>>()?,
+                timestamp: r.timestamp,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_round_trip_matches_json() {
+        let mut log: Vec<Transfer> = (0..200)
+            .map(|i| Transfer {
+                id: Uuid::new_v4().to_string(),
+                from_account: "system:genesis".to_string(),
+                to_account: format!("user:{}:balance", i % 20),
+                amount: i * 100,
+                metadata: HashMap::from([("reason".to_string(), "signup".to_string())]),
+                timestamp: 1_700_000_000 + i as u64,
+            })
+            .collect();
+        // Ids that are not canonical UUIDs are kept verbatim
+        log[0].id = "batch-7".to_string();
+        log[1].id = log[1].id.to_uppercase();
+
+        let json = serde_json::to_vec(&log).unwrap();
+        let binary = encode(&log).unwrap();
+        let from_json: Vec<Transfer> = serde_json::from_slice(&json).unwrap();
+        let from_binary = decode(&binary).unwrap();
+        assert_eq!(from_binary, from_json);
+        assert_eq!(from_binary, log);
+        assert!(
+            binary.len() * 3 < json.len(),
+            "{} vs {}",
+            binary.len(),
+            json.len()
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_payloads() {
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[9, 0, 0]).is_err());
+        assert!(decode(&[FORMAT_VERSION, 1]).is_err());
+
+        // A record pointing past the string table
+        let log = Log {
+            strings: vec![],
+            transfers: vec![Record {
+                id: Id::Text("t".to_string()),
+                from_account: 0,
+                to_account: 0,
+                amount: 1,
+                metadata: vec![],
+                timestamp: 0,
+            }],
+        };
+        let mut bytes = vec![FORMAT_VERSION];
+        options().serialize_into(&mut bytes, &log).unwrap();
+        assert!(decode(&bytes).is_err());
+    }
+}
@@ -0,0 +1,37 @@
+//! # ⏰ Clock
+//!
+//! Where the engine and the SLED store get "now" from. Production code uses
+//! [`SystemClock`]; tests inject a [`MockClock`](crate::testing::MockClock) and
+//! move time forward by hand instead of sleeping.
+//!
+//! ```ignore
+//! let clock = Arc::new(MockClock::new(1_700_000_000_000));
+//! let store = SledVarCharStore::new(path)?.with_clock(clock.clone());
+//! clock.advance(Duration::from_secs(60));
+//! ```
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of wall-clock time
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the UNIX epoch
+    fn now_millis(&self) -> u64;
+
+    /// Whole seconds since the UNIX epoch
+    fn now_secs(&self) -> u64 {
+        self.now_millis() / 1000
+    }
+}
+
+/// The real clock, `SystemTime::now()`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+}
@@ -0,0 +1,109 @@
+//! # 🔗 Request Correlation
+//!
+//! Every request gets an id: the client's `X-Request-Id` header when it sends a
+//! usable one, a fresh UUID otherwise. The request is served inside a `request`
+//! span carrying the id, so every log line it causes shows it, and inside a
+//! [`request_id::scope`] so every transfer it makes stores it under
+//! [`REQUEST_ID_KEY`](zik_zak::REQUEST_ID_KEY).
+//!
+//! The response echoes the id in `X-Request-Id`. Error responses also carry it
+//! in the body, as a `request_id` field of JSON objects and a trailing
+//! `(request id: …)` on plain text, so it ends up in whatever a client reports.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{info_span, warn, Instrument};
+use uuid::Uuid;
+use zik_zak::request_id;
+
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied id that is kept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The client's id if it is short printable ASCII, else a new one
+fn request_id_of(request: &Request) -> String {
+    request
+        .headers()
+        .get(&REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.bytes().all(|b| b.is_ascii_graphic())
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Serve `request` under its id, and hand the id back in the response
+pub async fn correlate(request: Request, next: Next) -> Response {
+    let id = request_id_of(&request);
+    let span = info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+
+    let response = request_id::scope(id.clone(), next.run(request))
+        .instrument(span)
+        .await;
+    let mut response = if response.status().is_client_error() || response.status().is_server_error()
+    {
+        with_request_id_in_body(response, &id).await
+    } else {
+        response
+    };
+    // Only ids that are valid header values get this far
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID, value);
+    }
+    response
+}
+
+/// `response` with `id` added to its JSON object or plain text body
+async fn with_request_id_in_body(response: Response, id: &str) -> Response {
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let is_json = content_type.starts_with("application/json");
+    if !is_json && !content_type.starts_with("text/plain") {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!(
+                "🔗 Could not read error body to add request id {}: {}",
+                id, e
+            );
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let body = if is_json {
+        match serde_json::from_slice::<serde_json::Value>(&bytes) {
+            Ok(serde_json::Value::Object(mut object)) => {
+                object.insert("request_id".to_string(), id.into());
+                serde_json::Value::Object(object).to_string().into_bytes()
+            }
+            _ => bytes.to_vec(),
+        }
+    } else {
+        let text = String::from_utf8_lossy(&bytes);
+        format!("{} (request id: {})", text, id).into_bytes()
+    };
+    // The body changed length, so the old length no longer holds
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
@@ -0,0 +1,104 @@
+//! # ⚠️ ZIK_ZAK Errors
+//!
+//! Most failures in ZIK_ZAK travel as plain `anyhow::Error`. The variants here are the
+//! ones callers need to tell apart, so they are raised as a typed `ZikZakError` inside
+//! the `anyhow::Error` and can be recovered with `downcast_ref`:
+//!
+//! ```ignore
+//! match engine.check_and_transfer("user:1:balance", "shop:revenue", 500, 500).await {
+//!     Err(e) if matches!(
+//!         e.downcast_ref::<ZikZakError>(),
+//!         Some(ZikZakError::InsufficientBalance { .. })
+//!     ) => { /* tell the user to top up */ }
+//!     other => { other?; }
+//! }
+//! ```
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ZikZakError {
+    /// The source account does not hold the balance an operation required
+    #[error("Insufficient balance in {account}: {balance} < {required}")]
+    InsufficientBalance {
+        account: String,
+        balance: i64,
+        required: i64,
+    },
+
+    /// No account (or entity) with this name exists
+    #[error("ZIK_ZAK account {account} not found")]
+    AccountNotFound { account: String },
+
+    /// TigerBeetle is unreachable or failed the request
+    #[error("TigerBeetle backend error: {message}")]
+    Backend { message: String },
+
+    /// Varchar content does not match its declared content type
+    #[error("Invalid {content_type} content for {key}: {reason}")]
+    InvalidContent {
+        key: String,
+        content_type: String,
+        reason: String,
+    },
+
+    /// An account pattern that is malformed or would scan too much of the ledger
+    #[error("Invalid account pattern {pattern:?}: {reason}")]
+    InvalidPattern { pattern: String, reason: String },
+
+    /// An account name segment that is empty or would split or wildcard the name
+    #[error("Invalid account name segment {segment:?}: {reason}")]
+    InvalidAccountName { segment: String, reason: String },
+
+    /// A state change the account's state enum does not allow
+    #[error("{account} may not move from {from} to {to}")]
+    InvalidTransition {
+        account: String,
+        from: String,
+        to: String,
+    },
+
+    /// Two account names hash to the same TigerBeetle id; `a` had it first
+    #[error("Account names {a} and {b} hash to the same TigerBeetle id")]
+    HashCollision { a: String, b: String },
+
+    /// A transfer would break one of the engine's `TransferLimits`
+    #[error("{account} exceeds the {limit} limit: {amount} > {max}")]
+    LimitExceeded {
+        account: String,
+        limit: String,
+        amount: i64,
+        max: i64,
+    },
+
+    /// The account was frozen with `ZikZakEngine::freeze_account`
+    #[error("ZIK_ZAK account {account} is frozen")]
+    AccountFrozen { account: String },
+
+    /// A transfer between monetary accounts holding different currencies
+    #[error("Cannot move {from_currency} from {from} into {to}, which holds {to_currency}")]
+    CurrencyMismatch {
+        from: String,
+        from_currency: String,
+        to: String,
+        to_currency: String,
+    },
+
+    /// A spark's `balance` condition did not hold; `message` is the spark's own
+    /// `error` text when it has one, `error_code` the HTTP status it asked for
+    #[error("{message}")]
+    ConditionFailed {
+        account: String,
+        balance: i64,
+        message: String,
+        error_code: Option<u16>,
+    },
+
+    /// Accounts matching `pattern` do not add up to the total they must conserve
+    #[error("Invariant {pattern} broken: balances sum to {actual}, expected {expected}")]
+    InvariantViolated {
+        pattern: String,
+        expected: i64,
+        actual: i128,
+    },
+}
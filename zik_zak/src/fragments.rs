@@ -0,0 +1,260 @@
+//! # 🧩 Spark Fragments
+//!
+//! Named operation lists that sparks pull in with an `include` operation, so a
+//! spark file does not repeat the same transfer shapes:
+//!
+//! ```json
+//! {
+//!   "fragments": {
+//!     "audit_log": [
+//!       { "type": "transfer", "zik": "system:genesis", "zak": "audit:{entity}:events", "amount": 1 }
+//!     ]
+//!   },
+//!   "sparks": {
+//!     "create_order": {
+//!       "operations": [
+//!         { "type": "include", "fragment": "audit_log", "args": { "entity": "order:{order_id}" } }
+//!       ]
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Includes are expanded once, when [`SparkEngine::new`](crate::SparkEngine::new)
+//! loads the file. Every `{name}` naming one of the include's `args` is replaced by
+//! that arg (a string arg verbatim, so it may itself hold placeholders); other
+//! placeholders are left for ignition to fill from the spark's inputs. The
+//! expanded operations take the include's place and are numbered `op_N` like any
+//! other. Fragments may include fragments, also inside `upsert` branches; an
+//! unknown fragment or an include cycle fails the load.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::sparks::{template_parts, Operation, Spark, TemplatePart};
+
+/// Replace every `include` in `sparks` with its fragment's operations
+pub(crate) fn expand(
+    sparks: &mut HashMap<String, Spark>,
+    fragments: &HashMap<String, Vec<Operation>>,
+) -> Result<()> {
+    // Unused fragments are checked too, so a cycle never waits for its first include
+    for name in fragments.keys() {
+        expand_fragment(name, &HashMap::new(), fragments, &mut Vec::new())?;
+    }
+    for (name, spark) in sparks.iter_mut() {
+        spark.operations = expand_operations(&spark.operations, fragments, &mut Vec::new())
+            .map_err(|e| anyhow!("Spark {}: {}", name, e))?;
+    }
+    Ok(())
+}
+
+/// `operations` with includes expanded; `stack` holds the fragments being expanded
+fn expand_operations(
+    operations: &[Operation],
+    fragments: &HashMap<String, Vec<Operation>>,
+    stack: &mut Vec<String>,
+) -> Result<Vec<Operation>> {
+    let mut expanded = Vec::with_capacity(operations.len());
+    for operation in operations {
+        if operation.op_type == "include" {
+            let name = operation
+                .fragment
+                .as_deref()
+                .ok_or(anyhow!("Missing 'fragment' field"))?;
+            let args = operation.args.clone().unwrap_or_default();
+            expanded.extend(expand_fragment(name, &args, fragments, stack)?);
+            continue;
+        }
+
+        let mut operation = operation.clone();
+        if let Some(create) = &operation.create {
+            operation.create = Some(expand_operations(create, fragments, stack)?);
+        }
+        if let Some(update) = &operation.update {
+            operation.update = Some(expand_operations(update, fragments, stack)?);
+        }
+        expanded.push(operation);
+    }
+    Ok(expanded)
+}
+
+fn expand_fragment(
+    name: &str,
+    args: &HashMap<String, Value>,
+    fragments: &HashMap<String, Vec<Operation>>,
+    stack: &mut Vec<String>,
+) -> Result<Vec<Operation>> {
+    if stack.iter().any(|including| including == name) {
+        return Err(anyhow!(
+            "Fragment cycle: {} -> {}",
+            stack.join(" -> "),
+            name
+        ));
+    }
+    let operations = fragments
+        .get(name)
+        .ok_or_else(|| anyhow!("Unknown fragment: {}", name))?
+        .iter()
+        .map(|operation| with_args(operation, args))
+        .collect::<Result<Vec<_>>>()?;
+
+    stack.push(name.to_string());
+    let expanded = expand_operations(&operations, fragments, stack);
+    stack.pop();
+    expanded
+}
+
+/// `operation` with `args` substituted into every string it holds
+fn with_args(operation: &Operation, args: &HashMap<String, Value>) -> Result<Operation> {
+    if args.is_empty() {
+        return Ok(operation.clone());
+    }
+    let mut value = serde_json::to_value(operation)?;
+    substitute_strings(&mut value, args);
+    Ok(serde_json::from_value(value)?)
+}
+
+fn substitute_strings(value: &mut Value, args: &HashMap<String, Value>) {
+    match value {
+        Value::String(template) => *template = substitute(template, args),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| substitute_strings(item, args)),
+        Value::Object(fields) => fields
+            .values_mut()
+            .for_each(|field| substitute_strings(field, args)),
+        _ => {}
+    }
+}
+
+/// `template` with the placeholders naming an arg replaced, escapes kept
+fn substitute(template: &str, args: &HashMap<String, Value>) -> String {
+    if !template.contains(['{', '}']) {
+        return template.to_string();
+    }
+    let mut substituted = String::with_capacity(template.len());
+    for part in template_parts(template) {
+        match part {
+            TemplatePart::Text(text) => {
+                substituted.push_str(&text.replace('{', "{{").replace('}', "}}"))
+            }
+            TemplatePart::Placeholder(name) => match args.get(name) {
+                Some(Value::String(arg)) => substituted.push_str(arg),
+                Some(arg) => substituted.push_str(&arg.to_string()),
+                None => {
+                    substituted.push('{');
+                    substituted.push_str(name);
+                    substituted.push('}');
+                }
+            },
+        }
+    }
+    substituted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SparkEngine;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn spark_file(dir: &TempDir, definition: Value) -> String {
+        let path = dir.path().join("sparks.json");
+        std::fs::write(&path, definition.to_string()).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn definition(fragments: Value, operations: Value) -> Value {
+        json!({
+            "schema_version": "1",
+            "title": "fragments",
+            "description": "fragment test",
+            "primitives": {},
+            "entities": {},
+            "fragments": fragments,
+            "sparks": {
+                "create_order": {
+                    "description": "Order with an audit entry",
+                    "inputs": ["order_id", "total"],
+                    "operations": operations
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_include_expands_at_load_with_args() {
+        let dir = TempDir::new().unwrap();
+        let file = spark_file(
+            &dir,
+            definition(
+                json!({
+                    "audit_log": [
+                        { "type": "transfer", "zik": "system:genesis", "zak": "audit:{entity}:events",
+                          "amount": 1, "metadata": { "event": "{event}", "literal": "{{raw}}" } },
+                        { "type": "include", "fragment": "touch", "args": { "what": "{entity}" } }
+                    ],
+                    "touch": [
+                        { "type": "transfer", "zik": "system:genesis", "zak": "{what}:touched", "amount": "{weight}" }
+                    ]
+                }),
+                json!([
+                    { "type": "transfer", "zik": "system:genesis", "zak": "order:{order_id}:total", "amount": "{total}" },
+                    { "type": "include", "fragment": "audit_log",
+                      "args": { "entity": "order:{order_id}", "event": "created", "weight": 2 } }
+                ]),
+            ),
+        );
+
+        let engine = SparkEngine::new(&file, dir.path().join("sled")).unwrap();
+        let operations = &engine.get_spark("create_order").unwrap().operations;
+        assert_eq!(operations.len(), 3);
+        assert_eq!(
+            operations[1].zak.as_deref(),
+            Some("audit:order:{order_id}:events")
+        );
+        let metadata = operations[1].metadata.as_ref().unwrap();
+        assert_eq!(metadata["event"], "created");
+        assert_eq!(metadata["literal"], "{{raw}}");
+        // Passed down through the nested include
+        assert_eq!(
+            operations[2].zak.as_deref(),
+            Some("order:{order_id}:touched")
+        );
+        // `weight` is not an arg of `touch`, so it is left for ignition
+        assert_eq!(operations[2].amount, Some(json!("{weight}")));
+    }
+
+    #[test]
+    fn test_include_cycle_is_rejected() {
+        let dir = TempDir::new().unwrap();
+        let file = spark_file(
+            &dir,
+            definition(
+                json!({
+                    "a": [{ "type": "include", "fragment": "b" }],
+                    "b": [{ "type": "include", "fragment": "a" }]
+                }),
+                json!([]),
+            ),
+        );
+
+        let error = SparkEngine::new(&file, dir.path().join("sled"))
+            .err()
+            .expect("cyclic fragments must not load");
+        assert!(error.to_string().contains("Fragment cycle"), "{}", error);
+
+        let unknown = expand_operations(
+            &[
+                serde_json::from_value(json!({ "type": "include", "fragment": "missing" }))
+                    .unwrap(),
+            ],
+            &HashMap::new(),
+            &mut Vec::new(),
+        );
+        assert!(unknown.is_err());
+    }
+}
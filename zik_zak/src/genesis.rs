@@ -21,8 +21,8 @@
 //!     zak: zak!{ order_id: 456 }
 //! }).await?;
 //!
-//! // ASK GENESIS: What orders did you create for this user?
-//! let orders = genesis.divine_query("user:123:order:*").await?;
+//! // ASK GENESIS: What orders did you create for this user? (first 100)
+//! let orders = genesis.divine_query("user:123:order:*", 100, None).await?;
 //! # Ok(())
 //! # }
 //! ```
@@ -33,9 +33,16 @@
 //! No controllers. No services. No repositories. Just divine sparks.
 
 use anyhow::Result;
+use futures::stream::{self, Stream};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::path::Path;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tracing::info;
 
+use crate::error::ZikZakError;
+use crate::query::AccountPattern;
+use crate::realtime::{SparkEvent, EVENT_BUFFER};
 use crate::sparks::{SparkEngine, Zak, ZikZak};
 use crate::zik_zak::ZikZakEngine;
 
@@ -43,6 +50,7 @@ use crate::zik_zak::ZikZakEngine;
 pub struct Genesis {
     pub spark_engine: SparkEngine,
     pub accounting: ZikZakEngine,
+    events: broadcast::Sender<SparkEvent>,
 }
 
 impl Genesis {
@@ -50,13 +58,10 @@ impl Genesis {
     pub async fn new<P: AsRef<Path>>(sparks_file: &str, sled_db_path: P) -> Result<Self> {
         info!("🌟 Initializing GENESIS - The Divine Creator");
 
-        let accounting = ZikZakEngine::new().await?;
+        let accounting = ZikZakEngine::new("").await?;
         let spark_engine = SparkEngine::new(sparks_file, sled_db_path)?;
 
-        let mut genesis = Self {
-            spark_engine,
-            accounting,
-        };
+        let mut genesis = Self::with_engines(spark_engine, accounting);
 
         // Ensure divine system accounts exist
         genesis.accounting.ensure_system_accounts().await?;
@@ -69,18 +74,31 @@ impl Genesis {
     pub async fn empty<P: AsRef<Path>>(sled_db_path: P) -> Result<Self> {
         info!("🌟 Creating empty GENESIS");
 
-        let accounting = ZikZakEngine::new().await?;
+        let accounting = ZikZakEngine::new("").await?;
         let spark_engine = SparkEngine::empty(sled_db_path)?;
 
-        let mut genesis = Self {
-            spark_engine,
-            accounting,
-        };
+        let mut genesis = Self::with_engines(spark_engine, accounting);
 
         genesis.accounting.ensure_system_accounts().await?;
         Ok(genesis)
     }
 
+    /// Wire the engines together, sending every committed transfer to subscribers
+    fn with_engines(spark_engine: SparkEngine, mut accounting: ZikZakEngine) -> Self {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let sender = events.clone();
+        accounting.on_transfer(Box::new(move |transfer| {
+            // No subscribers, no receivers - nothing to do
+            let _ = sender.send(SparkEvent::Transfer(transfer.clone()));
+        }));
+
+        Self {
+            spark_engine,
+            accounting,
+            events,
+        }
+    }
+
     /// IGNITE A DIVINE SPARK ⚡
     ///
     /// This is where creation happens. GENESIS ignites a spark with ZIK/ZAK flows
@@ -88,28 +106,174 @@ impl Genesis {
     pub async fn ignite_spark(&mut self, spark_name: &str, zikzak: ZikZak) -> Result<Zak> {
         info!("⚡ GENESIS igniting spark: {}", spark_name);
 
-        self.spark_engine
+        let inputs = zikzak.inputs();
+        let logged = self.accounting.get_transfer_count().await?;
+        let result = self
+            .spark_engine
             .ignite_spark(spark_name, zikzak, &mut self.accounting)
-            .await
+            .await?;
+        self.announce(spark_name, inputs, logged, &result);
+        Ok(result)
+    }
+
+    /// Ignite a spark and report how long each of its operations took
+    ///
+    /// The result carries a `steps` array, see [`SparkEngine::ignite_spark_profiled`].
+    pub async fn ignite_spark_profiled(&mut self, spark_name: &str, zikzak: ZikZak) -> Result<Zak> {
+        info!("⚡ GENESIS igniting spark (profiled): {}", spark_name);
+
+        let inputs = zikzak.inputs();
+        let logged = self.accounting.get_transfer_count().await?;
+        let result = self
+            .spark_engine
+            .ignite_spark_profiled(spark_name, zikzak, &mut self.accounting)
+            .await?;
+        self.announce(spark_name, inputs, logged, &result);
+        Ok(result)
+    }
+
+    /// Ignite a spark that is safe to retry with the same `idempotency_key`
+    ///
+    /// See [`SparkEngine::ignite_spark_idempotent`].
+    pub async fn ignite_spark_idempotent(
+        &mut self,
+        spark_name: &str,
+        zikzak: ZikZak,
+        idempotency_key: &str,
+    ) -> Result<Zak> {
+        info!(
+            "⚡ GENESIS igniting spark: {} (idempotency key {})",
+            spark_name, idempotency_key
+        );
+
+        let inputs = zikzak.inputs();
+        let logged = self.accounting.get_transfer_count().await?;
+        let result = self
+            .spark_engine
+            .ignite_spark_idempotent(spark_name, zikzak, idempotency_key, &mut self.accounting)
+            .await?;
+        self.announce(spark_name, inputs, logged, &result);
+        Ok(result)
+    }
+
+    /// Tell subscribers that `spark` ran, with the transfers logged after the first `logged`
+    fn announce(&self, spark: &str, inputs: HashMap<String, Value>, logged: usize, result: &Zak) {
+        let _ = self.events.send(SparkEvent::Ignited {
+            spark: spark.to_string(),
+            inputs,
+            transfers: self.accounting.transfers_since(logged).to_vec(),
+            result: result.clone(),
+        });
+    }
+
+    /// WATCH CREATION - Events for every account matching `pattern`, as they happen
+    ///
+    /// Yields a [`SparkEvent::Ignited`] when a spark run moved a matching account
+    /// and a [`SparkEvent::Transfer`] for each matching committed transfer, made
+    /// by a spark or directly on [`accounting`](Self::accounting). Transfers
+    /// reach subscribers through the engine's hook queue, so a spark's `Transfer`
+    /// events may arrive after its `Ignited` one.
+    ///
+    /// Nothing ever waits for a subscriber. Each one has a buffer of
+    /// [`EVENT_BUFFER`] events; one that falls further behind loses the oldest
+    /// and is sent [`SparkEvent::Lagged`] with how many it missed. The stream
+    /// ends when this `Genesis` is dropped.
+    ///
+    /// ```rust,no_run
+    /// use futures::StreamExt;
+    /// use zik_zak::{realtime::SparkEvent, Genesis};
+    ///
+    /// # async fn example(genesis: &Genesis) -> anyhow::Result<()> {
+    /// let mut events = Box::pin(genesis.subscribe("user:*:existence")?);
+    /// while let Some(event) = events.next().await {
+    ///     if let SparkEvent::Ignited { spark, result, .. } = event {
+    ///         println!("{} created {:?}", spark, result);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn subscribe(&self, pattern: &str) -> Result<impl Stream<Item = SparkEvent>> {
+        let pattern = AccountPattern::parse(pattern)?;
+        let receiver = self.events.subscribe();
+
+        Ok(stream::unfold(
+            (receiver, pattern),
+            |(mut receiver, pattern)| async move {
+                loop {
+                    let event = match receiver.recv().await {
+                        Ok(event) if event.touches(&pattern) => event,
+                        Ok(_) => continue,
+                        Err(RecvError::Lagged(missed)) => SparkEvent::Lagged(missed),
+                        Err(RecvError::Closed) => return None,
+                    };
+                    return Some((event, (receiver, pattern)));
+                }
+            },
+        ))
     }
 
     /// DIVINE QUERY - Ask GENESIS what it created
     ///
-    /// Query the omniscient transfer history to see what GENESIS brought into existence.
+    /// Up to `limit` accounts whose names match the pattern, with their balance and
+    /// latest transfer metadata (see [`crate::query`] for the rules), as
+    /// `{"results": [...], "next_cursor": "..."}`. Pass `next_cursor` back for the
+    /// next page; it is `null` on the last one. See
+    /// [`ZikZakEngine::find_accounts_page`] for ordering and cost.
     /// Pattern examples:
     /// - "user:123:order:*" - All orders for user 123
     /// - "product:*:existence" - All products that exist
     /// - "order:456:*" - All fields of order 456
-    pub async fn divine_query(&self, entity_pattern: &str) -> Result<serde_json::Value> {
+    pub async fn divine_query(
+        &self,
+        entity_pattern: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<serde_json::Value> {
         info!("🔍 GENESIS divine query: {}", entity_pattern);
 
-        // Get all transfers from system:genesis
-        let history = self.accounting.get_transaction_history().await?;
-
-        // Filter transfers that match the pattern
-        // TODO: Implement pattern matching logic
+        let page = self
+            .accounting
+            .find_accounts_page(entity_pattern, limit, cursor)
+            .await?;
+        Ok(serde_json::to_value(page)?)
+    }
 
-        Ok(history)
+    /// DIVINE ENTITY - One entity's fields as a single JSON object
+    ///
+    /// Every `{entity}:*` field account becomes a key: text written by a sled
+    /// transfer resolves to that text, any other field to its balance. Fails with
+    /// [`ZikZakError::AccountNotFound`] when the entity has no fields at all.
+    /// - "user:123" - `{"existence": 1, "balance": 5000, "email": "a@b.c"}`
+    pub async fn divine_entity(&self, entity: &str) -> Result<serde_json::Value> {
+        info!("🔍 GENESIS divine entity: {}", entity);
+
+        let matches = self
+            .accounting
+            .find_accounts(&format!("{}:*", entity))
+            .await?;
+        if matches.is_empty() {
+            return Err(ZikZakError::AccountNotFound {
+                account: entity.to_string(),
+            }
+            .into());
+        }
+
+        let mut fields = serde_json::Map::new();
+        for found in matches {
+            let field = found
+                .account
+                .rsplit(':')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let value = match self.spark_engine.get_text(&found.account).await? {
+                Some(text) => serde_json::Value::String(text),
+                None => serde_json::Value::from(found.balance),
+            };
+            fields.insert(field, value);
+        }
+        Ok(serde_json::Value::Object(fields))
     }
 
     /// Get the ledger state - the current reality as GENESIS sees it
@@ -171,15 +335,12 @@ mod tests {
                 zik: Some("system:genesis".to_string()),
                 zak: Some("test:{entity_id}:existence".to_string()),
                 amount: Some(serde_json::Value::Number(1.into())),
-                account: None,
-                condition: None,
-                on_fail: None,
-                field: None,
-                sled: None,
-                ledger: None,
-                metadata: None,
+                ..Default::default()
             }],
             return_value: None,
+            return_mode: Default::default(),
+            atomic: false,
+            default_metadata: HashMap::new(),
         };
 
         genesis
@@ -0,0 +1,141 @@
+//! # 🕸️ GraphQL
+//!
+//! The engine as a GraphQL schema, served at `/graphql` with a playground at
+//! `/graphql/playground`. Only built with the `graphql` feature.
+//!
+//! ```graphql
+//! query { balances(accounts: ["user:1:balance", "user:2:balance"]) { account balance } }
+//! mutation { transfer(from: "system:genesis", to: "user:1:balance", amount: 100) }
+//! ```
+//!
+//! Resolvers call the same `ZikZakEngine` methods as the REST handlers, so both
+//! APIs see the same errors and the same data.
+
+use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
+use async_graphql::{Context, EmptySubscription, Object, Schema, SimpleObject};
+use axum::{
+    response::{Html, IntoResponse},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use std::collections::HashMap;
+use zik_zak::{Transfer, TransferFilter};
+
+use crate::AppState;
+
+pub type ZikZakSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+#[derive(SimpleObject)]
+pub struct AccountBalance {
+    account: String,
+    balance: i64,
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Net balance of one account
+    async fn balance(&self, ctx: &Context<'_>, account: String) -> async_graphql::Result<i64> {
+        let engine = ctx.data_unchecked::<AppState>().engine.read().await;
+        Ok(engine.get_balance(account.as_str()).await?)
+    }
+
+    /// Net balances of several accounts, in the order asked for
+    async fn balances(
+        &self,
+        ctx: &Context<'_>,
+        accounts: Vec<String>,
+    ) -> async_graphql::Result<Vec<AccountBalance>> {
+        let engine = ctx.data_unchecked::<AppState>().engine.read().await;
+        let mut balances = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            let balance = engine.get_balance(account.as_str()).await?;
+            balances.push(AccountBalance { account, balance });
+        }
+        Ok(balances)
+    }
+
+    /// One page of the transfer log, newest first (same rules as `/transactions`)
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: TransferFilter,
+    ) -> async_graphql::Result<Vec<Transfer>> {
+        let engine = ctx.data_unchecked::<AppState>().engine.read().await;
+        Ok(engine.query_transfers(filter).await?)
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    /// Move `amount` from one account to another, returning the transfer id
+    async fn transfer(
+        &self,
+        ctx: &Context<'_>,
+        from: String,
+        to: String,
+        amount: i64,
+        metadata: Option<HashMap<String, String>>,
+    ) -> async_graphql::Result<String> {
+        let mut engine = ctx.data_unchecked::<AppState>().engine.write().await;
+        Ok(engine
+            .transfer(
+                from.as_str(),
+                to.as_str(),
+                amount,
+                metadata.unwrap_or_default(),
+            )
+            .await?)
+    }
+}
+
+fn schema(state: AppState) -> ZikZakSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(state)
+        .finish()
+}
+
+/// `/graphql` and the playground at `/graphql/playground`
+pub fn routes<S>(state: AppState) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/graphql", post(execute))
+        .route("/graphql/playground", get(playground))
+        .layer(Extension(schema(state)))
+}
+
+async fn execute(
+    Extension(schema): Extension<ZikZakSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+async fn playground() -> impl IntoResponse {
+    Html(playground_source(GraphQLPlaygroundConfig::new("/graphql")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_exposes_engine_operations() {
+        let sdl = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+            .finish()
+            .sdl();
+        for field in [
+            "balance(account: String!): Int!",
+            "balances(accounts: [String!]!): [AccountBalance!]!",
+            "transactions(filter: TransferFilter! = {",
+            "transfer(from: String!, to: String!, amount: Int!, metadata: JSONObject): String!",
+        ] {
+            assert!(sdl.contains(field), "missing {} in\n{}", field, sdl);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! # 🪝 Transfer Hooks
+//!
+//! React to committed transfers without polling: send the email when
+//! `order:42:status` moves, invalidate a cache when `product:*` changes.
+//!
+//! ```ignore
+//! engine.on_account_change("order:", Box::new(|t| notify(&t.to_account)));
+//! ```
+//!
+//! Hooks never run on the transfer path. Each committed transfer is queued on a
+//! bounded channel and a background task calls the matching hooks in commit order.
+//! When the queue is full the event is dropped and counted instead of slowing
+//! transfers down - check `dropped_hook_events` if every event matters.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::warn;
+
+use crate::zik_zak::Transfer;
+
+/// Callback invoked with each committed transfer
+pub type TransferHook = Box<dyn Fn(&Transfer) + Send + Sync>;
+
+/// Transfers that may wait for the hook worker before new ones are dropped
+const HOOK_QUEUE_CAPACITY: usize = 1024;
+
+struct RegisteredHook {
+    /// Only fire when either side of the transfer starts with this
+    prefix: Option<String>,
+    hook: TransferHook,
+}
+
+impl RegisteredHook {
+    fn matches(&self, transfer: &Transfer) -> bool {
+        match &self.prefix {
+            None => true,
+            Some(prefix) => {
+                transfer.from_account.starts_with(prefix.as_str())
+                    || transfer.to_account.starts_with(prefix.as_str())
+            }
+        }
+    }
+}
+
+/// Hook registry plus the queue feeding its worker task
+#[derive(Default)]
+pub(crate) struct TransferHooks {
+    hooks: Arc<RwLock<Vec<RegisteredHook>>>,
+    /// Started with the first registered hook
+    queue: Option<mpsc::Sender<Transfer>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl TransferHooks {
+    pub(crate) fn register(&mut self, prefix: Option<String>, hook: TransferHook) {
+        self.hooks
+            .write()
+            .unwrap()
+            .push(RegisteredHook { prefix, hook });
+
+        if self.queue.is_none() {
+            let (sender, mut receiver) = mpsc::channel::<Transfer>(HOOK_QUEUE_CAPACITY);
+            let hooks = Arc::clone(&self.hooks);
+
+            // Ends once the engine (and with it the sender) is dropped
+            tokio::spawn(async move {
+                while let Some(transfer) = receiver.recv().await {
+                    for registered in hooks.read().unwrap().iter() {
+                        if registered.matches(&transfer) {
+                            (registered.hook)(&transfer);
+                        }
+                    }
+                }
+            });
+
+            self.queue = Some(sender);
+        }
+    }
+
+    /// Queue a committed transfer for the hooks, never waiting
+    pub(crate) fn dispatch(&self, transfer: &Transfer) {
+        let Some(queue) = &self.queue else {
+            return;
+        };
+
+        if let Err(TrySendError::Full(transfer)) = queue.try_send(transfer.clone()) {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            warn!(
+                "🪝 Hook queue full, dropped event for transfer {} ({} dropped so far)",
+                transfer.id, dropped
+            );
+        }
+    }
+
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn transfer(id: &str, from: &str, to: &str) -> Transfer {
+        Transfer {
+            id: id.to_string(),
+            from_account: from.to_string(),
+            to_account: to.to_string(),
+            amount: 3,
+            metadata: HashMap::new(),
+            timestamp: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefix_hook_fires_with_matching_transfer() {
+        let (seen_tx, mut seen) = mpsc::unbounded_channel();
+        let mut hooks = TransferHooks::default();
+        hooks.register(
+            Some("order:".to_string()),
+            Box::new(move |t| seen_tx.send(t.clone()).unwrap()),
+        );
+
+        hooks.dispatch(&transfer("t1", "system:genesis", "user:1:balance"));
+        hooks.dispatch(&transfer("t2", "system:genesis", "order:42:status"));
+
+        let fired = tokio::time::timeout(Duration::from_secs(1), seen.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fired.id, "t2");
+        assert_eq!(fired.to_account, "order:42:status");
+        assert_eq!(fired.amount, 3);
+        assert!(seen.try_recv().is_err());
+        assert_eq!(hooks.dropped(), 0);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_full_queue_drops_and_counts() {
+        let mut hooks = TransferHooks::default();
+        hooks.register(None, Box::new(|_| {}));
+
+        // The worker cannot run until this test yields, so the queue only fills
+        for i in 0..HOOK_QUEUE_CAPACITY + 5 {
+            hooks.dispatch(&transfer(&i.to_string(), "a", "b"));
+        }
+        assert_eq!(hooks.dropped(), 5);
+    }
+}
@@ -66,17 +66,65 @@
 //!
 //! Welcome to the revolution. 🔥
 
+pub mod accounting;
+mod audit;
+pub mod backend;
+pub mod balance_cache;
+mod binary;
+pub mod clock;
+pub mod error;
+pub mod fragments;
 pub mod genesis;
+pub mod hooks;
+pub mod lint;
+pub mod memory_backend;
+pub mod money;
+pub mod query;
+pub mod realtime;
+pub mod request_id;
 pub mod sled;
 pub mod sparks;
+pub mod states;
+pub mod system_accounts;
+pub mod tenancy;
+pub mod testing;
 pub mod tigerbeetle_client;
+pub mod webhooks;
 pub mod zik_zak;
 
+pub use accounting::{Account, AccountId};
+pub use backend::{AccountingBackend, BackendFuture};
+pub use balance_cache::{BalanceCacheConfig, BalanceCacheStats, DEFAULT_BALANCE_CACHE_SIZE};
+pub use clock::{Clock, SystemClock};
+pub use error::ZikZakError;
 pub use genesis::Genesis;
-pub use sled::{SledVarCharStore, ZikZakSledEngine};
-pub use sparks::{Spark, SparkEngine, Zak, Zik, ZikZak};
-pub use tigerbeetle_client::TigerBeetleClient;
-pub use zik_zak::{Transfer, ZikZakEngine};
+pub use hooks::TransferHook;
+pub use lint::LintWarning;
+pub use memory_backend::MemoryBackend;
+pub use money::{Currencies, Currency, Money};
+pub use query::{AccountPattern, MAX_PATTERN_WILDCARDS};
+pub use request_id::REQUEST_ID_KEY;
+pub use sled::{
+    AccountNameStore, CheckpointStore, CurrencyStore, DailyOutflowStore, DeleteMode, MetadataIndex,
+    ObjectStore, SledVarCharStore, StoredObject, TagStore, WebhookDeadLetters, ZikZakSledEngine,
+};
+pub use sparks::{
+    ReturnMode, Spark, SparkEngine, StepTiming, Zak, Zik, ZikZak, MAX_SPARK_DEPTH, RUN_ID_KEY,
+};
+pub use states::StateEnum;
+pub use system_accounts::{SystemAccount, SystemAccounts};
+pub use tenancy::TenantView;
+pub use tigerbeetle_client::{
+    AccountDirection, AccountHasher, AccountPage, AccountSpec, IdStrategy, Sha256AccountHasher,
+    TigerBeetleClient, TransferPage, ZikZakAccount, ZikZakTransfer,
+};
+pub use webhooks::{DeadLetter, WebhookConfig};
+pub use zik_zak::{
+    AccountMatch, AccountMatchPage, BatchTransfer, BulkResult, Consistency, DailyLimit,
+    PendingTransfer, ReconResult, Statement, StatementEntry, StatementLine, StatementPage,
+    Transfer, TransferFilter, TransferLimits, ZikZakEngine, DEFAULT_BALANCE_CACHE_TTL,
+    DEFAULT_GENESIS_THRESHOLD, GENESIS_BALANCE,
+};
 
 // Re-export the divine macros (they're already at crate root due to #[macro_export])
 // pub use sparks::{zak, zik}; // Not needed - macros are exported at crate root
@@ -0,0 +1,433 @@
+//! # 🔍 Spark Linter
+//!
+//! Authoring mistakes [`SparkEngine::lint`](crate::SparkEngine::lint) finds without
+//! igniting anything:
+//!
+//! - operations that fail every time: an unknown `type`, a missing required field,
+//!   or a `call_spark` of a spark that is not loaded
+//! - operations after one of those, which never run - with `on_fail: "return"` the
+//!   spark quietly stops there, without it the spark errors
+//! - `{name}` placeholders that are not an input, `recipe_run_id`, or the result
+//!   (`op_N`, `store_as`) of an operation that runs earlier
+//! - numeric transfers of a literal zero (or negative) amount, which are rejected
+//!
+//! Placeholders are checked in account names, amounts, expressions, metadata,
+//! `call_spark` args and the `return` template. `upsert` branches are checked
+//! like the spark itself; what either branch stores counts as available after it.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::sparks::{template_parts, Operation, Spark, TemplatePart, RUN_ID_KEY};
+
+/// One finding of [`SparkEngine::lint`](crate::SparkEngine::lint)
+///
+/// `operation` is the key the operation's result is stored under (`op_2`, or
+/// `op_2_0` inside an `upsert` branch), or `return` for the return template.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LintWarning {
+    /// No spark by this name is loaded
+    SparkNotFound { spark: String },
+    /// The operation fails whenever it runs
+    AlwaysFails { operation: String, reason: String },
+    /// The operation comes after `after`, which always fails
+    Unreachable { operation: String, after: String },
+    /// `{name}` is never stored before the operation uses it
+    UndefinedValue { operation: String, name: String },
+    /// A numeric transfer whose literal amount is not positive
+    ZeroAmount { operation: String },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SparkNotFound { spark } => write!(f, "Spark not found: {}", spark),
+            Self::AlwaysFails { operation, reason } => {
+                write!(f, "{} always fails: {}", operation, reason)
+            }
+            Self::Unreachable { operation, after } => {
+                write!(f, "{} never runs: {} always fails first", operation, after)
+            }
+            Self::UndefinedValue { operation, name } => {
+                write!(f, "{} uses {{{}}}, which is never stored", operation, name)
+            }
+            Self::ZeroAmount { operation } => {
+                write!(f, "{} transfers a zero amount", operation)
+            }
+        }
+    }
+}
+
+/// Lint the spark `name` among `sparks`
+pub(crate) fn lint(sparks: &HashMap<String, Spark>, name: &str) -> Vec<LintWarning> {
+    let Some(spark) = sparks.get(name) else {
+        return vec![LintWarning::SparkNotFound {
+            spark: name.to_string(),
+        }];
+    };
+
+    let mut linter = Linter {
+        sparks,
+        warnings: Vec::new(),
+    };
+    let mut defined: HashSet<String> = spark.inputs.iter().cloned().collect();
+    defined.insert(RUN_ID_KEY.to_string());
+
+    linter.operations(&spark.operations, "op_", &mut defined);
+    for template in spark.return_value.iter().flat_map(|r| r.values()) {
+        linter.references("return", template, &defined);
+    }
+    linter.warnings
+}
+
+struct Linter<'a> {
+    sparks: &'a HashMap<String, Spark>,
+    warnings: Vec<LintWarning>,
+}
+
+impl Linter<'_> {
+    /// Lint `operations` stored as `{prefix}N`, adding what they store to `defined`
+    fn operations(
+        &mut self,
+        operations: &[Operation],
+        prefix: &str,
+        defined: &mut HashSet<String>,
+    ) {
+        let mut failed: Option<String> = None;
+
+        for (i, operation) in operations.iter().enumerate() {
+            let key = format!("{}{}", prefix, i);
+            if let Some(after) = &failed {
+                self.warnings.push(LintWarning::Unreachable {
+                    operation: key,
+                    after: after.clone(),
+                });
+                continue;
+            }
+
+            for template in templates(operation) {
+                self.references(&key, template, defined);
+            }
+
+            let moves_amount = match operation.op_type.as_str() {
+                "transfer" => !operation.sled.unwrap_or(false),
+                "counter" => true,
+                _ => false,
+            };
+            if moves_amount && operation.amount.as_ref().is_some_and(is_not_positive) {
+                self.warnings.push(LintWarning::ZeroAmount {
+                    operation: key.clone(),
+                });
+                failed = Some(key.clone());
+            } else if let Some(reason) = self.failure(operation) {
+                self.warnings.push(LintWarning::AlwaysFails {
+                    operation: key.clone(),
+                    reason,
+                });
+                failed = Some(key.clone());
+            }
+
+            if operation.op_type == "upsert" {
+                defined.insert(format!("{}_exists", key));
+                let mut stored = HashSet::new();
+                for branch in [&operation.create, &operation.update].into_iter().flatten() {
+                    let mut branch_defined = defined.clone();
+                    self.operations(branch, &format!("{}_", key), &mut branch_defined);
+                    stored.extend(branch_defined);
+                }
+                defined.extend(stored);
+            }
+            defined.insert(key);
+            defined.extend(operation.store_as.iter().cloned());
+        }
+    }
+
+    /// Warn about every placeholder of `template` that is not in `defined`
+    fn references(&mut self, operation: &str, template: &str, defined: &HashSet<String>) {
+        for name in placeholders(template) {
+            // `{op_0.status}` needs `op_0`
+            let base = name.split('.').next().unwrap_or(name);
+            if !defined.contains(name) && !defined.contains(base) {
+                self.warnings.push(LintWarning::UndefinedValue {
+                    operation: operation.to_string(),
+                    name: name.to_string(),
+                });
+            }
+        }
+    }
+
+    /// Why `operation` can never succeed, if it cannot
+    fn failure(&self, operation: &Operation) -> Option<String> {
+        let o = operation;
+        let required: &[(&str, bool)] = match o.op_type.as_str() {
+            "transfer" => &[
+                ("zik", o.zik.is_some()),
+                ("zak", o.zak.is_some()),
+                ("amount", o.amount.is_some()),
+            ],
+            "balance" | "get_metadata" | "read_text" => &[("account", o.account.is_some())],
+            "reserve" => &[
+                ("account", o.account.is_some()),
+                ("to", o.to.is_some()),
+                ("amount", o.amount.is_some()),
+            ],
+            "set_status" | "hash_field" => &[
+                ("account", o.account.is_some()),
+                ("amount", o.amount.is_some()),
+            ],
+            "counter" => &[
+                ("account", o.account.is_some()),
+                ("op", o.op.is_some()),
+                ("amount", o.amount.is_some()),
+            ],
+            "await_balance" => &[
+                ("account", o.account.is_some()),
+                ("condition", o.condition.is_some()),
+                ("timeout", o.timeout.is_some()),
+            ],
+            "compute" => &[("expression", o.expression.is_some())],
+            "delete" | "upsert" => &[("entity", o.entity.is_some())],
+            "call_spark" => &[("spark", o.spark.is_some())],
+            other => return Some(format!("unknown operation type '{}'", other)),
+        };
+
+        if let Some((field, _)) = required.iter().find(|(_, present)| !present) {
+            return Some(format!("missing '{}' field", field));
+        }
+        if o.op_type == "get_metadata" && o.field.is_none() {
+            return Some("missing 'field' field".to_string());
+        }
+        if let Some(op) =
+            o.op.as_deref()
+                .filter(|op| o.op_type == "counter" && !matches!(*op, "inc" | "dec"))
+        {
+            return Some(format!("counter op '{}' is not \"inc\" or \"dec\"", op));
+        }
+        match &o.spark {
+            Some(spark) if o.op_type == "call_spark" && !self.sparks.contains_key(spark) => {
+                Some(format!("spark '{}' is not loaded", spark))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Every interpolated string of `operation`
+fn templates(operation: &Operation) -> Vec<&str> {
+    let mut templates: Vec<&str> = [
+        &operation.zik,
+        &operation.zak,
+        &operation.account,
+        &operation.entity,
+        &operation.to,
+        &operation.expression,
+    ]
+    .into_iter()
+    .flatten()
+    .map(String::as_str)
+    .collect();
+
+    for value in [&operation.amount, &operation.min, &operation.max] {
+        if let Some(Value::String(template)) = value {
+            templates.push(template);
+        }
+    }
+    templates.extend(
+        operation
+            .metadata
+            .iter()
+            .flat_map(|m| m.values().map(String::as_str)),
+    );
+    templates.extend(
+        operation
+            .args
+            .iter()
+            .flat_map(|args| args.values())
+            .filter_map(Value::as_str),
+    );
+    templates
+}
+
+/// Names of the `{name}` placeholders in `template`
+fn placeholders(template: &str) -> Vec<&str> {
+    template_parts(template)
+        .into_iter()
+        .filter_map(|part| match part {
+            TemplatePart::Placeholder(name) => Some(name),
+            TemplatePart::Text(_) => None,
+        })
+        .collect()
+}
+
+/// A literal amount a numeric transfer always rejects
+fn is_not_positive(amount: &Value) -> bool {
+    match amount {
+        Value::Number(n) => n.as_f64().is_some_and(|n| n <= 0.0),
+        Value::Bool(b) => !b,
+        Value::String(s) => s.trim().parse::<i64>().is_ok_and(|n| n <= 0) || s == "false",
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sparks(definitions: Value) -> HashMap<String, Spark> {
+        serde_json::from_value(definitions).expect("valid spark definitions")
+    }
+
+    #[test]
+    fn test_operations_after_a_certain_failure_are_unreachable() {
+        let sparks = sparks(json!({
+            "checkout": {
+                "description": "Charge, but the stock check can never pass",
+                "inputs": ["id", "price"],
+                "operations": [
+                    { "type": "balance", "on_fail": "return" },
+                    { "type": "transfer", "zik": "user:{id}:balance", "zak": "shop:revenue", "amount": "{price}" },
+                    { "type": "transfer", "zik": "system:genesis", "zak": "order:{id}:paid", "amount": 1 }
+                ]
+            }
+        }));
+
+        assert_eq!(
+            lint(&sparks, "checkout"),
+            vec![
+                LintWarning::AlwaysFails {
+                    operation: "op_0".to_string(),
+                    reason: "missing 'account' field".to_string()
+                },
+                LintWarning::Unreachable {
+                    operation: "op_1".to_string(),
+                    after: "op_0".to_string()
+                },
+                LintWarning::Unreachable {
+                    operation: "op_2".to_string(),
+                    after: "op_0".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_counter_checks() {
+        let sparks = sparks(json!({
+            "restock": {
+                "description": "Counter with a typo'd op and an unknown floor",
+                "inputs": ["id"],
+                "operations": [
+                    { "type": "counter", "account": "product:{id}:stock", "op": "dec", "amount": 1, "min": "{floor}" },
+                    { "type": "counter", "account": "product:{id}:stock", "op": "add", "amount": 1 }
+                ]
+            }
+        }));
+
+        assert_eq!(
+            lint(&sparks, "restock"),
+            vec![
+                LintWarning::UndefinedValue {
+                    operation: "op_0".to_string(),
+                    name: "floor".to_string()
+                },
+                LintWarning::AlwaysFails {
+                    operation: "op_1".to_string(),
+                    reason: "counter op 'add' is not \"inc\" or \"dec\"".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undefined_values_and_zero_amounts() {
+        let sparks = sparks(json!({
+            "price": {
+                "description": "Uses {tax} before computing it, and a typo",
+                "inputs": ["id", "price"],
+                "operations": [
+                    { "type": "transfer", "zik": "system:genesis", "zak": "product:{id}:tax", "amount": "{tax}" },
+                    { "type": "compute", "expression": "{price} * 7 / 100", "store_as": "tax" },
+                    {
+                        "type": "upsert",
+                        "entity": "product:{id}",
+                        "create": [{ "type": "transfer", "zik": "system:genesis", "zak": "product:{id}:existence", "amount": 1 }],
+                        "update": [{ "type": "compute", "expression": "{op_2_exists} + {tax}" }]
+                    },
+                    { "type": "call_spark", "spark": "missing", "args": { "id": "{idd}" } }
+                ],
+                "return": { "tax": "{tax}:number", "created": "{op_2}", "new": "{op_2_0}", "gone": "{nope}" }
+            },
+            "reset": {
+                "description": "Zeroes a counter the wrong way",
+                "inputs": ["id"],
+                "operations": [
+                    { "type": "transfer", "zik": "system:genesis", "zak": "counter:{id}", "amount": 0 },
+                    { "type": "transfer", "zik": "system:genesis", "zak": "counter:{id}:name", "amount": "0", "sled": true }
+                ]
+            }
+        }));
+
+        let undefined = |operation: &str, name: &str| LintWarning::UndefinedValue {
+            operation: operation.to_string(),
+            name: name.to_string(),
+        };
+        assert_eq!(
+            lint(&sparks, "price"),
+            vec![
+                undefined("op_0", "tax"),
+                undefined("op_3", "idd"),
+                LintWarning::AlwaysFails {
+                    operation: "op_3".to_string(),
+                    reason: "spark 'missing' is not loaded".to_string()
+                },
+                undefined("return", "nope"),
+            ]
+        );
+
+        assert_eq!(
+            lint(&sparks, "reset"),
+            vec![
+                LintWarning::ZeroAmount {
+                    operation: "op_0".to_string()
+                },
+                LintWarning::Unreachable {
+                    operation: "op_1".to_string(),
+                    after: "op_0".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            lint(&sparks, "nope"),
+            vec![LintWarning::SparkNotFound {
+                spark: "nope".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(placeholders("user:{id}:balance"), vec!["id"]);
+        assert_eq!(
+            placeholders("{price} + {op_0.status} {} {not a name}"),
+            vec!["price", "op_0.status"]
+        );
+        assert!(placeholders("{{id}}").is_empty());
+        assert_eq!(placeholders("{{{id}}}"), vec!["id"]);
+        assert!(placeholders("hash({name").is_empty());
+    }
+
+    #[test]
+    fn test_literal_amounts() {
+        assert!(is_not_positive(&Value::from(0)));
+        assert!(is_not_positive(&Value::from(-5)));
+        assert!(is_not_positive(&Value::from("0")));
+        assert!(is_not_positive(&Value::from(false)));
+        assert!(!is_not_positive(&Value::from(1)));
+        assert!(!is_not_positive(&Value::from("{price}")));
+        assert!(!is_not_positive(&Value::from("{price} - 0")));
+    }
+}
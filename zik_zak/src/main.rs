@@ -3,19 +3,233 @@
 //! The simplest backend server ever created.
 //! Pure accounting replaces your entire tech stack.
 
+mod correlation;
+#[cfg(feature = "graphql")]
+mod graphql;
+#[cfg(feature = "openapi")]
+mod openapi;
+mod rate_limit;
+mod validation;
+#[cfg(feature = "realtime")]
+mod websocket;
+
 use anyhow::Result;
-use axum::{response::Json, routing::get, Router};
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderName, StatusCode},
+    middleware,
+    response::{IntoResponse, Json},
+    routing::{get, post},
+    Router,
+};
+use futures::{stream, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio;
+use tokio::sync::RwLock;
+use tower_http::compression::predicate::{DefaultPredicate, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
 use tracing::info;
+use zik_zak::{
+    AccountNameStore, BalanceCacheStats, BatchTransfer, SparkEngine, TransferFilter, ZikZak,
+    ZikZakEngine, ZikZakError, RUN_ID_KEY,
+};
+
+use rate_limit::RateLimiter;
+use validation::{FieldError, Valid, Validate};
+
+/// How often the background task checks that TigerBeetle is reachable
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Transfers copied out per lock of the engine while exporting
+const EXPORT_CHUNK: usize = 1000;
+
+/// Number of transfers matching a `/transactions` query, across all pages
+const TOTAL_COUNT: HeaderName = HeaderName::from_static("x-total-count");
+
+/// Responses smaller than this are sent uncompressed
+const COMPRESSION_MIN_BYTES: u16 = 1024;
+
+/// What `/query` can return besides the account name
+const QUERY_FIELDS: [&str; 2] = ["balance", "metadata"];
+
+/// Most transfers one `POST /transfers/batch` may hold
+const MAX_TRANSFER_BATCH: usize = 1000;
+
+/// Shared server state - one engine behind a read-write lock
+///
+/// Reads share the lock and run side by side; only what writes to the ledger
+/// (igniting a spark, GraphQL mutations) takes it exclusively.
+#[derive(Clone)]
+pub struct AppState {
+    pub engine: Arc<RwLock<ZikZakEngine>>,
+    /// Sparks served by `POST /sparks/{name}`; igniting only reads them
+    pub sparks: Arc<SparkEngine>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
     pub message: String,
     pub manifesto: String,
+    pub tigerbeetle_connected: bool,
+}
+
+/// Counters served by `/metrics`
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MetricsResponse {
+    /// Balance cache lookups; all misses unless `ZIK_ZAK_BALANCE_CACHE_TTL_MS` is set
+    pub balance_cache: BalanceCacheStats,
+    /// Hook events dropped because the hook queue was full
+    pub dropped_hook_events: u64,
+}
+
+/// Query string of `/query`
+#[derive(Debug, Deserialize)]
+#[cfg_attr(
+    feature = "openapi",
+    derive(utoipa::IntoParams),
+    into_params(parameter_in = Query)
+)]
+pub struct AccountQuery {
+    /// Account pattern, `*` standing for one segment, e.g. `user:123:*`
+    pub pattern: String,
+    /// Comma-separated `balance` and/or `metadata` (default both)
+    pub fields: Option<String>,
+}
+
+/// What `POST /sparks/{name}` produced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct IgniteSparkResponse {
+    /// The spark's returned values
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub result: serde_json::Value,
+    /// Ids of the transfers the spark made, in order
+    pub transfer_ids: Vec<String>,
+    pub execution_time_ms: u64,
+}
+
+/// Body of `POST /transfers`
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransferRequest {
+    pub from: String,
+    pub to: String,
+    /// Positive, in the ledger's minor units
+    pub amount: i64,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl Validate for TransferRequest {
+    const REQUIRED: &'static [&'static str] = &["from", "to", "amount"];
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        for (field, account) in [("from", &self.from), ("to", &self.to)] {
+            if account.is_empty() {
+                errors.push(FieldError::new(field, "must not be empty"));
+            }
+        }
+        if self.amount <= 0 {
+            errors.push(FieldError::new("amount", "must be positive"));
+        }
+        if !self.from.is_empty() && self.from == self.to {
+            errors.push(FieldError::new("to", "must differ from `from`"));
+        }
+        errors
+    }
+}
+
+/// Body of `POST /transfers/batch`
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransferBatchRequest {
+    /// At most 1000, each committed or refused on its own
+    pub transfers: Vec<TransferRequest>,
+}
+
+impl Validate for TransferBatchRequest {
+    const REQUIRED: &'static [&'static str] = &["transfers"];
+
+    fn validate(&self) -> Vec<FieldError> {
+        if self.transfers.is_empty() {
+            return vec![FieldError::new("transfers", "must not be empty")];
+        }
+        if self.transfers.len() > MAX_TRANSFER_BATCH {
+            return vec![FieldError::new(
+                "transfers",
+                format!("must hold at most {} transfers", MAX_TRANSFER_BATCH),
+            )];
+        }
+        self.transfers
+            .iter()
+            .enumerate()
+            .flat_map(|(i, transfer)| {
+                transfer.validate().into_iter().map(move |error| {
+                    FieldError::new(&format!("transfers[{}].{}", i, error.field), error.problem)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Body of `POST /balances`
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BalanceRequest {
+    pub accounts: Vec<String>,
+}
+
+impl Validate for BalanceRequest {
+    const REQUIRED: &'static [&'static str] = &["accounts"];
+
+    fn validate(&self) -> Vec<FieldError> {
+        if self.accounts.is_empty() {
+            return vec![FieldError::new("accounts", "must not be empty")];
+        }
+        self.accounts
+            .iter()
+            .enumerate()
+            .filter(|(_, account)| account.is_empty())
+            .map(|(i, _)| FieldError::new(&format!("accounts[{}]", i), "must not be empty"))
+            .collect()
+    }
+}
+
+/// What `POST /transfers` committed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransferResponse {
+    pub id: String,
+}
+
+/// What happened to one transfer of `POST /transfers/batch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransferBatchResult {
+    /// Id of the committed transfer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Why the transfer was refused
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What `POST /transfers/batch` did, one result per transfer in request order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransferBatchResponse {
+    pub results: Vec<TransferBatchResult>,
 }
 
 #[tokio::main]
@@ -27,11 +241,54 @@ async fn main() -> Result<()> {
 
     info!("🦖 Starting ZIK_ZAK Revolution Server");
 
+    let namespace = std::env::var("ZIK_ZAK_NAMESPACE").unwrap_or_default();
+    let mut engine = ZikZakEngine::new(&namespace).await?;
+    // Account names outlive restarts, so listings show names rather than ids
+    let names_path =
+        std::env::var("ZIK_ZAK_NAMES_DB").unwrap_or_else(|_| "./zik_zak_names.db".to_string());
+    engine.set_account_name_store(AccountNameStore::new(&names_path)?)?;
+    // Text written by sparks lives in its own SLED database
+    let sparks_db =
+        std::env::var("ZIK_ZAK_SPARKS_DB").unwrap_or_else(|_| "./zik_zak_sparks.db".to_string());
+    let sparks = match std::env::var("ZIK_ZAK_SPARKS") {
+        Ok(sparks_file) => SparkEngine::new(&sparks_file, &sparks_db)?,
+        Err(_) => SparkEngine::empty(&sparks_db)?,
+    };
+    let state = AppState {
+        engine: Arc::new(RwLock::new(engine)),
+        sparks: Arc::new(sparks),
+    };
+    ZikZakEngine::spawn_connection_monitor(Arc::clone(&state.engine), PING_INTERVAL);
+
+    let limiter = Arc::new(RateLimiter::from_env());
+    limiter.spawn_eviction();
+
     // Build our application with routes
-    let app = Router::new()
-        .route("/", get(revolution_manifesto))
-        .route("/health", get(health_check))
-        .layer(CorsLayer::permissive());
+    let app = api_routes();
+
+    #[cfg(feature = "openapi")]
+    let app = app.merge(openapi::routes());
+
+    #[cfg(feature = "graphql")]
+    let app = app.merge(graphql::routes(state.clone()));
+
+    #[cfg(feature = "realtime")]
+    let app = app.merge(websocket::routes(
+        websocket::transfer_events(&state).await,
+        websocket::expiry_events(&state),
+    ));
+
+    let app = app
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&limiter),
+            rate_limit::rate_limit,
+        ))
+        // Outside the rate limit so refusals carry an id too, inside compression
+        // so error bodies are still plain when it adds the id to them
+        .layer(middleware::from_fn(correlation::correlate))
+        .layer(compression())
+        .layer(CorsLayer::permissive())
+        .with_state(state);
 
     // Start server
     let port = std::env::var("PORT").unwrap_or_else(|_| "3002".to_string());
@@ -45,12 +302,48 @@ async fn main() -> Result<()> {
     info!("💀 Backend development is officially DEAD");
     info!("⚡ Pure accounting has replaced your entire tech stack");
 
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
 
+/// Every REST endpoint, before the optional docs/GraphQL routes and middleware
+fn api_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(revolution_manifesto))
+        .route("/health", get(health_check))
+        .route("/metrics", get(metrics))
+        .route("/query", get(query_accounts))
+        .route("/transactions", get(list_transactions))
+        .route("/transfers/export", get(export_transfers))
+        .route("/transfers", post(create_transfer))
+        .route("/transfers/batch", post(create_transfer_batch))
+        .route("/balances", post(get_balances))
+        .route("/sparks/:name", post(ignite_spark))
+}
+
+/// gzip or deflate, whichever the client's `Accept-Encoding` prefers
+///
+/// Streamed bodies like `/transfers/export` have no known size and are always
+/// compressed when asked for.
+fn compression() -> CompressionLayer<impl Predicate> {
+    CompressionLayer::new()
+        .compress_when(DefaultPredicate::new().and(SizeAbove::new(COMPRESSION_MIN_BYTES)))
+}
+
 // Revolution manifesto endpoint
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/",
+    responses(
+        (status = 200, description = "The manifesto and a map of the endpoints", body = Object),
+        (status = 429, description = "Rate limit exceeded, see `Retry-After`")
+    )
+))]
 async fn revolution_manifesto() -> Json<serde_json::Value> {
     Json(serde_json::json!({
         "message": "🦖 Welcome to the ZIK_ZAK Revolution",
@@ -59,18 +352,834 @@ async fn revolution_manifesto() -> Json<serde_json::Value> {
         "truth": "Backend development is dead. We killed it with divine sparks.",
         "endpoints": {
             "/health": "Check if the revolution is alive",
+            "/metrics": "Balance cache hits and misses, dropped hook events",
+            "/query": "Accounts matching a pattern such as user:123:* (pattern, fields)",
+            "/transactions": "Recent transfers, newest first (limit, offset, account, since, until)",
+            "/transfers/export": "Every transfer, oldest first, as NDJSON",
+            "/transfers": "POST {from, to, amount, metadata} to move value between accounts",
+            "/transfers/batch": "POST {transfers} to make up to 1000 transfers in one TigerBeetle request",
+            "/balances": "POST {accounts} for the net balance of each",
+            "/sparks/{name}": "POST {zik, zak} to ignite a spark loaded from ZIK_ZAK_SPARKS",
+            "/openapi.json": "OpenAPI document (Swagger UI at /docs)",
             "/": "The revolution manifesto"
         }
     }))
 }
 
-async fn health_check() -> Json<HealthResponse> {
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/health",
+    responses(
+        (status = 200, description = "TigerBeetle is reachable", body = HealthResponse),
+        (status = 503, description = "Degraded: TigerBeetle is unreachable", body = HealthResponse),
+        (status = 429, description = "Rate limit exceeded, see `Retry-After`")
+    )
+))]
+async fn health_check(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let connected = state.engine.read().await.is_connected();
+
+    let (code, status, message) = if connected {
+        (
+            StatusCode::OK,
+            "🦖 REVOLUTIONARY",
+            "The revolution is alive and well!",
+        )
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "🩹 DEGRADED",
+            "TigerBeetle is unreachable - transfers are failing fast until it returns",
+        )
+    };
+
     let health = HealthResponse {
-        status: "🦖 REVOLUTIONARY".to_string(),
+        status: status.to_string(),
         version: zik_zak::VERSION.to_string(),
-        message: "The revolution is alive and well!".to_string(),
+        message: message.to_string(),
         manifesto: "Backend development is DEAD! 💀".to_string(),
+        tigerbeetle_connected: connected,
+    };
+
+    (code, Json(health))
+}
+
+/// Engine counters since the server started
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Current counters", body = MetricsResponse),
+        (status = 429, description = "Rate limit exceeded, see `Retry-After`")
+    )
+))]
+async fn metrics(State(state): State<AppState>) -> Json<MetricsResponse> {
+    let engine = state.engine.read().await;
+    Json(MetricsResponse {
+        balance_cache: engine.balance_cache_stats(),
+        dropped_hook_events: engine.dropped_hook_events(),
+    })
+}
+
+/// Accounts matching a pattern, with the selected fields
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/query",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "Matching accounts by name: `account` plus the selected fields", body = [Object]),
+        (status = 400, description = "Malformed or too broad pattern, or an unknown field"),
+        (status = 429, description = "Rate limit exceeded, see `Retry-After`")
+    )
+))]
+async fn query_accounts(
+    State(state): State<AppState>,
+    Query(query): Query<AccountQuery>,
+) -> Result<Json<Vec<serde_json::Value>>, (StatusCode, String)> {
+    let fields: Vec<&str> = match &query.fields {
+        None => QUERY_FIELDS.to_vec(),
+        Some(list) => list
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .collect(),
     };
+    if let Some(unknown) = fields.iter().find(|f| !QUERY_FIELDS.contains(f)) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Unknown field {:?}, expected one of {:?}",
+                unknown, QUERY_FIELDS
+            ),
+        ));
+    }
+
+    let matches = state
+        .engine
+        .read()
+        .await
+        .find_accounts(&query.pattern)
+        .await
+        .map_err(|e| match e.downcast_ref::<ZikZakError>() {
+            Some(ZikZakError::InvalidPattern { .. }) => (StatusCode::BAD_REQUEST, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+
+    let mut rows = Vec::with_capacity(matches.len());
+    for found in matches {
+        let mut row = serde_json::json!({ "account": found.account });
+        if fields.contains(&"balance") {
+            row["balance"] = found.balance.into();
+        }
+        if fields.contains(&"metadata") {
+            row["metadata"] = serde_json::json!(found.metadata);
+        }
+        rows.push(row);
+    }
+    Ok(Json(rows))
+}
+
+/// One page of the transfer log, newest first
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/transactions",
+    params(TransferFilter),
+    responses(
+        (status = 200, description = "Matching transfers, newest first", body = [zik_zak::Transfer],
+            headers(("x-total-count" = usize, description = "Matching transfers across all pages"))),
+        (status = 429, description = "Rate limit exceeded, see `Retry-After`")
+    )
+))]
+async fn list_transactions(
+    State(state): State<AppState>,
+    Query(filter): Query<TransferFilter>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let engine = state.engine.read().await;
+    let total = engine.count_transfers(&filter);
+    let transfers = engine
+        .query_transfers(filter)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(([(TOTAL_COUNT, total.to_string())], Json(transfers)))
+}
+
+/// Stream the whole transfer log as `application/x-ndjson`
+///
+/// The engine is locked per chunk rather than for the whole response, so a slow
+/// client does not block transfers. The log is append-only, so chunking by offset
+/// keeps the output chronological.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/transfers/export",
+    responses(
+        (status = 200, description = "One JSON transfer per line, oldest first",
+            content_type = "application/x-ndjson", body = zik_zak::Transfer),
+        (status = 429, description = "Rate limit exceeded, see `Retry-After`")
+    )
+))]
+async fn export_transfers(State(state): State<AppState>) -> impl IntoResponse {
+    let lines = stream::unfold(0, move |offset| {
+        let engine = Arc::clone(&state.engine);
+        async move {
+            let engine = engine.read().await;
+            let chunk: Vec<_> = engine
+                .stream_transfers()
+                .skip(offset)
+                .take(EXPORT_CHUNK)
+                .collect()
+                .await;
+
+            if chunk.is_empty() {
+                None
+            } else {
+                let next = offset + chunk.len();
+                Some((stream::iter(chunk), next))
+            }
+        }
+    })
+    .flatten()
+    .map(|transfer| {
+        let transfer = transfer.map_err(std::io::Error::other)?;
+        let mut line = serde_json::to_string(&transfer)?;
+        line.push('\n');
+        Ok::<_, std::io::Error>(line)
+    });
+
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+}
+
+/// Move `amount` between two accounts
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/transfers",
+    request_body = TransferRequest,
+    responses(
+        (status = 200, description = "The transfer was committed", body = TransferResponse),
+        (status = 400, description = "Missing or invalid fields", body = validation::ValidationErrors),
+        (status = 422, description = "The transfer was refused, e.g. for an insufficient balance"),
+        (status = 429, description = "Rate limit exceeded, see `Retry-After`")
+    )
+))]
+async fn create_transfer(
+    State(state): State<AppState>,
+    Valid(request): Valid<TransferRequest>,
+) -> Result<Json<TransferResponse>, (StatusCode, String)> {
+    let id = state
+        .engine
+        .write()
+        .await
+        .transfer(
+            request.from.as_str(),
+            request.to.as_str(),
+            request.amount,
+            request.metadata,
+        )
+        .await
+        .map_err(refusal)?;
+    Ok(Json(TransferResponse { id }))
+}
+
+/// Make many transfers in one TigerBeetle request
+///
+/// Transfers are independent: one being refused does not stop the others, so the
+/// answer is `200` with a result per transfer unless TigerBeetle is unreachable.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/transfers/batch",
+    request_body = TransferBatchRequest,
+    responses(
+        (status = 200, description = "One result per transfer, in request order", body = TransferBatchResponse),
+        (status = 400, description = "Missing or invalid fields, or more than 1000 transfers", body = validation::ValidationErrors),
+        (status = 429, description = "Rate limit exceeded, see `Retry-After`")
+    )
+))]
+async fn create_transfer_batch(
+    State(state): State<AppState>,
+    Valid(request): Valid<TransferBatchRequest>,
+) -> Result<Json<TransferBatchResponse>, (StatusCode, String)> {
+    let transfers = request
+        .transfers
+        .into_iter()
+        .map(|t| BatchTransfer {
+            from_account: t.from,
+            to_account: t.to,
+            amount: t.amount,
+            metadata: t.metadata,
+        })
+        .collect();
+    let results = state
+        .engine
+        .write()
+        .await
+        .transfer_each(transfers)
+        .await
+        .map_err(refusal)?
+        .into_iter()
+        .map(|result| match result {
+            Ok(id) => TransferBatchResult {
+                id: Some(id),
+                error: None,
+            },
+            Err(error) => TransferBatchResult {
+                id: None,
+                error: Some(error),
+            },
+        })
+        .collect();
+    Ok(Json(TransferBatchResponse { results }))
+}
+
+/// Net balances of several accounts at once
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/balances",
+    request_body = BalanceRequest,
+    responses(
+        (status = 200, description = "Account -> net balance", body = HashMap<String, i64>),
+        (status = 400, description = "Missing or invalid fields", body = validation::ValidationErrors),
+        (status = 404, description = "One of the accounts does not exist"),
+        (status = 429, description = "Rate limit exceeded, see `Retry-After`")
+    )
+))]
+async fn get_balances(
+    State(state): State<AppState>,
+    Valid(request): Valid<BalanceRequest>,
+) -> Result<Json<HashMap<String, i64>>, (StatusCode, String)> {
+    let engine = state.engine.read().await;
+    let mut balances = HashMap::with_capacity(request.accounts.len());
+    for account in request.accounts {
+        let balance = engine
+            .get_balance(account.as_str())
+            .await
+            .map_err(refusal)?;
+        balances.insert(account, balance);
+    }
+    Ok(Json(balances))
+}
+
+/// Status and message for an engine error: typed refusals are the client's problem
+fn refusal(e: anyhow::Error) -> (StatusCode, String) {
+    match e.downcast_ref::<ZikZakError>() {
+        Some(ZikZakError::ConditionFailed {
+            error_code: Some(code),
+            ..
+        }) => (
+            // Only error statuses; anything else would report the failure as a success
+            StatusCode::from_u16(*code)
+                .ok()
+                .filter(|status| status.is_client_error() || status.is_server_error())
+                .unwrap_or(StatusCode::UNPROCESSABLE_ENTITY),
+            e.to_string(),
+        ),
+        Some(ZikZakError::AccountNotFound { .. }) => (StatusCode::NOT_FOUND, e.to_string()),
+        Some(
+            ZikZakError::ConditionFailed { .. }
+            | ZikZakError::InsufficientBalance { .. }
+            | ZikZakError::LimitExceeded { .. }
+            | ZikZakError::AccountFrozen { .. }
+            | ZikZakError::CurrencyMismatch { .. }
+            | ZikZakError::InvalidTransition { .. }
+            | ZikZakError::InvalidContent { .. }
+            | ZikZakError::InvalidAccountName { .. },
+        ) => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Ignite a spark and report its result and the transfers it made
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/sparks/{name}",
+    params(("name" = String, Path, description = "Spark to ignite")),
+    request_body(content = Object, description = "`{\"zik\": {...}, \"zak\": {...}}` inputs"),
+    responses(
+        (status = 200, description = "The spark ran to completion", body = IgniteSparkResponse),
+        (status = 404, description = "No spark with this name is loaded"),
+        (status = 422, description = "The spark was refused, e.g. for an insufficient balance; a failed `balance` check answers with its `error_code` instead when it has one"),
+        (status = 429, description = "Rate limit exceeded, see `Retry-After`")
+    )
+))]
+async fn ignite_spark(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(zikzak): Json<ZikZak>,
+) -> Result<Json<IgniteSparkResponse>, (StatusCode, String)> {
+    if state.sparks.get_spark(&name).is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("Unknown spark {:?}", name)));
+    }
+
+    let from = state
+        .engine
+        .read()
+        .await
+        .get_transfer_count()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // The lock is let go while an `await_balance` waits, so others' transfers
+    // may interleave with the spark's; its run id picks out its own
+    let started = Instant::now();
+    let (result, run_id) = state
+        .sparks
+        .ignite_spark_locked(&name, zikzak, &state.engine)
+        .await
+        .map_err(refusal)?;
+    let execution_time_ms = started.elapsed().as_millis() as u64;
+
+    let transfer_ids = state
+        .engine
+        .read()
+        .await
+        .transfers_since(from)
+        .iter()
+        .filter(|transfer| transfer.metadata.get(RUN_ID_KEY) == Some(&run_id))
+        .map(|transfer| transfer.id.clone())
+        .collect();
+    let result = serde_json::to_value(result.0)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(IgniteSparkResponse {
+        result,
+        transfer_ids,
+        execution_time_ms,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    async fn post_json(
+        app: Router,
+        uri: &str,
+        body: serde_json::Value,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    /// `engine` with no sparks loaded; their SLED database goes in `sparks_dir`
+    fn state(engine: ZikZakEngine, sparks_dir: &std::path::Path) -> AppState {
+        AppState {
+            engine: Arc::new(RwLock::new(engine)),
+            sparks: Arc::new(SparkEngine::empty(sparks_dir.join("sparks.db")).unwrap()),
+        }
+    }
+
+    /// Requires TigerBeetle running as described in `tests/tigerbeetle_integration_test.rs`
+    #[tokio::test]
+    async fn test_query_endpoint_returns_accounts_under_prefix() {
+        let mut engine = ZikZakEngine::new("").await.unwrap();
+        engine.ensure_system_accounts().await.unwrap();
+
+        let shop = format!("shop{}", Uuid::new_v4().simple());
+        let metadata = HashMap::from([("source".to_string(), "query_test".to_string())]);
+        for (account, amount) in [("1:balance", 100), ("1:points", 7), ("2:balance", 5)] {
+            engine
+                .transfer(
+                    "system:genesis",
+                    &format!("{}:{}", shop, account),
+                    amount,
+                    metadata.clone(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let sparks_dir = tempfile::TempDir::new().unwrap();
+        let app = api_routes().with_state(state(engine, sparks_dir.path()));
+
+        let (status, body) = get_json(
+            app.clone(),
+            &format!("/query?pattern={}:1:*&fields=balance", shop),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!([
+                { "account": format!("{}:1:balance", shop), "balance": 100 },
+                { "account": format!("{}:1:points", shop), "balance": 7 },
+            ])
+        );
+
+        let (status, body) =
+            get_json(app.clone(), &format!("/query?pattern={}:*:balance", shop)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_array().unwrap().len(), 2);
+        assert_eq!(body[1]["balance"], 5);
+        assert_eq!(body[1]["metadata"]["source"], "query_test");
+
+        let (status, _) = get_json(app.clone(), "/query?pattern=*:1:balance").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) =
+            get_json(app, &format!("/query?pattern={}:1:*&fields=secrets", shop)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    /// Requires TigerBeetle running as described in `tests/tigerbeetle_integration_test.rs`
+    #[tokio::test]
+    async fn test_large_responses_are_gzipped() {
+        let mut engine = ZikZakEngine::new("").await.unwrap();
+        engine.ensure_system_accounts().await.unwrap();
+
+        let shop = format!("shop{}", Uuid::new_v4().simple());
+        for i in 0..200 {
+            engine
+                .transfer(
+                    "system:genesis",
+                    &format!("{}:{}:balance", shop, i),
+                    100,
+                    HashMap::new(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let sparks_dir = tempfile::TempDir::new().unwrap();
+        let app = api_routes()
+            .layer(compression())
+            .with_state(state(engine, sparks_dir.path()));
+        let get = |uri: String, encoding: Option<&str>| {
+            let mut request = Request::get(uri);
+            if let Some(encoding) = encoding {
+                request = request.header(header::ACCEPT_ENCODING, encoding);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        let body_len = |response: axum::response::Response| async {
+            axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap()
+                .len()
+        };
+
+        let ledger = format!("/query?pattern={}:*:balance", shop);
+        let plain = get(ledger.clone(), None).await.unwrap();
+        assert!(plain.headers().get(header::CONTENT_ENCODING).is_none());
+        let plain_len = body_len(plain).await;
+
+        let gzipped = get(ledger, Some("gzip")).await.unwrap();
+        assert_eq!(gzipped.headers()[header::CONTENT_ENCODING], "gzip");
+        let gzipped_len = body_len(gzipped).await;
+        assert!(
+            gzipped_len * 5 < plain_len,
+            "{} vs {}",
+            gzipped_len,
+            plain_len
+        );
+
+        // One account is below the threshold
+        let small = get(
+            format!("/query?pattern={}:7:*&fields=balance", shop),
+            Some("gzip, deflate"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(small.status(), StatusCode::OK);
+        assert!(small.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+
+    /// Requires TigerBeetle running as described in `tests/tigerbeetle_integration_test.rs`
+    #[tokio::test]
+    async fn test_ignite_spark_endpoint_reports_result_and_transfers() {
+        let mut engine = ZikZakEngine::new("").await.unwrap();
+        engine.ensure_system_accounts().await.unwrap();
+
+        let sparks_dir = tempfile::TempDir::new().unwrap();
+        let mut state = state(engine, sparks_dir.path());
+        let mut sparks = SparkEngine::empty(sparks_dir.path().join("fund.db")).unwrap();
+        sparks.add_spark(
+            "fund".to_string(),
+            serde_json::from_value(serde_json::json!({
+                "description": "Open a wallet with a starting balance",
+                "inputs": ["id", "amount"],
+                "operations": [
+                    { "type": "transfer", "zik": "system:genesis", "zak": "wallet:{id}:existence", "amount": 1 },
+                    { "type": "transfer", "zik": "system:genesis", "zak": "wallet:{id}:balance", "amount": "{amount}" }
+                ],
+                "return": { "wallet": "wallet:{id}" }
+            }))
+            .unwrap(),
+        );
+        state.sparks = Arc::new(sparks);
+        let app = api_routes().with_state(state);
+
+        let post = |uri: &str, body: serde_json::Value| {
+            app.clone().oneshot(
+                Request::post(uri)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+        };
+
+        let id = Uuid::new_v4().simple().to_string();
+        let response = post(
+            "/sparks/fund",
+            serde_json::json!({ "zik": { "id": id, "amount": 250 }, "zak": {} }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let ignited: IgniteSparkResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(ignited.result["wallet"], format!("wallet:{}", id));
+        assert_eq!(ignited.transfer_ids.len(), 2);
+
+        let unknown = post(
+            "/sparks/no_such_spark",
+            serde_json::json!({ "zik": {}, "zak": {} }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// Requires TigerBeetle running as described in `tests/tigerbeetle_integration_test.rs`
+    #[tokio::test]
+    async fn test_transfer_batch_endpoint_applies_every_transfer() {
+        let mut engine = ZikZakEngine::new("").await.unwrap();
+        engine.ensure_system_accounts().await.unwrap();
+
+        let sparks_dir = tempfile::TempDir::new().unwrap();
+        let state = state(engine, sparks_dir.path());
+        let app = api_routes().with_state(state.clone());
+
+        let shop = format!("shop{}", Uuid::new_v4().simple());
+        let transfers: Vec<_> = (0..100)
+            .map(|i| {
+                serde_json::json!({
+                    "from": "system:genesis",
+                    "to": format!("{}:{}:balance", shop, i),
+                    "amount": i + 1,
+                    "metadata": { "source": "batch_test" }
+                })
+            })
+            .collect();
+        let (status, body) = post_json(
+            app,
+            "/transfers/batch",
+            serde_json::json!({ "transfers": transfers }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let response: TransferBatchResponse = serde_json::from_value(body).unwrap();
+        assert_eq!(response.results.len(), 100);
+        assert!(response
+            .results
+            .iter()
+            .all(|result| result.id.is_some() && result.error.is_none()));
+
+        let engine = state.engine.read().await;
+        for i in 0..100 {
+            let account = format!("{}:{}:balance", shop, i);
+            assert_eq!(engine.get_balance(account.as_str()).await.unwrap(), i + 1);
+        }
+    }
+
+    /// Bodies are rejected before the handler (and TigerBeetle) is reached
+    fn validated_routes() -> Router {
+        Router::new()
+            .route(
+                "/transfers",
+                post(|Valid(request): Valid<TransferRequest>| async move { Json(request.amount) }),
+            )
+            .route(
+                "/transfers/batch",
+                post(|Valid(request): Valid<TransferBatchRequest>| async move {
+                    Json(request.transfers.len())
+                }),
+            )
+            .route(
+                "/balances",
+                post(|Valid(request): Valid<BalanceRequest>| async move { Json(request.accounts) }),
+            )
+    }
+
+    #[tokio::test]
+    async fn test_transfer_without_amount_is_rejected() {
+        let (status, body) = post_json(
+            validated_routes(),
+            "/transfers",
+            serde_json::json!({ "from": "system:genesis", "to": "user:1:balance" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "error": "Invalid request body",
+                "fields": [{ "field": "amount", "problem": "missing" }]
+            })
+        );
+
+        let (status, body) =
+            post_json(validated_routes(), "/transfers", serde_json::json!({})).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["fields"].as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_with_negative_amount_is_rejected() {
+        let (status, body) = post_json(
+            validated_routes(),
+            "/transfers",
+            serde_json::json!({ "from": "system:genesis", "to": "", "amount": -5 }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["fields"],
+            serde_json::json!([
+                { "field": "to", "problem": "must not be empty" },
+                { "field": "amount", "problem": "must be positive" }
+            ])
+        );
+
+        let (status, body) = post_json(
+            validated_routes(),
+            "/transfers",
+            serde_json::json!({ "from": "system:genesis", "to": "user:1:balance", "amount": 5 }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, 5);
+
+        let (status, body) = post_json(
+            validated_routes(),
+            "/balances",
+            serde_json::json!({ "accounts": ["user:1:balance", ""] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["fields"][0]["field"], "accounts[1]");
+    }
+
+    #[tokio::test]
+    async fn test_transfer_batch_is_validated_per_item_and_capped() {
+        let transfer =
+            serde_json::json!({ "from": "system:genesis", "to": "user:1:balance", "amount": 5 });
+
+        let (status, body) = post_json(
+            validated_routes(),
+            "/transfers/batch",
+            serde_json::json!({ "transfers": [transfer, { "from": "a", "to": "b", "amount": 0 }] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["fields"],
+            serde_json::json!([{ "field": "transfers[1].amount", "problem": "must be positive" }])
+        );
+
+        let (status, body) = post_json(
+            validated_routes(),
+            "/transfers/batch",
+            serde_json::json!({ "transfers": vec![transfer.clone(); MAX_TRANSFER_BATCH] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, MAX_TRANSFER_BATCH);
+
+        let (status, body) = post_json(
+            validated_routes(),
+            "/transfers/batch",
+            serde_json::json!({ "transfers": vec![transfer; MAX_TRANSFER_BATCH + 1] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["fields"][0]["field"], "transfers");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_reaches_transfers_and_error_responses() {
+        let engine = ZikZakEngine::with_backend("", zik_zak::MemoryBackend::new())
+            .await
+            .unwrap();
+        let sparks_dir = tempfile::TempDir::new().unwrap();
+        let state = state(engine, sparks_dir.path());
+        let app = api_routes()
+            .layer(middleware::from_fn(correlation::correlate))
+            .with_state(state.clone());
+        let transfer = |request_id: Option<&str>, amount: i64| {
+            let mut request =
+                Request::post("/transfers").header(header::CONTENT_TYPE, "application/json");
+            if let Some(request_id) = request_id {
+                request = request.header(&correlation::REQUEST_ID, request_id);
+            }
+            let body = serde_json::json!({
+                "from": "user:1:balance", "to": "shop:1:balance", "amount": amount
+            });
+            app.clone()
+                .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        };
+        state
+            .engine
+            .write()
+            .await
+            .transfer("system:genesis", "user:1:balance", 100, HashMap::new())
+            .await
+            .unwrap();
+
+        let response = transfer(Some("checkout-42"), 60).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[&correlation::REQUEST_ID], "checkout-42");
+        let engine = state.engine.read().await;
+        let made = engine.transfers_since(1);
+        assert_eq!(made.len(), 1);
+        assert_eq!(made[0].metadata[zik_zak::REQUEST_ID_KEY], "checkout-42");
+        drop(engine);
+
+        // Overdrawn: the refusal names the request a client should report
+        let response = transfer(Some("checkout-43"), 60).await.unwrap();
+        assert!(!response.status().is_success());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).ends_with("(request id: checkout-43)"));
 
-    Json(health)
+        // Without one from the client, the server makes one up
+        let response = transfer(None, 0).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let request_id = response.headers()[&correlation::REQUEST_ID]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(Uuid::parse_str(&request_id).is_ok());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["request_id"], request_id.as_str());
+    }
 }
@@ -0,0 +1,705 @@
+//! # 🧠 Memory Backend
+//!
+//! An [`AccountingBackend`] holding the whole ledger in memory, so a
+//! [`ZikZakEngine`](crate::ZikZakEngine) can be built for tests without a
+//! TigerBeetle cluster:
+//!
+//! ```ignore
+//! let clock = Arc::new(MockClock::new(1_700_000_000_000));
+//! let backend = MemoryBackend::new().with_clock(clock.clone());
+//! let mut engine = ZikZakEngine::with_backend("", backend).await?.with_clock(clock);
+//! ```
+//!
+//! It answers the way TigerBeetle does for everything the engine relies on:
+//! - balance flags: `ExceedsCredits` / `ExceedsDebits`, counting pending amounts
+//! - linked chains commit or fail as one, the rest reporting `LinkedEventFailed`
+//! - pending transfers are posted (in full or in part) or voided once, and
+//!   released when their timeout passes on the backend's clock
+//! - timestamps are unique nanoseconds that never go backwards; filters, limits
+//!   and `Reversed` apply to queries, and accounts with history keep their balances
+//!
+//! Balancing and closing transfers, imported events and the `ExistsWith…`
+//! distinctions for transfers are not modelled: reusing a transfer id is `Exists`.
+
+use std::collections::HashMap;
+use std::future::ready;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tigerbeetle::{
+    Account, AccountBalance, AccountFilter, AccountFilterFlags, AccountFlags, CreateAccountResult,
+    CreateTransferResult, NotFound, QueryFilter, QueryFilterFlags, Transfer, TransferFlags,
+};
+
+use crate::backend::{AccountingBackend, BackendFuture};
+use crate::clock::{Clock, SystemClock};
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    Open,
+    Posted,
+    Voided,
+    Expired,
+}
+
+/// Everything a cluster would store; cloned to roll back a failed chain
+#[derive(Clone, Default)]
+struct Ledger {
+    /// In creation order, which is timestamp order
+    accounts: Vec<Account>,
+    account_index: HashMap<u128, usize>,
+    /// In creation order, which is timestamp order
+    transfers: Vec<Transfer>,
+    transfer_index: HashMap<u128, usize>,
+    /// State of every pending transfer
+    pending: HashMap<u128, Pending>,
+    /// `(account id, transfer timestamp)` -> balances right after that transfer
+    history: HashMap<(u128, u64), AccountBalance>,
+    last_timestamp: u64,
+}
+
+/// Ledger in a `HashMap` that behaves like a TigerBeetle cluster, see the [module docs](self)
+pub struct MemoryBackend {
+    ledger: Mutex<Ledger>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryBackend {
+    /// An empty ledger on the system clock
+    pub fn new() -> Self {
+        Self {
+            ledger: Mutex::new(Ledger::default()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Timestamp events and expire pending transfers by `clock` instead of the system time
+    ///
+    /// Give the engine the same clock, so both agree on when a hold expires.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn now_nanos(&self) -> u64 {
+        self.clock.now_millis().saturating_mul(1_000_000)
+    }
+
+    /// The ledger as of now, pending transfers past their timeout released
+    fn ledger(&self) -> MutexGuard<'_, Ledger> {
+        let mut ledger = self.ledger.lock().unwrap();
+        ledger.expire(self.now_nanos());
+        ledger
+    }
+}
+
+/// Results with TigerBeetle's linked-chain outcomes
+trait ChainResult: Copy + PartialEq {
+    const OK: Self;
+    const LINKED_EVENT_FAILED: Self;
+    const CHAIN_OPEN: Self;
+}
+
+impl ChainResult for CreateAccountResult {
+    const OK: Self = CreateAccountResult::Ok;
+    const LINKED_EVENT_FAILED: Self = CreateAccountResult::LinkedEventFailed;
+    const CHAIN_OPEN: Self = CreateAccountResult::LinkedEventChainOpen;
+}
+
+impl ChainResult for CreateTransferResult {
+    const OK: Self = CreateTransferResult::Ok;
+    const LINKED_EVENT_FAILED: Self = CreateTransferResult::LinkedEventFailed;
+    const CHAIN_OPEN: Self = CreateTransferResult::LinkedEventChainOpen;
+}
+
+impl Ledger {
+    /// Unique and increasing, and no earlier than `now`
+    fn next_timestamp(&mut self, now: u64) -> u64 {
+        self.last_timestamp = (self.last_timestamp + 1).max(now);
+        self.last_timestamp
+    }
+
+    fn account(&self, id: u128) -> Option<&Account> {
+        self.account_index.get(&id).map(|&i| &self.accounts[i])
+    }
+
+    fn account_mut(&mut self, id: u128) -> &mut Account {
+        &mut self.accounts[self.account_index[&id]]
+    }
+
+    fn transfer(&self, id: u128) -> Option<&Transfer> {
+        self.transfer_index.get(&id).map(|&i| &self.transfers[i])
+    }
+
+    /// Apply `events` one linked chain at a time; a failing chain leaves no trace
+    fn apply_chains<E, R: ChainResult>(
+        &mut self,
+        events: &[E],
+        linked: impl Fn(&E) -> bool,
+        mut apply: impl FnMut(&mut Self, &E) -> R,
+    ) -> Vec<R> {
+        let mut results = Vec::with_capacity(events.len());
+        let mut start = 0;
+        while start < events.len() {
+            let (end, open) = match events[start..].iter().position(|e| !linked(e)) {
+                Some(last) => (start + last + 1, false),
+                None => (events.len(), true),
+            };
+            let snapshot = (end - start > 1 || open).then(|| self.clone());
+
+            let mut failure = None;
+            for (i, event) in events[start..end].iter().enumerate() {
+                let result = if open && start + i == end - 1 {
+                    R::CHAIN_OPEN
+                } else {
+                    apply(self, event)
+                };
+                if result != R::OK {
+                    failure = Some((i, result));
+                    break;
+                }
+            }
+
+            match failure {
+                None => results.extend(std::iter::repeat_n(R::OK, end - start)),
+                Some((failed_at, error)) => {
+                    if let Some(snapshot) = snapshot {
+                        *self = snapshot;
+                    }
+                    results.extend((0..end - start).map(|i| {
+                        if i == failed_at {
+                            error
+                        } else {
+                            R::LINKED_EVENT_FAILED
+                        }
+                    }));
+                }
+            }
+            start = end;
+        }
+        results
+    }
+
+    fn create_account(&mut self, account: &Account, now: u64) -> CreateAccountResult {
+        if let Some(existing) = self.account(account.id) {
+            return if existing.flags == account.flags {
+                CreateAccountResult::Exists
+            } else {
+                CreateAccountResult::ExistsWithDifferentFlags
+            };
+        }
+
+        let mut account = *account;
+        account.timestamp = self.next_timestamp(now);
+        self.account_index.insert(account.id, self.accounts.len());
+        self.accounts.push(account);
+        CreateAccountResult::Ok
+    }
+
+    fn create_transfer(&mut self, transfer: &Transfer, now: u64) -> CreateTransferResult {
+        use CreateTransferResult as R;
+
+        if self.transfer_index.contains_key(&transfer.id) {
+            return R::Exists;
+        }
+        let mut transfer = *transfer;
+        let post = transfer.flags.contains(TransferFlags::PostPendingTransfer);
+        let void = transfer.flags.contains(TransferFlags::VoidPendingTransfer);
+
+        // Posting and voiding take accounts, ledger and (by default) amount from the hold
+        let resolves = if post || void {
+            let Some(&pending) = self.transfer(transfer.pending_id) else {
+                return R::PendingTransferNotFound;
+            };
+            match self.pending.get(&pending.id) {
+                None => return R::PendingTransferNotPending,
+                Some(Pending::Posted) => return R::PendingTransferAlreadyPosted,
+                Some(Pending::Voided) => return R::PendingTransferAlreadyVoided,
+                Some(Pending::Expired) => return R::PendingTransferExpired,
+                Some(Pending::Open) => {}
+            }
+            transfer.amount = match transfer.amount {
+                _ if void => pending.amount,
+                0 | u128::MAX => pending.amount,
+                amount if amount > pending.amount => return R::ExceedsPendingTransferAmount,
+                amount => amount,
+            };
+            transfer.debit_account_id = pending.debit_account_id;
+            transfer.credit_account_id = pending.credit_account_id;
+            transfer.ledger = pending.ledger;
+            if transfer.code == 0 {
+                transfer.code = pending.code;
+            }
+            Some(pending)
+        } else {
+            None
+        };
+
+        let Some(&debit) = self.account(transfer.debit_account_id) else {
+            return R::DebitAccountNotFound;
+        };
+        let Some(&credit) = self.account(transfer.credit_account_id) else {
+            return R::CreditAccountNotFound;
+        };
+        if debit.id == credit.id {
+            return R::AccountsMustBeDifferent;
+        }
+        if debit.ledger != credit.ledger {
+            return R::AccountsMustHaveTheSameLedger;
+        }
+        if transfer.ledger != debit.ledger {
+            return R::TransferMustHaveTheSameLedgerAsAccounts;
+        }
+
+        let amount = transfer.amount;
+        match resolves {
+            Some(pending) => {
+                self.release(&pending);
+                if post {
+                    self.account_mut(debit.id).debits_posted += amount;
+                    self.account_mut(credit.id).credits_posted += amount;
+                }
+                let resolved = if post {
+                    Pending::Posted
+                } else {
+                    Pending::Voided
+                };
+                self.pending.insert(pending.id, resolved);
+            }
+            None => {
+                if debit
+                    .flags
+                    .contains(AccountFlags::DebitsMustNotExceedCredits)
+                    && debit.debits_pending + debit.debits_posted + amount > debit.credits_posted
+                {
+                    return R::ExceedsCredits;
+                }
+                if credit
+                    .flags
+                    .contains(AccountFlags::CreditsMustNotExceedDebits)
+                    && credit.credits_pending + credit.credits_posted + amount
+                        > credit.debits_posted
+                {
+                    return R::ExceedsDebits;
+                }
+
+                if transfer.flags.contains(TransferFlags::Pending) {
+                    self.account_mut(debit.id).debits_pending += amount;
+                    self.account_mut(credit.id).credits_pending += amount;
+                    self.pending.insert(transfer.id, Pending::Open);
+                } else {
+                    self.account_mut(debit.id).debits_posted += amount;
+                    self.account_mut(credit.id).credits_posted += amount;
+                }
+            }
+        }
+
+        transfer.timestamp = self.next_timestamp(now);
+        for id in [debit.id, credit.id] {
+            let account = self.account_mut(id);
+            if account.flags.contains(AccountFlags::History) {
+                let balance = AccountBalance {
+                    debits_pending: account.debits_pending,
+                    debits_posted: account.debits_posted,
+                    credits_pending: account.credits_pending,
+                    credits_posted: account.credits_posted,
+                    timestamp: transfer.timestamp,
+                    reserved: Default::default(),
+                };
+                self.history.insert((id, transfer.timestamp), balance);
+            }
+        }
+        self.transfer_index
+            .insert(transfer.id, self.transfers.len());
+        self.transfers.push(transfer);
+        R::Ok
+    }
+
+    /// Give back what `pending` holds on both of its accounts
+    fn release(&mut self, pending: &Transfer) {
+        self.account_mut(pending.debit_account_id).debits_pending -= pending.amount;
+        self.account_mut(pending.credit_account_id).credits_pending -= pending.amount;
+    }
+
+    /// Release every open pending transfer whose timeout has passed at `now`
+    fn expire(&mut self, now: u64) {
+        let expired: Vec<Transfer> = self
+            .pending
+            .iter()
+            .filter(|(_, state)| **state == Pending::Open)
+            .filter_map(|(id, _)| self.transfer(*id).copied())
+            .filter(|t| t.timeout != 0 && t.timestamp + t.timeout as u64 * NANOS_PER_SEC <= now)
+            .collect();
+        for pending in expired {
+            self.release(&pending);
+            self.pending.insert(pending.id, Pending::Expired);
+        }
+    }
+
+    fn account_transfers(&self, filter: &AccountFilter) -> Vec<Transfer> {
+        let debits = filter.flags.contains(AccountFilterFlags::Debits);
+        let credits = filter.flags.contains(AccountFilterFlags::Credits);
+        let matching = self.transfers.iter().filter(|t| {
+            ((debits && t.debit_account_id == filter.account_id)
+                || (credits && t.credit_account_id == filter.account_id))
+                && user_data_matches(
+                    (
+                        filter.user_data_128,
+                        filter.user_data_64,
+                        filter.user_data_32,
+                    ),
+                    (t.user_data_128, t.user_data_64, t.user_data_32),
+                )
+                && (filter.code == 0 || t.code == filter.code)
+                && in_range(t.timestamp, filter.timestamp_min, filter.timestamp_max)
+        });
+        page(
+            matching,
+            filter.flags.contains(AccountFilterFlags::Reversed),
+            filter.limit,
+        )
+    }
+
+    fn account_balances(&self, filter: &AccountFilter) -> Vec<AccountBalance> {
+        let keeps_history = self
+            .account(filter.account_id)
+            .is_some_and(|a| a.flags.contains(AccountFlags::History));
+        if !keeps_history {
+            return Vec::new();
+        }
+        self.account_transfers(filter)
+            .iter()
+            .filter_map(|t| self.history.get(&(filter.account_id, t.timestamp)).copied())
+            .collect()
+    }
+
+    fn query_accounts(&self, filter: &QueryFilter) -> Vec<Account> {
+        let matching = self.accounts.iter().filter(|a| {
+            query_matches(
+                filter,
+                (a.user_data_128, a.user_data_64, a.user_data_32),
+                a.ledger,
+                a.code,
+                a.timestamp,
+            )
+        });
+        page(
+            matching,
+            filter.flags.contains(QueryFilterFlags::Reversed),
+            filter.limit,
+        )
+    }
+
+    fn query_transfers(&self, filter: &QueryFilter) -> Vec<Transfer> {
+        let matching = self.transfers.iter().filter(|t| {
+            query_matches(
+                filter,
+                (t.user_data_128, t.user_data_64, t.user_data_32),
+                t.ledger,
+                t.code,
+                t.timestamp,
+            )
+        });
+        page(
+            matching,
+            filter.flags.contains(QueryFilterFlags::Reversed),
+            filter.limit,
+        )
+    }
+}
+
+/// Whether `have` matches every non-zero field of `want`
+fn user_data_matches(want: (u128, u64, u32), have: (u128, u64, u32)) -> bool {
+    (want.0 == 0 || want.0 == have.0)
+        && (want.1 == 0 || want.1 == have.1)
+        && (want.2 == 0 || want.2 == have.2)
+}
+
+/// `timestamp` within `min..=max`, 0 leaving that end open
+fn in_range(timestamp: u64, min: u64, max: u64) -> bool {
+    timestamp >= min && (max == 0 || timestamp <= max)
+}
+
+fn query_matches(
+    filter: &QueryFilter,
+    user_data: (u128, u64, u32),
+    ledger: u32,
+    code: u16,
+    timestamp: u64,
+) -> bool {
+    user_data_matches(
+        (
+            filter.user_data_128,
+            filter.user_data_64,
+            filter.user_data_32,
+        ),
+        user_data,
+    ) && (filter.ledger == 0 || filter.ledger == ledger)
+        && (filter.code == 0 || filter.code == code)
+        && in_range(timestamp, filter.timestamp_min, filter.timestamp_max)
+}
+
+/// The first `limit` of `matching`, oldest first unless `reversed`
+fn page<'a, T: Copy + 'a>(
+    matching: impl DoubleEndedIterator<Item = &'a T>,
+    reversed: bool,
+    limit: u32,
+) -> Vec<T> {
+    if reversed {
+        matching.rev().take(limit as usize).copied().collect()
+    } else {
+        matching.take(limit as usize).copied().collect()
+    }
+}
+
+fn answer<T: Send + 'static>(value: T) -> BackendFuture<T> {
+    Box::pin(ready(Ok(value)))
+}
+
+impl AccountingBackend for MemoryBackend {
+    fn create_accounts(&self, accounts: &[Account]) -> BackendFuture<Vec<CreateAccountResult>> {
+        let now = self.now_nanos();
+        let results = self.ledger().apply_chains(
+            accounts,
+            |a| a.flags.contains(AccountFlags::Linked),
+            |ledger, a| ledger.create_account(a, now),
+        );
+        answer(results)
+    }
+
+    fn create_transfers(&self, transfers: &[Transfer]) -> BackendFuture<Vec<CreateTransferResult>> {
+        let now = self.now_nanos();
+        let results = self.ledger().apply_chains(
+            transfers,
+            |t| t.flags.contains(TransferFlags::Linked),
+            |ledger, t| ledger.create_transfer(t, now),
+        );
+        answer(results)
+    }
+
+    fn lookup_accounts(&self, ids: &[u128]) -> BackendFuture<Vec<Result<Account, NotFound>>> {
+        let ledger = self.ledger();
+        answer(
+            ids.iter()
+                .map(|id| ledger.account(*id).copied().ok_or(NotFound))
+                .collect(),
+        )
+    }
+
+    fn lookup_transfers(&self, ids: &[u128]) -> BackendFuture<Vec<Result<Transfer, NotFound>>> {
+        let ledger = self.ledger();
+        answer(
+            ids.iter()
+                .map(|id| ledger.transfer(*id).copied().ok_or(NotFound))
+                .collect(),
+        )
+    }
+
+    fn get_account_transfers(&self, filter: AccountFilter) -> BackendFuture<Vec<Transfer>> {
+        answer(self.ledger().account_transfers(&filter))
+    }
+
+    fn get_account_balances(&self, filter: AccountFilter) -> BackendFuture<Vec<AccountBalance>> {
+        answer(self.ledger().account_balances(&filter))
+    }
+
+    fn query_accounts(&self, filter: QueryFilter) -> BackendFuture<Vec<Account>> {
+        answer(self.ledger().query_accounts(&filter))
+    }
+
+    fn query_transfers(&self, filter: QueryFilter) -> BackendFuture<Vec<Transfer>> {
+        answer(self.ledger().query_transfers(&filter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClock;
+    use std::time::Duration;
+
+    fn account(id: u128, flags: AccountFlags) -> Account {
+        Account {
+            id,
+            debits_pending: 0,
+            debits_posted: 0,
+            credits_pending: 0,
+            credits_posted: 0,
+            user_data_128: 0,
+            user_data_64: 0,
+            user_data_32: 0,
+            reserved: Default::default(),
+            ledger: 1,
+            code: 1,
+            flags,
+            timestamp: 0,
+        }
+    }
+
+    fn transfer(id: u128, debit: u128, credit: u128, amount: u128) -> Transfer {
+        Transfer {
+            id,
+            debit_account_id: debit,
+            credit_account_id: credit,
+            amount,
+            pending_id: 0,
+            user_data_128: 0,
+            user_data_64: 0,
+            user_data_32: 0,
+            timeout: 0,
+            ledger: 1,
+            code: 1,
+            flags: TransferFlags::default(),
+            timestamp: 0,
+        }
+    }
+
+    async fn balances(backend: &MemoryBackend, id: u128) -> (u128, u128, u128, u128) {
+        let account = backend.lookup_accounts(&[id]).await.unwrap()[0].unwrap();
+        (
+            account.debits_posted,
+            account.credits_posted,
+            account.debits_pending,
+            account.credits_pending,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_linked_chain_fails_as_one() {
+        use CreateTransferResult as R;
+
+        let backend = MemoryBackend::new();
+        backend
+            .create_accounts(&[
+                account(1, AccountFlags::default()),
+                account(2, AccountFlags::DebitsMustNotExceedCredits),
+                account(3, AccountFlags::default()),
+            ])
+            .await
+            .unwrap();
+
+        let linked = |t: Transfer| Transfer {
+            flags: TransferFlags::Linked,
+            ..t
+        };
+        let results = backend
+            .create_transfers(&[
+                transfer(10, 1, 2, 100),
+                linked(transfer(11, 2, 3, 60)),
+                transfer(12, 2, 3, 60), // 120 out of 100 fails the pair
+                transfer(13, 2, 3, 0),
+                transfer(13, 2, 3, 0),
+                transfer(14, 9, 3, 1),
+                linked(transfer(15, 1, 3, 1)),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![
+                R::Ok,
+                R::LinkedEventFailed,
+                R::ExceedsCredits,
+                R::Ok,
+                R::Exists,
+                R::DebitAccountNotFound,
+                R::LinkedEventChainOpen
+            ]
+        );
+        assert_eq!(balances(&backend, 2).await, (0, 100, 0, 0));
+        assert!(backend.lookup_transfers(&[11]).await.unwrap()[0].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pending_transfers_post_void_and_expire() {
+        use CreateTransferResult as R;
+
+        let clock = Arc::new(MockClock::new(1_000));
+        let backend = MemoryBackend::new().with_clock(clock.clone());
+        backend
+            .create_accounts(&[
+                account(1, AccountFlags::default()),
+                account(
+                    2,
+                    AccountFlags::DebitsMustNotExceedCredits | AccountFlags::History,
+                ),
+            ])
+            .await
+            .unwrap();
+
+        let pending = |id, amount, timeout| Transfer {
+            flags: TransferFlags::Pending,
+            timeout,
+            ..transfer(id, 2, 1, amount)
+        };
+        let resolve = |id, pending_id, flags| Transfer {
+            pending_id,
+            flags,
+            ..transfer(id, 0, 0, 0)
+        };
+        let results = backend
+            .create_transfers(&[
+                transfer(10, 1, 2, 100),
+                pending(11, 70, 0),
+                pending(12, 40, 0), // only 30 left
+                pending(13, 30, 5),
+                resolve(14, 11, TransferFlags::PostPendingTransfer),
+                resolve(15, 11, TransferFlags::VoidPendingTransfer),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![
+                R::Ok,
+                R::Ok,
+                R::ExceedsCredits,
+                R::Ok,
+                R::Ok,
+                R::PendingTransferAlreadyPosted
+            ]
+        );
+        assert_eq!(balances(&backend, 2).await, (70, 100, 30, 0));
+
+        // The hold was stamped a few nanoseconds after the clock's millisecond
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(balances(&backend, 2).await, (70, 100, 30, 0));
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(balances(&backend, 2).await, (70, 100, 0, 0));
+        let late = backend
+            .create_transfers(&[resolve(16, 13, TransferFlags::VoidPendingTransfer)])
+            .await
+            .unwrap();
+        assert_eq!(late, vec![R::PendingTransferExpired]);
+
+        let filter = AccountFilter {
+            account_id: 2,
+            user_data_128: 0,
+            user_data_64: 0,
+            user_data_32: 0,
+            code: 0,
+            reserved: Default::default(),
+            timestamp_min: 0,
+            timestamp_max: 0,
+            limit: 10,
+            flags: AccountFilterFlags::Debits
+                | AccountFilterFlags::Credits
+                | AccountFilterFlags::Reversed,
+        };
+        let transfers = backend.get_account_transfers(filter).await.unwrap();
+        let ids: Vec<u128> = transfers.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![14, 13, 11, 10]);
+        assert!(transfers
+            .windows(2)
+            .all(|w| w[0].timestamp > w[1].timestamp));
+        let history = backend.get_account_balances(filter).await.unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[0].debits_posted, 70);
+    }
+}
@@ -0,0 +1,225 @@
+//! # 💱 Money
+//!
+//! Balances are integers in a currency's minor unit: cents for USD, yen for JPY,
+//! fils for BHD. How many decimals that is - the currency exponent - only matters
+//! when showing an amount to a human, so it lives here and nowhere near a transfer.
+//!
+//! Each TigerBeetle ledger holds one currency. [`Currencies`] maps ledger ids to
+//! their [`Currency`]; ledgers without an entry are shown as plain numbers.
+//!
+//! ```rust
+//! use zik_zak::money::{Currencies, Currency, Money};
+//!
+//! let jpy = Currency::new("JPY", "¥", 0);
+//! assert_eq!(Money::new(1000, &jpy).to_string(), "¥1000");
+//!
+//! let currencies = Currencies::default().with_ledger(2, Currency::new("BHD", "BD", 3));
+//! assert_eq!(currencies.format(1, 2999), "$29.99");
+//! assert_eq!(currencies.format(2, 1234), "BD1.234");
+//! assert_eq!(currencies.format(9, 1234), "1234");
+//! ```
+//!
+//! A deployment with other ledgers lists them in a JSON file named by
+//! `ZIK_ZAK_CURRENCIES`:
+//!
+//! ```json
+//! [
+//!   { "ledger": 1, "code": "USD", "symbol": "$", "currency_exponent": 2 },
+//!   { "ledger": 2, "code": "JPY", "symbol": "¥", "currency_exponent": 0 }
+//! ]
+//! ```
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Largest exponent accepted; `10^18` still fits an `i64`
+const MAX_EXPONENT: u32 = 18;
+
+/// A currency and how many decimals its minor unit has
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Currency {
+    /// ISO 4217 code, e.g. `USD`
+    pub code: String,
+    pub symbol: String,
+    /// Decimal places of the minor unit: 2 for USD, 0 for JPY, 3 for BHD
+    pub currency_exponent: u32,
+}
+
+impl Currency {
+    pub fn new(code: &str, symbol: &str, currency_exponent: u32) -> Self {
+        Self {
+            code: code.to_string(),
+            symbol: symbol.to_string(),
+            currency_exponent,
+        }
+    }
+
+    /// `minor` units as a decimal amount, e.g. 2999 cents -> 29.99
+    pub fn to_major(&self, minor: i64) -> f64 {
+        minor as f64 / 10f64.powi(self.currency_exponent as i32)
+    }
+}
+
+/// An amount of minor units, displayed in its currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Money<'a> {
+    pub minor: i64,
+    pub currency: &'a Currency,
+}
+
+impl<'a> Money<'a> {
+    pub fn new(minor: i64, currency: &'a Currency) -> Self {
+        Self { minor, currency }
+    }
+}
+
+impl fmt::Display for Money<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let minor = self.minor.unsigned_abs();
+        let exponent = self.currency.currency_exponent;
+        if exponent == 0 {
+            return write!(f, "{}{}{}", sign, self.currency.symbol, minor);
+        }
+
+        let scale = 10u64.pow(exponent);
+        write!(
+            f,
+            "{}{}{}.{:0width$}",
+            sign,
+            self.currency.symbol,
+            minor / scale,
+            minor % scale,
+            width = exponent as usize
+        )
+    }
+}
+
+/// One entry of a `ZIK_ZAK_CURRENCIES` file
+#[derive(Deserialize)]
+struct LedgerCurrency {
+    ledger: u32,
+    #[serde(flatten)]
+    currency: Currency,
+}
+
+/// Currency of each ledger, keyed by TigerBeetle ledger id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Currencies(HashMap<u32, Currency>);
+
+impl Default for Currencies {
+    /// Ledger 1, the engine's default, in US dollars
+    fn default() -> Self {
+        Self(HashMap::from([(1, Currency::new("USD", "$", 2))]))
+    }
+}
+
+impl Currencies {
+    /// No ledger has a currency
+    pub fn empty() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Use `currency` for `ledger`, replacing any earlier one
+    pub fn with_ledger(mut self, ledger: u32, currency: Currency) -> Self {
+        self.0.insert(ledger, currency);
+        self
+    }
+
+    /// Read a JSON array of `{ledger, code, symbol, currency_exponent}` entries
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read currencies {:?}: {}", path, e))?;
+        let entries: Vec<LedgerCurrency> = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse currencies {:?}: {}", path, e))?;
+
+        let mut currencies = Self::empty();
+        for entry in entries {
+            if entry.currency.currency_exponent > MAX_EXPONENT {
+                return Err(anyhow!(
+                    "Currency exponent {} of ledger {} is above {}",
+                    entry.currency.currency_exponent,
+                    entry.ledger,
+                    MAX_EXPONENT
+                ));
+            }
+            if currencies.0.insert(entry.ledger, entry.currency).is_some() {
+                return Err(anyhow!("Ledger {} has two currencies", entry.ledger));
+            }
+        }
+        Ok(currencies)
+    }
+
+    /// The file named by `ZIK_ZAK_CURRENCIES`, or the default when unset
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("ZIK_ZAK_CURRENCIES") {
+            Ok(path) => Self::from_file(path),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn for_ledger(&self, ledger: u32) -> Option<&Currency> {
+        self.0.get(&ledger)
+    }
+
+    /// `minor` units of `ledger` for display; a bare number for unknown ledgers
+    pub fn format(&self, ledger: u32, minor: i64) -> String {
+        match self.for_ledger(ledger) {
+            Some(currency) => Money::new(minor, currency).to_string(),
+            None => minor.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_money_follows_exponent() {
+        let usd = Currency::new("USD", "$", 2);
+        let jpy = Currency::new("JPY", "¥", 0);
+        let bhd = Currency::new("BHD", "BD", 3);
+
+        assert_eq!(Money::new(2999, &usd).to_string(), "$29.99");
+        assert_eq!(Money::new(5, &usd).to_string(), "$0.05");
+        assert_eq!(Money::new(-150, &usd).to_string(), "-$1.50");
+        assert_eq!(Money::new(1000, &jpy).to_string(), "¥1000");
+        assert_eq!(Money::new(1234, &bhd).to_string(), "BD1.234");
+        assert_eq!(
+            Money::new(i64::MIN, &usd).to_string(),
+            "-$92233720368547758.08"
+        );
+
+        assert_eq!(usd.to_major(2999), 29.99);
+        assert_eq!(jpy.to_major(1000), 1000.0);
+    }
+
+    #[test]
+    fn test_currencies_from_file() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let path = temp_dir.path().join("currencies.json");
+
+        fs::write(
+            &path,
+            r#"[{"ledger":1,"code":"JPY","symbol":"¥","currency_exponent":0},
+                {"ledger":7,"code":"BHD","symbol":"BD","currency_exponent":3}]"#,
+        )?;
+        let currencies = Currencies::from_file(&path)?;
+        assert_eq!(currencies.format(1, 1000), "¥1000");
+        assert_eq!(currencies.format(7, 1000), "BD1.000");
+        assert_eq!(currencies.format(2, 1000), "1000");
+
+        fs::write(
+            &path,
+            r#"[{"ledger":1,"code":"X","symbol":"X","currency_exponent":19}]"#,
+        )?;
+        assert!(Currencies::from_file(&path).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+//! # 🔍 Account Patterns
+//!
+//! The pattern language behind [`ZikZakEngine::find_accounts`](crate::ZikZakEngine::find_accounts),
+//! `Genesis::divine_query` and `GET /query`.
+//!
+//! A pattern is an account name in which whole `:`-separated segments may be
+//! `*`, standing for exactly one segment:
+//!
+//! - `user:123:*` - every field of user 123 (`user:123:balance`, `user:123:email`)
+//! - `product:*:existence` - every product's existence account
+//! - `user:*:order:*` - every order field of every user
+//!
+//! Every pattern is matched against every account on the ledger, so patterns that
+//! would select most of it are refused: the first segment (the entity type) must be
+//! literal and at most [`MAX_PATTERN_WILDCARDS`] segments may be `*`.
+
+use std::fmt;
+
+use crate::error::ZikZakError;
+
+/// Most `*` segments one pattern may contain
+pub const MAX_PATTERN_WILDCARDS: usize = 3;
+
+/// A validated account pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountPattern {
+    /// `None` for `*`
+    segments: Vec<Option<String>>,
+}
+
+impl AccountPattern {
+    pub fn parse(pattern: &str) -> Result<Self, ZikZakError> {
+        let invalid = |reason: &str| ZikZakError::InvalidPattern {
+            pattern: pattern.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let mut segments = Vec::new();
+        for segment in pattern.split(':') {
+            match segment {
+                "" => return Err(invalid("empty segment")),
+                "*" => segments.push(None),
+                s if s.contains('*') => {
+                    return Err(invalid("`*` must be a whole segment"));
+                }
+                s => segments.push(Some(s.to_string())),
+            }
+        }
+
+        if segments[0].is_none() {
+            return Err(invalid("the first segment must not be `*`"));
+        }
+        let wildcards = segments.iter().filter(|s| s.is_none()).count();
+        if wildcards > MAX_PATTERN_WILDCARDS {
+            return Err(invalid(&format!(
+                "{} wildcards, at most {} allowed",
+                wildcards, MAX_PATTERN_WILDCARDS
+            )));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Whether `account` has the same number of segments and agrees on every literal one
+    pub fn matches(&self, account: &str) -> bool {
+        let mut parts = account.split(':');
+        for segment in &self.segments {
+            match (segment, parts.next()) {
+                (_, None) => return false,
+                (Some(literal), Some(part)) if literal != part => return false,
+                _ => {}
+            }
+        }
+        parts.next().is_none()
+    }
+}
+
+impl fmt::Display for AccountPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                f.write_str(":")?;
+            }
+            f.write_str(segment.as_deref().unwrap_or("*"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_matches_exactly_one_segment() {
+        let pattern = AccountPattern::parse("user:123:*").unwrap();
+        assert!(pattern.matches("user:123:balance"));
+        assert!(pattern.matches("user:123:email"));
+        assert!(!pattern.matches("user:123"));
+        assert!(!pattern.matches("user:123:order:5"));
+        assert!(!pattern.matches("user:1234:balance"));
+
+        let pattern = AccountPattern::parse("product:*:existence").unwrap();
+        assert!(pattern.matches("product:9:existence"));
+        assert!(!pattern.matches("product:9:price"));
+
+        let exact = AccountPattern::parse("system:genesis").unwrap();
+        assert!(exact.matches("system:genesis"));
+        assert!(!exact.matches("system:deleted"));
+    }
+
+    #[test]
+    fn test_pathological_patterns_are_refused() {
+        for pattern in [
+            "*",
+            "*:123:balance",
+            "user:*:*:*:*",
+            "user::balance",
+            "user:12*:balance",
+            "",
+        ] {
+            assert!(
+                matches!(
+                    AccountPattern::parse(pattern),
+                    Err(ZikZakError::InvalidPattern { .. })
+                ),
+                "accepted {:?}",
+                pattern
+            );
+        }
+        assert!(AccountPattern::parse("user:*:order:*:status").is_ok());
+    }
+}
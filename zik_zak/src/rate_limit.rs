@@ -0,0 +1,195 @@
+//! # 🚦 Rate Limiting
+//!
+//! Token bucket per client: the `X-Api-Key` header when present, the peer IP
+//! otherwise. Each bucket holds up to `burst` tokens and refills at `rate` tokens
+//! per second; a request spends one token or gets `429 Too Many Requests` with a
+//! `Retry-After` header.
+//!
+//! - `ZIK_ZAK_RATE_LIMIT_RPS` - refill rate (default 50)
+//! - `ZIK_ZAK_RATE_LIMIT_BURST` - bucket size (default 100)
+//!
+//! Buckets of clients that went quiet are evicted periodically, so IP churn does
+//! not grow the map without bound.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+const DEFAULT_RATE: f64 = 50.0;
+const DEFAULT_BURST: f64 = 100.0;
+/// How often idle buckets are swept
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+pub struct RateLimiter {
+    buckets: DashMap<String, Bucket>,
+    /// Tokens added per second
+    rate: f64,
+    /// Bucket capacity
+    burst: f64,
+}
+
+impl RateLimiter {
+    pub fn new(rate: f64, burst: f64) -> Self {
+        Self {
+            buckets: DashMap::new(),
+            rate,
+            burst: burst.max(1.0),
+        }
+    }
+
+    /// Limits from `ZIK_ZAK_RATE_LIMIT_RPS` / `ZIK_ZAK_RATE_LIMIT_BURST`
+    pub fn from_env() -> Self {
+        let env = |name: &str, default: f64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<f64>().ok())
+                .filter(|v| *v > 0.0)
+                .unwrap_or(default)
+        };
+
+        Self::new(
+            env("ZIK_ZAK_RATE_LIMIT_RPS", DEFAULT_RATE),
+            env("ZIK_ZAK_RATE_LIMIT_BURST", DEFAULT_BURST),
+        )
+    }
+
+    /// Spend a token for `client`, or return how long until one is available
+    pub fn check(&self, client: &str) -> Result<(), Duration> {
+        self.check_at(client, Instant::now())
+    }
+
+    fn check_at(&self, client: &str, now: Instant) -> Result<(), Duration> {
+        let mut bucket = self
+            .buckets
+            .entry(client.to_string())
+            .or_insert_with(|| Bucket {
+                tokens: self.burst,
+                refilled_at: now,
+            });
+
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.rate).min(self.burst);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        }
+    }
+
+    /// Drop buckets that have refilled completely - they hold no state worth keeping
+    pub fn evict_idle(&self) -> usize {
+        self.evict_idle_at(Instant::now())
+    }
+
+    fn evict_idle_at(&self, now: Instant) -> usize {
+        let full_after = Duration::from_secs_f64(self.burst / self.rate);
+        let before = self.buckets.len();
+        self.buckets
+            .retain(|_, bucket| now.saturating_duration_since(bucket.refilled_at) < full_after);
+        before - self.buckets.len()
+    }
+
+    /// Sweep idle buckets in the background for as long as the limiter lives
+    pub fn spawn_eviction(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let limiter = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(EVICTION_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let Some(limiter) = limiter.upgrade() else {
+                    break;
+                };
+                let evicted = limiter.evict_idle();
+                if evicted > 0 {
+                    debug!("🚦 Evicted {} idle rate limit buckets", evicted);
+                }
+            }
+        })
+    }
+}
+
+/// Axum middleware enforcing the limiter; needs `into_make_service_with_connect_info`
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = match request
+        .headers()
+        .get("x-api-key")
+        .and_then(|key| key.to_str().ok())
+    {
+        Some(key) => format!("key:{}", key),
+        None => format!("ip:{}", peer.ip()),
+    };
+
+    match limiter.check(&client) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, seconds.to_string())],
+                "🚦 Rate limit exceeded",
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_limit_then_refill() {
+        let limiter = RateLimiter::new(2.0, 3.0);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at("ip:1.2.3.4", start).is_ok());
+        }
+        let retry_after = limiter.check_at("ip:1.2.3.4", start).unwrap_err();
+        assert_eq!(retry_after, Duration::from_millis(500));
+
+        // Other clients have their own bucket
+        assert!(limiter.check_at("key:abc", start).is_ok());
+
+        // Half a second at 2/s buys one more request
+        let later = start + Duration::from_millis(500);
+        assert!(limiter.check_at("ip:1.2.3.4", later).is_ok());
+        assert!(limiter.check_at("ip:1.2.3.4", later).is_err());
+    }
+
+    #[test]
+    fn test_evict_idle_buckets() {
+        let limiter = RateLimiter::new(10.0, 10.0);
+        let start = Instant::now();
+        limiter.check_at("ip:1.1.1.1", start).unwrap();
+        limiter
+            .check_at("ip:2.2.2.2", start + Duration::from_millis(900))
+            .unwrap();
+
+        // After 1s the first bucket is full again, the second is not
+        assert_eq!(limiter.evict_idle_at(start + Duration::from_secs(1)), 1);
+        assert_eq!(limiter.buckets.len(), 1);
+        assert!(limiter.buckets.contains_key("ip:2.2.2.2"));
+    }
+}
@@ -0,0 +1,265 @@
+//! # 📡 Realtime Subscriptions
+//!
+//! The JSON subprotocol spoken over the server's `/ws` WebSocket (feature
+//! `realtime`). A client subscribes to [account patterns](crate::query) and is
+//! sent every committed transfer from or to a matching account:
+//!
+//! ```text
+//! → {"action":"subscribe","pattern":"order:*:status"}
+//! ← {"ack":{"action":"subscribe","pattern":"order:*:status"}}
+//! ← {"transfer":{"id":"…","from_account":"system:genesis","to_account":"order:42:status",…}}
+//! → {"action":"unsubscribe","pattern":"order:*:status"}
+//! ← {"ack":{"action":"unsubscribe","pattern":"order:*:status"}}
+//! ```
+//!
+//! Subscribers are also told when a pending transfer touching a matching
+//! account times out unresolved (see [`ZikZakEngine::spawn_pending_watcher`]):
+//!
+//! ```text
+//! ← {"expired":{"id":…,"from_account":"wallet:7:balance","to_account":"hotel:3:holds",…}}
+//! ```
+//!
+//! A connection may hold any number of patterns; an event matching several is
+//! still sent once. Malformed JSON, unknown actions and invalid patterns are
+//! answered with an `{"error":"…"}` frame and the connection stays open.
+//!
+//! [`ZikZakEngine::spawn_pending_watcher`]: crate::ZikZakEngine::spawn_pending_watcher
+//!
+//! [`Session`] holds one connection's subscriptions and knows nothing about
+//! sockets, so the protocol can be driven (and tested) without a server.
+//!
+//! In-process, [`Genesis::subscribe`](crate::Genesis::subscribe) streams the same
+//! transfers plus a [`SparkEvent::Ignited`] for every spark run.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::query::AccountPattern;
+use crate::sparks::Zak;
+use crate::zik_zak::{PendingTransfer, Transfer};
+
+/// Events buffered per subscriber before it starts missing them
+pub const EVENT_BUFFER: usize = 1024;
+
+/// A frame sent by the client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum ClientMessage {
+    Subscribe { pattern: String },
+    Unsubscribe { pattern: String },
+}
+
+/// A frame sent by the server
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerMessage {
+    /// The client message that was applied
+    Ack(ClientMessage),
+    /// Why a client message was refused
+    Error(String),
+    /// A committed transfer touching a subscribed account
+    Transfer(Transfer),
+    /// A pending transfer touching a subscribed account that timed out unresolved
+    Expired(PendingTransfer),
+}
+
+/// Something [`Genesis`](crate::Genesis) created, as seen by a subscriber
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SparkEvent {
+    /// A spark finished igniting
+    Ignited {
+        spark: String,
+        inputs: HashMap<String, Value>,
+        /// Transfers the spark committed, oldest first
+        transfers: Vec<Transfer>,
+        result: Zak,
+    },
+    /// A committed transfer, whether made by a spark or directly on the engine
+    Transfer(Transfer),
+    /// The subscriber fell behind and this many events were dropped
+    Lagged(u64),
+}
+
+impl SparkEvent {
+    /// Whether `pattern` matches an account this event moved
+    ///
+    /// [`Lagged`](Self::Lagged) concerns every subscriber and always matches.
+    pub fn touches(&self, pattern: &AccountPattern) -> bool {
+        let moved =
+            |t: &Transfer| pattern.matches(&t.from_account) || pattern.matches(&t.to_account);
+        match self {
+            Self::Ignited { transfers, .. } => transfers.iter().any(moved),
+            Self::Transfer(transfer) => moved(transfer),
+            Self::Lagged(_) => true,
+        }
+    }
+}
+
+/// Subscriptions of one connection
+#[derive(Debug, Default)]
+pub struct Session {
+    /// Pattern as sent by the client, and parsed
+    patterns: Vec<(String, AccountPattern)>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a text frame from the client and return the reply
+    pub fn handle(&mut self, text: &str) -> ServerMessage {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(e) => return ServerMessage::Error(format!("Invalid message: {}", e)),
+        };
+
+        match &message {
+            ClientMessage::Subscribe { pattern } => {
+                let parsed = match AccountPattern::parse(pattern) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return ServerMessage::Error(e.to_string()),
+                };
+                if !self.patterns.iter().any(|(p, _)| p == pattern) {
+                    self.patterns.push((pattern.clone(), parsed));
+                }
+            }
+            ClientMessage::Unsubscribe { pattern } => {
+                let before = self.patterns.len();
+                self.patterns.retain(|(p, _)| p != pattern);
+                if self.patterns.len() == before {
+                    return ServerMessage::Error(format!("Not subscribed to {}", pattern));
+                }
+            }
+        }
+
+        ServerMessage::Ack(message)
+    }
+
+    /// The frame to send for `transfer`, if it touches a subscribed account
+    pub fn event(&self, transfer: &Transfer) -> Option<ServerMessage> {
+        self.watches(&transfer.from_account, &transfer.to_account)
+            .then(|| ServerMessage::Transfer(transfer.clone()))
+    }
+
+    /// The frame to send for an expired `pending` transfer, if it touches a subscribed account
+    pub fn expired(&self, pending: &PendingTransfer) -> Option<ServerMessage> {
+        self.watches(&pending.from_account, &pending.to_account)
+            .then(|| ServerMessage::Expired(pending.clone()))
+    }
+
+    fn watches(&self, from_account: &str, to_account: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|(_, pattern)| pattern.matches(from_account) || pattern.matches(to_account))
+    }
+
+    /// Patterns currently subscribed to, in subscription order
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.patterns.iter().map(|(pattern, _)| pattern.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    fn transfer(to_account: &str) -> Transfer {
+        Transfer {
+            id: format!("t-{}", to_account),
+            from_account: "system:genesis".to_string(),
+            to_account: to_account.to_string(),
+            amount: 1,
+            metadata: HashMap::new(),
+            timestamp: 0,
+        }
+    }
+
+    fn frame(message: &ServerMessage) -> Value {
+        serde_json::to_value(message).unwrap()
+    }
+
+    #[test]
+    fn test_subscribe_receive_unsubscribe() {
+        let mut session = Session::new();
+        let subscribe = json!({ "action": "subscribe", "pattern": "order:*:status" });
+
+        let ack = session.handle(&subscribe.to_string());
+        assert_eq!(frame(&ack), json!({ "ack": subscribe }));
+
+        let event = session.event(&transfer("order:42:status")).unwrap();
+        assert_eq!(frame(&event)["transfer"]["to_account"], "order:42:status");
+        assert!(session.event(&transfer("order:42:total")).is_none());
+
+        // A second pattern on the same connection
+        session.handle(r#"{"action":"subscribe","pattern":"user:7:*"}"#);
+        assert!(session.event(&transfer("user:7:balance")).is_some());
+
+        let ack = session.handle(r#"{"action":"unsubscribe","pattern":"order:*:status"}"#);
+        assert_eq!(frame(&ack)["ack"]["action"], "unsubscribe");
+        assert!(session.event(&transfer("order:42:status")).is_none());
+        assert_eq!(session.patterns().collect::<Vec<_>>(), ["user:7:*"]);
+    }
+
+    #[test]
+    fn test_expired_pending_reaches_subscribers() {
+        let mut session = Session::new();
+        session.handle(r#"{"action":"subscribe","pattern":"hotel:*:holds"}"#);
+
+        let pending = |to_account: &str| PendingTransfer {
+            id: 7,
+            from_account: "wallet:1:balance".to_string(),
+            to_account: to_account.to_string(),
+            amount: 200,
+            created_at: 100,
+            expires_at: Some(130),
+        };
+        let event = session.expired(&pending("hotel:3:holds")).unwrap();
+        assert_eq!(frame(&event)["expired"]["expires_at"], 130);
+        assert!(session.expired(&pending("car:3:holds")).is_none());
+    }
+
+    #[test]
+    fn test_spark_event_touches_pattern() {
+        let pattern = AccountPattern::parse("order:*:status").unwrap();
+        let ignited = |to_account: &str| SparkEvent::Ignited {
+            spark: "ship_order".to_string(),
+            inputs: HashMap::new(),
+            transfers: vec![transfer("order:9:total"), transfer(to_account)],
+            result: Zak(HashMap::new()),
+        };
+
+        assert!(ignited("order:9:status").touches(&pattern));
+        assert!(!ignited("order:9:paid").touches(&pattern));
+        assert!(SparkEvent::Transfer(transfer("order:9:status")).touches(&pattern));
+        assert!(!SparkEvent::Transfer(transfer("user:9:status")).touches(&pattern));
+        assert!(SparkEvent::Lagged(3).touches(&pattern));
+
+        let event = serde_json::to_value(ignited("order:9:status")).unwrap();
+        assert_eq!(event["ignited"]["spark"], "ship_order");
+        assert_eq!(
+            event["ignited"]["transfers"][1]["to_account"],
+            "order:9:status"
+        );
+    }
+
+    #[test]
+    fn test_bad_frames_get_error_replies() {
+        let mut session = Session::new();
+
+        for text in [
+            "not json",
+            r#"{"action":"publish","pattern":"order:*"}"#,
+            r#"{"action":"subscribe","pattern":"*:1"}"#,
+            r#"{"action":"unsubscribe","pattern":"order:*"}"#,
+        ] {
+            let reply = frame(&session.handle(text));
+            assert!(reply["error"].is_string(), "{} -> {}", text, reply);
+        }
+        assert_eq!(session.patterns().count(), 0);
+    }
+}
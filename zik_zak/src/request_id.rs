@@ -0,0 +1,35 @@
+//! # 🔗 Request Ids
+//!
+//! The id of the API call a task is serving, so everything it does can be traced
+//! back to that call. [`scope`] sets it for a future, and every transfer the
+//! engine records inside it carries the id in its metadata under
+//! [`REQUEST_ID_KEY`].
+//!
+//! ```ignore
+//! request_id::scope("3f2a…".to_string(), async {
+//!     engine.transfer("system:genesis", "user:1:balance", 100, HashMap::new()).await
+//! })
+//! .await?;
+//! ```
+//!
+//! The id lives in the task, not the engine: work handed to `tokio::spawn` does
+//! not inherit it.
+
+use std::future::Future;
+
+/// Transfer metadata key holding the id of the request that made the transfer
+pub const REQUEST_ID_KEY: &str = "request_id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Run `future` with `request_id` as the current request id
+pub async fn scope<F: Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+/// Id of the request being served, if any
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
@@ -20,14 +20,38 @@
 //! │ order:status    │    │ order:notes     │
 //! └─────────────────┘    └─────────────────┘
 //! ```
-
-use anyhow::Result;
+//!
+//! ## Content Interning
+//!
+//! With [`SledVarCharStore::with_interning`], identical content is stored once in
+//! the `content_pool` tree, keyed by its SHA-256, and records refer to it by that
+//! key. A thousand "Standard shipping" notes then cost one copy. Each pooled
+//! entry counts the records referring to it and is removed with the last one.
+//! Reads resolve references whether or not interning is on, so it can be turned
+//! on or off for an existing database. History entries always keep their own copy.
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use sled::{Db, Tree};
+use sled::transaction::TransactionError;
+use sled::{Db, Transactional, Tree};
 use std::collections::HashMap;
 use std::path::Path;
-use tracing::{debug, info};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::accounting::Account;
+use crate::clock::{Clock, SystemClock};
+use crate::error::ZikZakError;
+use crate::webhooks::DeadLetter;
+use crate::zik_zak::{is_account_not_found, BatchTransfer, Transfer};
+
+/// Previous values kept per varchar field unless configured otherwise
+const DEFAULT_MAX_VERSIONS: usize = 100;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VarCharRecord {
@@ -38,6 +62,27 @@ pub struct VarCharRecord {
     pub created_at: u64,
     pub updated_at: u64,
     pub metadata: HashMap<String, String>,
+    /// Bumped on every write, starting at 1; records written before versioning read as 0
+    #[serde(default)]
+    pub version: u64,
+}
+
+/// A `varchar_records` value: the record, its content possibly moved to the pool
+#[derive(Serialize, Deserialize)]
+struct StoredRecord {
+    #[serde(flatten)]
+    record: VarCharRecord,
+    /// `content_pool` key of the content; `record.content` is then empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_ref: Option<String>,
+}
+
+/// A `content_pool` value
+#[derive(Serialize, Deserialize)]
+struct PooledContent {
+    content: String,
+    /// Records referring to this content
+    refs: u64,
 }
 
 /// 🗄️ SLED-based VARCHAR storage engine
@@ -46,6 +91,18 @@ pub struct SledVarCharStore {
     records_tree: Tree,
     accounts_tree: Tree,
     content_hash_tree: Tree,
+    /// Overwritten versions, `{account}:{field}:{updated_at}:{version}` (zero-padded)
+    history_tree: Tree,
+    /// Interned content by SHA-256, with its reference count
+    content_pool: Tree,
+    /// Reject content that does not match its content type (off by default)
+    validate_content: bool,
+    /// Store new content in `content_pool` instead of inline (off by default)
+    intern_content: bool,
+    /// History entries kept per field; older ones are pruned
+    max_versions: usize,
+    /// Source of `created_at` / `updated_at`
+    clock: Arc<dyn Clock>,
 }
 
 impl SledVarCharStore {
@@ -62,12 +119,63 @@ impl SledVarCharStore {
         let records_tree = db.open_tree("varchar_records")?;
         let accounts_tree = db.open_tree("account_fields")?;
         let content_hash_tree = db.open_tree("content_hash_lookup")?;
+        let history_tree = db.open_tree("varchar_history")?;
+        let content_pool = db.open_tree("content_pool")?;
 
         Ok(Self {
             db,
             records_tree,
             accounts_tree,
             content_hash_tree,
+            history_tree,
+            content_pool,
+            validate_content: false,
+            intern_content: false,
+            max_versions: DEFAULT_MAX_VERSIONS,
+            clock: Arc::new(SystemClock),
+        })
+    }
+
+    /// Keep at most `max_versions` previous values per field (default 100)
+    pub fn with_max_versions(mut self, max_versions: usize) -> Self {
+        self.max_versions = max_versions;
+        self
+    }
+
+    /// Stamp records with time from `clock` instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Check content against its content type on every write
+    ///
+    /// `email`, `number` and `url` content must look like one, or the write fails
+    /// with [`ZikZakError::InvalidContent`]. Other content types are never checked.
+    pub fn with_validation(mut self, enabled: bool) -> Self {
+        self.validate_content = enabled;
+        self
+    }
+
+    /// Store identical content once, shared by every record holding it
+    ///
+    /// See the module docs on content interning.
+    pub fn with_interning(mut self, enabled: bool) -> Self {
+        self.intern_content = enabled;
+        self
+    }
+
+    fn check_content(&self, key: &str, content_type: &str, content: &str) -> Result<()> {
+        if !self.validate_content {
+            return Ok(());
+        }
+        validate_content(content_type, content).map_err(|reason| {
+            ZikZakError::InvalidContent {
+                key: key.to_string(),
+                content_type: content_type.to_string(),
+                reason,
+            }
+            .into()
         })
     }
 
@@ -80,10 +188,22 @@ impl SledVarCharStore {
         content_type: &str,
         metadata: HashMap<String, String>,
     ) -> Result<String> {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = self.clock.now_secs();
+
+        // Primary key: account_id:field_name
+        let key = format!("{}:{}", account_id, field_name);
+        self.check_content(&key, content_type, content)?;
+
+        // Overwriting keeps the version sequence going, and the old value in history
+        let previous = self.records_tree.get(&key)?;
+        let previous_version = match &previous {
+            Some(data) => {
+                let previous = self.decode(data)?;
+                self.archive(&previous)?;
+                previous.version
+            }
+            None => 0,
+        };
 
         let record = VarCharRecord {
             account_id: account_id.to_string(),
@@ -93,14 +213,15 @@ impl SledVarCharStore {
             created_at: now,
             updated_at: now,
             metadata,
+            version: previous_version + 1,
         };
-
-        // Primary key: account_id:field_name
-        let key = format!("{}:{}", account_id, field_name);
-        let value = serde_json::to_vec(&record)?;
+        let value = self.encode(&record)?;
 
         // Store in main records tree
         self.records_tree.insert(&key, value)?;
+        if let Some(previous) = previous {
+            self.release(&previous)?;
+        }
 
         // Index by account for fast account-based queries
         let account_key = format!("account:{}", account_id);
@@ -143,10 +264,7 @@ impl SledVarCharStore {
         let key = format!("{}:{}", account_id, field_name);
 
         match self.records_tree.get(&key)? {
-            Some(data) => {
-                let record: VarCharRecord = serde_json::from_slice(&data)?;
-                Ok(Some(record.content))
-            }
+            Some(data) => Ok(Some(self.decode(&data)?.content)),
             None => Ok(None),
         }
     }
@@ -179,15 +297,15 @@ impl SledVarCharStore {
         let key = format!("{}:{}", account_id, field_name);
 
         if let Some(existing_data) = self.records_tree.get(&key)? {
-            let mut record: VarCharRecord = serde_json::from_slice(&existing_data)?;
+            let mut record = self.decode(&existing_data)?;
+            self.check_content(&key, &record.content_type, new_content)?;
+            self.archive(&record)?;
             record.content = new_content.to_string();
-            record.updated_at = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+            record.version += 1;
+            record.updated_at = self.clock.now_secs();
 
-            self.records_tree
-                .insert(&key, serde_json::to_vec(&record)?)?;
+            self.records_tree.insert(&key, self.encode(&record)?)?;
+            self.release(&existing_data)?;
             self.db.flush()?;
         } else {
             // Create new record
@@ -198,12 +316,182 @@ impl SledVarCharStore {
         Ok(())
     }
 
+    /// Get the full varchar record, including its `version`
+    pub async fn get_varchar_record(
+        &self,
+        account_id: &str,
+        field_name: &str,
+    ) -> Result<Option<VarCharRecord>> {
+        let key = format!("{}:{}", account_id, field_name);
+
+        match self.records_tree.get(&key)? {
+            Some(data) => Ok(Some(self.decode(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Update varchar field only if it is still at `expected_version`
+    ///
+    /// Returns `false` without writing when someone else updated the field first
+    /// (re-read it with `get_varchar_record` and retry). A missing field is at
+    /// version 0, so `expected_version == 0` creates it.
+    pub async fn update_varchar_cas(
+        &self,
+        account_id: &str,
+        field_name: &str,
+        expected_version: u64,
+        new_content: &str,
+    ) -> Result<bool> {
+        let key = format!("{}:{}", account_id, field_name);
+
+        let Some(existing_data) = self.records_tree.get(&key)? else {
+            if expected_version != 0 {
+                return Ok(false);
+            }
+            self.store_varchar(account_id, field_name, new_content, "text", HashMap::new())
+                .await?;
+            return Ok(true);
+        };
+
+        let mut record = self.decode(&existing_data)?;
+        let previous = record.clone();
+        if record.version != expected_version {
+            debug!(
+                "🔒 Version conflict on {}: expected {}, found {}",
+                key, expected_version, record.version
+            );
+            return Ok(false);
+        }
+        self.check_content(&key, &record.content_type, new_content)?;
+
+        record.content = new_content.to_string();
+        record.version += 1;
+        record.updated_at = self.clock.now_secs();
+
+        // Swap against the exact bytes we checked, so a concurrent writer between
+        // the read and this write makes us lose instead of overwriting it
+        let value = self.encode(&record)?;
+        let swapped =
+            self.records_tree
+                .compare_and_swap(&key, Some(&existing_data), Some(value.clone()))?;
+        if swapped.is_err() {
+            self.release(&value)?;
+            return Ok(false);
+        }
+        // Only the winner of the swap archives what it replaced
+        self.release(&existing_data)?;
+        self.archive(&previous)?;
+
+        self.db.flush()?;
+        Ok(true)
+    }
+
+    /// Previous values of a field, newest first, at most `limit`
+    ///
+    /// The current value is not included - read it with `get_varchar_record`.
+    pub async fn get_varchar_history(
+        &self,
+        account_id: &str,
+        field_name: &str,
+        limit: usize,
+    ) -> Result<Vec<VarCharRecord>> {
+        let mut history = Vec::new();
+        for entry in self
+            .history_tree
+            .scan_prefix(history_prefix(account_id, field_name))
+            .rev()
+        {
+            if history.len() == limit {
+                break;
+            }
+            let (_, data) = entry?;
+            let record: VarCharRecord = serde_json::from_slice(&data)?;
+            // `a:b` + `c` and `a` + `b:c` share a prefix; only keep this exact field
+            if record.account_id == account_id && record.field_name == field_name {
+                history.push(record);
+            }
+        }
+        Ok(history)
+    }
+
+    /// Make the newest value from at or before `to_timestamp` current again
+    ///
+    /// The value being replaced goes to history like any other overwrite, and the
+    /// restored record gets the next version. Returns `false` if the field has no
+    /// history that old.
+    pub async fn revert_varchar(
+        &self,
+        account_id: &str,
+        field_name: &str,
+        to_timestamp: u64,
+    ) -> Result<bool> {
+        let key = format!("{}:{}", account_id, field_name);
+        let Some(current_data) = self.records_tree.get(&key)? else {
+            return Ok(false);
+        };
+        let current = self.decode(&current_data)?;
+
+        let target = self
+            .get_varchar_history(account_id, field_name, usize::MAX)
+            .await?
+            .into_iter()
+            .find(|record| record.updated_at <= to_timestamp);
+        let Some(target) = target else {
+            return Ok(false);
+        };
+
+        self.archive(&current)?;
+        let restored = VarCharRecord {
+            content: target.content,
+            content_type: target.content_type,
+            metadata: target.metadata,
+            version: current.version + 1,
+            updated_at: self.clock.now_secs(),
+            ..current
+        };
+        self.records_tree.insert(&key, self.encode(&restored)?)?;
+        self.release(&current_data)?;
+        self.db.flush()?;
+
+        debug!(
+            "⏪ Reverted {} to its value from {}",
+            key, target.updated_at
+        );
+        Ok(true)
+    }
+
+    /// Copy `record` into history, then prune the field down to `max_versions`
+    fn archive(&self, record: &VarCharRecord) -> Result<()> {
+        let prefix = history_prefix(&record.account_id, &record.field_name);
+        let key = format!("{}{:020}:{:020}", prefix, record.updated_at, record.version);
+        self.history_tree.insert(key, serde_json::to_vec(record)?)?;
+
+        let mut keys = Vec::new();
+        for entry in self.history_tree.scan_prefix(&prefix) {
+            let (key, data) = entry?;
+            let archived: VarCharRecord = serde_json::from_slice(&data)?;
+            if archived.account_id == record.account_id && archived.field_name == record.field_name
+            {
+                keys.push(key);
+            }
+        }
+        // Keys sort oldest first
+        let excess = keys.len().saturating_sub(self.max_versions);
+        for key in &keys[..excess] {
+            self.history_tree.remove(key)?;
+        }
+        Ok(())
+    }
+
     /// Delete varchar field
     pub async fn delete_varchar(&self, account_id: &str, field_name: &str) -> Result<bool> {
         let key = format!("{}:{}", account_id, field_name);
-        let removed = self.records_tree.remove(&key)?.is_some();
+        let previous = self.records_tree.remove(&key)?;
+        let removed = previous.is_some();
+
+        if let Some(previous) = previous {
+            self.release(&previous)?;
 
-        if removed {
             // Update account index
             let account_key = format!("account:{}", account_id);
             if let Some(fields_data) = self.accounts_tree.get(&account_key)? {
@@ -224,6 +512,27 @@ impl SledVarCharStore {
         Ok(removed)
     }
 
+    /// Delete every varchar field of every account under `prefix` (e.g. `product:123:`)
+    pub async fn delete_account_prefix(&self, prefix: &str) -> Result<usize> {
+        // Record keys are `account_id:field_name`, so a key prefix scan finds them all
+        let mut fields = Vec::new();
+        for entry in self.records_tree.scan_prefix(prefix) {
+            let (_, data) = entry?;
+            let record: VarCharRecord = serde_json::from_slice(&data)?;
+            fields.push((record.account_id, record.field_name));
+        }
+
+        let mut removed = 0;
+        for (account_id, field_name) in fields {
+            if self.delete_varchar(&account_id, &field_name).await? {
+                removed += 1;
+            }
+        }
+
+        debug!("🗑️ Deleted {} varchar fields under {}", removed, prefix);
+        Ok(removed)
+    }
+
     /// Search content by hash (for deduplication)
     pub async fn find_by_content_hash(&self, content: &str) -> Result<Vec<String>> {
         let content_hash = Self::hash_content(content);
@@ -238,58 +547,867 @@ impl SledVarCharStore {
         }
     }
 
-    /// Get database statistics
-    pub async fn get_stats(&self) -> Result<HashMap<String, u64>> {
-        let mut stats = HashMap::new();
+    /// Get database statistics
+    pub async fn get_stats(&self) -> Result<HashMap<String, u64>> {
+        let mut stats = HashMap::new();
+
+        stats.insert("total_records".to_string(), self.records_tree.len() as u64);
+        stats.insert(
+            "total_accounts".to_string(),
+            self.accounts_tree.len() as u64,
+        );
+        stats.insert(
+            "unique_content_hashes".to_string(),
+            self.content_hash_tree.len() as u64,
+        );
+        stats.insert(
+            "pooled_contents".to_string(),
+            self.content_pool.len() as u64,
+        );
+        stats.insert("db_size_bytes".to_string(), self.db.size_on_disk()? as u64);
+
+        Ok(stats)
+    }
+
+    /// Compact database now, returning the bytes reclaimed (negative if it grew)
+    ///
+    /// Sled 0.34 has no explicit GC call: flushing writes out dirty pages and lets
+    /// its segment cleaner rewrite and free sparse segments. Safe to run while
+    /// other tasks read and write.
+    pub async fn compact(&self) -> Result<i64> {
+        info!("🗜️ Compacting SLED database...");
+        compact_db(&self.db).await
+    }
+
+    /// Run [`compact`](Self::compact) every `interval` in the background
+    ///
+    /// The task keeps the database open; abort the handle to stop it.
+    pub fn spawn_compaction(&self, interval: Duration) -> JoinHandle<()> {
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick is immediate; there is nothing to reclaim at startup
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = compact_db(&db).await {
+                    warn!("🗜️ Scheduled SLED compaction failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Account tags kept in this store's database
+    pub fn tag_store(&self) -> Result<TagStore> {
+        TagStore::from_db(&self.db)
+    }
+
+    /// Transfer metadata index kept in this store's database
+    pub fn metadata_index(&self) -> Result<MetadataIndex> {
+        MetadataIndex::from_db(&self.db)
+    }
+
+    /// Account name record kept in this store's database
+    pub fn account_names(&self) -> Result<AccountNameStore> {
+        AccountNameStore::from_db(&self.db)
+    }
+
+    /// Webhook dead letters kept in this store's database
+    pub fn webhook_dead_letters(&self) -> Result<WebhookDeadLetters> {
+        WebhookDeadLetters::from_db(&self.db)
+    }
+
+    /// Balance checkpoints kept in this store's database
+    pub fn checkpoints(&self) -> Result<CheckpointStore> {
+        CheckpointStore::from_db(&self.db)
+    }
+
+    /// Daily outflow counters kept in this store's database
+    pub fn daily_outflow(&self) -> Result<DailyOutflowStore> {
+        DailyOutflowStore::from_db(&self.db)
+    }
+
+    /// Account currencies kept in this store's database
+    pub fn currencies(&self) -> Result<CurrencyStore> {
+        CurrencyStore::from_db(&self.db)
+    }
+
+    /// Object storage kept in this store's database
+    pub fn objects(&self) -> Result<ObjectStore> {
+        ObjectStore::from_db(&self.db)
+    }
+
+    /// Serialize `record` for `varchar_records`, interning its content if enabled
+    fn encode(&self, record: &VarCharRecord) -> Result<Vec<u8>> {
+        if !self.intern_content {
+            return Ok(serde_json::to_vec(record)?);
+        }
+
+        let pool_key = hex::encode(Sha256::digest(record.content.as_bytes()));
+        self.content_pool.update_and_fetch(&pool_key, |pooled| {
+            let mut entry = pooled
+                .and_then(|data| serde_json::from_slice::<PooledContent>(data).ok())
+                .unwrap_or_else(|| PooledContent {
+                    content: record.content.clone(),
+                    refs: 0,
+                });
+            entry.refs += 1;
+            serde_json::to_vec(&entry).ok()
+        })?;
+
+        let stored = StoredRecord {
+            record: VarCharRecord {
+                content: String::new(),
+                ..record.clone()
+            },
+            content_ref: Some(pool_key),
+        };
+        Ok(serde_json::to_vec(&stored)?)
+    }
+
+    /// Read a `varchar_records` value, resolving pooled content
+    fn decode(&self, data: &[u8]) -> Result<VarCharRecord> {
+        let StoredRecord {
+            mut record,
+            content_ref,
+        } = serde_json::from_slice(data)?;
+
+        if let Some(pool_key) = content_ref {
+            let pooled = self.content_pool.get(&pool_key)?.ok_or_else(|| {
+                anyhow!(
+                    "Pooled content {} of {}:{} is missing",
+                    pool_key,
+                    record.account_id,
+                    record.field_name
+                )
+            })?;
+            record.content = serde_json::from_slice::<PooledContent>(&pooled)?.content;
+        }
+        Ok(record)
+    }
+
+    /// Drop the pool reference of a replaced or deleted `varchar_records` value
+    ///
+    /// The pooled content goes with its last reference.
+    fn release(&self, data: &[u8]) -> Result<()> {
+        let stored: StoredRecord = serde_json::from_slice(data)?;
+        let Some(pool_key) = stored.content_ref else {
+            return Ok(());
+        };
+
+        self.content_pool.update_and_fetch(&pool_key, |pooled| {
+            let mut entry: PooledContent = serde_json::from_slice(pooled?).ok()?;
+            entry.refs = entry.refs.saturating_sub(1);
+            if entry.refs == 0 {
+                return None;
+            }
+            serde_json::to_vec(&entry).ok()
+        })?;
+        Ok(())
+    }
+
+    /// Hash content for deduplication
+    fn hash_content(content: &str) -> i64 {
+        let mut hasher = Sha256::new();
+        hasher.update(content.as_bytes());
+        let result = hasher.finalize();
+
+        // Take first 8 bytes and convert to i64
+        let bytes: [u8; 8] = result[0..8].try_into().unwrap();
+        i64::from_be_bytes(bytes)
+    }
+}
+
+fn history_prefix(account_id: &str, field_name: &str) -> String {
+    format!("{}:{}:", account_id, field_name)
+}
+
+/// Why `content` is not valid `content_type`, if it is not
+///
+/// Deliberately loose: the email pattern only wants `local@domain.tld`, numbers
+/// are anything `f64` parses, and URLs need a scheme.
+pub fn validate_content(content_type: &str, content: &str) -> std::result::Result<(), String> {
+    static EMAIL: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap());
+
+    match content_type {
+        "email" if !EMAIL.is_match(content) => {
+            Err(format!("{:?} is not an email address", content))
+        }
+        "number" if content.trim().parse::<f64>().is_err() => {
+            Err(format!("{:?} is not a number", content))
+        }
+        "url" => Url::parse(content)
+            .map(|_| ())
+            .map_err(|e| format!("{:?} is not a URL: {}", content, e)),
+        _ => Ok(()),
+    }
+}
+
+async fn compact_db(db: &Db) -> Result<i64> {
+    let before = db.size_on_disk()?;
+    db.flush_async().await?;
+    let after = db.size_on_disk()?;
+
+    let reclaimed = before as i64 - after as i64;
+    debug!(
+        "🗜️ SLED compaction: {} -> {} bytes on disk ({} reclaimed)",
+        before, after, reclaimed
+    );
+    Ok(reclaimed)
+}
+
+/// `COMPACT_INTERVAL_SECS`, if set to a positive number
+pub fn compaction_interval_from_env() -> Option<Duration> {
+    std::env::var("COMPACT_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// 🏷️ SLED-backed `key=value` tags on account names
+///
+/// Two indexes in one tree, separated by `\0` so keys and values may contain `:`:
+/// - `tag\0{key}\0{value}\0{account}` - accounts carrying a tag
+/// - `account\0{account}\0{key}` → value - tags of an account
+///
+/// An account holds at most one value per key; tagging again replaces it.
+#[derive(Clone)]
+pub struct TagStore {
+    db: Db,
+    tree: Tree,
+}
+
+impl TagStore {
+    /// Open (or create) a tag store on its own SLED database
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::from_db(&sled::open(db_path)?)
+    }
+
+    fn from_db(db: &Db) -> Result<Self> {
+        Ok(Self {
+            db: db.clone(),
+            tree: db.open_tree("account_tags")?,
+        })
+    }
+
+    fn tag_prefix(key: &str, value: &str) -> String {
+        format!("tag\0{}\0{}\0", key, value)
+    }
+
+    fn account_key(account: &str, key: &str) -> String {
+        format!("account\0{}\0{}", account, key)
+    }
+
+    /// Tag `account` with `key=value`, replacing any earlier value for `key`
+    pub fn add(&self, account: &str, key: &str, value: &str) -> Result<()> {
+        self.remove(account, key)?;
+
+        self.tree
+            .insert(Self::account_key(account, key), value.as_bytes())?;
+        self.tree
+            .insert(format!("{}{}", Self::tag_prefix(key, value), account), &[])?;
+        self.db.flush()?;
+
+        debug!("🏷️ Tagged {} with {}={}", account, key, value);
+        Ok(())
+    }
+
+    /// Remove the `key` tag from `account`; `false` if it had none
+    pub fn remove(&self, account: &str, key: &str) -> Result<bool> {
+        let Some(value) = self.tree.remove(Self::account_key(account, key))? else {
+            return Ok(false);
+        };
+
+        let value = String::from_utf8(value.to_vec())?;
+        self.tree
+            .remove(format!("{}{}", Self::tag_prefix(key, &value), account))?;
+        self.db.flush()?;
+
+        debug!("🏷️ Untagged {} ({}={})", account, key, value);
+        Ok(true)
+    }
+
+    /// Accounts tagged `key=value`, in name order
+    pub fn accounts(&self, key: &str, value: &str) -> Result<Vec<String>> {
+        let prefix = Self::tag_prefix(key, value);
+
+        self.tree
+            .scan_prefix(&prefix)
+            .map(|entry| {
+                let (tag_key, _) = entry?;
+                Ok(String::from_utf8(tag_key[prefix.len()..].to_vec())?)
+            })
+            .collect()
+    }
+
+    /// All tags of `account`
+    pub fn tags(&self, account: &str) -> Result<HashMap<String, String>> {
+        let prefix = format!("account\0{}\0", account);
+
+        self.tree
+            .scan_prefix(&prefix)
+            .map(|entry| {
+                let (tag_key, value) = entry?;
+                Ok((
+                    String::from_utf8(tag_key[prefix.len()..].to_vec())?,
+                    String::from_utf8(value.to_vec())?,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// 🔎 SLED-backed index of committed transfers by metadata entry
+///
+/// Keys are `{key}\0{value}\0{seq}` with a big-endian sequence from the
+/// database, so scanning a prefix backwards yields the newest transfers first.
+/// Values are the whole transfer, so the index still answers after a restart,
+/// when the engine's in-memory log is gone.
+///
+/// Each transfer's metadata is also kept under its id, which is what lets
+/// [`ZikZakEngine::account_transfers`](crate::ZikZakEngine::account_transfers)
+/// rebuild history from TigerBeetle. None of it travels inline in the
+/// TigerBeetle transfer: `user_data_128` and `user_data_32` hold the route hash
+/// and `user_data_64` the clock stamp, leaving no room. Metadata of any size is
+/// stored here, the only limit being SLED's own.
+#[derive(Clone)]
+pub struct MetadataIndex {
+    db: Db,
+    tree: Tree,
+    /// Transfer id -> its metadata as JSON
+    by_id: Tree,
+}
+
+impl MetadataIndex {
+    /// Open (or create) a metadata index on its own SLED database
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::from_db(&sled::open(db_path)?)
+    }
+
+    fn from_db(db: &Db) -> Result<Self> {
+        Ok(Self {
+            db: db.clone(),
+            tree: db.open_tree("transfer_metadata")?,
+            by_id: db.open_tree("transfer_metadata_by_id")?,
+        })
+    }
+
+    fn entry_prefix(key: &str, value: &str) -> String {
+        format!("{}\0{}\0", key, value)
+    }
+
+    /// Index `transfer` under each of its metadata entries
+    pub fn insert(&self, transfer: &Transfer) -> Result<()> {
+        if transfer.metadata.is_empty() {
+            return Ok(());
+        }
+
+        self.by_id.insert(
+            transfer.id.as_bytes(),
+            serde_json::to_vec(&transfer.metadata)?,
+        )?;
+        let seq = self.db.generate_id()?.to_be_bytes();
+        let record = serde_json::to_vec(transfer)?;
+        let mut batch = sled::Batch::default();
+        for (key, value) in &transfer.metadata {
+            let mut index_key = Self::entry_prefix(key, value).into_bytes();
+            index_key.extend_from_slice(&seq);
+            batch.insert(index_key, record.clone());
+        }
+        self.tree.apply_batch(batch)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Metadata of the transfer with `id`, `None` if it had none or was never indexed
+    pub fn metadata(&self, id: &str) -> Result<Option<HashMap<String, String>>> {
+        self.by_id
+            .get(id.as_bytes())?
+            .map(|record| Ok(serde_json::from_slice(&record)?))
+            .transpose()
+    }
+
+    /// Up to `limit` transfers whose metadata has `key=value`, newest first
+    pub fn find(&self, key: &str, value: &str, limit: usize) -> Result<Vec<Transfer>> {
+        self.tree
+            .scan_prefix(Self::entry_prefix(key, value))
+            .rev()
+            .take(limit)
+            .map(|entry| {
+                let (_, record) = entry?;
+                Ok(serde_json::from_slice(&record)?)
+            })
+            .collect()
+    }
+}
+
+/// 🪪 SLED-backed record of which account name owns which TigerBeetle id
+///
+/// Lets [`TigerBeetleClient`](crate::TigerBeetleClient) name accounts and detect
+/// hash collisions across restarts. Keys are big-endian ids, values the names.
+#[derive(Clone)]
+pub struct AccountNameStore {
+    db: Db,
+    tree: Tree,
+}
+
+impl AccountNameStore {
+    /// Open (or create) an account name store on its own SLED database
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::from_db(&sled::open(db_path)?)
+    }
+
+    fn from_db(db: &Db) -> Result<Self> {
+        Ok(Self {
+            db: db.clone(),
+            tree: db.open_tree("account_names")?,
+        })
+    }
+
+    /// Record that `name` owns `id`
+    pub fn insert(&self, id: u128, name: &str) -> Result<()> {
+        self.tree.insert(id.to_be_bytes(), name.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Every recorded `(id, name)`, by id
+    pub fn entries(&self) -> Result<Vec<(u128, String)>> {
+        self.tree
+            .iter()
+            .map(|entry| {
+                let (id, name) = entry?;
+                let id: [u8; 16] = id
+                    .as_ref()
+                    .try_into()
+                    .map_err(|_| anyhow!("Corrupt account name key: {:?}", id))?;
+                Ok((u128::from_be_bytes(id), String::from_utf8(name.to_vec())?))
+            })
+            .collect()
+    }
+}
+
+/// 📮 SLED tree of webhook deliveries that failed every attempt
+///
+/// Keys are a big-endian sequence from the database, so entries come out oldest
+/// first; values are [`DeadLetter`]s as JSON.
+#[derive(Clone)]
+pub struct WebhookDeadLetters {
+    db: Db,
+    tree: Tree,
+}
+
+impl WebhookDeadLetters {
+    /// Open (or create) a dead-letter tree on its own SLED database
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::from_db(&sled::open(db_path)?)
+    }
+
+    fn from_db(db: &Db) -> Result<Self> {
+        Ok(Self {
+            db: db.clone(),
+            tree: db.open_tree("webhook_dead_letters")?,
+        })
+    }
+
+    pub fn insert(&self, letter: &DeadLetter) -> Result<()> {
+        let seq = self.db.generate_id()?.to_be_bytes();
+        self.tree.insert(seq, serde_json::to_vec(letter)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Every dead letter, oldest first
+    pub fn entries(&self) -> Result<Vec<DeadLetter>> {
+        self.tree
+            .iter()
+            .map(|entry| {
+                let (_, letter) = entry?;
+                Ok(serde_json::from_slice(&letter)?)
+            })
+            .collect()
+    }
+}
+
+/// 📸 SLED-backed named balance checkpoints
+///
+/// `checkpoint:{name}:{account}` holds the account's net balance as a big-endian
+/// `i64`, and `checkpoint:{name}` the unix seconds the checkpoint was taken. A
+/// checkpoint is written in a single transaction and refused if its name is
+/// taken, so it never changes once written.
+#[derive(Clone)]
+pub struct CheckpointStore {
+    db: Db,
+    tree: Tree,
+}
+
+impl CheckpointStore {
+    /// Open (or create) a checkpoint store on its own SLED database
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::from_db(&sled::open(db_path)?)
+    }
+
+    fn from_db(db: &Db) -> Result<Self> {
+        Ok(Self {
+            db: db.clone(),
+            tree: db.open_tree("balance_checkpoints")?,
+        })
+    }
+
+    /// Record `balances` as checkpoint `name`; fails if `name` is already taken
+    ///
+    /// Names may not be empty or contain `:`, which separates them from accounts.
+    pub fn write(&self, name: &str, taken_at: u64, balances: &[(String, i64)]) -> Result<()> {
+        if name.is_empty() || name.contains(':') {
+            return Err(anyhow!("Invalid checkpoint name {:?}", name));
+        }
+
+        let marker = format!("checkpoint:{}", name);
+        let written = self.tree.transaction(|tree| {
+            if tree.get(&marker)?.is_some() {
+                return Ok(false);
+            }
+            tree.insert(marker.as_bytes(), &taken_at.to_be_bytes())?;
+            for (account, balance) in balances {
+                tree.insert(
+                    format!("checkpoint:{}:{}", name, account).as_bytes(),
+                    &balance.to_be_bytes(),
+                )?;
+            }
+            Ok(true)
+        });
+        let written = written.map_err(|e: TransactionError| anyhow!("{}", e))?;
+        if !written {
+            return Err(anyhow!("Checkpoint {} already exists", name));
+        }
+        self.db.flush()?;
+
+        info!("📸 Checkpoint {} holds {} balances", name, balances.len());
+        Ok(())
+    }
+
+    /// Unix seconds checkpoint `name` was taken at, `None` if there is no such checkpoint
+    pub fn taken_at(&self, name: &str) -> Result<Option<u64>> {
+        self.tree
+            .get(format!("checkpoint:{}", name))?
+            .map(|value| Ok(u64::from_be_bytes(value.as_ref().try_into()?)))
+            .transpose()
+    }
+
+    /// `account`'s balance in checkpoint `name`, `None` if it was not recorded there
+    pub fn balance(&self, name: &str, account: &str) -> Result<Option<i64>> {
+        self.tree
+            .get(format!("checkpoint:{}:{}", name, account))?
+            .map(|value| Ok(i64::from_be_bytes(value.as_ref().try_into()?)))
+            .transpose()
+    }
+}
+
+/// 📅 SLED-backed per-account daily outflow counters
+///
+/// The key is the UTC day (days since the unix epoch, big-endian `u64`, so one
+/// day's counters sit together) followed by the account; the value is what the
+/// account sent that day as a big-endian `i64`. Counting starts from zero again
+/// at UTC midnight. Past days stay until [`prune_before`](Self::prune_before).
+#[derive(Clone)]
+pub struct DailyOutflowStore {
+    db: Db,
+    tree: Tree,
+}
+
+impl DailyOutflowStore {
+    /// Open (or create) a daily outflow store on its own SLED database
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::from_db(&sled::open(db_path)?)
+    }
+
+    fn from_db(db: &Db) -> Result<Self> {
+        Ok(Self {
+            db: db.clone(),
+            tree: db.open_tree("daily_outflow")?,
+        })
+    }
+
+    fn key(account: &str, day: u64) -> Vec<u8> {
+        let mut key = day.to_be_bytes().to_vec();
+        key.extend_from_slice(account.as_bytes());
+        key
+    }
+
+    /// What `account` sent on UTC `day`
+    pub fn sent(&self, account: &str, day: u64) -> Result<i64> {
+        self.tree
+            .get(Self::key(account, day))?
+            .map(|value| Ok(i64::from_be_bytes(value.as_ref().try_into()?)))
+            .transpose()
+            .map(Option::unwrap_or_default)
+    }
+
+    /// Count `amount` more sent by `account` on UTC `day`, returning the day's total
+    pub fn add(&self, account: &str, day: u64, amount: i64) -> Result<i64> {
+        let total = self
+            .tree
+            .update_and_fetch(Self::key(account, day), |sent| {
+                let sent = sent
+                    .and_then(|value| value.try_into().ok())
+                    .map(i64::from_be_bytes)
+                    .unwrap_or(0);
+                Some(sent.saturating_add(amount).to_be_bytes().to_vec())
+            })?
+            .ok_or_else(|| anyhow!("Daily outflow of {} was not stored", account))?;
+        self.db.flush()?;
+        Ok(i64::from_be_bytes(total.as_ref().try_into()?))
+    }
+
+    /// Drop the counters of every day before `day`, returning how many went
+    pub fn prune_before(&self, day: u64) -> Result<usize> {
+        let mut pruned = 0;
+        for entry in self.tree.range(..day.to_be_bytes().to_vec()) {
+            let (key, _) = entry?;
+            self.tree.remove(key)?;
+            pruned += 1;
+        }
+        self.db.flush()?;
+        Ok(pruned)
+    }
+}
+
+/// 💱 SLED-backed currency codes of monetary accounts
+///
+/// Keyed by the full account name; the value is the code, e.g. `EUR`. Accounts
+/// without an entry hold no particular currency.
+#[derive(Clone)]
+pub struct CurrencyStore {
+    db: Db,
+    tree: Tree,
+}
+
+impl CurrencyStore {
+    /// Open (or create) a currency store on its own SLED database
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::from_db(&sled::open(db_path)?)
+    }
+
+    fn from_db(db: &Db) -> Result<Self> {
+        Ok(Self {
+            db: db.clone(),
+            tree: db.open_tree("account_currencies")?,
+        })
+    }
+
+    /// Record that `account` holds `currency`, replacing any earlier code
+    pub fn set(&self, account: &str, currency: &str) -> Result<()> {
+        self.tree.insert(account.as_bytes(), currency.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Currency of `account`, if one was recorded
+    pub fn get(&self, account: &str) -> Result<Option<String>> {
+        self.tree
+            .get(account.as_bytes())?
+            .map(|code| Ok(String::from_utf8(code.to_vec())?))
+            .transpose()
+    }
+}
+
+/// What [`ObjectStore::upload_object`] stored at `{bucket}/{path}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredObject {
+    /// Hex SHA-256 of the bytes
+    pub etag: String,
+    pub size: u64,
+}
+
+/// 🪣 SLED-backed object storage with content-addressed deduplication
+///
+/// `objects` maps `{bucket}/{path}` to a [`StoredObject`]; `object_content`
+/// holds each distinct content once under its SHA-256, as a big-endian `u64`
+/// count of the paths referring to it followed by the bytes. Uploading the same
+/// bytes again, to any path, only moves a reference, which makes retries
+/// idempotent. Content goes with its last reference.
+#[derive(Clone)]
+pub struct ObjectStore {
+    db: Db,
+    objects: Tree,
+    content: Tree,
+}
+
+impl ObjectStore {
+    /// Open (or create) an object store on its own SLED database
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::from_db(&sled::open(db_path)?)
+    }
+
+    fn from_db(db: &Db) -> Result<Self> {
+        Ok(Self {
+            db: db.clone(),
+            objects: db.open_tree("objects")?,
+            content: db.open_tree("object_content")?,
+        })
+    }
+
+    fn object_key(bucket: &str, path: &str) -> Result<String> {
+        if bucket.is_empty() || bucket.contains('/') || path.is_empty() {
+            return Err(anyhow!("Invalid object location {}/{}", bucket, path));
+        }
+        Ok(format!("{}/{}", bucket, path))
+    }
+
+    /// Store `bytes` at `{bucket}/{path}`, replacing whatever was there
+    ///
+    /// The returned `etag` is the hex SHA-256 of `bytes`.
+    pub fn upload_object(&self, bucket: &str, path: &str, bytes: &[u8]) -> Result<StoredObject> {
+        let key = Self::object_key(bucket, path)?;
+        let object = StoredObject {
+            etag: hex::encode(Sha256::digest(bytes)),
+            size: bytes.len() as u64,
+        };
+        let encoded = serde_json::to_vec(&object)?;
+
+        (&self.objects, &self.content)
+            .transaction(|(objects, content)| {
+                let previous = objects
+                    .get(&key)?
+                    .and_then(|data| serde_json::from_slice::<StoredObject>(&data).ok());
+                if previous.as_ref() == Some(&object) {
+                    return Ok(());
+                }
+
+                let refs = content
+                    .get(&object.etag)?
+                    .map_or(0, |data| content_refs(&data));
+                let mut entry = (refs + 1).to_be_bytes().to_vec();
+                entry.extend_from_slice(bytes);
+                content.insert(object.etag.as_bytes(), entry)?;
+                objects.insert(key.as_bytes(), encoded.clone())?;
+
+                if let Some(previous) = previous {
+                    release_content(content, &previous.etag)?;
+                }
+                Ok(())
+            })
+            .map_err(|e: TransactionError| anyhow!("{}", e))?;
+        self.db.flush()?;
 
-        stats.insert("total_records".to_string(), self.records_tree.len() as u64);
-        stats.insert(
-            "total_accounts".to_string(),
-            self.accounts_tree.len() as u64,
-        );
-        stats.insert(
-            "unique_content_hashes".to_string(),
-            self.content_hash_tree.len() as u64,
+        debug!(
+            "🪣 Stored {} ({} bytes, etag {})",
+            key, object.size, object.etag
         );
-        stats.insert("db_size_bytes".to_string(), self.db.size_on_disk()? as u64);
+        Ok(object)
+    }
 
-        Ok(stats)
+    /// What is stored at `{bucket}/{path}`, if anything
+    pub fn head_object(&self, bucket: &str, path: &str) -> Result<Option<StoredObject>> {
+        self.objects
+            .get(Self::object_key(bucket, path)?)?
+            .map(|data| Ok(serde_json::from_slice(&data)?))
+            .transpose()
     }
 
-    /// Compact database
-    pub async fn compact(&self) -> Result<()> {
-        info!("🗜️ Compacting SLED database...");
+    /// Bytes stored at `{bucket}/{path}`, if anything
+    pub fn get_object(&self, bucket: &str, path: &str) -> Result<Option<Vec<u8>>> {
+        let Some(object) = self.head_object(bucket, path)? else {
+            return Ok(None);
+        };
+        let data = self
+            .content
+            .get(&object.etag)?
+            .ok_or_else(|| anyhow!("Content {} of {}/{} is missing", object.etag, bucket, path))?;
+        Ok(Some(data[8..].to_vec()))
+    }
+
+    /// Remove `{bucket}/{path}`; `false` if nothing was stored there
+    pub fn delete_object(&self, bucket: &str, path: &str) -> Result<bool> {
+        let key = Self::object_key(bucket, path)?;
+        let deleted = (&self.objects, &self.content)
+            .transaction(|(objects, content)| {
+                let Some(data) = objects.remove(key.as_bytes())? else {
+                    return Ok(false);
+                };
+                if let Ok(previous) = serde_json::from_slice::<StoredObject>(&data) {
+                    release_content(content, &previous.etag)?;
+                }
+                Ok(true)
+            })
+            .map_err(|e: TransactionError| anyhow!("{}", e))?;
         self.db.flush()?;
-        Ok(())
+        Ok(deleted)
     }
 
-    /// Hash content for deduplication
-    fn hash_content(content: &str) -> i64 {
-        let mut hasher = Sha256::new();
-        hasher.update(content.as_bytes());
-        let result = hasher.finalize();
+    /// Distinct contents stored, however many paths refer to them
+    pub fn content_count(&self) -> usize {
+        self.content.len()
+    }
+}
 
-        // Take first 8 bytes and convert to i64
-        let bytes: [u8; 8] = result[0..8].try_into().unwrap();
-        i64::from_be_bytes(bytes)
+/// Reference count at the front of an `object_content` value
+fn content_refs(data: &[u8]) -> u64 {
+    data.get(..8)
+        .and_then(|refs| refs.try_into().ok())
+        .map_or(0, u64::from_be_bytes)
+}
+
+/// Drop one reference to `etag`'s content, removing it with the last one
+fn release_content(
+    content: &sled::transaction::TransactionalTree,
+    etag: &str,
+) -> sled::transaction::ConflictableTransactionResult<(), sled::Error> {
+    let Some(mut data) = content.get(etag)?.map(|data| data.to_vec()) else {
+        return Ok(());
+    };
+    let refs = content_refs(&data).saturating_sub(1);
+    if refs == 0 {
+        content.remove(etag.as_bytes())?;
+    } else {
+        data[..8].copy_from_slice(&refs.to_be_bytes());
+        content.insert(etag.as_bytes(), data)?;
     }
+    Ok(())
+}
+
+/// How [`ZikZakSledEngine::delete_entity`] deletes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeleteMode {
+    /// Move `{entity}:existence` to `system:deleted`; text fields stay in SLED
+    Soft,
+    /// Soft delete, then erase every SLED text field of the entity and record a
+    /// tombstone transfer to `{entity}:erased` carrying `reason`
+    Hard { reason: String },
 }
 
 /// 🦖 Enhanced ZIK_ZAK Engine with SLED VARCHAR support
 pub struct ZikZakSledEngine {
     pub accounting: crate::zik_zak::ZikZakEngine,
     pub varchar_store: SledVarCharStore,
+    /// Background compaction, when `COMPACT_INTERVAL_SECS` is set
+    compaction: Option<JoinHandle<()>>,
 }
 
 impl ZikZakSledEngine {
     /// Initialize ZIK_ZAK with both TigerBeetle and SLED
+    ///
+    /// Set `COMPACT_INTERVAL_SECS` to compact the SLED database periodically for
+    /// as long as the engine lives.
     pub async fn new<P: AsRef<Path>>(sled_db_path: P) -> Result<Self> {
-        let accounting = crate::zik_zak::ZikZakEngine::new().await?;
+        let mut accounting = crate::zik_zak::ZikZakEngine::new("").await?;
         let varchar_store = SledVarCharStore::new(sled_db_path)?;
+        accounting.set_tag_store(varchar_store.tag_store()?);
+        accounting.set_metadata_index(varchar_store.metadata_index()?)?;
+        accounting.set_account_name_store(varchar_store.account_names()?)?;
+        accounting.set_webhook_dead_letters(varchar_store.webhook_dead_letters()?);
+
+        let compaction = compaction_interval_from_env().map(|interval| {
+            info!("🗜️ Compacting SLED every {:?}", interval);
+            varchar_store.spawn_compaction(interval)
+        });
 
         Ok(Self {
             accounting,
             varchar_store,
+            compaction,
         })
     }
 
@@ -302,25 +1420,21 @@ impl ZikZakSledEngine {
         price_cents: i64,
         category: &str,
     ) -> Result<String> {
+        let product = Account::try_entity("product", product_id)?;
+        let genesis = Account::system("genesis");
+
         // 1. Create product existence (numeric)
-        let existence_account = format!("product:{}:existence", product_id);
         self.accounting
-            .transfer("system:genesis", &existence_account, 1, HashMap::new())
+            .transfer(&genesis, product.existence(), 1, HashMap::new())
             .await?;
 
         // 2. Set price (numeric)
-        let price_account = format!("product:{}:price", product_id);
         self.accounting
-            .transfer(
-                "system:genesis",
-                &price_account,
-                price_cents,
-                HashMap::new(),
-            )
+            .transfer(&genesis, product.price(), price_cents, HashMap::new())
             .await?;
 
         // 3. Store text fields (varchar)
-        let base_account = format!("product:{}", product_id);
+        let base_account = product.prefix();
 
         self.varchar_store
             .store_varchar(
@@ -353,39 +1467,43 @@ impl ZikZakSledEngine {
             .await?;
 
         info!(
-            "🛍️ Created product: {} ({}) - ${:.2}",
+            "🛍️ Created product: {} ({}) - {}",
             product_id,
             name,
-            price_cents as f64 / 100.0
+            self.accounting.format_amount(price_cents)
         );
         Ok(product_id.to_string())
     }
 
     /// Get complete product data
     pub async fn get_product(&self, product_id: &str) -> Result<Option<serde_json::Value>> {
-        // Check if product exists
-        let existence_account = format!("product:{}:existence", product_id);
-        let exists = self.accounting.get_balance(&existence_account).await? > 0;
+        let product = Account::try_entity("product", product_id)?;
 
-        if !exists {
+        // Check if product exists
+        if !self.accounting.exists(&product.prefix()).await? {
             return Ok(None);
         }
 
         // Get price
-        let price_account = format!("product:{}:price", product_id);
-        let price = self.accounting.get_balance(&price_account).await?;
+        let price = self.accounting.get_balance(product.price()).await?;
+
+        let price_major = self
+            .accounting
+            .currencies()
+            .for_ledger(self.accounting.ledger())
+            .map_or(price as f64, |currency| currency.to_major(price));
 
         // Get varchar fields
-        let base_account = format!("product:{}", product_id);
         let varchar_fields = self
             .varchar_store
-            .get_account_varchars(&base_account)
+            .get_account_varchars(&product.prefix())
             .await?;
 
         let product_data = serde_json::json!({
             "id": product_id,
             "price_cents": price,
-            "price_dollars": price as f64 / 100.0,
+            "price_dollars": price_major,
+            "price_display": self.accounting.format_amount(price),
             "name": varchar_fields.get("name").unwrap_or(&"Unknown".to_string()),
             "description": varchar_fields.get("description").unwrap_or(&"No description".to_string()),
             "category": varchar_fields.get("category").unwrap_or(&"Uncategorized".to_string()),
@@ -402,13 +1520,136 @@ impl ZikZakSledEngine {
         field_name: &str,
         new_value: &str,
     ) -> Result<()> {
-        let base_account = format!("product:{}", product_id);
+        let product = Account::try_entity("product", product_id)?;
+        product.try_field(field_name)?;
         self.varchar_store
-            .update_varchar(&base_account, field_name, new_value)
+            .update_varchar(&product.prefix(), field_name, new_value)
             .await?;
         Ok(())
     }
 
+    /// Insert many rows of `table` with a single linked TigerBeetle batch
+    ///
+    /// Each row is a JSON object. `id` is taken from the row (or generated), integers
+    /// and booleans become `{table}:{id}:{field}` balances, and strings (plus nested
+    /// values, as JSON text) go to SLED under `{table}:{id}`. Zero/false/null fields
+    /// need no transfer. If the batch is rejected nothing is written at all; SLED is
+    /// only touched after TigerBeetle has committed.
+    pub async fn insert_batch(
+        &mut self,
+        table: &str,
+        rows: Vec<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        use serde_json::Value;
+
+        let mut transfers = Vec::new();
+        let mut varchars = Vec::new();
+        let mut created = Vec::with_capacity(rows.len());
+
+        for row in rows {
+            let Value::Object(mut fields) = row else {
+                return Err(anyhow!("Rows for {} must be JSON objects", table));
+            };
+
+            let id = match fields.get("id") {
+                Some(Value::String(id)) => id.clone(),
+                Some(Value::Number(id)) => id.to_string(),
+                Some(other) => return Err(anyhow!("Invalid id for {}: {}", table, other)),
+                None => uuid::Uuid::new_v4().to_string(),
+            };
+            let entity = Account::try_entity(table, &id)?;
+            fields.insert("id".to_string(), Value::String(id));
+
+            let base_account = entity.prefix();
+            transfers.push(genesis_transfer(entity.existence().into_string(), 1));
+
+            for (field, value) in &fields {
+                if field == "id" {
+                    continue;
+                }
+                let account = entity.try_field(field)?.into_string();
+
+                match value {
+                    Value::Null | Value::Bool(false) => {}
+                    Value::Bool(true) => transfers.push(genesis_transfer(account, 1)),
+                    Value::Number(n) => match n.as_i64() {
+                        Some(0) => {}
+                        Some(amount) if amount > 0 => {
+                            transfers.push(genesis_transfer(account, amount))
+                        }
+                        _ => return Err(anyhow!("Unsupported number for {}: {}", account, n)),
+                    },
+                    Value::String(text) => {
+                        varchars.push((base_account.clone(), field.clone(), text.clone()))
+                    }
+                    nested => {
+                        varchars.push((base_account.clone(), field.clone(), nested.to_string()))
+                    }
+                }
+            }
+
+            created.push(Value::Object(fields));
+        }
+
+        self.accounting.transfer_batch(transfers).await?;
+
+        for (account, field, content) in varchars {
+            self.varchar_store
+                .store_varchar(&account, &field, &content, "text", HashMap::new())
+                .await?;
+        }
+
+        info!("📦 Inserted {} {} rows in one batch", created.len(), table);
+        Ok(serde_json::Value::Array(created))
+    }
+
+    /// Delete `entity` (e.g. `product:123`) softly or for good
+    ///
+    /// Fails with [`ZikZakError::AccountNotFound`] when the entity never existed, or
+    /// for [`DeleteMode::Soft`] when it is already deleted. A hard delete of a
+    /// soft-deleted entity still erases its text, for erasure requests that arrive
+    /// later. Numeric balances and the transfer log are kept either way.
+    pub async fn delete_entity(&mut self, entity: &str, mode: DeleteMode) -> Result<()> {
+        let existence = format!("{}:existence", entity);
+        let not_found = || {
+            anyhow::Error::from(ZikZakError::AccountNotFound {
+                account: entity.to_string(),
+            })
+        };
+        let balance = match self.accounting.get_balance(existence.as_str()).await {
+            Ok(balance) => balance,
+            Err(e) if is_account_not_found(&e) => return Err(not_found()),
+            Err(e) => return Err(e),
+        };
+
+        if balance > 0 {
+            let metadata = HashMap::from([("reason".to_string(), "delete".to_string())]);
+            self.accounting
+                .transfer(&existence, "system:deleted", balance, metadata)
+                .await?;
+        }
+
+        match mode {
+            DeleteMode::Soft if balance > 0 => Ok(()),
+            DeleteMode::Soft => Err(not_found()),
+            DeleteMode::Hard { reason } => {
+                let removed = self
+                    .varchar_store
+                    .delete_account_prefix(&format!("{}:", entity))
+                    .await?;
+                let metadata = HashMap::from([
+                    ("reason".to_string(), "erasure".to_string()),
+                    ("erasure_reason".to_string(), reason),
+                ]);
+                self.accounting
+                    .transfer("system:genesis", &format!("{}:erased", entity), 1, metadata)
+                    .await?;
+                info!("🗑️ Erased {} ({} text fields)", entity, removed);
+                Ok(())
+            }
+        }
+    }
+
     /// Get system statistics
     pub async fn get_system_stats(&self) -> Result<serde_json::Value> {
         let account_count = self.accounting.get_account_count().await?;
@@ -427,11 +1668,499 @@ impl ZikZakSledEngine {
     }
 }
 
+impl Drop for ZikZakSledEngine {
+    fn drop(&mut self) {
+        if let Some(compaction) = &self.compaction {
+            compaction.abort();
+        }
+    }
+}
+
+fn genesis_transfer(to_account: String, amount: i64) -> BatchTransfer {
+    BatchTransfer {
+        from_account: "system:genesis".to_string(),
+        to_account,
+        amount,
+        metadata: HashMap::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::MockClock;
     use tempfile::TempDir;
 
+    #[tokio::test]
+    async fn test_varchar_history_prune_and_revert() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let clock = Arc::new(MockClock::new(1_000_000));
+        let store = SledVarCharStore::new(temp_dir.path().join("history.db"))?
+            .with_max_versions(3)
+            .with_clock(clock.clone());
+
+        // One write a minute: v1 at 1000s, v2 at 1060s, ... v5 at 1240s
+        store
+            .store_varchar("product:1", "description", "v1", "text", HashMap::new())
+            .await?;
+        for content in ["v2", "v3", "v4", "v5"] {
+            clock.advance(Duration::from_secs(60));
+            store
+                .update_varchar("product:1", "description", content)
+                .await?;
+        }
+        // A field whose key shares the prefix must not leak into the history
+        store
+            .store_varchar(
+                "product:1",
+                "description:short",
+                "s1",
+                "text",
+                HashMap::new(),
+            )
+            .await?;
+        store
+            .update_varchar("product:1", "description:short", "s2")
+            .await?;
+
+        let contents = |records: Vec<VarCharRecord>| -> Vec<String> {
+            records.into_iter().map(|r| r.content).collect()
+        };
+        let history = store
+            .get_varchar_history("product:1", "description", 10)
+            .await?;
+        assert_eq!(contents(history), vec!["v4", "v3", "v2"]);
+        let latest = store
+            .get_varchar_history("product:1", "description", 1)
+            .await?;
+        assert_eq!(contents(latest), vec!["v4"]);
+
+        // Between the v3 and v4 writes, so v3 comes back
+        clock.advance(Duration::from_secs(60));
+        assert!(
+            store
+                .revert_varchar("product:1", "description", 1150)
+                .await?
+        );
+        let current = store
+            .get_varchar_record("product:1", "description")
+            .await?
+            .unwrap();
+        assert_eq!(current.content, "v3");
+        assert_eq!(current.version, 6);
+        assert_eq!(current.updated_at, 1300);
+        let history = store
+            .get_varchar_history("product:1", "description", 10)
+            .await?;
+        assert_eq!(contents(history), vec!["v5", "v4", "v3"]);
+
+        // v1 and v2 were pruned, nothing that old is left
+        assert!(
+            !store
+                .revert_varchar("product:1", "description", 1100)
+                .await?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_interned_content_is_shared_and_reclaimed() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let store =
+            SledVarCharStore::new(temp_dir.path().join("interned.db"))?.with_interning(true);
+        let shipping = "Standard shipping";
+
+        for order in ["order:1", "order:2", "order:3"] {
+            store
+                .store_varchar(order, "note", shipping, "text", HashMap::new())
+                .await?;
+        }
+        assert_eq!(store.content_pool.len(), 1);
+        assert_eq!(
+            store.get_varchar("order:2", "note").await?.as_deref(),
+            Some(shipping)
+        );
+        let record = store.get_varchar_record("order:3", "note").await?.unwrap();
+        assert_eq!(record.content, shipping);
+        assert_eq!(
+            store.find_by_content_hash(shipping).await?,
+            vec!["order:1:note", "order:2:note", "order:3:note"]
+        );
+
+        // Rewriting a field moves its reference; the old value stays readable in history
+        store.update_varchar("order:1", "note", "Express").await?;
+        assert!(
+            store
+                .update_varchar_cas("order:2", "note", 1, "Express")
+                .await?
+        );
+        assert_eq!(store.content_pool.len(), 2);
+        assert_eq!(
+            store.get_varchar_history("order:1", "note", 1).await?[0].content,
+            shipping
+        );
+
+        // The last referrer of each content takes it out of the pool
+        store.delete_varchar("order:3", "note").await?;
+        assert_eq!(store.content_pool.len(), 1);
+        store.delete_account_prefix("order:").await?;
+        assert_eq!(store.content_pool.len(), 0);
+
+        // A store without interning still reads pooled records
+        store
+            .store_varchar("order:4", "note", shipping, "text", HashMap::new())
+            .await?;
+        drop(store);
+        let plain = SledVarCharStore::new(temp_dir.path().join("interned.db"))?;
+        assert_eq!(
+            plain.get_varchar("order:4", "note").await?.as_deref(),
+            Some(shipping)
+        );
+        plain.update_varchar("order:4", "note", "Inline").await?;
+        assert_eq!(plain.content_pool.len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_index_finds_newest_first() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let index = MetadataIndex::new(temp_dir.path().join("metadata.db"))?;
+
+        for (id, order) in [("t1", "789"), ("t2", "7890"), ("t3", "789")] {
+            index.insert(&Transfer {
+                id: id.to_string(),
+                from_account: "user:1:balance".to_string(),
+                to_account: "merchant:revenue".to_string(),
+                amount: 1,
+                metadata: HashMap::from([("order_id".to_string(), order.to_string())]),
+                timestamp: 0,
+            })?;
+        }
+
+        let ids = |transfers: Vec<Transfer>| -> Vec<String> {
+            transfers.into_iter().map(|t| t.id).collect()
+        };
+        assert_eq!(ids(index.find("order_id", "789", 10)?), vec!["t3", "t1"]);
+        assert_eq!(ids(index.find("order_id", "789", 1)?), vec!["t3"]);
+        assert_eq!(ids(index.find("order_id", "7890", 10)?), vec!["t2"]);
+        assert!(index.find("order", "789", 10)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_by_id_survives_reopening() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("metadata.db");
+        let note = "x".repeat(64 * 1024);
+        {
+            let index = MetadataIndex::new(&path)?;
+            index.insert(&Transfer {
+                id: "t1".to_string(),
+                from_account: "user:1:balance".to_string(),
+                to_account: "merchant:revenue".to_string(),
+                amount: 1,
+                metadata: HashMap::from([("note".to_string(), note.clone())]),
+                timestamp: 0,
+            })?;
+        }
+
+        let index = MetadataIndex::new(&path)?;
+        assert_eq!(index.metadata("t1")?.unwrap()["note"], note);
+        assert!(index.metadata("t2")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkpoints_are_written_once() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let checkpoints = CheckpointStore::new(temp_dir.path().join("checkpoints.db"))?;
+        let balances = vec![
+            ("user:1:balance".to_string(), 250),
+            ("a:b:c".to_string(), -5),
+        ];
+
+        checkpoints.write("2026-09", 1_790_000_000, &balances)?;
+        assert_eq!(checkpoints.taken_at("2026-09")?, Some(1_790_000_000));
+        assert_eq!(checkpoints.balance("2026-09", "user:1:balance")?, Some(250));
+        assert_eq!(checkpoints.balance("2026-09", "a:b:c")?, Some(-5));
+        assert_eq!(checkpoints.balance("2026-09", "user:2:balance")?, None);
+
+        let rewrite = vec![("user:1:balance".to_string(), 0)];
+        assert!(checkpoints
+            .write("2026-09", 1_800_000_000, &rewrite)
+            .is_err());
+        assert_eq!(checkpoints.balance("2026-09", "user:1:balance")?, Some(250));
+        assert!(checkpoints.write("bad:name", 0, &rewrite).is_err());
+        assert_eq!(checkpoints.taken_at("2026-10")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_daily_outflow_counts_per_day() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let outflow = DailyOutflowStore::new(temp_dir.path().join("outflow.db"))?;
+
+        assert_eq!(outflow.sent("user:1:balance", 20_000)?, 0);
+        assert_eq!(outflow.add("user:1:balance", 20_000, 300)?, 300);
+        assert_eq!(outflow.add("user:1:balance", 20_000, 200)?, 500);
+        outflow.add("user:2:balance", 20_000, 7)?;
+        // Midnight starts a new count
+        assert_eq!(outflow.add("user:1:balance", 20_001, 50)?, 50);
+        assert_eq!(outflow.sent("user:1:balance", 20_000)?, 500);
+
+        assert_eq!(outflow.prune_before(20_001)?, 2);
+        assert_eq!(outflow.sent("user:1:balance", 20_000)?, 0);
+        assert_eq!(outflow.sent("user:1:balance", 20_001)?, 50);
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_uploads_are_idempotent_and_deduplicated() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let store = ObjectStore::new(temp_dir.path().join("objects.db"))?;
+        let logo = b"\x89PNG logo bytes";
+
+        let first = store.upload_object("avatars", "user/1.png", logo)?;
+        assert_eq!(first.etag, hex::encode(Sha256::digest(logo)));
+        assert_eq!(first.size, logo.len() as u64);
+        // A retried upload changes nothing
+        assert_eq!(store.upload_object("avatars", "user/1.png", logo)?, first);
+        // The same bytes elsewhere share the content
+        store.upload_object("avatars", "user/2.png", logo)?;
+        assert_eq!(store.content_count(), 1);
+
+        let replaced = store.upload_object("avatars", "user/1.png", b"new logo")?;
+        assert_ne!(replaced.etag, first.etag);
+        assert_eq!(store.content_count(), 2);
+        assert_eq!(
+            store.get_object("avatars", "user/1.png")?.unwrap(),
+            b"new logo"
+        );
+        assert_eq!(store.get_object("avatars", "user/2.png")?.unwrap(), logo);
+
+        // The old content goes with its last reference
+        assert!(store.delete_object("avatars", "user/2.png")?);
+        assert!(!store.delete_object("avatars", "user/2.png")?);
+        assert_eq!(store.content_count(), 1);
+        assert!(store.head_object("avatars", "user/2.png")?.is_none());
+        assert!(store.upload_object("", "x", logo).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_account_names_survive_reopening() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("names.db");
+        {
+            let names = AccountNameStore::new(&path)?;
+            names.insert(u128::MAX, "system:genesis")?;
+            names.insert(42, "user:1:balance")?;
+        }
+
+        let names = AccountNameStore::new(&path)?;
+        assert_eq!(
+            names.entries()?,
+            vec![
+                (42, "user:1:balance".to_string()),
+                (u128::MAX, "system:genesis".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_content_by_type() {
+        let cases = [
+            ("email", "ada@example.com", true),
+            ("email", "not-an-email", false),
+            ("email", "two@@example.com", false),
+            ("number", "42", true),
+            ("number", "-3.5", true),
+            ("number", "forty two", false),
+            ("url", "https://zikzak.dev/docs?x=1", true),
+            ("url", "zikzak.dev", false),
+            ("text", "anything at all", true),
+        ];
+        for (content_type, content, valid) in cases {
+            assert_eq!(
+                validate_content(content_type, content).is_ok(),
+                valid,
+                "{} {:?}",
+                content_type,
+                content
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validation_is_opt_in() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let lenient = SledVarCharStore::new(temp_dir.path().join("lenient.db"))?;
+        lenient
+            .store_varchar("user:1", "email", "not-an-email", "email", HashMap::new())
+            .await?;
+
+        let strict =
+            SledVarCharStore::new(temp_dir.path().join("strict.db"))?.with_validation(true);
+        let error = strict
+            .store_varchar("user:1", "email", "not-an-email", "email", HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ZikZakError>(),
+            Some(ZikZakError::InvalidContent { content_type, .. }) if content_type == "email"
+        ));
+
+        // Updates are checked against the stored content type
+        strict
+            .store_varchar(
+                "user:1",
+                "email",
+                "ada@example.com",
+                "email",
+                HashMap::new(),
+            )
+            .await?;
+        assert!(strict
+            .update_varchar("user:1", "email", "nope")
+            .await
+            .is_err());
+        assert_eq!(
+            strict.get_varchar("user:1", "email").await?.as_deref(),
+            Some("ada@example.com")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compaction_runs_alongside_writes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let store = SledVarCharStore::new(temp_dir.path().join("compact.db"))?;
+        let compaction = store.spawn_compaction(Duration::from_millis(5));
+
+        for i in 0..200 {
+            let field = format!("field_{}", i);
+            store
+                .store_varchar("doc:1", &field, &"x".repeat(512), "text", HashMap::new())
+                .await?;
+            if i % 50 == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+        store.delete_account_prefix("doc:1").await?;
+
+        // On demand, while the scheduled task is still running
+        store.compact().await?;
+        assert!(!compaction.is_finished());
+        compaction.abort();
+
+        store
+            .store_varchar("doc:2", "title", "still writable", "text", HashMap::new())
+            .await?;
+        assert_eq!(
+            store.get_varchar("doc:2", "title").await?.as_deref(),
+            Some("still writable")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_varchar_cas() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let store = SledVarCharStore::new(temp_dir.path().join("cas.db"))?;
+
+        store
+            .store_varchar("doc:1", "title", "Draft", "text", HashMap::new())
+            .await?;
+        let version = store
+            .get_varchar_record("doc:1", "title")
+            .await?
+            .unwrap()
+            .version;
+        assert_eq!(version, 1);
+
+        // First editor wins and bumps the version
+        assert!(
+            store
+                .update_varchar_cas("doc:1", "title", version, "Alice's title")
+                .await?
+        );
+
+        // Second editor still holds version 1 and is refused
+        assert!(
+            !store
+                .update_varchar_cas("doc:1", "title", version, "Bob's title")
+                .await?
+        );
+
+        let record = store.get_varchar_record("doc:1", "title").await?.unwrap();
+        assert_eq!(record.content, "Alice's title");
+        assert_eq!(record.version, 2);
+
+        // Version 0 means "must not exist yet"
+        assert!(!store.update_varchar_cas("doc:1", "title", 0, "x").await?);
+        assert!(store.update_varchar_cas("doc:2", "title", 0, "New").await?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_store_add_replace_remove() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let tags = TagStore::new(temp_dir.path().join("tags.db"))?;
+
+        tags.add("product:1:stock", "category", "electronics")?;
+        tags.add("product:2:stock", "category", "electronics")?;
+        tags.add("product:3:stock", "category", "books")?;
+        tags.add("product:1:stock", "region", "us:east")?;
+
+        assert_eq!(
+            tags.accounts("category", "electronics")?,
+            vec!["product:1:stock", "product:2:stock"]
+        );
+        assert_eq!(tags.tags("product:1:stock")?.len(), 2);
+
+        // Re-tagging moves the account to the new value
+        tags.add("product:2:stock", "category", "books")?;
+        assert_eq!(
+            tags.accounts("category", "electronics")?,
+            vec!["product:1:stock"]
+        );
+
+        assert!(tags.remove("product:1:stock", "category")?);
+        assert!(!tags.remove("product:1:stock", "category")?);
+        assert!(tags.accounts("category", "electronics")?.is_empty());
+        assert_eq!(tags.accounts("region", "us:east")?, vec!["product:1:stock"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_prefix() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let store = SledVarCharStore::new(temp_dir.path().join("prefix.db"))?;
+
+        for account in ["product:1:name", "product:1:sku", "product:10:name"] {
+            store
+                .store_varchar(account, "value", account, "text", HashMap::new())
+                .await?;
+        }
+
+        assert_eq!(store.delete_account_prefix("product:1:").await?, 2);
+        assert_eq!(store.get_varchar("product:1:name", "value").await?, None);
+        assert!(store
+            .get_account_varchars("product:1:sku")
+            .await?
+            .is_empty());
+        // `product:10` shares the digits but not the prefix
+        assert!(store
+            .get_varchar("product:10:name", "value")
+            .await?
+            .is_some());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_sled_varchar_storage() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -491,6 +2220,54 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_delete_entity_soft_then_hard() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut engine = ZikZakSledEngine::new(temp_dir.path().join("delete.db")).await?;
+
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let product = format!("product:{}", id);
+        engine
+            .create_product(&id, "Mug", "Holds coffee", 1200, "Kitchen")
+            .await?;
+
+        // Soft: gone for reads, text still stored
+        engine.delete_entity(&product, DeleteMode::Soft).await?;
+        assert!(engine.get_product(&id).await?.is_none());
+        assert_eq!(
+            engine.varchar_store.get_varchar(&product, "name").await?,
+            Some("Mug".to_string())
+        );
+        assert!(engine
+            .delete_entity(&product, DeleteMode::Soft)
+            .await
+            .is_err());
+
+        // Hard, after the fact: text erased and a tombstone with the reason
+        let reason = format!("gdpr-{}", id);
+        engine
+            .delete_entity(
+                &product,
+                DeleteMode::Hard {
+                    reason: reason.clone(),
+                },
+            )
+            .await?;
+        assert!(engine
+            .varchar_store
+            .get_account_varchars(&product)
+            .await?
+            .is_empty());
+        let tombstones = engine
+            .accounting
+            .find_transfers_by_metadata("erasure_reason", &reason, 10)
+            .await?;
+        assert_eq!(tombstones.len(), 1);
+        assert_eq!(tombstones[0].to_account, format!("{}:erased", product));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_complete_zik_zak_sled_engine() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -542,4 +2319,48 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_insert_batch_single_linked_batch() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db_path = temp_dir.path().join("test_batch.db");
+
+        let mut engine = ZikZakSledEngine::new(&db_path).await?;
+        let table = format!("widget_{}", uuid::Uuid::new_v4().simple());
+
+        let rows = (0..50)
+            .map(|i| serde_json::json!({ "id": i, "price": 100 + i, "name": format!("Widget {}", i) }))
+            .collect();
+        let created = engine.insert_batch(&table, rows).await?;
+        assert_eq!(created.as_array().unwrap().len(), 50);
+
+        // existence + price per row, all in the same batch
+        let history = engine.accounting.get_transaction_history().await?;
+        let batch_ids: std::collections::HashSet<_> = history
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["metadata"]["batch_id"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(history.as_array().unwrap().len(), 100);
+        assert_eq!(batch_ids.len(), 1);
+
+        for i in 0..50 {
+            let entity = format!("{}:{}", table, i);
+            assert!(engine.accounting.exists(&entity).await?);
+            assert_eq!(
+                engine
+                    .accounting
+                    .get_balance(format!("{}:price", entity))
+                    .await?,
+                100 + i
+            );
+            assert_eq!(
+                engine.varchar_store.get_varchar(&entity, "name").await?,
+                Some(format!("Widget {}", i))
+            );
+        }
+
+        Ok(())
+    }
 }
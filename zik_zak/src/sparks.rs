@@ -45,8 +45,102 @@
 //! ## Spark Operations
 //!
 //! - `transfer` - Move value between accounts (ZIK→ZAK flow)
-//! - `balance` - Check account balance with conditions
+//! - `balance` - Check account balance with conditions (`> 0`, `== N`, `>= N`). A
+//!   failed check raises `ConditionFailed` with the operation's `error` message,
+//!   interpolated with `{account}` and `{balance}` on top of the usual values, and
+//!   its `error_code`, the HTTP status the server answers with
 //! - `get_metadata` - Extract transaction metadata
+//! - `read_text` - Read the Sled text stored for `account` (`field` defaults to
+//!   `value`, where `"sled": true` transfers put it). Missing text is `null`, or
+//!   fails if `on_fail` is set. `balance` with `"sled": true` reads `value` the same way
+//! - `reserve` - Move `amount` from the stock `account` to `to` only if the stock
+//!   holds that much, checked by TigerBeetle in the same step so concurrent
+//!   reservations cannot oversell. Short stock takes the `on_fail` path
+//! - `set_status` - Set the status `account` to `amount` and stamp the time, in unix
+//!   seconds, on `{account}:{amount}:at`, so `order:{id}:status:2:at` says when the
+//!   order was shipped. With `transitions` (`{"0": [1], "1": [2, 3]}`, keyed by
+//!   the current status, 0 while unset) any other move fails with
+//!   `InvalidTransition`. The result is `{"status": …, "at": …}`
+//! - `counter` - Add (`"op": "inc"`, from `system:genesis`) or take away (`"op": "dec"`,
+//!   to `system:sold`) `amount` on `account`. A step that would leave it below `min`
+//!   or above `max` fails with `InsufficientBalance` / `LimitExceeded` and moves
+//!   nothing. The result is the new value
+//! - `hash_field` - Set `account` to the hash of the interpolated `amount` text, as
+//!   `hash(…)` in an amount would give it, so entities with the same content hold
+//!   the same hash. With `"sled": true` the text is kept too, for `read_text`. The
+//!   result is the hash
+//! - `await_balance` - Wait until `account` meets `condition`, reading its balance
+//!   every `poll_interval` milliseconds (default 1000) for up to `timeout`. The
+//!   result is the balance that met it; a timeout fails like a `balance` check.
+//!   Ignited with [`SparkEngine::ignite_spark_locked`], the engine lock is let go
+//!   between reads, so others can move money meanwhile - including into `account`
+//! - `compute` - Evaluate integer arithmetic (`{price} * 7 / 100`) without touching any account
+//! - `delete` - Move `{entity}:existence` to `system:deleted` and drop the entity's Sled text.
+//!   A missing entity is skipped with a warning, or fails if `on_fail` is set
+//! - `upsert` - Run the inline `create` operations if `{entity}:existence` is zero,
+//!   the `update` operations otherwise. The result is `"created"` or `"updated"`
+//! - `call_spark` - Ignite `spark` with `args` (interpolated) as its inputs. Its
+//!   result fields are available as `{op_N.field}` / `{store_as.field}`; calls
+//!   nested deeper than [`MAX_SPARK_DEPTH`] fail
+//! - `include` - Replaced by the operations of `fragment`, with `args` substituted,
+//!   when the spark file is loaded (see [`crate::fragments`])
+//!
+//! A spark's `default_metadata` is added to the `metadata` of each of its operations,
+//! whose own keys win, before interpolation; `{"source": "api"}` tags every transfer.
+//!
+//! Every operation result is kept as `{op_N}` for later operations; set `store_as`
+//! to also keep it under a readable name (`{tax}`). Operations inside an `upsert`
+//! branch share the same values: they see everything stored before them, the
+//! existence check as `{op_N_exists}`, and are kept as `{op_N_M}` themselves.
+//!
+//! Return templates can name their type: `"{op_0}:number"` gives a JSON number,
+//! `"{flag}:bool"` a boolean and `"{id}:string"` a string. Without a suffix, a
+//! template that is a single placeholder returns its value with the JSON type it
+//! has - `"{order_total}"` stays `2999` - and anything else is a string. A spark
+//! with `"return_mode": "strings"` turns every untyped template into a string, as
+//! sparks did before return types were inferred.
+//!
+//! Write `{{` and `}}` for literal braces, e.g. to store JSON as text. A
+//! placeholder naming no input or stored value is left as written, or fails the
+//! operation on an engine built [`with_strict_interpolation`](SparkEngine::with_strict_interpolation).
+//!
+//! Each ignition gets a fresh `recipe_run_id`, shared by the sparks it calls. It
+//! is a field of the ignition's tracing span, available as `{recipe_run_id}`, and
+//! added to the metadata of every transfer the run makes, so
+//! `TransferFilter { recipe_run_id, .. }` finds all of them.
+//!
+//! ## Atomic Sparks
+//!
+//! With `"atomic": true` a spark commits all of its transfers or none of them.
+//! `transfer` operations are planned - accounts and amounts interpolated - but
+//! only sent, as one linked TigerBeetle batch, after the last operation ran. A
+//! failure anywhere, or an `on_fail: "return"`, leaves the ledger untouched.
+//!
+//! Everything else still runs inline while planning, so `balance` conditions and
+//! `upsert` existence checks see the ledger as it was *before* the batch: a
+//! `balance` of an account the spark has just paid into does not include that
+//! payment yet. Transfer results are `null` until the batch commits; the real ids
+//! are what the `return` template sees. Operations that write on their own
+//! (`reserve`, `set_status`, `hash_field`, `delete`, `call_spark`, text transfers
+//! with `"sled": true`) cannot be part of an atomic spark and fail it before
+//! anything runs.
+//!
+//! ## Idempotent Ignition
+//!
+//! [`SparkEngine::ignite_spark_idempotent`] makes a spark safe to retry after a
+//! timeout. Each numeric `transfer` gets a TigerBeetle id derived from the
+//! idempotency key and the operation (`op_2`, `op_3_0` in an `upsert` branch,
+//! nested per `call_spark`), so a retried transfer that already went through is
+//! answered with `Exists` and not applied twice. Once the spark completes, its
+//! result is kept in Sled under the key, and later ignitions with that key return
+//! it without running anything. Other writes - text transfers, `reserve`, `counter`,
+//! `set_status`, `hash_field`, `delete` - do run again on a retry of a spark that
+//! failed part way.
+//!
+//! [`SparkEngine::ignite_spark_profiled`] additionally times every top-level
+//! operation on a monotonic clock and adds them to the result as
+//! `"steps": [{"op_type": "transfer", "ms": 1.25}, …]`, in execution order. An
+//! `upsert` or `call_spark` is one step, including everything it runs.
 //!
 //! ## Storage Strategy
 //!
@@ -68,17 +162,31 @@
 //! Just ignite sparks and watch your backend evolve in real-time.
 
 use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 
 use std::fs;
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
-use tracing::{debug, info};
-use xxhash_rust::xxh3::xxh3_64;
-
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, RwLockWriteGuard};
+use tracing::{debug, info, info_span, warn, Instrument};
+use uuid::Uuid;
+use xxhash_rust::xxh3::{xxh3_128, xxh3_64};
+
+use crate::error::ZikZakError;
+use crate::fragments;
+use crate::lint::{self, LintWarning};
 use crate::sled::SledVarCharStore;
-use crate::zik_zak::ZikZakEngine;
+use crate::zik_zak::{is_account_not_found, BatchTransfer, ZikZakEngine};
+
+/// How deep `call_spark` may nest before the spark is assumed to recurse forever
+pub const MAX_SPARK_DEPTH: usize = 16;
+
+/// Metadata key (and stored value) carrying the id of one spark ignition
+pub const RUN_ID_KEY: &str = "recipe_run_id";
 
 /// ZIK flow - what flows OUT (source, give, debit)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -164,15 +272,36 @@ pub struct Spark {
     pub operations: Vec<Operation>,
     #[serde(rename = "return")]
     pub return_value: Option<HashMap<String, String>>,
+    /// How `return` templates without a type suffix become JSON
+    #[serde(default)]
+    pub return_mode: ReturnMode,
+    /// Commit every transfer in one linked batch at the end (see the module docs)
+    #[serde(default)]
+    pub atomic: bool,
+    /// Metadata every operation gets, under its own `metadata` keys
+    #[serde(default)]
+    pub default_metadata: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How a spark's untyped return templates become JSON (see the module docs)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReturnMode {
+    /// A lone `{placeholder}` keeps its value's JSON type, anything else is a string
+    #[default]
+    Inferred,
+    /// Every untyped template is a string
+    Strings,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Operation {
     #[serde(rename = "type")]
     pub op_type: String,
     pub zik: Option<String>, // ZIK account (OUT)
     pub zak: Option<String>, // ZAK account (IN)
     pub account: Option<String>,
+    pub entity: Option<String>, // Entity prefix for `delete`, e.g. `product:{id}`
     pub amount: Option<Value>,
     pub condition: Option<String>,
     pub on_fail: Option<String>,
@@ -180,6 +309,109 @@ pub struct Operation {
     pub sled: Option<bool>,  // true = store text in Sled
     pub ledger: Option<u32>, // TigerBeetle ledger ID (defaults to 1)
     pub metadata: Option<HashMap<String, String>>,
+    pub expression: Option<String>,     // Arithmetic for `compute`
+    pub store_as: Option<String>,       // Extra name for the operation result
+    pub create: Option<Vec<Operation>>, // `upsert` branch when the entity is missing
+    pub update: Option<Vec<Operation>>, // `upsert` branch when the entity exists
+    pub spark: Option<String>,          // Spark ignited by `call_spark`
+    pub args: Option<HashMap<String, Value>>, // Inputs for `call_spark`, interpolated
+    pub to: Option<String>,             // Receiving account for `reserve`
+    pub transitions: Option<HashMap<String, Vec<i64>>>, // Legal `set_status` moves
+    pub op: Option<String>,             // `counter` direction: "inc" or "dec"
+    pub min: Option<Value>,             // Lowest value a `counter` may reach
+    pub max: Option<Value>,             // Highest value a `counter` may reach
+    pub fragment: Option<String>,       // Fragment expanded by `include`
+    pub error: Option<String>,          // `balance` failure message, interpolated
+    pub error_code: Option<u16>,        // HTTP status for a failed `balance` condition
+    pub timeout: Option<u64>,           // Longest `await_balance` wait, in milliseconds
+    pub poll_interval: Option<u64>,     // Milliseconds between `await_balance` reads
+}
+
+/// Operations an atomic spark may contain; anything else writes on its own
+const ATOMIC_OPERATIONS: [&str; 7] = [
+    "transfer",
+    "balance",
+    "await_balance",
+    "get_metadata",
+    "read_text",
+    "compute",
+    "upsert",
+];
+
+/// Time between `await_balance` reads unless the operation sets `poll_interval`
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The engine an ignition runs against
+///
+/// Borrowed for the whole run, or write-locked for it - except while an
+/// `await_balance` waits, which lets the lock go between its reads.
+enum Accounting<'a> {
+    Borrowed(&'a mut ZikZakEngine),
+    Locked {
+        lock: &'a RwLock<ZikZakEngine>,
+        /// `None` only inside `await_balance`
+        guard: Option<RwLockWriteGuard<'a, ZikZakEngine>>,
+    },
+}
+
+impl Deref for Accounting<'_> {
+    type Target = ZikZakEngine;
+
+    fn deref(&self) -> &ZikZakEngine {
+        match self {
+            Accounting::Borrowed(engine) => engine,
+            Accounting::Locked { guard, .. } => guard.as_deref().expect("engine lock is held"),
+        }
+    }
+}
+
+impl DerefMut for Accounting<'_> {
+    fn deref_mut(&mut self) -> &mut ZikZakEngine {
+        match self {
+            Accounting::Borrowed(engine) => engine,
+            Accounting::Locked { guard, .. } => guard.as_deref_mut().expect("engine lock is held"),
+        }
+    }
+}
+
+impl Accounting<'_> {
+    /// Net balance of `account`, 0 if it does not exist yet, read without
+    /// keeping a locked engine locked afterwards
+    async fn poll_balance(&mut self, account: &str) -> Result<i64> {
+        match self {
+            Accounting::Borrowed(engine) => balance_or_zero(engine, account).await,
+            Accounting::Locked { lock, guard } => {
+                *guard = None;
+                let engine = lock.read().await;
+                balance_or_zero(&engine, account).await
+            }
+        }
+    }
+
+    /// Lock the engine again after [`poll_balance`](Self::poll_balance)
+    async fn hold(&mut self) {
+        if let Accounting::Locked { lock, guard } = self {
+            if guard.is_none() {
+                *guard = Some(lock.write().await);
+            }
+        }
+    }
+}
+
+/// A transfer of an atomic spark, waiting for the batch
+struct PlannedTransfer {
+    /// `op_N` the transfer id is stored as once committed
+    key: String,
+    store_as: Option<String>,
+    transfer: BatchTransfer,
+}
+
+/// How long one operation of a profiled ignition took
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepTiming {
+    pub op_type: String,
+    /// Wall time in milliseconds, fractional
+    pub ms: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -189,12 +421,16 @@ pub struct SparkDefinition {
     pub description: String,
     pub primitives: HashMap<String, String>,
     pub entities: Value,
+    /// Operation lists sparks pull in with `include` (see [`crate::fragments`])
+    #[serde(default)]
+    pub fragments: HashMap<String, Vec<Operation>>,
     pub sparks: HashMap<String, Spark>,
 }
 
 pub struct SparkEngine {
     sparks: HashMap<String, Spark>,
     sled_store: SledVarCharStore,
+    strict_interpolation: bool,
 }
 
 // SAFETY: SparkEngine only contains HashMap<String, Spark> and SledVarCharStore
@@ -209,8 +445,12 @@ impl SparkEngine {
         let sparks_content = fs::read_to_string(sparks_file)
             .map_err(|e| anyhow!("Failed to read sparks file: {}", e))?;
 
-        let spark_def: SparkDefinition = serde_json::from_str(&sparks_content)
+        let mut spark_def: SparkDefinition = serde_json::from_str(&sparks_content)
             .map_err(|e| anyhow!("Failed to parse sparks JSON: {}", e))?;
+        fragments::expand(&mut spark_def.sparks, &spark_def.fragments)?;
+        for spark in spark_def.sparks.values_mut() {
+            merge_default_metadata(&mut spark.operations, &spark.default_metadata);
+        }
 
         let sled_store = SledVarCharStore::new(sled_db_path)?;
 
@@ -219,6 +459,7 @@ impl SparkEngine {
         Ok(Self {
             sparks: spark_def.sparks,
             sled_store,
+            strict_interpolation: false,
         })
     }
 
@@ -229,9 +470,17 @@ impl SparkEngine {
         Ok(Self {
             sparks: HashMap::new(),
             sled_store,
+            strict_interpolation: false,
         })
     }
 
+    /// Fail an operation whose templates name an unknown `{placeholder}`
+    /// instead of leaving the placeholder in place
+    pub fn with_strict_interpolation(mut self, strict: bool) -> Self {
+        self.strict_interpolation = strict;
+        self
+    }
+
     pub fn list_sparks(&self) -> Value {
         let mut spark_list = HashMap::new();
 
@@ -255,62 +504,475 @@ impl SparkEngine {
         zikzak: ZikZak,
         accounting: &mut ZikZakEngine,
     ) -> Result<Zak> {
-        let spark = self
-            .sparks
-            .get(spark_name)
-            .ok_or_else(|| anyhow!("Spark not found: {}", spark_name))?;
-
-        info!("⚡ Igniting spark: {}", spark_name);
-        let inputs = zikzak.inputs();
-        debug!("📥 Spark inputs: {:?}", inputs);
-
-        let mut stored_values = HashMap::new();
-
-        for (i, operation) in spark.operations.iter().enumerate() {
-            debug!("🔄 Executing operation {}: {:?}", i + 1, operation.op_type);
-
-            match self
-                .execute_operation(operation, &inputs, &stored_values, accounting)
-                .await
-            {
-                Ok(result) => {
-                    // Store result with operation index as key
-                    stored_values.insert(format!("op_{}", i), result);
-                }
-                Err(e) => {
-                    if let Some(on_fail) = &operation.on_fail {
-                        if on_fail.starts_with("return") {
-                            return Ok(Zak::new(HashMap::new()));
-                        } else if on_fail.starts_with("throw") {
-                            return Err(e);
-                        }
-                    }
-                    return Err(e);
-                }
-            }
+        let run_id = Uuid::new_v4().to_string();
+        let span = info_span!("spark", spark = spark_name, recipe_run_id = %run_id);
+        self.ignite_at_depth(
+            spark_name,
+            zikzak.inputs(),
+            &mut Accounting::Borrowed(accounting),
+            0,
+            run_id,
+            None,
+            None,
+        )
+        .instrument(span)
+        .await
+    }
+
+    /// [`ignite_spark`](Self::ignite_spark) on an engine shared behind `lock`
+    ///
+    /// The write lock is held for the run, except while an `await_balance` waits
+    /// between two reads. Other transfers can therefore land in the middle of
+    /// the run; the `recipe_run_id` returned with the result tells the run's own
+    /// transfers apart. Dropping the future stops a wait and releases the lock.
+    pub async fn ignite_spark_locked(
+        &self,
+        spark_name: &str,
+        zikzak: ZikZak,
+        lock: &RwLock<ZikZakEngine>,
+    ) -> Result<(Zak, String)> {
+        let run_id = Uuid::new_v4().to_string();
+        let span = info_span!("spark", spark = spark_name, recipe_run_id = %run_id);
+        let mut accounting = Accounting::Locked {
+            lock,
+            guard: Some(lock.write().await),
+        };
+        let result = self
+            .ignite_at_depth(
+                spark_name,
+                zikzak.inputs(),
+                &mut accounting,
+                0,
+                run_id.clone(),
+                None,
+                None,
+            )
+            .instrument(span)
+            .await?;
+        Ok((result, run_id))
+    }
+
+    /// [`ignite_spark`](Self::ignite_spark) that can be retried with the same `idempotency_key`
+    ///
+    /// The first ignition that completes stores its result; every later one with
+    /// the same key returns that result unchanged and moves nothing. See the
+    /// module docs for which operations a retry of a failed ignition repeats.
+    pub async fn ignite_spark_idempotent(
+        &self,
+        spark_name: &str,
+        zikzak: ZikZak,
+        idempotency_key: &str,
+        accounting: &mut ZikZakEngine,
+    ) -> Result<Zak> {
+        let cache_account = format!("idempotency:{}", idempotency_key);
+        if let Some(cached) = self
+            .sled_store
+            .get_varchar(&cache_account, "result")
+            .await?
+        {
+            info!(
+                "♻️ Spark {} already ran for key {}, returning its result",
+                spark_name, idempotency_key
+            );
+            return Ok(serde_json::from_str(&cached)?);
         }
 
+        let run_id = Uuid::new_v4().to_string();
+        let span = info_span!("spark", spark = spark_name, recipe_run_id = %run_id);
+        let result = self
+            .ignite_at_depth(
+                spark_name,
+                zikzak.inputs(),
+                &mut Accounting::Borrowed(accounting),
+                0,
+                run_id,
+                None,
+                Some(idempotency_key.to_string()),
+            )
+            .instrument(span)
+            .await?;
+
+        let metadata = HashMap::from([("spark".to_string(), spark_name.to_string())]);
+        self.sled_store
+            .store_varchar(
+                &cache_account,
+                "result",
+                &serde_json::to_string(&result)?,
+                "application/json",
+                metadata,
+            )
+            .await?;
+        Ok(result)
+    }
+
+    /// [`ignite_spark`](Self::ignite_spark), with the result's `steps` timing each operation
+    pub async fn ignite_spark_profiled(
+        &self,
+        spark_name: &str,
+        zikzak: ZikZak,
+        accounting: &mut ZikZakEngine,
+    ) -> Result<Zak> {
+        let run_id = Uuid::new_v4().to_string();
+        let span = info_span!("spark", spark = spark_name, recipe_run_id = %run_id);
+        let mut steps = Vec::new();
+        let result = self
+            .ignite_at_depth(
+                spark_name,
+                zikzak.inputs(),
+                &mut Accounting::Borrowed(accounting),
+                0,
+                run_id,
+                Some(&mut steps),
+                None,
+            )
+            .instrument(span)
+            .await?;
+
+        let mut result = result.into_map();
+        result.insert("steps".to_string(), serde_json::to_value(steps)?);
+        Ok(Zak::new(result))
+    }
+
+    /// `ignite_spark` for a spark `depth` levels deep in `call_spark` operations
+    ///
+    /// With `steps`, the time each operation took is appended to it. With
+    /// `idempotency`, transfer ids are derived from it (see [`transfer_id`]).
+    #[allow(clippy::too_many_arguments)]
+    fn ignite_at_depth<'a>(
+        &'a self,
+        spark_name: &'a str,
+        inputs: HashMap<String, Value>,
+        accounting: &'a mut Accounting<'_>,
+        depth: usize,
+        run_id: String,
+        steps: Option<&'a mut Vec<StepTiming>>,
+        idempotency: Option<String>,
+    ) -> BoxFuture<'a, Result<Zak>> {
+        Box::pin(async move {
+            let spark = self
+                .sparks
+                .get(spark_name)
+                .ok_or_else(|| anyhow!("Spark not found: {}", spark_name))?;
+
+            info!("⚡ Igniting spark: {}", spark_name);
+            debug!("📥 Spark inputs: {:?}", inputs);
+
+            if spark.atomic {
+                check_atomic(&spark.operations)
+                    .map_err(|e| anyhow!("Atomic spark {}: {}", spark_name, e))?;
+            }
+
+            let mut stored_values =
+                HashMap::from([(RUN_ID_KEY.to_string(), Value::String(run_id))]);
+            let mut batch = spark.atomic.then(Vec::new);
+
+            let completed = self
+                .run_operations(
+                    &spark.operations,
+                    "op_".to_string(),
+                    &inputs,
+                    &mut stored_values,
+                    accounting,
+                    depth,
+                    steps,
+                    batch.as_mut(),
+                    idempotency.as_deref(),
+                )
+                .await?;
+            if !completed {
+                // Planned transfers of an atomic spark are dropped unsent
+                return Ok(Zak::new(HashMap::new()));
+            }
+
+            if let Some(batch) = batch {
+                commit_batch(batch, &mut stored_values, accounting).await?;
+            }
+
+            self.build_return(spark, &inputs, &stored_values)
+        })
+    }
+
+    fn build_return(
+        &self,
+        spark: &Spark,
+        inputs: &HashMap<String, Value>,
+        stored_values: &HashMap<String, Value>,
+    ) -> Result<Zak> {
         // Build return value
         if let Some(return_template) = &spark.return_value {
             let mut result = HashMap::new();
 
             for (key, template) in return_template {
-                let value = self.interpolate(template, &inputs, &stored_values);
-                result.insert(key.clone(), serde_json::to_value(value)?);
+                let (template, kind) = split_return_type(template);
+                let inferred = match (kind, spark.return_mode) {
+                    (None, ReturnMode::Inferred) => sole_placeholder(template)
+                        .and_then(|name| inputs.get(name).or_else(|| stored_values.get(name))),
+                    _ => None,
+                };
+                let value = match inferred {
+                    Some(value) => value.clone(),
+                    None => {
+                        let value = self.interpolate(template, inputs, stored_values)?;
+                        coerce_return_value(&value, kind.unwrap_or("string"))?
+                    }
+                };
+                result.insert(key.clone(), value);
             }
 
             Ok(Zak::new(result))
         } else {
-            Ok(Zak::new(stored_values))
+            Ok(Zak::new(stored_values.clone()))
         }
     }
 
+    /// Run `operations` in order, keeping each result as `{<prefix>N}`
+    ///
+    /// Returns `false` when an `on_fail: "return"` stopped the spark early. Boxed
+    /// because `upsert` branches recurse back in here. Operation timings are
+    /// logged, and appended to `steps` when given. With `batch`, transfers are
+    /// planned into it instead of executed; with `idempotency`, they get fixed ids.
+    #[allow(clippy::too_many_arguments)]
+    fn run_operations<'a>(
+        &'a self,
+        operations: &'a [Operation],
+        prefix: String,
+        inputs: &'a HashMap<String, Value>,
+        stored: &'a mut HashMap<String, Value>,
+        accounting: &'a mut Accounting<'_>,
+        depth: usize,
+        mut steps: Option<&'a mut Vec<StepTiming>>,
+        mut batch: Option<&'a mut Vec<PlannedTransfer>>,
+        idempotency: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<bool>> {
+        Box::pin(async move {
+            for (i, operation) in operations.iter().enumerate() {
+                debug!(
+                    "🔄 Executing operation {}{}: {:?}",
+                    prefix, i, operation.op_type
+                );
+                let key = format!("{}{}", prefix, i);
+                let started = Instant::now();
+
+                let result = match (
+                    operation.op_type.as_str(),
+                    batch.as_deref_mut(),
+                    idempotency,
+                ) {
+                    ("transfer", Some(batch), _) => self
+                        .plan_transfer(operation, inputs, stored)
+                        .map(|transfer| {
+                            batch.push(PlannedTransfer {
+                                key: key.clone(),
+                                store_as: operation.store_as.clone(),
+                                transfer,
+                            });
+                            Some(Value::Null)
+                        }),
+                    ("transfer", None, Some(scope)) if !operation.sled.unwrap_or(false) => {
+                        let transfer_id = transfer_id(scope, &key);
+                        self.execute_transfer_once(
+                            operation,
+                            transfer_id,
+                            inputs,
+                            stored,
+                            accounting,
+                        )
+                        .await
+                        .map(Some)
+                    }
+                    ("upsert", batch, idempotency) => {
+                        self.execute_upsert(
+                            operation,
+                            &key,
+                            inputs,
+                            stored,
+                            accounting,
+                            depth,
+                            batch,
+                            idempotency,
+                        )
+                        .await
+                    }
+                    ("call_spark", _, idempotency) => self
+                        .execute_call_spark(
+                            operation,
+                            inputs,
+                            stored,
+                            accounting,
+                            depth,
+                            idempotency.map(|scope| format!("{}/{}", scope, key)),
+                        )
+                        .await
+                        .map(Some),
+                    _ => self
+                        .execute_operation(operation, inputs, stored, accounting)
+                        .await
+                        .map(Some),
+                };
+
+                let ms = started.elapsed().as_secs_f64() * 1000.0;
+                debug!(
+                    "⏱️ Operation {} ({}) took {:.3}ms",
+                    key, operation.op_type, ms
+                );
+                if let Some(steps) = steps.as_deref_mut() {
+                    steps.push(StepTiming {
+                        op_type: operation.op_type.clone(),
+                        ms,
+                    });
+                }
+
+                match result {
+                    Ok(Some(result)) => {
+                        if let Some(name) = &operation.store_as {
+                            store_result(stored, name, &result);
+                        }
+                        // Store result with operation index as key
+                        store_result(stored, &key, &result);
+                    }
+                    // A branch operation returned early; so does the spark
+                    Ok(None) => return Ok(false),
+                    Err(e) => {
+                        if let Some(on_fail) = &operation.on_fail {
+                            if on_fail.starts_with("return") {
+                                return Ok(false);
+                            } else if on_fail.starts_with("throw") {
+                                return Err(e);
+                            }
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+
+            Ok(true)
+        })
+    }
+
+    /// Create-or-update: pick the `create` or `update` branch by existence
+    ///
+    /// `None` means an operation in the branch stopped the spark early.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_upsert(
+        &self,
+        operation: &Operation,
+        key: &str,
+        inputs: &HashMap<String, Value>,
+        stored: &mut HashMap<String, Value>,
+        accounting: &mut Accounting<'_>,
+        depth: usize,
+        batch: Option<&mut Vec<PlannedTransfer>>,
+        idempotency: Option<&str>,
+    ) -> Result<Option<Value>> {
+        let entity = self.interpolate(
+            operation
+                .entity
+                .as_ref()
+                .ok_or(anyhow!("Missing 'entity' field"))?,
+            inputs,
+            stored,
+        )?;
+
+        let exists = accounting.exists(&entity).await?;
+        let (outcome, branch) = if exists {
+            ("updated", operation.update.as_ref())
+        } else {
+            ("created", operation.create.as_ref())
+        };
+        let branch = branch.ok_or_else(|| {
+            anyhow!(
+                "Missing '{}' operations for upsert of {}",
+                if exists { "update" } else { "create" },
+                entity
+            )
+        })?;
+
+        debug!("🔀 Upsert of {}: {}", entity, outcome);
+        stored.insert(format!("{}_exists", key), Value::Bool(exists));
+
+        let completed = self
+            .run_operations(
+                branch,
+                format!("{}_", key),
+                inputs,
+                stored,
+                accounting,
+                depth,
+                None,
+                batch,
+                idempotency,
+            )
+            .await?;
+
+        Ok(completed.then(|| Value::String(outcome.to_string())))
+    }
+
+    /// Ignite another spark with interpolated `args` and return its result object
+    ///
+    /// `idempotency` is the scope of the called spark's transfer ids.
+    async fn execute_call_spark(
+        &self,
+        operation: &Operation,
+        inputs: &HashMap<String, Value>,
+        stored: &HashMap<String, Value>,
+        accounting: &mut Accounting<'_>,
+        depth: usize,
+        idempotency: Option<String>,
+    ) -> Result<Value> {
+        let spark_name = operation
+            .spark
+            .as_ref()
+            .ok_or(anyhow!("Missing 'spark' field"))?;
+
+        if depth >= MAX_SPARK_DEPTH {
+            return Err(anyhow!(
+                "Spark call depth limit ({}) exceeded calling {}",
+                MAX_SPARK_DEPTH,
+                spark_name
+            ));
+        }
+
+        let args = operation
+            .args
+            .iter()
+            .flatten()
+            .map(|(name, value)| {
+                let value = match value {
+                    Value::String(template) => {
+                        Value::String(self.interpolate(template, inputs, stored)?)
+                    }
+                    other => other.clone(),
+                };
+                Ok((name.clone(), value))
+            })
+            .collect::<Result<_>>()?;
+
+        debug!("📞 Calling spark {} (depth {})", spark_name, depth + 1);
+        let run_id = match stored.get(RUN_ID_KEY) {
+            Some(Value::String(run_id)) => run_id.clone(),
+            _ => Uuid::new_v4().to_string(),
+        };
+        let result = self
+            .ignite_at_depth(
+                spark_name,
+                args,
+                accounting,
+                depth + 1,
+                run_id,
+                None,
+                idempotency,
+            )
+            .await?;
+
+        Ok(Value::Object(result.into_map().into_iter().collect()))
+    }
+
     async fn execute_operation(
         &self,
         operation: &Operation,
         inputs: &HashMap<String, Value>,
         stored: &HashMap<String, Value>,
-        accounting: &mut ZikZakEngine,
+        accounting: &mut Accounting<'_>,
     ) -> Result<Value> {
         match operation.op_type.as_str() {
             "transfer" => {
@@ -321,7 +983,7 @@ impl SparkEngine {
                         .ok_or(anyhow!("Missing 'zik' field"))?,
                     inputs,
                     stored,
-                );
+                )?;
                 let zak_account = self.interpolate(
                     operation
                         .zak
@@ -329,16 +991,12 @@ impl SparkEngine {
                         .ok_or(anyhow!("Missing 'zak' field"))?,
                     inputs,
                     stored,
-                );
+                )?;
 
                 let is_sled = operation.sled.unwrap_or(false);
                 let ledger_id = operation.ledger.unwrap_or(1);
 
-                let metadata = operation
-                    .metadata
-                    .as_ref()
-                    .map(|m| self.interpolate_metadata(m, inputs, stored))
-                    .unwrap_or_default();
+                let metadata = self.operation_metadata(operation, inputs, stored)?;
 
                 if is_sled {
                     // Text storage: Store in Sled and create TigerBeetle reference
@@ -350,7 +1008,7 @@ impl SparkEngine {
                             .to_string(),
                         inputs,
                         stored,
-                    );
+                    )?;
 
                     debug!(
                         "Executing text transfer: {} -> {} ({})",
@@ -408,61 +1066,25 @@ impl SparkEngine {
                         .ok_or(anyhow!("Missing 'account' field"))?,
                     inputs,
                     stored,
-                );
+                )?;
 
                 let is_sled = operation.sled.unwrap_or(false);
 
                 if is_sled {
-                    // Text balance: Get from Sled using TigerBeetle user_data_128 as key
-                    let tb_balance = accounting.get_balance(&account).await?;
-
-                    if tb_balance > 0 {
-                        // TigerBeetle has reference, get user_data_128 for Sled key
-                        // For now, try direct Sled lookup with account name
-                        match self.sled_store.get_varchar(&account, "value").await? {
-                            Some(content) => Ok(Value::String(content)),
-                            None => Ok(Value::Null),
-                        }
-                    } else {
-                        Ok(Value::Null)
-                    }
+                    // Text balance: the same lookup as `read_text`
+                    Ok(self
+                        .read_text(&account, "value")
+                        .await?
+                        .map_or(Value::Null, Value::String))
                 } else {
                     // Numeric/boolean/enum balance: Direct TigerBeetle
                     let balance = accounting.get_balance(&account).await?;
 
                     if let Some(condition) = &operation.condition {
-                        if condition == "> 0" && balance <= 0 {
-                            return Err(anyhow!(
-                                "Balance condition failed: {} = {}",
-                                account,
-                                balance
-                            ));
-                        }
-                        if condition.starts_with("== ") {
-                            let expected: i64 = condition[3..]
-                                .parse()
-                                .map_err(|_| anyhow!("Invalid balance condition: {}", condition))?;
-                            if balance != expected {
-                                return Err(anyhow!(
-                                    "Balance condition failed: {} = {} (expected {})",
-                                    account,
-                                    balance,
-                                    expected
-                                ));
-                            }
-                        }
-                        if condition.starts_with(">= ") {
-                            let min_balance: i64 = condition[3..]
-                                .parse()
-                                .map_err(|_| anyhow!("Invalid balance condition: {}", condition))?;
-                            if balance < min_balance {
-                                return Err(anyhow!(
-                                    "Balance condition failed: {} = {} (expected >= {})",
-                                    account,
-                                    balance,
-                                    min_balance
-                                ));
-                            }
+                        if let Some(default) = failed_condition(condition, &account, balance)? {
+                            return Err(self.condition_failed(
+                                operation, inputs, stored, account, balance, default,
+                            )?);
                         }
                     }
 
@@ -477,7 +1099,7 @@ impl SparkEngine {
                         .ok_or(anyhow!("Missing 'account' field"))?,
                     inputs,
                     stored,
-                );
+                )?;
                 let field = operation
                     .field
                     .as_ref()
@@ -492,10 +1114,383 @@ impl SparkEngine {
                 // In a real implementation, we'd parse the transaction history
                 Ok(Value::String(format!("{}_{}", account, field)))
             }
+            "read_text" => {
+                let account = self.interpolate(
+                    operation
+                        .account
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'account' field"))?,
+                    inputs,
+                    stored,
+                )?;
+                let field = operation.field.as_deref().unwrap_or("value");
+
+                match self.read_text(&account, field).await? {
+                    Some(content) => Ok(Value::String(content)),
+                    None if operation.on_fail.is_some() => {
+                        Err(anyhow!("No text stored for {} ({})", account, field))
+                    }
+                    None => Ok(Value::Null),
+                }
+            }
+            "reserve" => {
+                let stock = self.interpolate(
+                    operation
+                        .account
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'account' field"))?,
+                    inputs,
+                    stored,
+                )?;
+                let to = self.interpolate(
+                    operation.to.as_ref().ok_or(anyhow!("Missing 'to' field"))?,
+                    inputs,
+                    stored,
+                )?;
+                let amount = self.evaluate_amount(
+                    operation
+                        .amount
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'amount' field"))?,
+                    inputs,
+                    stored,
+                )?;
+                let metadata = self.operation_metadata(operation, inputs, stored)?;
+
+                let transfer_id = accounting
+                    .reserve_stock(&stock, &to, amount, metadata)
+                    .await?;
+                Ok(Value::String(transfer_id))
+            }
+            "counter" => {
+                let account = self.interpolate(
+                    operation
+                        .account
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'account' field"))?,
+                    inputs,
+                    stored,
+                )?;
+                let amount = self.evaluate_amount(
+                    operation
+                        .amount
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'amount' field"))?,
+                    inputs,
+                    stored,
+                )?;
+                if amount <= 0 {
+                    return Err(anyhow!("Counter amount must be positive, got {}", amount));
+                }
+                let min = operation
+                    .min
+                    .as_ref()
+                    .map(|min| self.evaluate_amount(min, inputs, stored))
+                    .transpose()?;
+                let max = operation
+                    .max
+                    .as_ref()
+                    .map(|max| self.evaluate_amount(max, inputs, stored))
+                    .transpose()?;
+
+                let balance = balance_or_zero(accounting, &account).await?;
+                let (from, to, value) = match operation.op.as_deref() {
+                    Some("inc") => (
+                        "system:genesis",
+                        account.as_str(),
+                        balance.checked_add(amount),
+                    ),
+                    Some("dec") => (account.as_str(), "system:sold", balance.checked_sub(amount)),
+                    other => {
+                        return Err(anyhow!(
+                            "Counter 'op' must be \"inc\" or \"dec\", got {:?}",
+                            other
+                        ))
+                    }
+                };
+                let value = value.ok_or_else(|| anyhow!("Counter {} overflows", account))?;
+
+                if let Some(min) = min.filter(|min| value < *min) {
+                    return Err(ZikZakError::InsufficientBalance {
+                        account,
+                        balance,
+                        required: min.saturating_add(amount),
+                    }
+                    .into());
+                }
+                if let Some(max) = max.filter(|max| value > *max) {
+                    return Err(ZikZakError::LimitExceeded {
+                        account,
+                        limit: "max".to_string(),
+                        amount: value,
+                        max,
+                    }
+                    .into());
+                }
+
+                let metadata = self.operation_metadata(operation, inputs, stored)?;
+                accounting.transfer(from, to, amount, metadata).await?;
+                Ok(Value::from(value))
+            }
+            "set_status" => {
+                let account = self.interpolate(
+                    operation
+                        .account
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'account' field"))?,
+                    inputs,
+                    stored,
+                )?;
+                let status = self.evaluate_amount(
+                    operation
+                        .amount
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'amount' field"))?,
+                    inputs,
+                    stored,
+                )?;
+                let current = balance_or_zero(accounting, &account).await?;
+
+                if let Some(transitions) = &operation.transitions {
+                    let allowed = transitions
+                        .get(&current.to_string())
+                        .is_some_and(|next| next.contains(&status));
+                    if !allowed {
+                        return Err(ZikZakError::InvalidTransition {
+                            account,
+                            from: current.to_string(),
+                            to: status.to_string(),
+                        }
+                        .into());
+                    }
+                }
+
+                let metadata = self.operation_metadata(operation, inputs, stored)?;
+                let at = accounting.now_secs() as i64;
+                debug!("🚦 {} {} -> {} at {}", account, current, status, at);
+                set_balance(accounting, &account, current, status, metadata.clone()).await?;
+                let stamp = format!("{}:{}:at", account, status);
+                let previous = balance_or_zero(accounting, &stamp).await?;
+                set_balance(accounting, &stamp, previous, at, metadata).await?;
+
+                Ok(json!({ "status": status, "at": at }))
+            }
+            "hash_field" => {
+                let account = self.interpolate(
+                    operation
+                        .account
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'account' field"))?,
+                    inputs,
+                    stored,
+                )?;
+                let value = self.interpolate(
+                    operation
+                        .amount
+                        .as_ref()
+                        .and_then(Value::as_str)
+                        .ok_or(anyhow!("Missing 'amount' text to hash"))?,
+                    inputs,
+                    stored,
+                )?;
+
+                let hash = ZikZakEngine::hash_string(&value);
+                let metadata = self.operation_metadata(operation, inputs, stored)?;
+                debug!("#️⃣ {} = hash of {} bytes", account, value.len());
+                if operation.sled.unwrap_or(false) {
+                    self.sled_store
+                        .store_varchar(&account, "value", &value, "text/plain", metadata.clone())
+                        .await?;
+                }
+                let current = balance_or_zero(accounting, &account).await?;
+                set_balance(accounting, &account, current, hash, metadata).await?;
+
+                Ok(Value::from(hash))
+            }
+            "await_balance" => {
+                let account = self.interpolate(
+                    operation
+                        .account
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'account' field"))?,
+                    inputs,
+                    stored,
+                )?;
+                let condition = operation
+                    .condition
+                    .as_ref()
+                    .ok_or(anyhow!("Missing 'condition' field"))?;
+                let timeout = Duration::from_millis(
+                    operation
+                        .timeout
+                        .ok_or(anyhow!("Missing 'timeout' field"))?,
+                );
+                let poll_interval = operation
+                    .poll_interval
+                    .map_or(DEFAULT_POLL_INTERVAL, Duration::from_millis);
+
+                debug!("⏳ Awaiting {} {} for {:?}", account, condition, timeout);
+                let waited =
+                    await_balance(accounting, &account, condition, timeout, poll_interval).await;
+                accounting.hold().await;
+                match waited? {
+                    Ok(balance) => Ok(Value::from(balance)),
+                    Err((balance, unmet)) => {
+                        let default = format!("Timed out after {:?}: {}", timeout, unmet);
+                        Err(self.condition_failed(
+                            operation, inputs, stored, account, balance, default,
+                        )?)
+                    }
+                }
+            }
+            "compute" => {
+                let expression = self.interpolate(
+                    operation
+                        .expression
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'expression' field"))?,
+                    inputs,
+                    stored,
+                )?;
+
+                let value = evaluate_arithmetic(&expression)?;
+                debug!("🧮 Computed {} = {}", expression, value);
+
+                Ok(Value::Number(serde_json::Number::from(value)))
+            }
+            "delete" => {
+                let entity = self.interpolate(
+                    operation
+                        .entity
+                        .as_ref()
+                        .ok_or(anyhow!("Missing 'entity' field"))?,
+                    inputs,
+                    stored,
+                )?;
+
+                if !accounting.exists(&entity).await? {
+                    if operation.on_fail.is_some() {
+                        return Err(anyhow!("Cannot delete {}: entity does not exist", entity));
+                    }
+                    warn!("⚠️ Skipping delete of {}: entity does not exist", entity);
+                    return Ok(Value::Null);
+                }
+
+                let existence = format!("{}:existence", entity);
+                let balance = accounting.get_balance(&existence).await?;
+                let metadata = self.operation_metadata(operation, inputs, stored)?;
+
+                let transfer_id = accounting
+                    .transfer(&existence, "system:deleted", balance, metadata)
+                    .await?;
+
+                let removed = self
+                    .sled_store
+                    .delete_account_prefix(&format!("{}:", entity))
+                    .await?;
+                debug!("🗑️ Deleted {} ({} text fields)", entity, removed);
+
+                Ok(Value::String(transfer_id))
+            }
             _ => Err(anyhow!("Unknown operation type: {}", operation.op_type)),
         }
     }
 
+    /// `ConditionFailed` for `account` at `balance`, with the operation's own
+    /// `error` message when it has one and `default` otherwise
+    fn condition_failed(
+        &self,
+        operation: &Operation,
+        inputs: &HashMap<String, Value>,
+        stored: &HashMap<String, Value>,
+        account: String,
+        balance: i64,
+        default: String,
+    ) -> Result<anyhow::Error> {
+        let message = match &operation.error {
+            Some(template) => {
+                let mut values = inputs.clone();
+                values.insert("account".to_string(), json!(account));
+                values.insert("balance".to_string(), json!(balance));
+                self.interpolate(template, &values, stored)?
+            }
+            None => default,
+        };
+        Ok(ZikZakError::ConditionFailed {
+            account,
+            balance,
+            message,
+            error_code: operation.error_code,
+        }
+        .into())
+    }
+
+    /// A numeric `transfer` operation, interpolated but not sent
+    fn plan_transfer(
+        &self,
+        operation: &Operation,
+        inputs: &HashMap<String, Value>,
+        stored: &HashMap<String, Value>,
+    ) -> Result<BatchTransfer> {
+        let from_account = self.interpolate(
+            operation
+                .zik
+                .as_ref()
+                .ok_or(anyhow!("Missing 'zik' field"))?,
+            inputs,
+            stored,
+        )?;
+        let to_account = self.interpolate(
+            operation
+                .zak
+                .as_ref()
+                .ok_or(anyhow!("Missing 'zak' field"))?,
+            inputs,
+            stored,
+        )?;
+        let amount = self.evaluate_amount(
+            operation
+                .amount
+                .as_ref()
+                .ok_or(anyhow!("Missing 'amount' field"))?,
+            inputs,
+            stored,
+        )?;
+
+        debug!(
+            "📝 Planning transfer: {} -> {} ({})",
+            from_account, to_account, amount
+        );
+        Ok(BatchTransfer {
+            from_account,
+            to_account,
+            amount,
+            metadata: self.operation_metadata(operation, inputs, stored)?,
+        })
+    }
+
+    /// A numeric `transfer` operation sent with a fixed TigerBeetle id
+    async fn execute_transfer_once(
+        &self,
+        operation: &Operation,
+        transfer_id: u128,
+        inputs: &HashMap<String, Value>,
+        stored: &HashMap<String, Value>,
+        accounting: &mut ZikZakEngine,
+    ) -> Result<Value> {
+        let t = self.plan_transfer(operation, inputs, stored)?;
+        let transfer_id = accounting
+            .transfer_with_id(
+                transfer_id,
+                &t.from_account,
+                &t.to_account,
+                t.amount,
+                t.metadata,
+            )
+            .await?;
+        Ok(Value::String(transfer_id))
+    }
+
     /// Generate Sled key from account name using xxHash
     fn generate_sled_key(&self, account: &str) -> u128 {
         let hash = xxh3_64(account.as_bytes());
@@ -503,39 +1498,63 @@ impl SparkEngine {
         hash as u128
     }
 
+    /// Fill the `{name}` placeholders of `template` from `inputs`, then `stored`
+    ///
+    /// `{{` and `}}` are literal braces. An unknown placeholder is an error under
+    /// [`with_strict_interpolation`](Self::with_strict_interpolation) and is left
+    /// as written otherwise.
     fn interpolate(
         &self,
         template: &str,
         inputs: &HashMap<String, Value>,
         stored: &HashMap<String, Value>,
-    ) -> String {
-        let mut result = template.to_string();
-
-        // Replace input variables
-        for (key, value) in inputs {
-            let placeholder = format!("{{{}}}", key);
-            let replacement = match value {
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                Value::Bool(b) => b.to_string(),
-                _ => value.to_string(),
-            };
-            result = result.replace(&placeholder, &replacement);
+    ) -> Result<String> {
+        let mut result = String::with_capacity(template.len());
+
+        for part in template_parts(template) {
+            match part {
+                TemplatePart::Text(text) => result.push_str(text),
+                TemplatePart::Placeholder(name) => {
+                    match inputs.get(name).or_else(|| stored.get(name)) {
+                        Some(Value::String(s)) => result.push_str(s),
+                        Some(value) => result.push_str(&value.to_string()),
+                        None if self.strict_interpolation => {
+                            return Err(anyhow!(
+                                "Unresolved placeholder {{{}}} in {:?}",
+                                name,
+                                template
+                            ))
+                        }
+                        None => {
+                            result.push('{');
+                            result.push_str(name);
+                            result.push('}');
+                        }
+                    }
+                }
+            }
         }
 
-        // Replace stored variables
-        for (key, value) in stored {
-            let placeholder = format!("{{{}}}", key);
-            let replacement = match value {
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                Value::Bool(b) => b.to_string(),
-                _ => value.to_string(),
-            };
-            result = result.replace(&placeholder, &replacement);
-        }
+        Ok(result)
+    }
 
-        result
+    /// The operation's interpolated `metadata`, tagged with the spark run id
+    fn operation_metadata(
+        &self,
+        operation: &Operation,
+        inputs: &HashMap<String, Value>,
+        stored: &HashMap<String, Value>,
+    ) -> Result<HashMap<String, String>> {
+        let mut metadata = operation
+            .metadata
+            .as_ref()
+            .map(|m| self.interpolate_metadata(m, inputs, stored))
+            .transpose()?
+            .unwrap_or_default();
+        if let Some(Value::String(run_id)) = stored.get(RUN_ID_KEY) {
+            metadata.insert(RUN_ID_KEY.to_string(), run_id.clone());
+        }
+        Ok(metadata)
     }
 
     fn interpolate_metadata(
@@ -543,14 +1562,14 @@ impl SparkEngine {
         metadata: &HashMap<String, String>,
         inputs: &HashMap<String, Value>,
         stored: &HashMap<String, Value>,
-    ) -> HashMap<String, String> {
+    ) -> Result<HashMap<String, String>> {
         let mut result = HashMap::new();
 
         for (key, value) in metadata {
-            result.insert(key.clone(), self.interpolate(value, inputs, stored));
+            result.insert(key.clone(), self.interpolate(value, inputs, stored)?);
         }
 
-        result
+        Ok(result)
     }
 
     fn evaluate_amount(
@@ -563,7 +1582,7 @@ impl SparkEngine {
             Value::Number(n) => Ok(n.as_i64().unwrap_or(0)),
             Value::Bool(b) => Ok(if *b { 1 } else { 0 }),
             Value::String(s) => {
-                let interpolated = self.interpolate(s, inputs, stored);
+                let interpolated = self.interpolate(s, inputs, stored)?;
 
                 // Handle special functions
                 if interpolated.starts_with("hash(") && interpolated.ends_with(")") {
@@ -576,9 +1595,10 @@ impl SparkEngine {
                 } else if interpolated == "false" {
                     Ok(0)
                 } else {
-                    // Try to parse as number
+                    // Try to parse as number, then as arithmetic ("{price} * 2")
                     interpolated
                         .parse::<i64>()
+                        .or_else(|_| evaluate_arithmetic(&interpolated))
                         .map_err(|_| anyhow!("Cannot evaluate amount: {}", interpolated))
                 }
             }
@@ -586,9 +1606,17 @@ impl SparkEngine {
         }
     }
 
+    /// Mistakes in the spark `spark_name` that can be found without igniting it
+    ///
+    /// See [`crate::lint`] for what is checked. An empty list means nothing was found.
+    pub fn lint(&self, spark_name: &str) -> Vec<LintWarning> {
+        lint::lint(&self.sparks, spark_name)
+    }
+
     /// Add or update a spark at runtime
-    pub fn add_spark(&mut self, name: String, spark: Spark) {
+    pub fn add_spark(&mut self, name: String, mut spark: Spark) {
         info!("➕ Adding spark: {}", name);
+        merge_default_metadata(&mut spark.operations, &spark.default_metadata);
         self.sparks.insert(name, spark);
     }
 
@@ -608,4 +1636,490 @@ impl SparkEngine {
         let stats = self.sled_store.get_stats().await?;
         Ok(serde_json::to_value(stats)?)
     }
+
+    /// Text last written to `account` by a sled transfer, if any
+    pub async fn get_text(&self, account: &str) -> Result<Option<String>> {
+        self.read_text(account, "value").await
+    }
+
+    /// Sled text stored for `account` under `field`
+    ///
+    /// Looked up by account name alone, so text whose TigerBeetle account was
+    /// never created or has been deleted reads as `None` instead of failing.
+    async fn read_text(&self, account: &str, field: &str) -> Result<Option<String>> {
+        self.sled_store.get_varchar(account, field).await
+    }
+}
+
+/// Net balance of `account`, 0 if it does not exist yet
+async fn balance_or_zero(accounting: &ZikZakEngine, account: &str) -> Result<i64> {
+    match accounting.get_balance(account).await {
+        Ok(balance) => Ok(balance),
+        Err(e) if is_account_not_found(&e) => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Read `account` every `poll_interval` until its balance meets `condition`
+///
+/// `Err((balance, message))` when `timeout` passes first. The engine may be let
+/// go between reads; [`Accounting::hold`] takes it back.
+async fn await_balance(
+    accounting: &mut Accounting<'_>,
+    account: &str,
+    condition: &str,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Result<Result<i64, (i64, String)>> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let balance = accounting.poll_balance(account).await?;
+        let Some(unmet) = failed_condition(condition, account, balance)? else {
+            return Ok(Ok(balance));
+        };
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return Ok(Err((balance, unmet)));
+        }
+        tokio::time::sleep(poll_interval.min(deadline - now)).await;
+    }
+}
+
+/// Move `account` from `current` to `target` against genesis
+async fn set_balance(
+    accounting: &mut ZikZakEngine,
+    account: &str,
+    current: i64,
+    target: i64,
+    metadata: HashMap<String, String>,
+) -> Result<()> {
+    if target != current {
+        accounting
+            .adjust_against(account, target - current, "system:genesis", metadata)
+            .await?;
+    }
+    Ok(())
+}
+
+/// TigerBeetle id of the transfer stored as `key` in an idempotent ignition
+///
+/// `scope` is the idempotency key, followed by `/op_N` for every `call_spark` on
+/// the way to the transfer's spark. Never 0, which TigerBeetle rejects.
+fn transfer_id(scope: &str, key: &str) -> u128 {
+    xxh3_128(format!("{}/{}", scope, key).as_bytes()).max(1)
+}
+
+/// Fail on the first operation, branches included, an atomic spark cannot contain
+fn check_atomic(operations: &[Operation]) -> Result<()> {
+    for operation in operations {
+        if !ATOMIC_OPERATIONS.contains(&operation.op_type.as_str()) {
+            return Err(anyhow!("'{}' cannot be batched", operation.op_type));
+        }
+        if operation.op_type == "transfer" && operation.sled.unwrap_or(false) {
+            return Err(anyhow!("text transfers cannot be batched"));
+        }
+        for branch in [&operation.create, &operation.update].into_iter().flatten() {
+            check_atomic(branch)?;
+        }
+    }
+    Ok(())
+}
+
+/// Send an atomic spark's planned transfers and store their ids
+async fn commit_batch(
+    batch: Vec<PlannedTransfer>,
+    stored: &mut HashMap<String, Value>,
+    accounting: &mut ZikZakEngine,
+) -> Result<()> {
+    let (names, transfers): (Vec<_>, Vec<_>) = batch
+        .into_iter()
+        .map(|planned| ((planned.key, planned.store_as), planned.transfer))
+        .unzip();
+
+    info!("🔗 Committing {} planned transfers", transfers.len());
+    let transfer_ids = accounting.transfer_batch(transfers).await?;
+
+    for ((key, store_as), transfer_id) in names.into_iter().zip(transfer_ids) {
+        let transfer_id = Value::String(transfer_id);
+        if let Some(name) = store_as {
+            store_result(stored, &name, &transfer_id);
+        }
+        store_result(stored, &key, &transfer_id);
+    }
+    Ok(())
+}
+
+/// Keep `result` as `{name}`, plus `{name.field}` for each field of an object
+fn store_result(stored: &mut HashMap<String, Value>, name: &str, result: &Value) {
+    if let Value::Object(fields) = result {
+        for (field, value) in fields {
+            stored.insert(format!("{}.{}", name, field), value.clone());
+        }
+    }
+    stored.insert(name.to_string(), result.clone());
+}
+
+/// A piece of an interpolation template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TemplatePart<'a> {
+    Text(&'a str),
+    /// The name inside `{name}`
+    Placeholder(&'a str),
+}
+
+/// Give every operation, `upsert` branches included, the `defaults` it has no key for
+///
+/// Done once when a spark is loaded. Keys are never interpolated, so the merged
+/// templates interpolate to the same map as merging the interpolated ones would.
+fn merge_default_metadata(operations: &mut [Operation], defaults: &HashMap<String, String>) {
+    if defaults.is_empty() {
+        return;
+    }
+    for operation in operations {
+        let metadata = operation.metadata.get_or_insert_with(HashMap::new);
+        for (key, value) in defaults {
+            metadata.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        for branch in [&mut operation.create, &mut operation.update]
+            .into_iter()
+            .flatten()
+        {
+            merge_default_metadata(branch, defaults);
+        }
+    }
+}
+
+/// Default message for a `balance` of `balance` failing `condition`, `None` if it holds
+///
+/// Conditions are `> 0`, `== N` and `>= N`; anything else always holds.
+fn failed_condition(condition: &str, account: &str, balance: i64) -> Result<Option<String>> {
+    let bound = |n: &str| {
+        n.parse::<i64>()
+            .map_err(|_| anyhow!("Invalid balance condition: {}", condition))
+    };
+    let failed = if condition == "> 0" {
+        (balance <= 0).then(String::new)
+    } else if let Some(expected) = condition.strip_prefix("== ") {
+        let expected = bound(expected)?;
+        (balance != expected).then(|| format!(" (expected {})", expected))
+    } else if let Some(min_balance) = condition.strip_prefix(">= ") {
+        let min_balance = bound(min_balance)?;
+        (balance < min_balance).then(|| format!(" (expected >= {})", min_balance))
+    } else {
+        None
+    };
+    Ok(failed.map(|expected| {
+        format!(
+            "Balance condition failed: {} = {}{}",
+            account, balance, expected
+        )
+    }))
+}
+
+/// Split `template` into literal text and `{name}` placeholders
+///
+/// `{{` and `}}` are literal braces. A brace that does not open a placeholder
+/// (`{"a": 1}`, `{}`, an unclosed `{`) is kept as text.
+pub(crate) fn template_parts(template: &str) -> Vec<TemplatePart<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(at) = rest.find(['{', '}']) {
+        if at > 0 {
+            parts.push(TemplatePart::Text(&rest[..at]));
+        }
+        let tail = &rest[at..];
+        let name = tail
+            .strip_prefix('{')
+            .and_then(|after| after.split_once('}'))
+            .map(|(name, _)| name)
+            .filter(|name| is_placeholder_name(name));
+        match name {
+            _ if tail.starts_with("{{") || tail.starts_with("}}") => {
+                parts.push(TemplatePart::Text(&tail[..1]));
+                rest = &tail[2..];
+            }
+            Some(name) => {
+                parts.push(TemplatePart::Placeholder(name));
+                rest = &tail[name.len() + 2..];
+            }
+            None => {
+                parts.push(TemplatePart::Text(&tail[..1]));
+                rest = &tail[1..];
+            }
+        }
+    }
+    if !rest.is_empty() {
+        parts.push(TemplatePart::Text(rest));
+    }
+    parts
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Split a return template like `{balance}:number` into template and type
+fn split_return_type(template: &str) -> (&str, Option<&str>) {
+    for kind in ["number", "bool", "string"] {
+        if let Some(rest) = template
+            .strip_suffix(kind)
+            .and_then(|rest| rest.strip_suffix(':'))
+        {
+            return (rest, Some(kind));
+        }
+    }
+    (template, None)
+}
+
+/// The name in a template that is nothing but one `{name}`
+fn sole_placeholder(template: &str) -> Option<&str> {
+    match template_parts(template).as_slice() {
+        [TemplatePart::Placeholder(name)] => Some(name),
+        _ => None,
+    }
+}
+
+/// Turn an interpolated return value into JSON of the requested type
+fn coerce_return_value(value: &str, kind: &str) -> Result<Value> {
+    match kind {
+        "number" => {
+            if let Ok(n) = value.parse::<i64>() {
+                Ok(Value::from(n))
+            } else {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+                    .ok_or_else(|| anyhow!("Return value '{}' is not a number", value))
+            }
+        }
+        "bool" => match value {
+            "true" | "1" => Ok(Value::Bool(true)),
+            "false" | "0" => Ok(Value::Bool(false)),
+            _ => Err(anyhow!("Return value '{}' is not a boolean", value)),
+        },
+        _ => Ok(Value::String(value.to_string())),
+    }
+}
+
+/// Evaluate integer arithmetic: `+ - * / %`, parentheses and unary minus
+///
+/// Division truncates like Rust integer division; overflow and division by zero
+/// are errors rather than wrapping.
+fn evaluate_arithmetic(expression: &str) -> Result<i64> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut parser = ArithmeticParser {
+        chars: &chars,
+        pos: 0,
+    };
+
+    let value = parser.expr()?;
+    match parser.peek() {
+        None => Ok(value),
+        Some(c) => Err(anyhow!("Unexpected '{}' in expression: {}", c, expression)),
+    }
+}
+
+struct ArithmeticParser<'a> {
+    chars: &'a [char],
+    pos: usize,
+}
+
+impl ArithmeticParser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        while matches!(self.chars.get(self.pos), Some(c) if c.is_whitespace()) {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expr(&mut self) -> Result<i64> {
+        let mut value = self.term()?;
+
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = match op {
+                '+' => value.checked_add(rhs),
+                _ => value.checked_sub(rhs),
+            }
+            .ok_or_else(|| anyhow!("Arithmetic overflow"))?;
+        }
+
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<i64> {
+        let mut value = self.factor()?;
+
+        while let Some(op @ ('*' | '/' | '%')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.factor()?;
+            if op != '*' && rhs == 0 {
+                return Err(anyhow!("Division by zero"));
+            }
+            value = match op {
+                '*' => value.checked_mul(rhs),
+                '/' => value.checked_div(rhs),
+                _ => value.checked_rem(rhs),
+            }
+            .ok_or_else(|| anyhow!("Arithmetic overflow"))?;
+        }
+
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<i64> {
+        match self.peek() {
+            Some('-') => {
+                self.pos += 1;
+                self.factor()?
+                    .checked_neg()
+                    .ok_or_else(|| anyhow!("Arithmetic overflow"))
+            }
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expr()?;
+                if self.peek() != Some(')') {
+                    return Err(anyhow!("Missing ')' in expression"));
+                }
+                self.pos += 1;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let start = self.pos;
+                while matches!(self.chars.get(self.pos), Some(c) if c.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+                let digits: String = self.chars[start..self.pos].iter().collect();
+                digits
+                    .parse()
+                    .map_err(|_| anyhow!("Number out of range: {}", digits))
+            }
+            Some(c) => Err(anyhow!("Unexpected '{}' in expression", c)),
+            None => Err(anyhow!("Unexpected end of expression")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic_precedence_and_parentheses() {
+        assert_eq!(evaluate_arithmetic("2999 * 7 / 100").unwrap(), 209);
+        assert_eq!(evaluate_arithmetic("1 + 2 * 3").unwrap(), 7);
+        assert_eq!(evaluate_arithmetic("(1 + 2) * 3").unwrap(), 9);
+        assert_eq!(evaluate_arithmetic("-5 + 10 % 4").unwrap(), -3);
+    }
+
+    #[test]
+    fn test_arithmetic_rejects_bad_input() {
+        assert!(evaluate_arithmetic("10 / 0").is_err());
+        assert!(evaluate_arithmetic("1 +").is_err());
+        assert!(evaluate_arithmetic("(1 + 2").is_err());
+        assert!(evaluate_arithmetic("abc").is_err());
+        assert!(evaluate_arithmetic("9223372036854775807 + 1").is_err());
+    }
+
+    #[test]
+    fn test_typed_return_values() {
+        assert_eq!(
+            split_return_type("{balance}:number"),
+            ("{balance}", Some("number"))
+        );
+        assert_eq!(split_return_type("{ok}:bool"), ("{ok}", Some("bool")));
+        assert_eq!(split_return_type("user:{id}"), ("user:{id}", None));
+        assert_eq!(sole_placeholder("{order_total}"), Some("order_total"));
+        assert_eq!(sole_placeholder("user:{id}"), None);
+        assert_eq!(sole_placeholder("{{id}}"), None);
+
+        assert_eq!(coerce_return_value("2999", "number").unwrap(), json!(2999));
+        assert_eq!(coerce_return_value("-1.5", "number").unwrap(), json!(-1.5));
+        assert_eq!(coerce_return_value("1", "bool").unwrap(), json!(true));
+        assert_eq!(
+            coerce_return_value("2999", "string").unwrap(),
+            json!("2999")
+        );
+        assert!(coerce_return_value("abc", "number").is_err());
+        assert!(coerce_return_value("yes", "bool").is_err());
+    }
+
+    #[test]
+    fn test_interpolation_escapes_braces() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let engine = SparkEngine::empty(temp_dir.path().join("interpolate.db"))?;
+        let inputs = HashMap::from([("name".to_string(), json!("alice"))]);
+        let stored = HashMap::from([("op_0".to_string(), json!(3))]);
+
+        assert_eq!(
+            engine.interpolate(r#"{{"name": "{name}", "n": {op_0}}}"#, &inputs, &stored)?,
+            r#"{"name": "alice", "n": 3}"#
+        );
+        assert_eq!(engine.interpolate("{{name}}", &inputs, &stored)?, "{name}");
+        // Braces that cannot be a placeholder, and unknown names, stay as written
+        assert_eq!(
+            engine.interpolate(r#"{"a": {} } {unknown}"#, &inputs, &stored)?,
+            r#"{"a": {} } {unknown}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_untyped_returns_keep_json_types() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let engine = SparkEngine::empty(temp_dir.path().join("returns.db"))?;
+        let mut spark: Spark = serde_json::from_value(json!({
+            "description": "order totals",
+            "inputs": ["order_total", "paid"],
+            "operations": [],
+            "return": {
+                "balance": "{order_total}",
+                "paid": "{paid}",
+                "label": "order:{order_total}",
+                "as_text": "{order_total}:string",
+                "missing": "{nope}"
+            }
+        }))?;
+        let inputs = HashMap::from([
+            ("order_total".to_string(), json!(2999)),
+            ("paid".to_string(), json!(true)),
+        ]);
+
+        let result = engine.build_return(&spark, &inputs, &HashMap::new())?.0;
+        assert_eq!(result["balance"], json!(2999));
+        assert_eq!(result["paid"], json!(true));
+        assert_eq!(result["label"], json!("order:2999"));
+        assert_eq!(result["as_text"], json!("2999"));
+        assert_eq!(result["missing"], json!("{nope}"));
+
+        spark.return_mode = ReturnMode::Strings;
+        let result = engine.build_return(&spark, &inputs, &HashMap::new())?.0;
+        assert_eq!(result["balance"], json!("2999"));
+        assert_eq!(result["paid"], json!("true"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_strict_interpolation_rejects_unknown_placeholder() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let engine = SparkEngine::empty(temp_dir.path().join("interpolate.db"))?
+            .with_strict_interpolation(true);
+        let inputs = HashMap::from([("id".to_string(), json!(7))]);
+
+        assert_eq!(
+            engine.interpolate("user:{id}:{{x}}", &inputs, &HashMap::new())?,
+            "user:7:{x}"
+        );
+        let err = engine
+            .interpolate("user:{id}:{field}", &inputs, &HashMap::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("{field}"));
+        Ok(())
+    }
 }
@@ -0,0 +1,158 @@
+//! # 🚦 Named States
+//!
+//! Status fields are plain balances (`order:789:status = 2`). Declare the mapping
+//! once and read and write names instead of re-hardcoding the numbers:
+//!
+//! ```rust
+//! use zik_zak::{state_enum, StateEnum};
+//!
+//! state_enum! {
+//!     pub enum OrderStatus {
+//!         Pending = 1,
+//!         Shipped = 2,
+//!         Delivered = 3,
+//!     }
+//!     transitions {
+//!         Pending => Shipped,
+//!         Shipped => Delivered,
+//!     }
+//! }
+//!
+//! # async fn example(engine: &mut zik_zak::ZikZakEngine) -> anyhow::Result<()> {
+//! engine.set_state("order:789:status", OrderStatus::Pending).await?;
+//! engine.set_state("order:789:status", OrderStatus::Shipped).await?;
+//! let status: Option<OrderStatus> = engine.get_state("order:789:status").await?;
+//! assert_eq!(status.map(|s| s.name()), Some("Shipped"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! State values must be positive: a zero balance means "no state yet". Without a
+//! `transitions` block any state may follow any other; with one, only the listed
+//! moves are allowed (plus the first state set and re-setting the current one),
+//! and anything else fails with [`ZikZakError::InvalidTransition`](crate::ZikZakError::InvalidTransition).
+
+/// A named set of states stored as a balance, usually declared with [`state_enum!`](crate::state_enum)
+pub trait StateEnum: Copy + PartialEq + Sized + 'static {
+    /// Name of the enum, for error messages
+    const NAME: &'static str;
+
+    /// Every state, in declaration order
+    fn states() -> &'static [Self];
+
+    /// The balance that stands for this state
+    fn value(self) -> i64;
+
+    /// The state's name
+    fn name(self) -> &'static str;
+
+    /// The state a balance stands for, if any
+    fn from_value(value: i64) -> Option<Self> {
+        Self::states().iter().copied().find(|s| s.value() == value)
+    }
+
+    /// Whether `to` may follow `from` (`None` while no state is set)
+    fn allows(_from: Option<Self>, _to: Self) -> bool {
+        true
+    }
+}
+
+/// [`StateEnum::allows`] for a declared list of `(from, to)` moves
+pub fn allowed_by<S: StateEnum>(from: Option<S>, to: S, transitions: &[(S, S)]) -> bool {
+    match from {
+        None => true,
+        Some(from) => from == to || transitions.contains(&(from, to)),
+    }
+}
+
+/// Declare a [`StateEnum`], optionally with the transitions it allows
+///
+/// See the [module docs](crate::states) for an example.
+#[macro_export]
+macro_rules! state_enum {
+    (
+        @enum $(#[$meta:meta])*
+        $vis:vis enum $name:ident { $($state:ident = $value:expr),+ } { $($allows:tt)* }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis enum $name {
+            $($state),+
+        }
+
+        impl $crate::states::StateEnum for $name {
+            const NAME: &'static str = stringify!($name);
+
+            fn states() -> &'static [Self] {
+                &[$($name::$state),+]
+            }
+
+            fn value(self) -> i64 {
+                match self {
+                    $($name::$state => $value),+
+                }
+            }
+
+            fn name(self) -> &'static str {
+                match self {
+                    $($name::$state => stringify!($state)),+
+                }
+            }
+
+            $($allows)*
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident { $($state:ident = $value:expr),+ $(,)? }
+    ) => {
+        $crate::state_enum!(@enum $(#[$meta])* $vis enum $name { $($state = $value),+ } {});
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident { $($state:ident = $value:expr),+ $(,)? }
+        transitions { $($from:ident => $to:ident),* $(,)? }
+    ) => {
+        $crate::state_enum!(@enum $(#[$meta])* $vis enum $name { $($state = $value),+ } {
+            fn allows(from: Option<Self>, to: Self) -> bool {
+                $crate::states::allowed_by(from, to, &[$(($name::$from, $name::$to)),*])
+            }
+        });
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    state_enum! {
+        enum Light { Red = 1, Green = 2, Amber = 3 }
+        transitions { Red => Green, Green => Amber, Amber => Red }
+    }
+
+    state_enum! {
+        enum Flag { Off = 1, On = 2 }
+    }
+
+    #[test]
+    fn test_values_and_names_round_trip() {
+        assert_eq!(Light::NAME, "Light");
+        assert_eq!(Light::Amber.value(), 3);
+        assert_eq!(Light::Amber.name(), "Amber");
+        assert_eq!(Light::from_value(2), Some(Light::Green));
+        assert_eq!(Light::from_value(0), None);
+        assert_eq!(Light::states(), &[Light::Red, Light::Green, Light::Amber]);
+    }
+
+    #[test]
+    fn test_declared_transitions_constrain_moves() {
+        assert!(Light::allows(None, Light::Amber));
+        assert!(Light::allows(Some(Light::Red), Light::Green));
+        assert!(Light::allows(Some(Light::Red), Light::Red));
+        assert!(!Light::allows(Some(Light::Red), Light::Amber));
+        assert!(!Light::allows(Some(Light::Green), Light::Red));
+
+        // No transitions block: anything goes
+        assert!(Flag::allows(Some(Flag::On), Flag::Off));
+    }
+}
@@ -0,0 +1,156 @@
+//! # 🔧 System Accounts
+//!
+//! The `system:*` accounts every ledger needs: created when a
+//! [`TigerBeetleClient`](crate::TigerBeetleClient) connects and again by
+//! [`ZikZakEngine::ensure_system_accounts`](crate::ZikZakEngine::ensure_system_accounts).
+//!
+//! The built-in set can be replaced with a JSON file named by
+//! `ZIK_ZAK_SYSTEM_ACCOUNTS`, shaped like `system_accounts.json`:
+//!
+//! ```json
+//! [
+//!   { "name": "system:genesis", "initial_zik": 1000000000000 },
+//!   { "name": "system:deleted" },
+//!   { "name": "system:void" }
+//! ]
+//! ```
+//!
+//! Opening balances default to 0. Every name must start with `system:`.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// One account of a [`SystemAccounts`] set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemAccount {
+    pub name: String,
+    /// Opening debits (ZIK side)
+    #[serde(default)]
+    pub initial_zik: u128,
+    /// Opening credits (ZAK side)
+    #[serde(default)]
+    pub initial_zak: u128,
+}
+
+impl SystemAccount {
+    fn new(name: &str, initial_zik: u128, initial_zak: u128) -> Self {
+        Self {
+            name: name.to_string(),
+            initial_zik,
+            initial_zak,
+        }
+    }
+}
+
+/// The `system:*` accounts to create, in creation order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemAccounts(Vec<SystemAccount>);
+
+impl Default for SystemAccounts {
+    fn default() -> Self {
+        Self(vec![
+            SystemAccount::new("system:genesis", 1_000_000_000_000, 0), // Genesis ZIK account
+            SystemAccount::new("system:treasury", 0, 1_000_000_000_000), // Genesis ZAK account
+            SystemAccount::new("system:deleted", 0, 0),                 // Where deleted entities go
+            SystemAccount::new("system:void", 0, 0),                    // Where cleared values go
+            SystemAccount::new("system:operations", 0, 0),              // Operational metadata
+            SystemAccount::new("system:analytics", 0, 0),               // Analytics data
+            SystemAccount::new("system:temp", 0, 0),                    // Temporary operations
+        ])
+    }
+}
+
+impl SystemAccounts {
+    pub fn new(accounts: Vec<SystemAccount>) -> Result<Self> {
+        let mut seen = HashSet::new();
+        for account in &accounts {
+            if !account.name.starts_with("system:") {
+                return Err(anyhow!(
+                    "System account {:?} must start with \"system:\"",
+                    account.name
+                ));
+            }
+            if !seen.insert(account.name.as_str()) {
+                return Err(anyhow!("System account {:?} is listed twice", account.name));
+            }
+        }
+        Ok(Self(accounts))
+    }
+
+    /// Read a JSON array of [`SystemAccount`]s
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read system accounts {:?}: {}", path, e))?;
+        let accounts = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse system accounts {:?}: {}", path, e))?;
+        Self::new(accounts)
+    }
+
+    /// The file named by `ZIK_ZAK_SYSTEM_ACCOUNTS`, or the built-in set when unset
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("ZIK_ZAK_SYSTEM_ACCOUNTS") {
+            Ok(path) => Self::from_file(path),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SystemAccount> {
+        self.0.iter()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SystemAccount> {
+        self.0.iter().find(|account| account.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_set_includes_void() {
+        let accounts = SystemAccounts::default();
+        assert!(accounts.contains("system:void"));
+        assert!(accounts.contains("system:deleted"));
+        assert!(SystemAccounts::new(accounts.iter().cloned().collect()).is_ok());
+
+        // The shipped example file spells out the same set
+        let shipped =
+            SystemAccounts::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/system_accounts.json"));
+        assert_eq!(shipped.unwrap(), accounts);
+    }
+
+    #[test]
+    fn test_from_file_checks_names() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let path = temp_dir.path().join("system_accounts.json");
+
+        fs::write(
+            &path,
+            r#"[{"name":"system:genesis","initial_zik":5},{"name":"system:void"}]"#,
+        )?;
+        let accounts = SystemAccounts::from_file(&path)?;
+        let accounts: Vec<_> = accounts.iter().cloned().collect();
+        assert_eq!(
+            accounts,
+            vec![
+                SystemAccount::new("system:genesis", 5, 0),
+                SystemAccount::new("system:void", 0, 0)
+            ]
+        );
+
+        fs::write(&path, r#"[{"name":"user:1:balance"}]"#)?;
+        assert!(SystemAccounts::from_file(&path).is_err());
+        fs::write(&path, r#"[{"name":"system:void"},{"name":"system:void"}]"#)?;
+        assert!(SystemAccounts::from_file(&path).is_err());
+        Ok(())
+    }
+}
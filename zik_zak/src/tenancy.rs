@@ -0,0 +1,266 @@
+//! # 🏢 Tenancy
+//!
+//! Several tenants sharing one [`ZikZakEngine`]. [`ZikZakEngine::scoped`] returns
+//! a [`TenantView`] that stores every account under `tenant:{id}:` and hands
+//! names back without that prefix:
+//!
+//! ```rust
+//! use zik_zak::ZikZakEngine;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let mut engine = ZikZakEngine::new("shop").await?;
+//!
+//! // Stored as tenant:acme:product:123:price
+//! let mut acme = engine.scoped("acme");
+//! acme.transfer("system:genesis", "product:123:price", 2999, Default::default())
+//!     .await?;
+//! assert_eq!(acme.get_balance("product:123:price").await?, 2999);
+//!
+//! // Another tenant never sees it
+//! assert_eq!(engine.scoped("globex").get_balance("product:123:price").await?, 0);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `system:*` accounts are shared by all tenants unless the engine was built
+//! [`with_tenant_system_accounts`](ZikZakEngine::with_tenant_system_accounts), in
+//! which case each tenant gets its own `tenant:{id}:system:genesis` and friends.
+//! Shared system accounts show up as the counterparty of a tenant's transfers,
+//! but never in its ledger or account queries.
+
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::tigerbeetle_client::{AccountDirection, AccountSpec};
+use crate::zik_zak::{
+    is_account_not_found, page, AccountMatch, Transfer, TransferFilter, ZikZakEngine,
+};
+
+/// Account names of one tenant
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Scope {
+    /// `tenant:{id}:`
+    prefix: String,
+    shared_system: bool,
+}
+
+impl Scope {
+    fn new(tenant_id: &str, shared_system: bool) -> Self {
+        Self {
+            prefix: format!("tenant:{}:", tenant_id),
+            shared_system,
+        }
+    }
+
+    fn is_shared(&self, account: &str) -> bool {
+        self.shared_system && account.starts_with("system:")
+    }
+
+    /// Tenant account name -> engine account name
+    fn scope(&self, account: &str) -> String {
+        if self.is_shared(account) {
+            account.to_string()
+        } else {
+            format!("{}{}", self.prefix, account)
+        }
+    }
+
+    /// Engine account name -> tenant account name, `None` unless the tenant owns it
+    fn local<'a>(&self, account: &'a str) -> Option<&'a str> {
+        account.strip_prefix(self.prefix.as_str())
+    }
+
+    /// Like [`local`](Self::local), but shared system accounts are visible too
+    fn visible<'a>(&self, account: &'a str) -> Option<&'a str> {
+        if self.is_shared(account) {
+            Some(account)
+        } else {
+            self.local(account)
+        }
+    }
+
+    /// `transfer` with tenant names, if it moved value into or out of this tenant
+    fn unscope(&self, transfer: &Transfer) -> Option<Transfer> {
+        if self.local(&transfer.from_account).is_none()
+            && self.local(&transfer.to_account).is_none()
+        {
+            return None;
+        }
+        Some(Transfer {
+            from_account: self.visible(&transfer.from_account)?.to_string(),
+            to_account: self.visible(&transfer.to_account)?.to_string(),
+            ..transfer.clone()
+        })
+    }
+}
+
+/// One tenant's view of a [`ZikZakEngine`], from [`ZikZakEngine::scoped`]
+pub struct TenantView<'a> {
+    engine: &'a mut ZikZakEngine,
+    tenant_id: String,
+    scope: Scope,
+}
+
+impl<'a> TenantView<'a> {
+    pub(crate) fn new(engine: &'a mut ZikZakEngine, tenant_id: &str, shared_system: bool) -> Self {
+        Self {
+            engine,
+            tenant_id: tenant_id.to_string(),
+            scope: Scope::new(tenant_id, shared_system),
+        }
+    }
+
+    pub fn tenant_id(&self) -> &str {
+        &self.tenant_id
+    }
+
+    /// Engine account name of the tenant's `account`
+    pub fn qualify(&self, account: &str) -> String {
+        self.scope.scope(account)
+    }
+
+    /// Create the system accounts this tenant uses
+    ///
+    /// With shared system accounts this is [`ZikZakEngine::ensure_system_accounts`].
+    /// A per-tenant genesis starts empty and may go negative, so its balance is
+    /// minus everything the tenant has issued.
+    pub async fn ensure_system_accounts(&mut self) -> Result<()> {
+        if self.scope.shared_system {
+            return self.engine.ensure_system_accounts().await;
+        }
+        self.engine
+            .create_account(
+                self.qualify("system:genesis").as_str(),
+                AccountSpec {
+                    direction: AccountDirection::Unconstrained,
+                    ..AccountSpec::default()
+                },
+            )
+            .await
+    }
+
+    /// [`ZikZakEngine::transfer`] between two of the tenant's accounts
+    pub async fn transfer(
+        &mut self,
+        from_account: &str,
+        to_account: &str,
+        amount: i64,
+        metadata: HashMap<String, String>,
+    ) -> Result<String> {
+        let (from_account, to_account) = (self.qualify(from_account), self.qualify(to_account));
+        self.engine
+            .transfer(from_account.as_str(), to_account.as_str(), amount, metadata)
+            .await
+    }
+
+    /// Net balance of `account`; 0 if the tenant never used it
+    pub async fn get_balance(&self, account: &str) -> Result<i64> {
+        match self.engine.get_balance(self.qualify(account)).await {
+            Err(e) if is_account_not_found(&e) => Ok(0),
+            balance => balance,
+        }
+    }
+
+    /// [`ZikZakEngine::exists`] for one of the tenant's entities
+    pub async fn exists(&self, entity: &str) -> Result<bool> {
+        self.engine.exists(&self.qualify(entity)).await
+    }
+
+    /// [`ZikZakEngine::query_transfers`] over the tenant's transfers only
+    ///
+    /// `filter.account` is a tenant account name, and so are the returned ones.
+    pub async fn query_transfers(&self, filter: TransferFilter) -> Result<Vec<Transfer>> {
+        let transfers: Vec<Transfer> = self
+            .engine
+            .transfer_log()
+            .iter()
+            .filter_map(|t| self.scope.unscope(t))
+            .collect();
+        Ok(page(&transfers, &filter))
+    }
+
+    /// [`ZikZakEngine::get_active_ledger_state`] of the tenant's accounts, by tenant name
+    pub async fn get_ledger_state(&self) -> Result<Value> {
+        let ledger = self.engine.get_active_ledger_state().await?;
+        let ledger: HashMap<&str, &Value> = ledger
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, balance)| Some((self.scope.local(name)?, balance)))
+            .collect();
+        Ok(serde_json::to_value(ledger)?)
+    }
+
+    /// [`ZikZakEngine::find_accounts`] among the tenant's accounts
+    pub async fn find_accounts(&self, pattern: &str) -> Result<Vec<AccountMatch>> {
+        let matches = self.engine.find_accounts(&self.qualify(pattern)).await?;
+        Ok(matches
+            .into_iter()
+            .filter_map(|m| {
+                let account = self.scope.local(&m.account)?.to_string();
+                Some(AccountMatch { account, ..m })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from_account: &str, to_account: &str) -> Transfer {
+        Transfer {
+            id: "t".to_string(),
+            from_account: from_account.to_string(),
+            to_account: to_account.to_string(),
+            amount: 1,
+            metadata: HashMap::new(),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_scope_names() {
+        let shared = Scope::new("acme", true);
+        assert_eq!(shared.scope("user:1:balance"), "tenant:acme:user:1:balance");
+        assert_eq!(shared.scope("system:genesis"), "system:genesis");
+        assert_eq!(
+            shared.local("tenant:acme:user:1:balance"),
+            Some("user:1:balance")
+        );
+        assert_eq!(shared.local("tenant:globex:user:1:balance"), None);
+        assert_eq!(shared.local("system:genesis"), None);
+
+        let isolated = Scope::new("acme", false);
+        assert_eq!(
+            isolated.scope("system:genesis"),
+            "tenant:acme:system:genesis"
+        );
+        assert_eq!(isolated.visible("system:genesis"), None);
+    }
+
+    #[test]
+    fn test_unscope_only_keeps_own_transfers() {
+        let scope = Scope::new("acme", true);
+
+        let own = scope
+            .unscope(&transfer("system:genesis", "tenant:acme:user:1:balance"))
+            .unwrap();
+        assert_eq!(own.from_account, "system:genesis");
+        assert_eq!(own.to_account, "user:1:balance");
+
+        assert!(scope
+            .unscope(&transfer("system:genesis", "tenant:globex:user:1:balance"))
+            .is_none());
+        assert!(scope
+            .unscope(&transfer(
+                "tenant:acme:user:1:balance",
+                "tenant:globex:user:1:balance"
+            ))
+            .is_none());
+        assert!(scope
+            .unscope(&transfer("system:genesis", "system:operations"))
+            .is_none());
+    }
+}
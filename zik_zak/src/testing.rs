@@ -0,0 +1,61 @@
+//! # 🧪 Test Support
+//!
+//! Helpers for testing code built on ZIK_ZAK deterministically.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::clock::Clock;
+
+/// A [`Clock`] that only moves when told to
+///
+/// Share it through an `Arc` so the test keeps a handle after injecting it.
+#[derive(Debug, Default)]
+pub struct MockClock {
+    millis: AtomicU64,
+}
+
+impl MockClock {
+    /// A clock frozen at `millis` since the UNIX epoch
+    pub fn new(millis: u64) -> Self {
+        Self {
+            millis: AtomicU64::new(millis),
+        }
+    }
+
+    /// Move time forward by `by`
+    pub fn advance(&self, by: Duration) {
+        self.millis
+            .fetch_add(by.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    /// Jump to `millis` since the UNIX epoch, backwards included
+    pub fn set(&self, millis: u64) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_only_moves_when_told() {
+        let clock = MockClock::new(1_000);
+        assert_eq!(clock.now_millis(), 1_000);
+        assert_eq!(clock.now_secs(), 1);
+
+        clock.advance(Duration::from_millis(2_500));
+        assert_eq!(clock.now_millis(), 3_500);
+        assert_eq!(clock.now_secs(), 3);
+
+        clock.set(0);
+        assert_eq!(clock.now_millis(), 0);
+    }
+}
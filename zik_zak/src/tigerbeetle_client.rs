@@ -22,13 +22,95 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tigerbeetle::{
-    Account, AccountBalance, AccountFilter, AccountFilterFlags, AccountFlags, Client,
-    CreateAccountResult, CreateTransferResult, QueryFilter, QueryFilterFlags, Transfer,
-    TransferFlags,
+    Account, AccountBalance, AccountFilter, AccountFilterFlags, AccountFlags, CreateAccountResult,
+    CreateTransferResult, QueryFilter, QueryFilterFlags, Transfer, TransferFlags,
 };
-use tracing::{debug, info, warn};
+use tracing::{debug, error, info, warn};
+
+use crate::backend::{AccountingBackend, TigerBeetleBackend};
+use crate::clock::{Clock, SystemClock};
+use crate::error::ZikZakError;
+use crate::sled::AccountNameStore;
+use crate::system_accounts::SystemAccounts;
+
+/// Connection attempts before `TigerBeetleClient::new` gives up
+const DEFAULT_CONNECT_ATTEMPTS: u32 = 10;
+/// How long a single connection attempt may hang before it counts as failed
+const CONNECT_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long any other TigerBeetle request may take before the cluster counts as down
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Pings hold the engine lock, so they give up sooner
+const PING_TIMEOUT: Duration = Duration::from_secs(1);
+/// Largest page a single `query_accounts` request returns
+pub const ACCOUNT_PAGE_SIZE: u32 = 8189;
+/// Backoff grows from the base delay up to the cap
+const CONNECT_BACKOFF_BASE: Duration = Duration::from_millis(100);
+const CONNECT_BACKOFF_MAX: Duration = Duration::from_secs(5);
+
+/// Exponential backoff after failed attempt `attempt` (1-based), plus up to 50% jitter
+fn backoff_delay(attempt: u32) -> Duration {
+    let exponential = CONNECT_BACKOFF_BASE.saturating_mul(1 << attempt.min(16).saturating_sub(1));
+    let delay = exponential.min(CONNECT_BACKOFF_MAX);
+    delay + delay.mul_f64(fastrand::f64() * 0.5)
+}
+
+/// Parse a numeric environment variable, ignoring unset or malformed values
+fn env_number(name: &str) -> Option<u64> {
+    std::env::var(name).ok()?.trim().parse().ok()
+}
+
+/// Which side of an account TigerBeetle keeps from going negative
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountDirection {
+    /// Debit account (assets, expenses): credits may never exceed debits
+    Zik,
+    /// Credit account (balances, revenue): debits may never exceed credits
+    Zak,
+    /// No balance constraint either way
+    Unconstrained,
+}
+
+/// How to create an account, instead of guessing from its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountSpec {
+    pub direction: AccountDirection,
+    /// Keep a balance snapshot per transfer (`get_account_balances`)
+    pub history: bool,
+    /// Opening debits (ZIK side)
+    pub initial_zik: u128,
+    /// Opening credits (ZAK side)
+    pub initial_zak: u128,
+}
+
+impl Default for AccountSpec {
+    fn default() -> Self {
+        Self {
+            direction: AccountDirection::Zak,
+            history: false,
+            initial_zik: 0,
+            initial_zak: 0,
+        }
+    }
+}
+
+impl AccountSpec {
+    fn flags(&self) -> AccountFlags {
+        let mut flags = match self.direction {
+            AccountDirection::Zik => AccountFlags::CreditsMustNotExceedDebits,
+            AccountDirection::Zak => AccountFlags::DebitsMustNotExceedCredits,
+            AccountDirection::Unconstrained => AccountFlags::default(),
+        };
+        if self.history {
+            flags |= AccountFlags::History;
+        }
+        flags
+    }
+}
 
 /// ZIK_ZAK account representation - maps to TigerBeetle Account
 /// ZIK = DEBIT side, ZAK = CREDIT side
@@ -53,6 +135,38 @@ pub struct ZikZakAccount {
     pub created_at: u64,
 }
 
+impl ZikZakAccount {
+    /// Which side TigerBeetle keeps from going negative, read back from `flags`
+    pub fn direction(&self) -> AccountDirection {
+        let flags = AccountFlags::from_bits_truncate(self.flags);
+        if flags.contains(AccountFlags::DebitsMustNotExceedCredits) {
+            AccountDirection::Zak
+        } else if flags.contains(AccountFlags::CreditsMustNotExceedDebits) {
+            AccountDirection::Zik
+        } else {
+            AccountDirection::Unconstrained
+        }
+    }
+}
+
+/// One page from [`TigerBeetleClient::query_accounts_page`]
+#[derive(Debug, Clone)]
+pub struct AccountPage {
+    /// Oldest first
+    pub accounts: Vec<ZikZakAccount>,
+    /// Pass as `after_timestamp` for the next page; `None` on the last page
+    pub next: Option<u64>,
+}
+
+/// One page of [`TigerBeetleClient::get_account_transfers_paged`]
+#[derive(Debug, Clone)]
+pub struct TransferPage {
+    /// Newest first
+    pub transfers: Vec<ZikZakTransfer>,
+    /// Pass as `before_timestamp` for the next, older page; `None` on the last page
+    pub next: Option<u64>,
+}
+
 /// ZIK_ZAK transfer representation - maps to TigerBeetle Transfer
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZikZakTransfer {
@@ -60,15 +174,40 @@ pub struct ZikZakTransfer {
     pub zik_account_id: u128, // Debit account (money flowing OUT)
     pub zak_account_id: u128, // Credit account (money flowing IN)
     pub amount: u128,
+    /// Pending transfer this one posts or voids (0 otherwise)
+    pub pending_id: u128,
     pub ledger: u32,
     pub code: u16,
     pub user_data_128: u128,
     pub user_data_64: u64,
     pub user_data_32: u32,
+    /// Seconds a pending transfer may stay unresolved (0 = no timeout)
+    pub timeout: u32,
     pub flags: u16,
+    /// Nanoseconds since the UNIX epoch, assigned by TigerBeetle
     pub timestamp: u64,
 }
 
+impl From<Transfer> for ZikZakTransfer {
+    fn from(t: Transfer) -> Self {
+        Self {
+            id: t.id,
+            zik_account_id: t.debit_account_id,  // ZIK = DEBIT
+            zak_account_id: t.credit_account_id, // ZAK = CREDIT
+            amount: t.amount,
+            pending_id: t.pending_id,
+            ledger: t.ledger,
+            code: t.code,
+            user_data_128: t.user_data_128,
+            user_data_64: t.user_data_64,
+            user_data_32: t.user_data_32,
+            timeout: t.timeout,
+            flags: t.flags.bits(),
+            timestamp: t.timestamp,
+        }
+    }
+}
+
 /// ZIK_ZAK operation codes for internal tracking
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum ZikZakOperationCode {
@@ -88,10 +227,60 @@ impl From<ZikZakOperationCode> for u16 {
     }
 }
 
+/// Maps ZIK_ZAK account names to TigerBeetle account ids
+///
+/// Every account id the client sends to TigerBeetle goes through this, so swap it out
+/// when ids have to line up with another system's scheme. It must be deterministic:
+/// the same name has to hash to the same id across restarts.
+pub trait AccountHasher: Send + Sync {
+    fn hash_u128(&self, name: &str) -> u128;
+}
+
+/// Default hasher: first 16 bytes of SHA-256(name), little-endian
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha256AccountHasher;
+
+impl AccountHasher for Sha256AccountHasher {
+    fn hash_u128(&self, name: &str) -> u128 {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        hasher.update(name.as_bytes());
+        let result = hasher.finalize();
+
+        // Convert first 16 bytes to u128
+        let bytes: [u8; 16] = result[0..16].try_into().unwrap();
+        u128::from_le_bytes(bytes)
+    }
+}
+
+/// How [`TigerBeetleClient`] picks ids for new transfers
+///
+/// A trade between ids that sort by creation time (`TimeBased`, `Sequential`) and
+/// ids that carry more entropy (`Random`, `MachineUnique`, `ClientUnique`). Applies
+/// to regular and linked transfers; two-phase transfers (pending, post, void)
+/// always get machine-unique ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IdStrategy {
+    /// Millisecond timestamp XOR both account ids XOR 64 random bits
+    #[default]
+    Mixed,
+    /// 48-bit millisecond timestamp in the high bits, 80 random bits below
+    TimeBased,
+    /// Microsecond timestamp in the high bits, 32 random bits below
+    Sequential,
+    /// 128 random bits
+    Random,
+    /// Nanosecond timestamp mixed with the process id, thread and random bits
+    MachineUnique,
+    /// Nanosecond timestamp mixed with a hash of the cluster and ledger
+    ClientUnique,
+}
+
 /// NUCLEAR TigerBeetle client with ZIK=DEBIT, ZAK=CREDIT semantics
 pub struct TigerBeetleClient {
-    /// Official TigerBeetle client (FULL POWER)
-    client: Client,
+    /// Where requests go: the official TigerBeetle client (FULL POWER), or a stand-in
+    client: Box<dyn AccountingBackend>,
     /// Cluster ID for this connection
     #[allow(dead_code)]
     cluster_id: u128,
@@ -101,16 +290,40 @@ pub struct TigerBeetleClient {
     account_cache: HashMap<String, u128>,
     /// Account ID to name reverse cache
     reverse_cache: HashMap<u128, String>,
+    /// Where new name -> id mappings are persisted, if anywhere
+    name_store: Option<AccountNameStore>,
+    /// Account name -> account ID scheme
+    hasher: Arc<dyn AccountHasher>,
+    /// Cleared when a request fails, set again by a successful `ping`
+    connected: AtomicBool,
+    /// Source of the timestamps stamped into `user_data_64`
+    clock: Arc<dyn Clock>,
+    /// Generator for new transfer ids
+    id_strategy: IdStrategy,
 }
 
-// SAFETY: TigerBeetleClient is used within a Mutex, ensuring exclusive access
-// The raw pointers in the Client are protected by the mutex synchronization
+// SAFETY: TigerBeetleClient is shared behind a lock; the caches are only written
+// through `&mut self`, and the underlying client accepts concurrent requests
 unsafe impl Send for TigerBeetleClient {}
 unsafe impl Sync for TigerBeetleClient {}
 
 impl TigerBeetleClient {
     /// Create new TigerBeetle client with FULL POWER
+    ///
+    /// Retries until the cluster answers, see [`with_hasher`](Self::with_hasher).
     pub async fn new() -> Result<Self> {
+        Self::with_hasher(Sha256AccountHasher).await
+    }
+
+    /// Create a client that derives account ids with `hasher` instead of SHA-256
+    ///
+    /// Connecting (including system account creation) is retried with jittered
+    /// exponential backoff so the app can start before TigerBeetle is up:
+    /// - `ZIK_ZAK_CONNECT_ATTEMPTS` - attempts before giving up (default 10)
+    /// - `ZIK_ZAK_CONNECT_TIMEOUT` - upper bound on the total wait, in seconds
+    ///
+    /// The system accounts created on connect come from [`SystemAccounts::from_env`].
+    pub async fn with_hasher(hasher: impl AccountHasher + 'static) -> Result<Self> {
         info!("🐅 Initializing NUCLEAR TigerBeetle client with ZIK=DEBIT, ZAK=CREDIT...");
 
         // Seed fastrand with high-entropy sources for truly unique IDs
@@ -126,60 +339,231 @@ impl TigerBeetleClient {
         let addresses =
             std::env::var("TB_ADDRESS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
 
-        info!(
-            "🔌 Connecting to TigerBeetle cluster {} at {}",
-            cluster_id, addresses
-        );
+        let hasher: Arc<dyn AccountHasher> = Arc::new(hasher);
+        let system_accounts = SystemAccounts::from_env()?;
+        let attempts = env_number("ZIK_ZAK_CONNECT_ATTEMPTS")
+            .map(|n| n.max(1) as u32)
+            .unwrap_or(DEFAULT_CONNECT_ATTEMPTS);
+        let deadline = env_number("ZIK_ZAK_CONNECT_TIMEOUT")
+            .map(|secs| Instant::now() + Duration::from_secs(secs));
+
+        let mut attempt = 1;
+        loop {
+            info!(
+                "🔌 Connecting to TigerBeetle cluster {} at {} (attempt {}/{})",
+                cluster_id, addresses, attempt, attempts
+            );
+
+            let connected = tokio::time::timeout(
+                CONNECT_ATTEMPT_TIMEOUT,
+                Self::connect(
+                    cluster_id,
+                    &addresses,
+                    Arc::clone(&hasher),
+                    &system_accounts,
+                ),
+            )
+            .await
+            .unwrap_or_else(|_| {
+                Err(anyhow!(
+                    "No answer from TigerBeetle within {:?}",
+                    CONNECT_ATTEMPT_TIMEOUT
+                ))
+            });
+
+            let error = match connected {
+                Ok(tb_client) => {
+                    info!("✅ NUCLEAR TigerBeetle client initialized with ZIK=DEBIT, ZAK=CREDIT semantics");
+                    return Ok(tb_client);
+                }
+                Err(e) => e,
+            };
+
+            let delay = backoff_delay(attempt);
+            let out_of_time = deadline.is_some_and(|d| Instant::now() + delay > d);
+            if attempt >= attempts || out_of_time {
+                return Err(error.context(format!(
+                    "Giving up on TigerBeetle at {} after {} attempts",
+                    addresses, attempt
+                )));
+            }
+
+            warn!(
+                "⏳ TigerBeetle connection attempt {}/{} failed: {} (retrying in {:?})",
+                attempt, attempts, error, delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
 
+    /// A client sending its requests to `backend` instead of a TigerBeetle cluster,
+    /// e.g. a [`MemoryBackend`](crate::MemoryBackend)
+    ///
+    /// Creates the built-in [`SystemAccounts`] like connecting does, without
+    /// reading the environment or retrying.
+    pub async fn with_backend(backend: impl AccountingBackend + 'static) -> Result<Self> {
+        let mut tb_client = Self::from_backend(0, Box::new(backend), Arc::new(Sha256AccountHasher));
+        tb_client
+            .create_system_accounts(&SystemAccounts::default())
+            .await?;
+        Ok(tb_client)
+    }
+
+    /// One connection attempt: create the client and make sure system accounts exist
+    async fn connect(
+        cluster_id: u128,
+        addresses: &str,
+        hasher: Arc<dyn AccountHasher>,
+        system_accounts: &SystemAccounts,
+    ) -> Result<Self> {
         // Create official TigerBeetle client with FULL POWER
-        let client = Client::new(cluster_id, &addresses)
-            .map_err(|e| anyhow!("Failed to initialize TigerBeetle client: {:?}", e))?;
+        let backend = TigerBeetleBackend::new(cluster_id, addresses)?;
+        let mut tb_client = Self::from_backend(cluster_id, Box::new(backend), hasher);
 
-        let mut tb_client = Self {
+        // Initialize system accounts with ZIK/ZAK semantics
+        tb_client.create_system_accounts(system_accounts).await?;
+
+        Ok(tb_client)
+    }
+
+    fn from_backend(
+        cluster_id: u128,
+        client: Box<dyn AccountingBackend>,
+        hasher: Arc<dyn AccountHasher>,
+    ) -> Self {
+        Self {
             client,
             cluster_id,
             default_ledger: 1, // ZIK_ZAK default ledger
             account_cache: HashMap::new(),
             reverse_cache: HashMap::new(),
-        };
-
-        // Initialize system accounts with ZIK/ZAK semantics
-        tb_client.create_system_accounts().await?;
+            name_store: None,
+            hasher,
+            connected: AtomicBool::new(true),
+            clock: Arc::new(SystemClock),
+            id_strategy: IdStrategy::default(),
+        }
+    }
 
-        info!("✅ NUCLEAR TigerBeetle client initialized with ZIK=DEBIT, ZAK=CREDIT semantics");
-        Ok(tb_client)
+    /// TigerBeetle ledger this client creates accounts and transfers on
+    pub fn ledger(&self) -> u32 {
+        self.default_ledger
     }
 
-    /// Check if client is connected
+    /// Whether the last TigerBeetle request (or [`ping`](Self::ping)) succeeded
     pub fn is_connected(&self) -> bool {
-        true // Official client handles connection state internally
+        self.connected.load(Ordering::Relaxed)
     }
 
-    /// Hash account name to 128-bit account ID (deterministic)
-    fn hash_account_name(&self, account_name: &str) -> u128 {
-        use sha2::{Digest, Sha256};
+    /// Cheap round trip (a lookup of `system:genesis`) that refreshes `is_connected`
+    ///
+    /// Unlike other requests this is sent even while the cluster is marked down,
+    /// so it is what flips the client back once TigerBeetle returns.
+    pub async fn ping(&self) -> bool {
+        let genesis = self.hash_account_name("system:genesis");
+        let alive = matches!(
+            tokio::time::timeout(PING_TIMEOUT, self.client.lookup_accounts(&[genesis])).await,
+            Ok(Ok(_))
+        );
 
-        let mut hasher = Sha256::new();
-        hasher.update(account_name.as_bytes());
-        let result = hasher.finalize();
+        let was_alive = self.connected.swap(alive, Ordering::Relaxed);
+        if alive && !was_alive {
+            info!("✅ TigerBeetle is reachable again");
+        } else if !alive && was_alive {
+            warn!("💔 TigerBeetle stopped answering pings");
+        }
+        alive
+    }
 
-        // Convert first 16 bytes to u128
-        let bytes: [u8; 16] = result[0..16].try_into().unwrap();
-        u128::from_le_bytes(bytes)
+    /// Send one request to TigerBeetle, keeping `is_connected` truthful
+    ///
+    /// While the cluster is marked down this fails fast with
+    /// [`ZikZakError::Backend`] instead of sending anything. A request that errors or
+    /// does not answer within `REQUEST_TIMEOUT` marks the cluster down.
+    async fn backend<T, E, F>(
+        &self,
+        what: &str,
+        request: impl FnOnce(&dyn AccountingBackend) -> F,
+    ) -> Result<T>
+    where
+        E: std::fmt::Debug,
+        F: Future<Output = std::result::Result<T, E>>,
+    {
+        if !self.is_connected() {
+            return Err(ZikZakError::Backend {
+                message: format!("{}: TigerBeetle is unreachable", what),
+            }
+            .into());
+        }
+
+        let message =
+            match tokio::time::timeout(REQUEST_TIMEOUT, request(self.client.as_ref())).await {
+                Ok(Ok(value)) => return Ok(value),
+                Ok(Err(e)) => format!("{}: {:?}", what, e),
+                Err(_) => format!("{}: no answer within {:?}", what, REQUEST_TIMEOUT),
+            };
+
+        if self.connected.swap(false, Ordering::Relaxed) {
+            warn!("💔 Marking TigerBeetle as disconnected ({})", message);
+        }
+        Err(ZikZakError::Backend { message }.into())
+    }
+
+    /// Hash account name to 128-bit account ID (deterministic)
+    pub(crate) fn hash_account_name(&self, account_name: &str) -> u128 {
+        self.hasher.hash_u128(account_name)
+    }
+
+    /// TigerBeetle account for `account_name` with ZIK/ZAK semantics
+    fn build_account(&self, account_name: &str, spec: &AccountSpec) -> Account {
+        Account {
+            id: self.hash_account_name(account_name),
+            debits_pending: 0,
+            debits_posted: spec.initial_zik, // ZIK = DEBIT
+            credits_pending: 0,
+            credits_posted: spec.initial_zak, // ZAK = CREDIT
+            user_data_128: self.encode_account_metadata(account_name),
+            user_data_64: self.get_current_timestamp(),
+            user_data_32: self.hash_string_32(account_name),
+            reserved: Default::default(),
+            ledger: self.default_ledger,
+            code: self.account_code(account_name),
+            flags: spec.flags(),
+            timestamp: 0, // Let TigerBeetle set timestamp
+        }
     }
 
     /// Create account with ZIK/ZAK semantics and FULL TigerBeetle features
+    ///
+    /// Direction and history come from the account name, see
+    /// [`default_account_spec`](Self::default_account_spec). Use
+    /// [`create_account_with_flags`](Self::create_account_with_flags) to choose them.
     pub async fn create_account(
         &mut self,
         account_name: &str,
         initial_zik_balance: u128,
         initial_zak_balance: u128,
+    ) -> Result<()> {
+        let spec = AccountSpec {
+            initial_zik: initial_zik_balance,
+            initial_zak: initial_zak_balance,
+            ..self.default_account_spec(account_name)
+        };
+        self.create_account_with_flags(account_name, spec).await
+    }
+
+    /// Create account with an explicit direction, history and opening balances
+    pub async fn create_account_with_flags(
+        &mut self,
+        account_name: &str,
+        spec: AccountSpec,
     ) -> Result<()> {
         let account_id = self.hash_account_name(account_name);
 
         info!(
-            "🆕 Creating ZIK_ZAK account: {} (ID: {}, ZIK: {}, ZAK: {})",
-            account_name, account_id, initial_zik_balance, initial_zak_balance
+            "🆕 Creating ZIK_ZAK account: {} (ID: {}, {:?})",
+            account_name, account_id, spec
         );
 
         // Check cache first
@@ -187,49 +571,28 @@ impl TigerBeetleClient {
             debug!("Account {} already exists in cache", account_name);
             return Ok(());
         }
+        // Never touch an account that another name already owns
+        check_collision(&self.reverse_cache, account_name, account_id)?;
 
-        // Determine account type and flags based on name
-        let (code, flags) = self.determine_account_properties(account_name);
-
-        let account = Account {
-            id: account_id,
-            debits_pending: 0,
-            debits_posted: initial_zik_balance, // ZIK = DEBIT
-            credits_pending: 0,
-            credits_posted: initial_zak_balance, // ZAK = CREDIT
-            user_data_128: self.encode_account_metadata(account_name),
-            user_data_64: self.get_current_timestamp(),
-            user_data_32: self.hash_string_32(account_name),
-            reserved: Default::default(),
-            ledger: self.default_ledger,
-            code,
-            flags,
-            timestamp: 0, // Let TigerBeetle set timestamp
-        };
+        let account = self.build_account(account_name, &spec);
 
         // Create account using FULL POWER TigerBeetle client
         let results = self
-            .client
-            .create_accounts(&[account])
-            .await
-            .map_err(|e| anyhow!("Failed to submit account creation: {:?}", e))?;
+            .backend("Failed to submit account creation", |client| {
+                client.create_accounts(&[account])
+            })
+            .await?;
 
         // Handle results with proper error handling
         for result in results.iter() {
             match result {
                 CreateAccountResult::Ok => {
                     info!("✅ ZIK_ZAK account {} created successfully", account_name);
-                    self.account_cache
-                        .insert(account_name.to_string(), account_id);
-                    self.reverse_cache
-                        .insert(account_id, account_name.to_string());
+                    self.remember(account_name, account_id)?;
                 }
                 CreateAccountResult::Exists => {
                     info!("ℹ️  ZIK_ZAK account {} already exists", account_name);
-                    self.account_cache
-                        .insert(account_name.to_string(), account_id);
-                    self.reverse_cache
-                        .insert(account_id, account_name.to_string());
+                    self.remember(account_name, account_id)?;
                 }
                 error => {
                     return Err(anyhow!(
@@ -259,10 +622,10 @@ impl TigerBeetleClient {
 
         // Lookup account using FULL POWER client
         let accounts = self
-            .client
-            .lookup_accounts(&[account_id])
-            .await
-            .map_err(|e| anyhow!("Failed to lookup account: {:?}", e))?;
+            .backend("Failed to lookup account", |client| {
+                client.lookup_accounts(&[account_id])
+            })
+            .await?;
 
         if let Some(Ok(account)) = accounts.first() {
             let zik_balance = account.debits_posted; // ZIK = DEBIT
@@ -274,7 +637,10 @@ impl TigerBeetleClient {
             );
             Ok((zik_balance, zak_balance))
         } else {
-            Err(anyhow!("ZIK_ZAK account {} not found", account_name))
+            Err(ZikZakError::AccountNotFound {
+                account: account_name.to_string(),
+            }
+            .into())
         }
     }
 
@@ -288,7 +654,7 @@ impl TigerBeetleClient {
     ) -> Result<u128> {
         let zik_account_id = self.hash_account_name(zik_account);
         let zak_account_id = self.hash_account_name(zak_account);
-        let transfer_id = self.generate_transfer_id(zik_account_id, zak_account_id);
+        let transfer_id = self.next_transfer_id(zik_account_id, zak_account_id);
 
         info!(
             "💸 Creating ZIK→ZAK transfer: {} → {} (amount: {}, ID: {})",
@@ -321,10 +687,10 @@ impl TigerBeetleClient {
 
         // Create transfer using FULL POWER client
         let results = self
-            .client
-            .create_transfers(&[transfer])
-            .await
-            .map_err(|e| anyhow!("Failed to submit ZIK→ZAK transfer: {:?}", e))?;
+            .backend("Failed to submit ZIK→ZAK transfer", |client| {
+                client.create_transfers(&[transfer])
+            })
+            .await?;
 
         // Handle results
         for result in &results {
@@ -342,6 +708,65 @@ impl TigerBeetleClient {
         Ok(transfer_id)
     }
 
+    /// Create a transfer with a caller-chosen id; `false` if it already exists
+    ///
+    /// Every field is derived from the arguments - `user_data_64` carries no
+    /// wall-clock stamp - so sending the same transfer again is answered with
+    /// `Exists` instead of moving the amount twice.
+    pub async fn create_transfer_with_id(
+        &mut self,
+        transfer_id: u128,
+        zik_account: &str,
+        zak_account: &str,
+        amount: u128,
+    ) -> Result<bool> {
+        let zik_account_id = self.hash_account_name(zik_account);
+        let zak_account_id = self.hash_account_name(zak_account);
+
+        info!(
+            "💸 Creating ZIK→ZAK transfer: {} → {} (amount: {}, fixed ID: {})",
+            zik_account, zak_account, amount, transfer_id
+        );
+
+        if !self.account_cache.contains_key(zik_account) {
+            self.create_account(zik_account, 0, 0).await?;
+        }
+        if !self.account_cache.contains_key(zak_account) {
+            self.create_account(zak_account, 0, 0).await?;
+        }
+
+        let transfer = Transfer {
+            id: transfer_id,
+            debit_account_id: zik_account_id,
+            credit_account_id: zak_account_id,
+            amount,
+            pending_id: 0,
+            user_data_128: self.encode_transfer_metadata(zik_account, zak_account),
+            user_data_64: 0,
+            user_data_32: self.hash_string_32(&format!("{}→{}", zik_account, zak_account)),
+            timeout: 0,
+            ledger: self.default_ledger,
+            code: self.determine_transfer_code(zik_account, zak_account),
+            flags: TransferFlags::default(),
+            timestamp: 0,
+        };
+
+        let results = self
+            .backend("Failed to submit ZIK→ZAK transfer", |client| {
+                client.create_transfers(&[transfer])
+            })
+            .await?;
+
+        match results.first() {
+            None | Some(CreateTransferResult::Ok) => Ok(true),
+            Some(CreateTransferResult::Exists) => {
+                debug!("♻️ Transfer {} already exists", transfer_id);
+                Ok(false)
+            }
+            Some(error) => Err(anyhow!("Failed to create ZIK→ZAK transfer: {}", error)),
+        }
+    }
+
     /// Create linked transfers for atomic operations with ZIK/ZAK semantics
     #[allow(dead_code)]
     pub async fn create_linked_transfers(
@@ -356,7 +781,7 @@ impl TigerBeetleClient {
         for (i, (zik_account, zak_account, amount)) in transfers.iter().enumerate() {
             let zik_account_id = self.hash_account_name(zik_account);
             let zak_account_id = self.hash_account_name(zak_account);
-            let transfer_id = self.generate_transfer_id(zik_account_id, zak_account_id);
+            let transfer_id = self.next_transfer_id(zik_account_id, zak_account_id);
             transfer_ids.push(transfer_id);
 
             // Ensure accounts exist
@@ -395,10 +820,10 @@ impl TigerBeetleClient {
 
         // Create linked transfers using FULL POWER client
         let results = self
-            .client
-            .create_transfers(&tb_transfers)
-            .await
-            .map_err(|e| anyhow!("Failed to submit linked ZIK→ZAK transfers: {:?}", e))?;
+            .backend("Failed to submit linked ZIK→ZAK transfers", |client| {
+                client.create_transfers(&tb_transfers)
+            })
+            .await?;
 
         // Handle results
         for (i, result) in results.iter().enumerate() {
@@ -457,28 +882,14 @@ impl TigerBeetleClient {
 
         // Get transfers using FULL POWER client
         let transfers = self
-            .client
-            .get_account_transfers(filter)
-            .await
-            .map_err(|e| anyhow!("Failed to get ZIK_ZAK account transfers: {:?}", e))?;
+            .backend("Failed to get ZIK_ZAK account transfers", |client| {
+                client.get_account_transfers(filter)
+            })
+            .await?;
 
         // Convert to ZikZakTransfer
-        let zik_zak_transfers: Vec<ZikZakTransfer> = transfers
-            .into_iter()
-            .map(|t| ZikZakTransfer {
-                id: t.id,
-                zik_account_id: t.debit_account_id,  // ZIK = DEBIT
-                zak_account_id: t.credit_account_id, // ZAK = CREDIT
-                amount: t.amount,
-                ledger: t.ledger,
-                code: t.code,
-                user_data_128: t.user_data_128,
-                user_data_64: t.user_data_64,
-                user_data_32: t.user_data_32,
-                flags: t.flags.bits(),
-                timestamp: t.timestamp,
-            })
-            .collect();
+        let zik_zak_transfers: Vec<ZikZakTransfer> =
+            transfers.into_iter().map(ZikZakTransfer::from).collect();
 
         debug!(
             "📄 Found {} ZIK_ZAK transfers for account {}",
@@ -488,6 +899,82 @@ impl TigerBeetleClient {
         Ok(zik_zak_transfers)
     }
 
+    /// Transfers of `account_name` older than `before_timestamp`, newest first, at most `limit`
+    ///
+    /// Start with `before_timestamp = 0` for the most recent transfers and keep
+    /// passing [`TransferPage::next`] until it is `None`. TigerBeetle timestamps are
+    /// unique, so the order is stable and no transfer is skipped or repeated
+    /// between pages, even while new transfers arrive.
+    pub async fn get_account_transfers_paged(
+        &self,
+        account_name: &str,
+        before_timestamp: u64,
+        limit: u32,
+    ) -> Result<TransferPage> {
+        let account_id = self.hash_account_name(account_name);
+
+        debug!(
+            "📄 Paging ZIK_ZAK transfers for account: {} (before: {}, limit: {})",
+            account_name, before_timestamp, limit
+        );
+
+        let filter = AccountFilter {
+            account_id,
+            user_data_128: 0,
+            user_data_64: 0,
+            user_data_32: 0,
+            code: 0,
+            reserved: Default::default(),
+            timestamp_min: 0,
+            // 0 means "no upper bound", which is what the first page wants anyway
+            timestamp_max: before_timestamp.saturating_sub(1),
+            limit,
+            flags: AccountFilterFlags::Debits
+                | AccountFilterFlags::Credits
+                | AccountFilterFlags::Reversed,
+        };
+
+        let transfers = self
+            .backend("Failed to get ZIK_ZAK account transfers", |client| {
+                client.get_account_transfers(filter)
+            })
+            .await?;
+
+        let transfers: Vec<ZikZakTransfer> =
+            transfers.into_iter().map(ZikZakTransfer::from).collect();
+        let next = next_transfer_cursor(&transfers, limit);
+        Ok(TransferPage { transfers, next })
+    }
+
+    /// Get the most recent transfers on the default ledger (newest first)
+    pub async fn get_ledger_transfers(&self, limit: u32) -> Result<Vec<ZikZakTransfer>> {
+        debug!(
+            "📄 Getting transfers on ledger {} (limit: {})",
+            self.default_ledger, limit
+        );
+
+        let filter = QueryFilter {
+            user_data_128: 0,
+            user_data_64: 0,
+            user_data_32: 0,
+            ledger: self.default_ledger,
+            code: 0,
+            reserved: Default::default(),
+            timestamp_min: 0,
+            timestamp_max: 0,
+            limit,
+            flags: QueryFilterFlags::Reversed,
+        };
+
+        let transfers = self
+            .backend("Failed to query ZIK_ZAK transfers", |client| {
+                client.query_transfers(filter)
+            })
+            .await?;
+
+        Ok(transfers.into_iter().map(ZikZakTransfer::from).collect())
+    }
+
     /// Query accounts using FULL POWER client
     pub async fn query_accounts(
         &self,
@@ -515,43 +1002,93 @@ impl TigerBeetleClient {
 
         // Query accounts using FULL POWER client
         let accounts = self
-            .client
-            .query_accounts(filter)
-            .await
-            .map_err(|e| anyhow!("Failed to query ZIK_ZAK accounts: {:?}", e))?;
+            .backend("Failed to query ZIK_ZAK accounts", |client| {
+                client.query_accounts(filter)
+            })
+            .await?;
 
-        // Convert to ZikZakAccount
         let zik_zak_accounts: Vec<ZikZakAccount> = accounts
             .into_iter()
-            .map(|a| {
-                let name = self
-                    .reverse_cache
-                    .get(&a.id)
-                    .map(|s| s.clone())
-                    .unwrap_or_else(|| format!("account:{}", a.id));
-
-                ZikZakAccount {
-                    id: a.id,
-                    name,
-                    ledger: a.ledger,
-                    code: a.code,
-                    zik_balance: a.debits_posted,  // ZIK = DEBIT
-                    zak_balance: a.credits_posted, // ZAK = CREDIT
-                    zik_pending: a.debits_pending,
-                    zak_pending: a.credits_pending,
-                    user_data_128: a.user_data_128,
-                    user_data_64: a.user_data_64,
-                    user_data_32: a.user_data_32,
-                    flags: a.flags.bits(),
-                    created_at: a.timestamp,
-                }
-            })
+            .map(|a| self.to_zik_zak_account(a))
             .collect();
 
         debug!("🔍 Found {} ZIK_ZAK accounts", zik_zak_accounts.len());
         Ok(zik_zak_accounts)
     }
 
+    /// Accounts created after `after_timestamp`, oldest first, at most `limit`
+    ///
+    /// Start with `after_timestamp = 0` and keep passing [`AccountPage::next`]
+    /// until it is `None`. TigerBeetle timestamps are unique per account, so no
+    /// account is skipped or repeated between pages. `ledger`/`code` 0 match any.
+    pub async fn query_accounts_page(
+        &self,
+        ledger: u32,
+        code: u16,
+        after_timestamp: u64,
+        limit: u32,
+    ) -> Result<AccountPage> {
+        debug!(
+            "🔍 Paging ZIK_ZAK accounts (ledger: {}, code: {}, after: {}, limit: {})",
+            ledger, code, after_timestamp, limit
+        );
+
+        let filter = QueryFilter {
+            user_data_128: 0,
+            user_data_64: 0,
+            user_data_32: 0,
+            ledger,
+            code,
+            reserved: Default::default(),
+            // 0 means "no lower bound", which is what the first page wants anyway
+            timestamp_min: if after_timestamp == 0 {
+                0
+            } else {
+                after_timestamp + 1
+            },
+            timestamp_max: 0,
+            limit,
+            flags: QueryFilterFlags::empty(),
+        };
+
+        let accounts = self
+            .backend("Failed to query ZIK_ZAK accounts", |client| {
+                client.query_accounts(filter)
+            })
+            .await?;
+
+        let accounts: Vec<ZikZakAccount> = accounts
+            .into_iter()
+            .map(|a| self.to_zik_zak_account(a))
+            .collect();
+        let next = next_page_cursor(&accounts, limit);
+        Ok(AccountPage { accounts, next })
+    }
+
+    fn to_zik_zak_account(&self, a: Account) -> ZikZakAccount {
+        let name = self
+            .reverse_cache
+            .get(&a.id)
+            .cloned()
+            .unwrap_or_else(|| format!("account:{}", a.id));
+
+        ZikZakAccount {
+            id: a.id,
+            name,
+            ledger: a.ledger,
+            code: a.code,
+            zik_balance: a.debits_posted,  // ZIK = DEBIT
+            zak_balance: a.credits_posted, // ZAK = CREDIT
+            zik_pending: a.debits_pending,
+            zak_pending: a.credits_pending,
+            user_data_128: a.user_data_128,
+            user_data_64: a.user_data_64,
+            user_data_32: a.user_data_32,
+            flags: a.flags.bits(),
+            created_at: a.timestamp,
+        }
+    }
+
     /// Get account balances using FULL POWER client
     #[allow(dead_code)]
     pub async fn get_account_balances(
@@ -583,10 +1120,10 @@ impl TigerBeetleClient {
 
         // Get balances using FULL POWER client
         let balances = self
-            .client
-            .get_account_balances(filter)
-            .await
-            .map_err(|e| anyhow!("Failed to get ZIK_ZAK account balances: {:?}", e))?;
+            .backend("Failed to get ZIK_ZAK account balances", |client| {
+                client.get_account_balances(filter)
+            })
+            .await?;
 
         debug!(
             "📊 Found {} ZIK_ZAK balance entries for account {}",
@@ -596,34 +1133,60 @@ impl TigerBeetleClient {
         Ok(balances)
     }
 
-    /// Get all accounts with default limits
+    /// Every account on every ledger, paging through as many queries as needed
+    ///
+    /// Holds the whole ledger in memory; prefer `ZikZakEngine::iter_accounts`.
     pub async fn get_all_accounts(&self) -> Result<Vec<ZikZakAccount>> {
-        self.query_accounts(0, 0, 1000).await
+        let mut accounts = Vec::new();
+        let mut after = 0;
+        loop {
+            let page = self
+                .query_accounts_page(0, 0, after, ACCOUNT_PAGE_SIZE)
+                .await?;
+            accounts.extend(page.accounts);
+            match page.next {
+                Some(next) => after = next,
+                None => return Ok(accounts),
+            }
+        }
     }
 
     /// Create system accounts for ZIK_ZAK operations
-    async fn create_system_accounts(&mut self) -> Result<()> {
+    async fn create_system_accounts(&mut self, system_accounts: &SystemAccounts) -> Result<()> {
         info!("🔧 Creating ZIK_ZAK system accounts with ZIK=DEBIT, ZAK=CREDIT...");
 
-        let system_accounts = vec![
-            ("system:genesis", 1_000_000_000_000_u128, 0_u128), // Genesis ZIK account
-            ("system:treasury", 0_u128, 1_000_000_000_000_u128), // Genesis ZAK account
-            ("system:deleted", 0_u128, 0_u128),                 // Where deleted entities go
-            ("system:operations", 0_u128, 0_u128),              // Operational metadata
-            ("system:analytics", 0_u128, 0_u128),               // Analytics data
-            ("system:temp", 0_u128, 0_u128),                    // Temporary operations
-        ];
-
-        for (account_name, zik_balance, zak_balance) in system_accounts {
-            match self
-                .create_account(account_name, zik_balance, zak_balance)
-                .await
-            {
-                Ok(_) => info!("✅ Created ZIK_ZAK system account: {}", account_name),
-                Err(e) => {
+        let accounts: Vec<Account> = system_accounts
+            .iter()
+            .map(|account| {
+                let spec = AccountSpec {
+                    initial_zik: account.initial_zik,
+                    initial_zak: account.initial_zak,
+                    ..self.default_account_spec(&account.name)
+                };
+                self.build_account(&account.name, &spec)
+            })
+            .collect();
+
+        // A failed submission means the cluster is unreachable - let the caller retry
+        let results = self
+            .backend("Failed to submit ZIK_ZAK system accounts", |client| {
+                client.create_accounts(&accounts)
+            })
+            .await?;
+
+        for (system_account, (account, result)) in
+            system_accounts.iter().zip(accounts.iter().zip(&results))
+        {
+            let account_name = &system_account.name;
+            match result {
+                CreateAccountResult::Ok | CreateAccountResult::Exists => {
+                    info!("✅ ZIK_ZAK system account ready: {}", account_name);
+                    self.remember(account_name, account.id)?;
+                }
+                error => {
                     warn!(
                         "⚠️  ZIK_ZAK system account {} creation: {}",
-                        account_name, e
+                        account_name, error
                     );
                     // Continue with other accounts even if one fails
                 }
@@ -634,9 +1197,9 @@ impl TigerBeetleClient {
         Ok(())
     }
 
-    /// Determine account properties based on name
-    fn determine_account_properties(&self, account_name: &str) -> (u16, AccountFlags) {
-        let code = if account_name.starts_with("system:") {
+    /// Determine the TigerBeetle account code based on name
+    fn account_code(&self, account_name: &str) -> u16 {
+        if account_name.starts_with("system:") {
             ZikZakOperationCode::Genesis.into()
         } else if account_name.contains(":price") || account_name.contains(":balance") {
             ZikZakOperationCode::SetField.into()
@@ -644,23 +1207,38 @@ impl TigerBeetleClient {
             ZikZakOperationCode::CreateEntity.into()
         } else {
             ZikZakOperationCode::Transfer.into()
-        };
-
-        let mut flags = AccountFlags::default();
-
-        // Enable history for important accounts
-        if account_name.starts_with("user:") || account_name.starts_with("order:") {
-            flags |= AccountFlags::History;
         }
+    }
 
-        // Set balance constraints based on account type
-        if self.is_zik_account(account_name) {
-            flags |= AccountFlags::CreditsMustNotExceedDebits; // ZIK accounts can't go negative
+    /// Spec used when an account is created without one (no opening balances)
+    ///
+    /// Guessed from substrings of the name, and the guess is binding - TigerBeetle
+    /// enforces the constraint on every later transfer:
+    /// - names containing `:inventory`, `:expense`, `:asset` or `:cash`, and
+    ///   `system:genesis`, are [`AccountDirection::Zik`]: they may never be
+    ///   credited past their debits, so their net balance stays <= 0
+    /// - `system:treasury` is [`AccountDirection::Unconstrained`]: it funds
+    ///   genesis refills and may go negative doing so
+    /// - everything else is [`AccountDirection::Zak`]: never debited past its
+    ///   credits, so its net balance stays >= 0
+    /// - `user:*` and `order:*` accounts keep balance history
+    ///
+    /// So `user:1:inventory` is a ZIK account and cannot receive value from
+    /// genesis. Create such accounts with an explicit [`AccountSpec`] first.
+    pub fn default_account_spec(&self, account_name: &str) -> AccountSpec {
+        let direction = if account_name == "system:treasury" {
+            AccountDirection::Unconstrained
+        } else if self.is_zik_account(account_name) {
+            AccountDirection::Zik
         } else {
-            flags |= AccountFlags::DebitsMustNotExceedCredits; // ZAK accounts can't go negative
-        }
+            AccountDirection::Zak
+        };
 
-        (code, flags)
+        AccountSpec {
+            direction,
+            history: account_name.starts_with("user:") || account_name.starts_with("order:"),
+            ..AccountSpec::default()
+        }
     }
 
     /// Determine if account should use ZIK (debit) balance semantics
@@ -704,12 +1282,64 @@ impl TigerBeetleClient {
         self.hash_account_name(&combined)
     }
 
+    /// Generate transfer ids with `strategy` instead of [`IdStrategy::Mixed`]
+    pub fn with_id_strategy(mut self, strategy: IdStrategy) -> Self {
+        self.id_strategy = strategy;
+        self
+    }
+
+    /// Persist account names in `store`, loading the names it already holds
+    ///
+    /// Names then survive restarts: accounts are named in listings, and a new
+    /// name hashing onto an id recorded by an earlier process is still caught as a
+    /// [`ZikZakError::HashCollision`].
+    pub fn set_name_store(&mut self, store: AccountNameStore) -> Result<()> {
+        for (id, name) in store.entries()? {
+            check_collision(&self.reverse_cache, &name, id)?;
+            self.account_cache.insert(name.clone(), id);
+            self.reverse_cache.insert(id, name);
+        }
+        // Names cached before the store was set
+        for (id, name) in &self.reverse_cache {
+            store.insert(*id, name)?;
+        }
+        self.name_store = Some(store);
+        Ok(())
+    }
+
+    /// Name of the account with `account_id`, if this client (or the name store) knows it
+    pub fn resolve_name(&self, account_id: u128) -> Option<&str> {
+        self.reverse_cache.get(&account_id).map(String::as_str)
+    }
+
+    /// Id of the account named `account_name`, if it was created or loaded
+    ///
+    /// Unlike hashing the name, this only answers for accounts known to exist.
+    pub fn resolve_id(&self, account_name: &str) -> Option<u128> {
+        self.account_cache.get(account_name).copied()
+    }
+
+    /// Cache (and persist) that `account_name` owns `account_id`
+    fn remember(&mut self, account_name: &str, account_id: u128) -> Result<()> {
+        check_collision(&self.reverse_cache, account_name, account_id)?;
+        if let Some(store) = &self.name_store {
+            store.insert(account_id, account_name)?;
+        }
+        self.account_cache
+            .insert(account_name.to_string(), account_id);
+        self.reverse_cache
+            .insert(account_id, account_name.to_string());
+        Ok(())
+    }
+
+    /// Read timestamps from `clock` instead of the system time
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
     /// Get current timestamp
     fn get_current_timestamp(&self) -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64
+        self.clock.now_millis()
     }
 
     /// Hash string to 32-bit value
@@ -725,22 +1355,11 @@ impl TigerBeetleClient {
     }
 
     /// Generate TigerBeetle-optimized time-based ID
-    #[allow(dead_code)]
     pub fn generate_time_based_id(&self) -> u128 {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-
-        // Enhanced format: 48-bit timestamp + 80-bit random for true uniqueness
-        let random_high: u64 = fastrand::u64(..);
-        let random_low: u16 = fastrand::u16(..);
-
-        ((timestamp as u128) << 80) | ((random_high as u128) << 16) | (random_low as u128)
+        time_based_id(self.get_current_timestamp())
     }
 
     /// Generate ID with machine-specific entropy for absolute uniqueness
-    #[allow(dead_code)]
     pub fn generate_machine_unique_id(&self) -> u128 {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
@@ -765,7 +1384,6 @@ impl TigerBeetleClient {
     }
 
     /// Generate a purely random 128-bit ID for maximum entropy
-    #[allow(dead_code)]
     pub fn generate_random_id(&self) -> u128 {
         let high: u64 = fastrand::u64(..);
         let low: u64 = fastrand::u64(..);
@@ -773,7 +1391,6 @@ impl TigerBeetleClient {
     }
 
     /// Generate ID with client instance entropy to avoid collisions across clients
-    #[allow(dead_code)]
     pub fn generate_client_unique_id(&self) -> u128 {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -789,7 +1406,6 @@ impl TigerBeetleClient {
     }
 
     /// Generate sequential ID with microsecond precision and random suffix
-    #[allow(dead_code)]
     pub fn generate_sequential_id(&self) -> u128 {
         let micros = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -800,6 +1416,18 @@ impl TigerBeetleClient {
         (micros << 32) | (random_suffix as u128)
     }
 
+    /// Id for a new transfer, from the configured [`IdStrategy`]
+    fn next_transfer_id(&self, from_account: u128, to_account: u128) -> u128 {
+        match self.id_strategy {
+            IdStrategy::Mixed => self.generate_transfer_id(from_account, to_account),
+            IdStrategy::TimeBased => self.generate_time_based_id(),
+            IdStrategy::Sequential => self.generate_sequential_id(),
+            IdStrategy::Random => self.generate_random_id(),
+            IdStrategy::MachineUnique => self.generate_machine_unique_id(),
+            IdStrategy::ClientUnique => self.generate_client_unique_id(),
+        }
+    }
+
     /// Generate ID for transfers with collision resistance
     pub fn generate_transfer_id(&self, from_account: u128, to_account: u128) -> u128 {
         let timestamp = self.get_current_timestamp() as u128;
@@ -810,68 +1438,110 @@ impl TigerBeetleClient {
         timestamp ^ account_mix ^ (random_part as u128)
     }
 
+    /// Create independent ZIK→ZAK transfers between named accounts in one request
+    ///
+    /// Missing accounts are created first. Unlike
+    /// [`create_linked_transfers`](Self::create_linked_transfers), each transfer
+    /// commits or fails on its own; see [`create_transfers_batch`](Self::create_transfers_batch).
+    pub async fn create_independent_transfers(
+        &mut self,
+        transfers: Vec<(String, String, u128)>, // (zik_account, zak_account, amount)
+    ) -> Result<Vec<std::result::Result<u128, String>>> {
+        let mut batch = Vec::with_capacity(transfers.len());
+        for (zik_account, zak_account, amount) in &transfers {
+            for account in [zik_account, zak_account] {
+                if !self.account_cache.contains_key(account) {
+                    self.create_account(account, 0, 0).await?;
+                }
+            }
+
+            let zik_account_id = self.hash_account_name(zik_account);
+            let zak_account_id = self.hash_account_name(zak_account);
+            batch.push(ZikZakTransfer {
+                id: self.next_transfer_id(zik_account_id, zak_account_id),
+                zik_account_id,
+                zak_account_id,
+                amount: *amount,
+                pending_id: 0,
+                ledger: self.default_ledger,
+                code: self.determine_transfer_code(zik_account, zak_account),
+                user_data_128: self.encode_transfer_metadata(zik_account, zak_account),
+                user_data_64: self.get_current_timestamp(),
+                user_data_32: self.hash_string_32(&format!("{}→{}", zik_account, zak_account)),
+                timeout: 0,
+                flags: 0,
+                timestamp: 0,
+            });
+        }
+
+        self.create_transfers_batch(batch).await
+    }
+
     /// Batch create transfers for maximum performance
-    #[allow(dead_code)]
+    ///
+    /// One result per transfer, in order: its id, or why TigerBeetle refused it. A
+    /// refused transfer does not stop the others unless they are linked to it; only
+    /// failing to reach TigerBeetle fails the whole call.
     pub async fn create_transfers_batch(
         &mut self,
         transfers: Vec<ZikZakTransfer>,
-    ) -> Result<Vec<u128>> {
+    ) -> Result<Vec<std::result::Result<u128, String>>> {
         if transfers.is_empty() {
             return Ok(Vec::new());
         }
 
         info!("🚀 Creating batch of {} ZIK→ZAK transfers", transfers.len());
 
-        let mut tb_transfers = Vec::new();
-        let mut transfer_ids = Vec::new();
-
-        for zik_transfer in &transfers {
-            let transfer_id = zik_transfer.id;
-            transfer_ids.push(transfer_id);
-
-            let transfer = Transfer {
-                id: transfer_id,
+        let tb_transfers: Vec<Transfer> = transfers
+            .iter()
+            .map(|zik_transfer| Transfer {
+                id: zik_transfer.id,
                 debit_account_id: zik_transfer.zik_account_id, // ZIK = DEBIT
                 credit_account_id: zik_transfer.zak_account_id, // ZAK = CREDIT
                 amount: zik_transfer.amount,
-                pending_id: 0,
+                pending_id: zik_transfer.pending_id,
                 user_data_128: zik_transfer.user_data_128,
                 user_data_64: zik_transfer.user_data_64,
                 user_data_32: zik_transfer.user_data_32,
-                timeout: 0,
+                timeout: zik_transfer.timeout,
                 ledger: zik_transfer.ledger,
                 code: zik_transfer.code,
                 flags: TransferFlags::from_bits_truncate(zik_transfer.flags),
                 timestamp: 0,
-            };
-
-            tb_transfers.push(transfer);
-        }
+            })
+            .collect();
 
         // Execute batch transfer using FULL POWER client
         let results = self
-            .client
-            .create_transfers(&tb_transfers)
-            .await
-            .map_err(|e| anyhow!("ZIK→ZAK batch transfer failed: {:?}", e))?;
+            .backend("ZIK→ZAK batch transfer failed", |client| {
+                client.create_transfers(&tb_transfers)
+            })
+            .await?;
 
-        // Check for errors
-        for (i, result) in results.iter().enumerate() {
-            match result {
+        let outcomes: Vec<_> = transfers
+            .iter()
+            .zip(results.iter())
+            .map(|(zik_transfer, result)| match result {
                 CreateTransferResult::Ok => {
-                    debug!("✅ ZIK→ZAK batch transfer {} completed", transfer_ids[i]);
+                    debug!("✅ ZIK→ZAK batch transfer {} completed", zik_transfer.id);
+                    Ok(zik_transfer.id)
                 }
                 error => {
-                    return Err(anyhow!("ZIK→ZAK batch transfer {} failed: {}", i, error));
+                    warn!(
+                        "❌ ZIK→ZAK batch transfer {} failed: {}",
+                        zik_transfer.id, error
+                    );
+                    Err(error.to_string())
                 }
-            }
-        }
+            })
+            .collect();
 
         info!(
-            "✅ Batch of {} ZIK→ZAK transfers completed successfully",
-            transfers.len()
+            "✅ Batch of {} ZIK→ZAK transfers done, {} committed",
+            transfers.len(),
+            outcomes.iter().filter(|outcome| outcome.is_ok()).count()
         );
-        Ok(transfer_ids)
+        Ok(outcomes)
     }
 
     /// Get comprehensive account information
@@ -882,10 +1552,10 @@ impl TigerBeetleClient {
         debug!("ℹ️  Getting ZIK_ZAK account info for: {}", account_name);
 
         let accounts = self
-            .client
-            .lookup_accounts(&[account_id])
-            .await
-            .map_err(|e| anyhow!("Failed to lookup ZIK_ZAK account: {:?}", e))?;
+            .backend("Failed to lookup ZIK_ZAK account", |client| {
+                client.lookup_accounts(&[account_id])
+            })
+            .await?;
 
         if let Some(Ok(account)) = accounts.first() {
             let name = self
@@ -957,10 +1627,10 @@ impl TigerBeetleClient {
         };
 
         let results = self
-            .client
-            .create_transfers(&[transfer])
-            .await
-            .map_err(|e| anyhow!("Failed to create pending ZIK→ZAK transfer: {:?}", e))?;
+            .backend("Failed to create pending ZIK→ZAK transfer", |client| {
+                client.create_transfers(&[transfer])
+            })
+            .await?;
 
         for result in &results {
             match result {
@@ -1004,10 +1674,10 @@ impl TigerBeetleClient {
         };
 
         let results = self
-            .client
-            .create_transfers(&[transfer])
-            .await
-            .map_err(|e| anyhow!("Failed to post pending transfer: {:?}", e))?;
+            .backend("Failed to post pending transfer", |client| {
+                client.create_transfers(&[transfer])
+            })
+            .await?;
 
         for result in &results {
             match result {
@@ -1048,10 +1718,10 @@ impl TigerBeetleClient {
         };
 
         let results = self
-            .client
-            .create_transfers(&[transfer])
-            .await
-            .map_err(|e| anyhow!("Failed to void pending transfer: {:?}", e))?;
+            .backend("Failed to void pending transfer", |client| {
+                client.create_transfers(&[transfer])
+            })
+            .await?;
 
         for result in &results {
             match result {
@@ -1082,7 +1752,8 @@ impl TigerBeetleClient {
 
         // Take first 8 bytes and convert to i64
         let bytes: [u8; 8] = result[0..8].try_into().unwrap();
-        i64::from_be_bytes(bytes).abs() // Use absolute value to ensure positive
+        // Mask the sign bit: `.abs()` maps x and -x together and overflows on i64::MIN
+        i64::from_be_bytes(bytes) & i64::MAX
     }
 
     /// Get current timestamp for ZIK_ZAK operations
@@ -1120,3 +1791,195 @@ impl TigerBeetleClient {
         (zak as i64) - (zik as i64)
     }
 }
+
+/// Cursor after `accounts`, unless the page came back short (the last one)
+fn next_page_cursor(accounts: &[ZikZakAccount], limit: u32) -> Option<u64> {
+    if accounts.len() < limit as usize {
+        return None;
+    }
+    accounts.last().map(|account| account.created_at)
+}
+
+/// Cursor after a newest-first page of transfers: `None` once a page comes back short
+fn next_transfer_cursor(transfers: &[ZikZakTransfer], limit: u32) -> Option<u64> {
+    if transfers.len() < limit as usize {
+        return None;
+    }
+    transfers.last().map(|transfer| transfer.timestamp)
+}
+
+/// Fails if `id` is already known under a name other than `name`
+fn check_collision(
+    known: &HashMap<u128, String>,
+    name: &str,
+    id: u128,
+) -> std::result::Result<(), ZikZakError> {
+    match known.get(&id) {
+        Some(owner) if owner != name => {
+            error!(
+                "💥 Account hash collision: {} and {} both map to {}",
+                owner, name, id
+            );
+            Err(ZikZakError::HashCollision {
+                a: owner.clone(),
+                b: name.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 48-bit millisecond timestamp over 80 random bits, so ids sort by time
+fn time_based_id(millis: u64) -> u128 {
+    let random_high: u64 = fastrand::u64(..);
+    let random_low: u16 = fastrand::u16(..);
+
+    (((millis & 0xFFFF_FFFF_FFFF) as u128) << 80)
+        | ((random_high as u128) << 16)
+        | (random_low as u128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_hasher_is_deterministic() {
+        let hasher = Sha256AccountHasher;
+        assert_eq!(
+            hasher.hash_u128("user:1:balance"),
+            hasher.hash_u128("user:1:balance")
+        );
+        assert_ne!(
+            hasher.hash_u128("user:1:balance"),
+            hasher.hash_u128("user:2:balance")
+        );
+    }
+
+    #[test]
+    fn test_time_based_ids_increase_with_time() {
+        let start = 1_700_000_000_000u64;
+        let mut previous = None;
+        for millis in [start, start + 1, start + 2, start + 500, start + 60_000] {
+            let id = time_based_id(millis);
+            assert_eq!((id >> 80) as u64, millis);
+            if let Some(previous) = previous {
+                assert!(id > previous, "{} not above {}", id, previous);
+            }
+            previous = Some(id);
+        }
+
+        // Within one millisecond the high bits agree and the random bits differ
+        let (a, b) = (time_based_id(start), time_based_id(start));
+        assert_eq!(a >> 80, b >> 80);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_check_collision_rejects_second_name_for_an_id() {
+        let known = HashMap::from([(7u128, "user:1:balance".to_string())]);
+
+        assert!(check_collision(&known, "user:1:balance", 7).is_ok());
+        assert!(check_collision(&known, "user:2:balance", 8).is_ok());
+        assert_eq!(
+            check_collision(&known, "user:2:balance", 7),
+            Err(ZikZakError::HashCollision {
+                a: "user:1:balance".to_string(),
+                b: "user:2:balance".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_backoff_delay_grows_and_caps() {
+        let first = backoff_delay(1);
+        assert!(first >= CONNECT_BACKOFF_BASE && first <= CONNECT_BACKOFF_BASE.mul_f64(1.5));
+
+        let third = backoff_delay(3);
+        assert!(third >= CONNECT_BACKOFF_BASE * 4);
+
+        for attempt in [10, 32, u32::MAX] {
+            let delay = backoff_delay(attempt);
+            assert!(delay >= CONNECT_BACKOFF_MAX && delay <= CONNECT_BACKOFF_MAX.mul_f64(1.5));
+        }
+    }
+
+    #[test]
+    fn test_next_page_cursor_stops_on_short_page() {
+        let account = |created_at| ZikZakAccount {
+            id: created_at as u128,
+            name: String::new(),
+            ledger: 1,
+            code: 1,
+            zik_balance: 0,
+            zak_balance: 0,
+            zik_pending: 0,
+            zak_pending: 0,
+            user_data_128: 0,
+            user_data_64: 0,
+            user_data_32: 0,
+            flags: 0,
+            created_at,
+        };
+
+        let full = vec![account(10), account(20), account(30)];
+        assert_eq!(next_page_cursor(&full, 3), Some(30));
+        assert_eq!(next_page_cursor(&full[..2], 3), None);
+        assert_eq!(next_page_cursor(&[], 3), None);
+    }
+
+    #[test]
+    fn test_next_transfer_cursor_stops_on_short_page() {
+        let transfer = |timestamp| ZikZakTransfer {
+            id: timestamp as u128,
+            zik_account_id: 1,
+            zak_account_id: 2,
+            amount: 1,
+            pending_id: 0,
+            ledger: 1,
+            code: 1,
+            user_data_128: 0,
+            user_data_64: 0,
+            user_data_32: 0,
+            timeout: 0,
+            flags: 0,
+            timestamp,
+        };
+
+        // Newest first, so the cursor is the oldest timestamp seen
+        let full = vec![transfer(30), transfer(20), transfer(10)];
+        assert_eq!(next_transfer_cursor(&full, 3), Some(10));
+        assert_eq!(next_transfer_cursor(&full[..2], 3), None);
+        assert_eq!(next_transfer_cursor(&[], 3), None);
+    }
+
+    #[test]
+    fn test_account_spec_flags() {
+        let spec = AccountSpec {
+            direction: AccountDirection::Zik,
+            history: true,
+            ..AccountSpec::default()
+        };
+        assert_eq!(
+            spec.flags(),
+            AccountFlags::CreditsMustNotExceedDebits | AccountFlags::History
+        );
+        assert_eq!(
+            AccountSpec::default().flags(),
+            AccountFlags::DebitsMustNotExceedCredits
+        );
+
+        let unconstrained = AccountSpec {
+            direction: AccountDirection::Unconstrained,
+            ..AccountSpec::default()
+        };
+        assert!(unconstrained.flags().is_empty());
+    }
+
+    #[test]
+    fn test_hash_string_is_never_negative() {
+        for i in 0..1000 {
+            assert!(TigerBeetleClient::hash_string(&format!("input:{}", i)) >= 0);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! # ✅ Request Validation
+//!
+//! [`Valid<T>`] extracts a JSON body and checks it before the handler runs, so a
+//! bad request never reaches the engine. Every problem is reported at once as
+//! `400 Bad Request`:
+//!
+//! ```json
+//! {
+//!   "error": "Invalid request body",
+//!   "fields": [
+//!     { "field": "amount", "problem": "missing" },
+//!     { "field": "to", "problem": "must not be empty" }
+//!   ]
+//! }
+//! ```
+//!
+//! Bodies opt in by implementing [`Validate`]: the fields they require, and the
+//! checks serde cannot express.
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+/// A request body that can tell what is wrong with it
+pub trait Validate: DeserializeOwned {
+    /// Fields the body must contain
+    const REQUIRED: &'static [&'static str];
+
+    /// Problems with a body that did deserialize, e.g. a negative amount
+    fn validate(&self) -> Vec<FieldError>;
+}
+
+/// One field of a rejected body and what is wrong with it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    pub field: String,
+    pub problem: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, problem: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            problem: problem.into(),
+        }
+    }
+}
+
+/// Body of a `400` answered by [`Valid`]
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ValidationErrors {
+    pub error: String,
+    pub fields: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    fn new(fields: Vec<FieldError>) -> Self {
+        Self {
+            error: "Invalid request body".to_string(),
+            fields,
+        }
+    }
+}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, Json(self)).into_response()
+    }
+}
+
+/// A JSON body of type `T` that passed [`Validate`]
+pub struct Valid<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Valid<T>
+where
+    S: Send + Sync,
+    T: Validate,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(|rejection| {
+                ValidationErrors::new(vec![FieldError::new("", rejection.body_text())])
+                    .into_response()
+            })?;
+        check::<T>(body)
+            .map(Valid)
+            .map_err(|errors| errors.into_response())
+    }
+}
+
+/// `body` as a `T`, or everything wrong with it
+fn check<T: Validate>(body: Value) -> Result<T, ValidationErrors> {
+    let Value::Object(fields) = &body else {
+        return Err(ValidationErrors::new(vec![FieldError::new(
+            "",
+            "must be a JSON object",
+        )]));
+    };
+    let missing: Vec<_> = T::REQUIRED
+        .iter()
+        .filter(|field| fields.get(**field).is_none_or(Value::is_null))
+        .map(|field| FieldError::new(field, "missing"))
+        .collect();
+    if !missing.is_empty() {
+        return Err(ValidationErrors::new(missing));
+    }
+
+    let parsed: T = serde_json::from_value(body)
+        .map_err(|e| ValidationErrors::new(vec![FieldError::new("", e.to_string())]))?;
+    let invalid = parsed.validate();
+    if invalid.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(ValidationErrors::new(invalid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize)]
+    struct Named {
+        name: String,
+        #[allow(dead_code)]
+        size: u32,
+    }
+
+    impl Validate for Named {
+        const REQUIRED: &'static [&'static str] = &["name", "size"];
+
+        fn validate(&self) -> Vec<FieldError> {
+            if self.name.is_empty() {
+                vec![FieldError::new("name", "must not be empty")]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    fn problems(body: Value) -> Vec<FieldError> {
+        check::<Named>(body).unwrap_err().fields
+    }
+
+    #[test]
+    fn test_check_reports_every_problem() {
+        assert_eq!(
+            check::<Named>(json!({ "name": "a", "size": 1 }))
+                .unwrap()
+                .name,
+            "a"
+        );
+        assert_eq!(
+            problems(json!({ "size": null })),
+            vec![
+                FieldError::new("name", "missing"),
+                FieldError::new("size", "missing")
+            ]
+        );
+        assert_eq!(
+            problems(json!({ "name": "", "size": 1 })),
+            vec![FieldError::new("name", "must not be empty")]
+        );
+        assert_eq!(problems(json!({ "name": "a", "size": -1 }))[0].field, "");
+        assert_eq!(problems(json!([1, 2]))[0].problem, "must be a JSON object");
+    }
+}
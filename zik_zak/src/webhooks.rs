@@ -0,0 +1,368 @@
+//! # 📮 Webhooks
+//!
+//! POST committed transfers to an external URL - Slack, Zapier, your own
+//! service - whenever either side matches an [account pattern](crate::query):
+//!
+//! ```ignore
+//! engine.add_webhook(WebhookConfig {
+//!     url: "http://hooks.internal/revenue".to_string(),
+//!     account_pattern: "shop:*:revenue".to_string(),
+//!     secret: "s3cret".to_string(),
+//! })?;
+//! ```
+//!
+//! The body is the [`Transfer`] as JSON, signed with HMAC-SHA256 over the body in
+//! the `X-ZikZak-Signature: sha256=<hex>` header so receivers can check it came
+//! from us (see [`sign`]).
+//!
+//! Delivery never blocks a transfer: matches are queued for a background worker
+//! that retries failures up to [`MAX_ATTEMPTS`] times, doubling the delay each
+//! time. Deliveries that still fail are written to the dead-letter tree set with
+//! `set_webhook_dead_letters`, or only logged without one.
+
+use anyhow::{anyhow, Result};
+use reqwest::{header, Client, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::{debug, error, warn};
+
+use crate::query::AccountPattern;
+use crate::sled::WebhookDeadLetters;
+use crate::zik_zak::Transfer;
+
+/// Header carrying `sha256=<hex HMAC of the body>`
+pub const SIGNATURE_HEADER: &str = "x-zikzak-signature";
+
+/// Deliveries attempted per transfer before it is dead-lettered
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry; doubled for every further one
+const BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// How long one attempt may take
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Matches that may wait for the worker before new ones are dead-lettered
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Where and when to POST transfers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// `http://` or `https://` endpoint
+    pub url: String,
+    /// Transfers from or to a matching account are sent, e.g. `shop:*:revenue`
+    pub account_pattern: String,
+    /// HMAC-SHA256 key for the signature header
+    pub secret: String,
+}
+
+/// A delivery that failed every attempt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub url: String,
+    pub transfer: Transfer,
+    /// Why the last attempt failed
+    pub error: String,
+    pub attempts: u32,
+}
+
+struct Webhook {
+    config: WebhookConfig,
+    pattern: AccountPattern,
+}
+
+struct Delivery {
+    config: WebhookConfig,
+    transfer: Transfer,
+}
+
+/// Webhook registry plus the queue feeding its delivery worker
+#[derive(Default)]
+pub(crate) struct Webhooks {
+    webhooks: Vec<Webhook>,
+    /// Started with the first webhook
+    queue: Option<mpsc::Sender<Delivery>>,
+    dead_letters: Arc<RwLock<Option<WebhookDeadLetters>>>,
+}
+
+impl Webhooks {
+    pub(crate) fn register(&mut self, config: WebhookConfig) -> Result<()> {
+        let pattern = AccountPattern::parse(&config.account_pattern)?;
+        let url = Url::parse(&config.url)
+            .map_err(|e| anyhow!("Invalid webhook URL {}: {}", config.url, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(anyhow!(
+                "Webhook URL {} must use http:// or https://",
+                config.url
+            ));
+        }
+
+        self.webhooks.push(Webhook { config, pattern });
+
+        if self.queue.is_none() {
+            let (sender, mut receiver) = mpsc::channel::<Delivery>(QUEUE_CAPACITY);
+            let dead_letters = Arc::clone(&self.dead_letters);
+            let client = Client::builder()
+                .timeout(ATTEMPT_TIMEOUT)
+                .build()
+                .map_err(|e| anyhow!("Failed to build webhook HTTP client: {}", e))?;
+
+            // Ends once the engine (and with it the sender) is dropped
+            tokio::spawn(async move {
+                while let Some(delivery) = receiver.recv().await {
+                    // One slow endpoint must not hold up the others
+                    let (client, dead_letters) = (client.clone(), Arc::clone(&dead_letters));
+                    tokio::spawn(async move {
+                        if let Err(letter) =
+                            deliver(&client, delivery, MAX_ATTEMPTS, BASE_DELAY).await
+                        {
+                            bury(&dead_letters, letter);
+                        }
+                    });
+                }
+            });
+
+            self.queue = Some(sender);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_dead_letters(&mut self, store: WebhookDeadLetters) {
+        *self.dead_letters.write().unwrap() = Some(store);
+    }
+
+    /// Queue `transfer` for every webhook it matches, never waiting
+    pub(crate) fn dispatch(&self, transfer: &Transfer) {
+        let Some(queue) = &self.queue else {
+            return;
+        };
+
+        for webhook in &self.webhooks {
+            if !webhook.pattern.matches(&transfer.from_account)
+                && !webhook.pattern.matches(&transfer.to_account)
+            {
+                continue;
+            }
+
+            let delivery = Delivery {
+                config: webhook.config.clone(),
+                transfer: transfer.clone(),
+            };
+            if let Err(TrySendError::Full(delivery)) = queue.try_send(delivery) {
+                bury(
+                    &self.dead_letters,
+                    DeadLetter {
+                        url: delivery.config.url,
+                        transfer: delivery.transfer,
+                        error: "webhook queue full".to_string(),
+                        attempts: 0,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// `sha256=<hex>` signature of `body` under `secret`, as sent in [`SIGNATURE_HEADER`]
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    format!(
+        "sha256={}",
+        hex::encode(hmac_sha256(secret.as_bytes(), body))
+    )
+}
+
+/// HMAC (RFC 2104) over SHA-256
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// POST `delivery`, retrying with doubling delays; the dead letter if all attempts fail
+async fn deliver(
+    client: &Client,
+    delivery: Delivery,
+    attempts: u32,
+    base_delay: Duration,
+) -> std::result::Result<(), DeadLetter> {
+    let body = match serde_json::to_vec(&delivery.transfer) {
+        Ok(body) => body,
+        Err(e) => {
+            return Err(DeadLetter {
+                url: delivery.config.url,
+                transfer: delivery.transfer,
+                error: e.to_string(),
+                attempts: 0,
+            })
+        }
+    };
+    let signature = sign(&delivery.config.secret, &body);
+
+    let mut delay = base_delay;
+    let mut last_error = String::new();
+    for attempt in 1..=attempts {
+        match post(client, &delivery.config.url, &signature, body.clone()).await {
+            Ok(()) => {
+                debug!(
+                    "📮 Delivered transfer {} to {}",
+                    delivery.transfer.id, delivery.config.url
+                );
+                return Ok(());
+            }
+            Err(e) => {
+                warn!(
+                    "📮 Webhook {} attempt {}/{} failed: {}",
+                    delivery.config.url, attempt, attempts, e
+                );
+                last_error = e.to_string();
+            }
+        }
+        if attempt < attempts {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    Err(DeadLetter {
+        url: delivery.config.url,
+        transfer: delivery.transfer,
+        error: last_error,
+        attempts,
+    })
+}
+
+async fn post(client: &Client, url: &str, signature: &str, body: Vec<u8>) -> Result<()> {
+    let response = client
+        .post(url)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, signature)
+        .body(body)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("endpoint answered {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Keep a failed delivery in the dead-letter tree, or at least log it
+fn bury(dead_letters: &RwLock<Option<WebhookDeadLetters>>, letter: DeadLetter) {
+    error!(
+        "📮 Giving up on webhook {} for transfer {}: {}",
+        letter.url, letter.transfer.id, letter.error
+    );
+    if let Some(store) = dead_letters.read().unwrap().as_ref() {
+        if let Err(e) = store.insert(&letter) {
+            error!("📮 Failed to store dead letter: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, extract::State, http::HeaderMap, routing::post as route_post, Router};
+    use std::collections::HashMap;
+    use tokio::net::TcpListener;
+
+    fn delivery(url: String) -> Delivery {
+        Delivery {
+            config: WebhookConfig {
+                url,
+                account_pattern: "shop:*:revenue".to_string(),
+                secret: "s3cret".to_string(),
+            },
+            transfer: Transfer {
+                id: "t1".to_string(),
+                from_account: "user:1:balance".to_string(),
+                to_account: "shop:1:revenue".to_string(),
+                amount: 250,
+                metadata: HashMap::new(),
+                timestamp: 0,
+            },
+        }
+    }
+
+    fn client() -> Client {
+        Client::new()
+    }
+
+    #[test]
+    fn test_hmac_matches_rfc_4231() {
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delivery_is_signed() {
+        let (seen_tx, mut seen) = mpsc::unbounded_channel::<(HeaderMap, Bytes)>();
+        let app = Router::new()
+            .route(
+                "/hook",
+                route_post(
+                    |State(seen): State<mpsc::UnboundedSender<_>>, headers, body| async move {
+                        seen.send((headers, body)).unwrap();
+                    },
+                ),
+            )
+            .with_state(seen_tx);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        deliver(&client(), delivery(url), 1, Duration::ZERO)
+            .await
+            .unwrap();
+
+        let (headers, body) = seen.recv().await.unwrap();
+        let transfer: Transfer = serde_json::from_slice(&body).unwrap();
+        assert_eq!(transfer.id, "t1");
+        assert_eq!(headers[SIGNATURE_HEADER], sign("s3cret", &body).as_str());
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_dead_lettered_after_retries() {
+        // A port nobody listens on
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        drop(listener);
+
+        let letter = deliver(
+            &client(),
+            delivery(url.clone()),
+            3,
+            Duration::from_millis(1),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(letter.url, url);
+        assert_eq!(letter.attempts, 3);
+        assert_eq!(letter.transfer.id, "t1");
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let store = WebhookDeadLetters::new(temp_dir.path().join("dead.db")).unwrap();
+        bury(&RwLock::new(Some(store.clone())), letter.clone());
+        assert_eq!(store.entries().unwrap(), vec![letter]);
+    }
+}
@@ -0,0 +1,124 @@
+//! # 📡 WebSocket
+//!
+//! Serves the [`zik_zak::realtime`] subprotocol at `/ws`. Only built with the
+//! `realtime` feature.
+//!
+//! Committed transfers reach every connection through a broadcast channel fed by
+//! an engine hook, and expired pending transfers through one fed by the pending
+//! watcher; each connection filters them by its own subscriptions. A connection
+//! that falls too far behind is told how many events it missed.
+
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::Response,
+    routing::get,
+    Extension, Router,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::debug;
+use zik_zak::realtime::{ServerMessage, Session, EVENT_BUFFER};
+use zik_zak::{PendingTransfer, Transfer, ZikZakEngine};
+
+use crate::AppState;
+
+/// How often the ledger is scanned for expired pending transfers
+const PENDING_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Forward every transfer the engine commits into a broadcast channel
+pub async fn transfer_events(state: &AppState) -> broadcast::Sender<Transfer> {
+    let (events, _) = broadcast::channel(EVENT_BUFFER);
+    let sender = events.clone();
+    state
+        .engine
+        .write()
+        .await
+        .on_transfer(Box::new(move |transfer| {
+            // No connections, no receivers - nothing to do
+            let _ = sender.send(transfer.clone());
+        }));
+    events
+}
+
+/// Forward every pending transfer that expires unresolved into a broadcast channel
+pub fn expiry_events(state: &AppState) -> broadcast::Sender<PendingTransfer> {
+    let (events, _) = broadcast::channel(EVENT_BUFFER);
+    ZikZakEngine::spawn_pending_watcher(
+        Arc::clone(&state.engine),
+        PENDING_CHECK_INTERVAL,
+        events.clone(),
+    );
+    events
+}
+
+/// `/ws`
+pub fn routes<S>(
+    events: broadcast::Sender<Transfer>,
+    expiries: broadcast::Sender<PendingTransfer>,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/ws", get(upgrade))
+        .layer(Extension(events))
+        .layer(Extension(expiries))
+}
+
+async fn upgrade(
+    ws: WebSocketUpgrade,
+    Extension(events): Extension<broadcast::Sender<Transfer>>,
+    Extension(expiries): Extension<broadcast::Sender<PendingTransfer>>,
+) -> Response {
+    let events = events.subscribe();
+    let expiries = expiries.subscribe();
+    ws.on_upgrade(move |socket| serve(socket, events, expiries))
+}
+
+async fn serve(
+    mut socket: WebSocket,
+    mut events: broadcast::Receiver<Transfer>,
+    mut expiries: broadcast::Receiver<PendingTransfer>,
+) {
+    let mut session = Session::new();
+
+    loop {
+        let reply = tokio::select! {
+            frame = socket.recv() => match frame {
+                Some(Ok(Message::Text(text))) => Some(session.handle(&text)),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum; binary frames are not part of the protocol
+                Some(Ok(_)) => None,
+            },
+            event = events.recv() => match event {
+                Ok(transfer) => session.event(&transfer),
+                Err(RecvError::Lagged(missed)) => Some(lagged(missed)),
+                Err(RecvError::Closed) => break,
+            },
+            expired = expiries.recv() => match expired {
+                Ok(pending) => session.expired(&pending),
+                Err(RecvError::Lagged(missed)) => Some(lagged(missed)),
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        if let Some(reply) = reply {
+            let Ok(text) = serde_json::to_string(&reply) else {
+                continue;
+            };
+            if socket.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    debug!("📡 WebSocket closed");
+}
+
+fn lagged(missed: u64) -> ServerMessage {
+    ServerMessage::Error(format!(
+        "Connection too slow, {} events were dropped",
+        missed
+    ))
+}
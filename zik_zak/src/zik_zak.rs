@@ -14,7 +14,7 @@
 //! use zik_zak::ZikZakEngine;
 //!
 //! # async fn example() -> anyhow::Result<()> {
-//! let mut engine = ZikZakEngine::new().await?;
+//! let mut engine = ZikZakEngine::new("shop").await?;
 //!
 //! // Create product by transferring existence
 //! engine.transfer(
@@ -47,206 +47,2667 @@
 //! - `user:456:balance` - User 456's balance
 //! - `order:789:status` - Order 789's status
 //! - `system:genesis` - Unlimited source of value
+//! - `system:treasury` - Where genesis is refilled from, should it ever run low
 //! - `system:deleted` - Where deleted entities go
 //!
+//! ## Namespaces
+//!
+//! `ZikZakEngine::new("auth")` transparently stores `user:1:balance` as
+//! `auth:user:1:balance` in TigerBeetle, so several services can share one cluster
+//! without seeing each other's accounts. Callers always use the short names.
+//! `system:*` accounts are shared by every namespace (they are the common source
+//! and sink of value); an empty namespace means the global account space.
+//!
 //! ## The Magic
 //!
 //! No schemas. No migrations. No complexity.
 //! Just pure accounting math that scales infinitely.
 
 use anyhow::{anyhow, Result};
-use serde::Serialize;
-use serde_json::Value;
-use std::collections::HashMap;
-use tracing::{debug, error, info};
+use futures::stream::{self, Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::pin::pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use crate::tigerbeetle_client::TigerBeetleClient;
+use crate::accounting::{check_segment, AccountId};
+use crate::audit;
+use crate::backend::AccountingBackend;
+use crate::balance_cache::{BalanceCache, BalanceCacheConfig, BalanceCacheStats};
+use crate::binary;
+use crate::clock::{Clock, SystemClock};
+use crate::error::ZikZakError;
+use crate::hooks::{TransferHook, TransferHooks};
+use crate::money::Currencies;
+use crate::query::AccountPattern;
+use crate::request_id::{self, REQUEST_ID_KEY};
+use crate::sled::{
+    AccountNameStore, CheckpointStore, CurrencyStore, DailyOutflowStore, MetadataIndex, TagStore,
+    WebhookDeadLetters,
+};
+use crate::sparks::RUN_ID_KEY;
+use crate::states::StateEnum;
+use crate::system_accounts::SystemAccounts;
+use crate::tenancy::TenantView;
+use crate::tigerbeetle_client::{
+    AccountDirection, AccountSpec, TigerBeetleClient, ZikZakAccount, ZikZakTransfer,
+    ACCOUNT_PAGE_SIZE,
+};
+use crate::webhooks::{WebhookConfig, Webhooks};
+
+/// Most transfers TigerBeetle returns for one query
+const TRANSFER_QUERY_LIMIT: u32 = 8189;
+
+/// What a refill tops [`ZikZakEngine::genesis_remaining`] back up to
+pub const GENESIS_BALANCE: i64 = i64::MAX / 2;
+
+/// [`ZikZakEngine::genesis_remaining`] below which [`ZikZakEngine::ensure_genesis_account`] refills it
+pub const DEFAULT_GENESIS_THRESHOLD: i64 = GENESIS_BALANCE / 4;
+
+/// How long [`Consistency::Cached`] reads may serve a balance, see
+/// [`ZikZakEngine::with_balance_cache_ttl`]
+pub const DEFAULT_BALANCE_CACHE_TTL: Duration = Duration::from_secs(1);
+
+/// Where genesis refills come from
+const TREASURY: &str = "system:treasury";
+
+/// Briefly holds what [`ZikZakEngine::check_and_transfer`] requires beyond the amount
+const BALANCE_CHECK: &str = "system:balance_check";
+
+/// Last name segments of accounts that count things rather than hold money
+const NON_MONETARY_SEGMENTS: [&str; 3] = ["existence", "status", "frozen"];
+
+/// Window of [`TransferLimits::daily_outflow`]
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Transfers per linked batch in [`ZikZakEngine::mint_to`] and [`ZikZakEngine::burn_from`]
+const BULK_CHUNK_SIZE: usize = 1000;
+
+/// `TransferFlags` bits as stored in [`ZikZakTransfer::flags`]
+const FLAG_PENDING: u16 = 2;
+const FLAG_POST_PENDING: u16 = 4;
+const FLAG_VOID_PENDING: u16 = 8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct Transfer {
+    /// TigerBeetle id of the transfer, as a UUID
+    pub id: String,
+    pub from_account: String,
+    pub to_account: String,
+    pub amount: i64,
+    pub metadata: HashMap<String, String>,
+    pub timestamp: u64,
+}
+
+/// One transfer inside a [`ZikZakEngine::transfer_batch`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchTransfer {
+    pub from_account: String,
+    pub to_account: String,
+    pub amount: i64,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+/// Page size when [`TransferFilter::limit`] is not given
+const DEFAULT_PAGE_SIZE: usize = 100;
+/// Largest page [`ZikZakEngine::query_transfers`] will return
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Which slice of the transfer log [`ZikZakEngine::query_transfers`] returns
+///
+/// Doubles as the `/transactions` query string.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(
+    feature = "openapi",
+    derive(utoipa::IntoParams),
+    into_params(parameter_in = Query)
+)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::InputObject))]
+pub struct TransferFilter {
+    /// Page size, default 100, capped at 1000
+    pub limit: Option<usize>,
+    /// Matching transfers to skip, newest first
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    pub offset: usize,
+    /// Only transfers from or to this account
+    pub account: Option<String>,
+    /// Only transfers at or after this unix timestamp (seconds)
+    pub since: Option<u64>,
+    /// Only transfers at or before this unix timestamp (seconds)
+    pub until: Option<u64>,
+    /// Only transfers made by this spark ignition
+    pub recipe_run_id: Option<String>,
+}
+
+impl TransferFilter {
+    fn page_size(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE)
+    }
+
+    fn matches(&self, transfer: &Transfer) -> bool {
+        let on_account = self.account.as_deref().is_none_or(|account| {
+            transfer.from_account == account || transfer.to_account == account
+        });
+        on_account
+            && self.since.is_none_or(|since| transfer.timestamp >= since)
+            && self.until.is_none_or(|until| transfer.timestamp <= until)
+            && self.recipe_run_id.as_deref().is_none_or(|run_id| {
+                transfer.metadata.get(RUN_ID_KEY).map(String::as_str) == Some(run_id)
+            })
+    }
+}
+
+/// Account activity over a period, from [`ZikZakEngine::generate_statement`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Statement {
+    pub account: String,
+    /// Period start, unix seconds, inclusive
+    pub since: u64,
+    /// Period end, unix seconds, inclusive
+    pub until: u64,
+    /// Balance from every transfer before `since`
+    pub opening_balance: i64,
+    /// Transfers in the period, oldest first
+    pub lines: Vec<StatementLine>,
+    pub closing_balance: i64,
+}
+
+/// One transfer on a [`Statement`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatementLine {
+    pub transfer_id: String,
+    pub timestamp: u64,
+    /// The other side of the transfer
+    pub counterparty: String,
+    /// Credits positive, debits negative
+    pub amount: i64,
+    /// Balance after this line
+    pub balance: i64,
+    pub metadata: HashMap<String, String>,
+}
+
+/// One page of an account's TigerBeetle history, from [`ZikZakEngine::account_statement`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatementPage {
+    pub account: String,
+    /// Newest first
+    pub entries: Vec<StatementEntry>,
+    /// Pass as `cursor` for the next, older page; `None` once there is none
+    pub next: Option<u64>,
+}
+
+/// One transfer on a [`StatementPage`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatementEntry {
+    /// TigerBeetle id
+    pub id: u128,
+    /// Unix seconds
+    pub timestamp: u64,
+    /// The other side of the transfer
+    pub counterparty: String,
+    /// Credits positive, debits negative
+    pub amount: i64,
+}
+
+/// Local transfer log vs TigerBeetle for one account, from [`ZikZakEngine::reconcile`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReconResult {
+    pub account: String,
+    /// Net balance replayed from this engine's transfer log
+    pub local_balance: i64,
+    /// Net balance TigerBeetle reports (0 if the account does not exist)
+    pub tigerbeetle_balance: i64,
+}
+
+impl ReconResult {
+    pub fn is_consistent(&self) -> bool {
+        self.local_balance == self.tigerbeetle_balance
+    }
+
+    /// TigerBeetle minus local; positive means TigerBeetle holds more
+    pub fn difference(&self) -> i64 {
+        self.tigerbeetle_balance - self.local_balance
+    }
+}
+
+/// One account found by [`ZikZakEngine::find_accounts`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountMatch {
+    pub account: String,
+    /// Net balance (credits - debits)
+    pub balance: i64,
+    /// Metadata of the latest transfer touching the account in the local log
+    pub metadata: HashMap<String, String>,
+}
+
+/// One page of [`ZikZakEngine::find_accounts_page`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountMatchPage {
+    /// In account creation order
+    pub results: Vec<AccountMatch>,
+    /// Pass as `cursor` for the next page; `None` once every match was returned
+    pub next_cursor: Option<String>,
+}
+
+/// How fresh a balance read with [`ZikZakEngine::get_balance_with`] has to be
+///
+/// Balances are cached per account as [`get_balance_with`](ZikZakEngine::get_balance_with)
+/// reads them live, see [`crate::balance_cache`]. Transfers made through this
+/// engine drop their accounts from the cache; anything but `Strong` may miss
+/// changes made elsewhere.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Consistency {
+    /// Always read TigerBeetle
+    #[default]
+    Strong,
+    /// A balance read within the [cache TTL](ZikZakEngine::with_balance_cache_ttl) will do
+    Cached,
+    /// The last balance read will do, however old; reads live only the first time
+    Eventual,
+}
+
+/// Guards against fat-finger and runaway transfers; every limit is off by default
+///
+/// `system:*` accounts are never limited, so minting and funding keep working.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransferLimits {
+    /// Largest amount a single transfer may move
+    pub max_amount: Option<i64>,
+    /// Most any one non-system account may send within a rolling 24 hours
+    pub daily_outflow: Option<i64>,
+    /// Caps on what matching accounts may send per UTC day, counted in the
+    /// engine's [`DailyOutflowStore`](crate::DailyOutflowStore)
+    pub daily_limits: Vec<DailyLimit>,
+}
+
+/// Most an account matching `pattern` may send per UTC day, see [`TransferLimits`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyLimit {
+    pub pattern: AccountPattern,
+    pub max: i64,
+}
+
+impl DailyLimit {
+    /// Cap accounts matching `pattern` at `max` a day
+    pub fn new(pattern: &str, max: i64) -> Result<Self> {
+        Ok(Self {
+            pattern: AccountPattern::parse(pattern)?,
+            max,
+        })
+    }
+}
+
+/// What happened to one account in [`ZikZakEngine::mint_to`] or [`ZikZakEngine::burn_from`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BulkResult {
+    pub account: String,
+    /// The transfer id, or why nothing moved for this account
+    pub result: Result<String, String>,
+}
+
+/// What one account sent within the last [`DAY_SECS`], oldest first
+#[derive(Debug, Default)]
+struct RecentOutflow {
+    sent: VecDeque<(u64, i64)>,
+    /// Sum of the amounts in `sent`
+    total: i64,
+}
+
+impl RecentOutflow {
+    /// Count `amount` sent at `timestamp`, dropping what was sent at or before `since`
+    fn add(&mut self, timestamp: u64, amount: i64, since: u64) {
+        while let Some(&(sent_at, sent)) = self.sent.front() {
+            if sent_at > since {
+                break;
+            }
+            self.sent.pop_front();
+            self.total -= sent;
+        }
+        self.sent.push_back((timestamp, amount));
+        self.total += amount;
+    }
+
+    /// Total sent after `since`
+    fn since(&self, since: u64) -> i64 {
+        let expired: i64 = self
+            .sent
+            .iter()
+            .take_while(|(sent_at, _)| *sent_at <= since)
+            .map(|(_, sent)| sent)
+            .sum();
+        self.total - expired
+    }
+}
+
+/// A pending transfer that has been neither posted nor voided
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingTransfer {
+    /// TigerBeetle id, as returned by [`ZikZakEngine::reserve`]
+    pub id: u128,
+    pub from_account: String,
+    pub to_account: String,
+    pub amount: i64,
+    /// Unix seconds
+    pub created_at: u64,
+    /// Unix seconds at which TigerBeetle releases the funds, `None` without a timeout
+    pub expires_at: Option<u64>,
+}
+
+pub struct ZikZakEngine {
+    tigerbeetle: TigerBeetleClient,
+    transfers: Vec<Transfer>,
+    /// Hash of the last audit chain record, see [`crate::audit`]
+    audit_head: [u8; 32],
+    namespace: String,
+    hooks: TransferHooks,
+    webhooks: Webhooks,
+    tags: Option<TagStore>,
+    /// `(key, value)` -> positions in `transfers`, while no SLED index is set
+    by_metadata: HashMap<(String, String), Vec<usize>>,
+    metadata_index: Option<MetadataIndex>,
+    checkpoints: Option<CheckpointStore>,
+    daily_outflow: Option<DailyOutflowStore>,
+    /// What each non-system account sent within the last day, for
+    /// [`TransferLimits::daily_outflow`]
+    recent_outflow: HashMap<String, RecentOutflow>,
+    /// Currency of each monetary account, by full account name
+    account_currencies: Option<CurrencyStore>,
+    clock: Arc<dyn Clock>,
+    limits: TransferLimits,
+    genesis_threshold: i64,
+    /// Tenants get their own `system:*` accounts instead of sharing them
+    tenant_system_accounts: bool,
+    system_accounts: SystemAccounts,
+    currencies: Currencies,
+    /// Balances for [`Consistency`] reads, by engine account name
+    balance_cache: BalanceCache,
+    balance_cache_ttl: Duration,
+    /// Whether [`get_balance`](Self::get_balance) reads through `balance_cache`
+    cache_balances: bool,
+}
+
+// SAFETY: ZikZakEngine is shared behind a lock; everything that mutates it takes
+// `&mut self`, so shared `&self` reads only ever issue concurrent TigerBeetle
+// requests, which the client supports
+unsafe impl Send for ZikZakEngine {}
+unsafe impl Sync for ZikZakEngine {}
+
+impl ZikZakEngine {
+    /// Connect to TigerBeetle with all accounts living under `{namespace}:`
+    ///
+    /// System accounts come from [`SystemAccounts::from_env`], ledger currencies
+    /// from [`Currencies::from_env`] and the balance cache, if any, from
+    /// [`BalanceCacheConfig::from_env`].
+    pub async fn new(namespace: &str) -> Result<Self> {
+        info!(
+            "🔌 Initializing TigerBeetle connection (namespace: {:?})...",
+            namespace
+        );
+        let system_accounts = SystemAccounts::from_env()?;
+        let currencies = Currencies::from_env()?;
+        let balance_cache = BalanceCacheConfig::from_env()?;
+        let tigerbeetle = TigerBeetleClient::new().await?;
+
+        let engine = Self::with_client(namespace, tigerbeetle)
+            .with_system_accounts(system_accounts)
+            .with_currencies(currencies);
+        Ok(match balance_cache {
+            Some(config) => engine.with_balance_cache(config),
+            None => engine,
+        })
+    }
+
+    /// An engine on `backend` instead of a TigerBeetle cluster, e.g. a
+    /// [`MemoryBackend`](crate::MemoryBackend) for tests
+    ///
+    /// Unlike [`new`](Self::new) nothing is read from the environment.
+    pub async fn with_backend(
+        namespace: &str,
+        backend: impl AccountingBackend + 'static,
+    ) -> Result<Self> {
+        let tigerbeetle = TigerBeetleClient::with_backend(backend).await?;
+        Ok(Self::with_client(namespace, tigerbeetle))
+    }
+
+    /// Wrap an already connected client, e.g. one built with `TigerBeetleClient::with_hasher`
+    pub fn with_client(namespace: &str, tigerbeetle: TigerBeetleClient) -> Self {
+        Self {
+            tigerbeetle,
+            transfers: Vec::new(),
+            audit_head: audit::GENESIS_HASH,
+            namespace: namespace.to_string(),
+            hooks: TransferHooks::default(),
+            webhooks: Webhooks::default(),
+            tags: None,
+            by_metadata: HashMap::new(),
+            metadata_index: None,
+            checkpoints: None,
+            daily_outflow: None,
+            recent_outflow: HashMap::new(),
+            account_currencies: None,
+            clock: Arc::new(SystemClock),
+            limits: TransferLimits::default(),
+            genesis_threshold: DEFAULT_GENESIS_THRESHOLD,
+            tenant_system_accounts: false,
+            system_accounts: SystemAccounts::default(),
+            currencies: Currencies::default(),
+            balance_cache: BalanceCache::new(BalanceCacheConfig::default().max_entries),
+            balance_cache_ttl: DEFAULT_BALANCE_CACHE_TTL,
+            cache_balances: false,
+        }
+    }
+
+    /// Take "now" from `clock` - transfer timestamps, pending expiry and the
+    /// client's `user_data_64` stamps - instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.tigerbeetle.set_clock(Arc::clone(&clock));
+        self.clock = clock;
+        self
+    }
+
+    /// Enforce `limits` in [`transfer`](Self::transfer)
+    ///
+    /// The rolling daily outflow is kept in memory, so it only counts transfers made
+    /// through this engine since it started. Per-pattern
+    /// [`daily_limits`](TransferLimits::daily_limits) are counted in SLED instead
+    /// and need [`set_daily_outflow_store`](Self::set_daily_outflow_store).
+    pub fn with_limits(mut self, limits: TransferLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Create `accounts` in [`ensure_system_accounts`](Self::ensure_system_accounts)
+    /// instead of the built-in set
+    pub fn with_system_accounts(mut self, accounts: SystemAccounts) -> Self {
+        self.system_accounts = accounts;
+        self
+    }
+
+    /// Format balances with `currencies` instead of US dollars on ledger 1
+    pub fn with_currencies(mut self, currencies: Currencies) -> Self {
+        self.currencies = currencies;
+        self
+    }
+
+    /// Currency of each ledger, for displaying balances
+    pub fn currencies(&self) -> &Currencies {
+        &self.currencies
+    }
+
+    /// TigerBeetle ledger this engine works on
+    pub fn ledger(&self) -> u32 {
+        self.tigerbeetle.ledger()
+    }
+
+    /// `minor` units of this engine's ledger formatted in its currency
+    pub fn format_amount(&self, minor: i64) -> String {
+        self.currencies.format(self.ledger(), minor)
+    }
+
+    /// Refill genesis once it drops below `threshold` instead of [`DEFAULT_GENESIS_THRESHOLD`]
+    pub fn with_genesis_threshold(mut self, threshold: i64) -> Self {
+        self.genesis_threshold = threshold;
+        self
+    }
+
+    /// Let [`Consistency::Cached`] reads serve balances up to `ttl` old instead of
+    /// [`DEFAULT_BALANCE_CACHE_TTL`]
+    pub fn with_balance_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.balance_cache_ttl = ttl;
+        self
+    }
+
+    /// Serve [`get_balance`](Self::get_balance) from memory as described in
+    /// [`crate::balance_cache`]
+    ///
+    /// `config.ttl` also becomes the age [`Consistency::Cached`] reads accept.
+    pub fn with_balance_cache(mut self, config: BalanceCacheConfig) -> Self {
+        self.balance_cache = BalanceCache::new(config.max_entries);
+        self.balance_cache_ttl = config.ttl;
+        self.cache_balances = true;
+        self
+    }
+
+    /// Give every [tenant](Self::scoped) its own `system:*` accounts instead of
+    /// sharing the engine's
+    pub fn with_tenant_system_accounts(mut self, per_tenant: bool) -> Self {
+        self.tenant_system_accounts = per_tenant;
+        self
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// This engine as seen by `tenant_id`, see [`crate::tenancy`]
+    pub fn scoped(&mut self, tenant_id: &str) -> TenantView<'_> {
+        let shared_system = !self.tenant_system_accounts;
+        TenantView::new(self, tenant_id, shared_system)
+    }
+
+    /// Current unix time in seconds, from this engine's [clock](Self::with_clock)
+    pub fn now_secs(&self) -> u64 {
+        self.clock.now_secs()
+    }
+
+    /// Current time in TigerBeetle timestamp units (nanoseconds since the UNIX epoch)
+    fn now_nanos(&self) -> u64 {
+        self.clock.now_millis().saturating_mul(1_000_000)
+    }
+
+    /// Engine account name -> TigerBeetle account name
+    fn qualify(&self, account: &str) -> String {
+        if self.namespace.is_empty() || account.starts_with("system:") {
+            account.to_string()
+        } else {
+            format!("{}:{}", self.namespace, account)
+        }
+    }
+
+    /// TigerBeetle account name -> engine account name, `None` outside this namespace
+    fn unqualify<'a>(&self, account: &'a str) -> Option<&'a str> {
+        if self.namespace.is_empty() || account.starts_with("system:") {
+            return Some(account);
+        }
+        account
+            .strip_prefix(self.namespace.as_str())
+            .and_then(|rest| rest.strip_prefix(':'))
+    }
+
+    /// Keep account tags in `tags` (tagging fails until a store is set)
+    pub fn set_tag_store(&mut self, tags: TagStore) {
+        self.tags = Some(tags);
+    }
+
+    fn tag_store(&self) -> Result<&TagStore> {
+        self.tags
+            .as_ref()
+            .ok_or_else(|| anyhow!("No tag store configured, call set_tag_store first"))
+    }
+
+    /// Tag `account` with `key=value` (e.g. `category=electronics`)
+    pub async fn tag_account(&mut self, account: &str, key: &str, value: &str) -> Result<()> {
+        let account = self.qualify(account);
+        self.tag_store()?.add(&account, key, value)
+    }
+
+    /// Remove the `key` tag from `account`; `false` if it was not tagged
+    pub async fn untag_account(&mut self, account: &str, key: &str) -> Result<bool> {
+        let account = self.qualify(account);
+        self.tag_store()?.remove(&account, key)
+    }
+
+    /// Accounts of this namespace tagged `key=value`
+    pub async fn accounts_with_tag(&self, key: &str, value: &str) -> Result<Vec<String>> {
+        Ok(self
+            .tag_store()?
+            .accounts(key, value)?
+            .iter()
+            .filter_map(|account| self.unqualify(account).map(str::to_string))
+            .collect())
+    }
+
+    /// Sum of the net balances of all accounts tagged `key=value`
+    ///
+    /// Tagged accounts that were never created count as 0.
+    pub async fn sum_by_tag(&self, key: &str, value: &str) -> Result<i64> {
+        let mut total = 0i64;
+        for account in self.accounts_with_tag(key, value).await? {
+            match self.get_balance(&account).await {
+                Ok(balance) => total += balance,
+                Err(e) if is_account_not_found(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(total)
+    }
+
+    /// Put `account` in the group `tag`, e.g. `eu_merchants`
+    ///
+    /// A bare tag is the key `tag` with an empty value, kept in the same store as
+    /// [`tag_account`](Self::tag_account) tags, so groups can span accounts that
+    /// share no name prefix.
+    pub async fn add_tag(&mut self, account: &str, tag: &str) -> Result<()> {
+        if tag.is_empty() {
+            return Err(anyhow!("Tag must not be empty"));
+        }
+        self.tag_account(account, tag, "").await
+    }
+
+    /// Take `account` out of the group `tag`; `false` if it was not in it
+    ///
+    /// A `tag=value` tag with the same key is left alone.
+    pub async fn remove_tag(&mut self, account: &str, tag: &str) -> Result<bool> {
+        let qualified = self.qualify(account);
+        let bare = self
+            .tag_store()?
+            .tags(&qualified)?
+            .get(tag)
+            .is_some_and(String::is_empty);
+        if !bare {
+            return Ok(false);
+        }
+        self.untag_account(account, tag).await
+    }
+
+    /// Accounts of this namespace in the group `tag`, by name
+    pub async fn accounts_tagged(&self, tag: &str) -> Result<Vec<String>> {
+        self.accounts_with_tag(tag, "").await
+    }
+
+    /// Sum of the net balances of the accounts in the group `tag`
+    ///
+    /// Tagged accounts that were never created count as 0.
+    pub async fn sum_tagged(&self, tag: &str) -> Result<i64> {
+        self.sum_by_tag(tag, "").await
+    }
+
+    /// Tags of `account` in name order: bare tags as `tag`, the others as `key=value`
+    pub async fn tags_of(&self, account: &str) -> Result<Vec<String>> {
+        let mut tags: Vec<String> = self
+            .tag_store()?
+            .tags(&self.qualify(account))?
+            .into_iter()
+            .map(|(key, value)| {
+                if value.is_empty() {
+                    key
+                } else {
+                    format!("{}={}", key, value)
+                }
+            })
+            .collect();
+        tags.sort();
+        Ok(tags)
+    }
+
+    /// Call `hook` after every committed transfer (see [`crate::hooks`])
+    pub fn on_transfer(&mut self, hook: TransferHook) {
+        self.hooks.register(None, hook);
+    }
+
+    /// Call `hook` after committed transfers from or to an account starting with `prefix`
+    pub fn on_account_change(&mut self, prefix: &str, hook: TransferHook) {
+        self.hooks.register(Some(prefix.to_string()), hook);
+    }
+
+    /// POST committed transfers matching `config` to its URL (see [`crate::webhooks`])
+    pub fn add_webhook(&mut self, config: WebhookConfig) -> Result<()> {
+        self.webhooks.register(config)
+    }
+
+    /// Keep webhook deliveries that failed every attempt in `store`
+    pub fn set_webhook_dead_letters(&mut self, store: WebhookDeadLetters) {
+        self.webhooks.set_dead_letters(store);
+    }
+
+    /// Hook events dropped because the hook queue was full
+    pub fn dropped_hook_events(&self) -> u64 {
+        self.hooks.dropped()
+    }
+
+    /// Balance cache lookups so far; counted whether or not
+    /// [`with_balance_cache`](Self::with_balance_cache) is set
+    pub fn balance_cache_stats(&self) -> BalanceCacheStats {
+        self.balance_cache.stats()
+    }
+
+    /// Drop cached balances of `accounts` that changed behind this engine's back
+    ///
+    /// Transfers made through this engine need no call; this is for changes it
+    /// cannot see, e.g. another engine's transfers reported by its hooks.
+    pub fn invalidate_balances<'a>(&self, accounts: impl IntoIterator<Item = &'a str>) {
+        for account in accounts {
+            self.balance_cache.invalidate(account);
+        }
+    }
+
+    /// Append a committed transfer to the log, index it and queue it for the hooks
+    ///
+    /// Inside a [`request_id::scope`] the transfer's metadata gets the request id.
+    fn record(&mut self, mut transfer: Transfer) {
+        if let Some(request_id) = request_id::current() {
+            transfer
+                .metadata
+                .entry(REQUEST_ID_KEY.to_string())
+                .or_insert(request_id);
+        }
+        // Before anyone is told, so whoever reacts reads the new balances
+        self.invalidate_balances([transfer.from_account.as_str(), transfer.to_account.as_str()]);
+        self.hooks.dispatch(&transfer);
+        self.webhooks.dispatch(&transfer);
+        match &self.metadata_index {
+            Some(index) => {
+                // The transfer is committed either way; only the lookup would miss it
+                if let Err(e) = index.insert(&transfer) {
+                    warn!(
+                        "🔎 Failed to index metadata of transfer {}: {}",
+                        transfer.id, e
+                    );
+                }
+            }
+            None => {
+                for (key, value) in &transfer.metadata {
+                    self.by_metadata
+                        .entry((key.clone(), value.clone()))
+                        .or_default()
+                        .push(self.transfers.len());
+                }
+            }
+        }
+        self.audit_head = audit::link(&self.audit_head, &transfer);
+        self.transfers.push(transfer);
+    }
+
+    /// Persist account names so listings and collision checks survive restarts
+    ///
+    /// See [`TigerBeetleClient::set_name_store`].
+    pub fn set_account_name_store(&mut self, store: AccountNameStore) -> Result<()> {
+        self.tigerbeetle.set_name_store(store)
+    }
+
+    /// Index transfer metadata in SLED instead of memory, so it outlives the process
+    ///
+    /// Transfers already in this engine's log are copied over.
+    pub fn set_metadata_index(&mut self, index: MetadataIndex) -> Result<()> {
+        for transfer in &self.transfers {
+            index.insert(transfer)?;
+        }
+        self.by_metadata.clear();
+        self.metadata_index = Some(index);
+        Ok(())
+    }
+
+    /// Keep balance checkpoints in `store` (checkpointing fails until one is set)
+    pub fn set_checkpoint_store(&mut self, store: CheckpointStore) {
+        self.checkpoints = Some(store);
+    }
+
+    fn checkpoint_store(&self) -> Result<&CheckpointStore> {
+        self.checkpoints.as_ref().ok_or_else(|| {
+            anyhow!("No checkpoint store configured, call set_checkpoint_store first")
+        })
+    }
+
+    /// Count what accounts send per UTC day in `store`, for
+    /// [`TransferLimits::daily_limits`] (transfers they cover fail until one is set)
+    pub fn set_daily_outflow_store(&mut self, store: DailyOutflowStore) {
+        self.daily_outflow = Some(store);
+    }
+
+    fn daily_outflow_store(&self) -> Result<&DailyOutflowStore> {
+        self.daily_outflow.as_ref().ok_or_else(|| {
+            anyhow!("No daily outflow store configured, call set_daily_outflow_store first")
+        })
+    }
+
+    /// Keep account currencies in `store`, refusing transfers between accounts
+    /// of different currencies (no account has one until a store is set)
+    pub fn set_currency_store(&mut self, store: CurrencyStore) {
+        self.account_currencies = Some(store);
+    }
+
+    fn currency_store(&self) -> Result<&CurrencyStore> {
+        self.account_currencies
+            .as_ref()
+            .ok_or_else(|| anyhow!("No currency store configured, call set_currency_store first"))
+    }
+
+    /// Create the monetary `account` holding `currency`, e.g. `EUR`
+    ///
+    /// The account gets the spec guessed from its name. Transfers between it and
+    /// an account holding another currency then fail with
+    /// [`ZikZakError::CurrencyMismatch`]; accounts without a currency, like
+    /// `system:genesis`, can still pay into it. Existence, status and frozen flag
+    /// accounts count things rather than money and cannot hold a currency.
+    /// Calling this again with the same currency does nothing.
+    pub async fn create_account_with_currency(
+        &mut self,
+        account: impl Into<AccountId>,
+        currency: &str,
+    ) -> Result<()> {
+        let account = account.into();
+        let account = account.as_str();
+        if currency.is_empty() || !currency.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(anyhow!("Invalid currency code {:?}", currency));
+        }
+        if !is_monetary(account) {
+            return Err(anyhow!("{} is not a monetary account", account));
+        }
+        let qualified = self.qualify(account);
+        match self.currency_store()?.get(&qualified)? {
+            Some(held) if held == currency => return Ok(()),
+            Some(held) => {
+                return Err(anyhow!(
+                    "{} already holds {}, not {}",
+                    account,
+                    held,
+                    currency
+                ))
+            }
+            None => {}
+        }
+
+        let spec = self.tigerbeetle.default_account_spec(&qualified);
+        self.tigerbeetle
+            .create_account_with_flags(&qualified, spec)
+            .await?;
+        self.currency_store()?.set(&qualified, currency)
+    }
+
+    /// Currency `account` was created with, `None` for accounts without one
+    pub fn currency_of(&self, account: &str) -> Result<Option<String>> {
+        match &self.account_currencies {
+            Some(store) if is_monetary(account) => store.get(&self.qualify(account)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Fail with `CurrencyMismatch` if `from` and `to` hold different currencies
+    fn check_currencies(&self, from: &str, to: &str) -> Result<()> {
+        let (Some(from_currency), Some(to_currency)) =
+            (self.currency_of(from)?, self.currency_of(to)?)
+        else {
+            return Ok(());
+        };
+        if from_currency != to_currency {
+            debug!("💱 Refusing {} -> {} across currencies", from, to);
+            return Err(ZikZakError::CurrencyMismatch {
+                from: from.to_string(),
+                from_currency,
+                to: to.to_string(),
+                to_currency,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Record every account's current net balance as checkpoint `name`
+    ///
+    /// Reads the whole ledger through [`iter_accounts`](Self::iter_accounts), so
+    /// the checkpoint covers every namespace. A name can be used once; taking it
+    /// again fails and leaves the first checkpoint as it was.
+    pub async fn checkpoint(&mut self, name: &str) -> Result<()> {
+        let store = self.checkpoint_store()?;
+        if store.taken_at(name)?.is_some() {
+            return Err(anyhow!("Checkpoint {} already exists", name));
+        }
+
+        let balances: Vec<(String, i64)> = self
+            .iter_accounts()
+            .map_ok(|account| {
+                let balance = account.zak_balance as i64 - account.zik_balance as i64;
+                (account.name, balance)
+            })
+            .try_collect()
+            .await?;
+        store.write(name, self.now_secs(), &balances)
+    }
+
+    /// `account`'s net balance when checkpoint `name` was taken
+    ///
+    /// Fails for an unknown checkpoint, and with [`ZikZakError::AccountNotFound`]
+    /// for an account that did not exist yet at the time.
+    pub async fn balance_at_checkpoint(&self, name: &str, account: &str) -> Result<i64> {
+        let store = self.checkpoint_store()?;
+        if store.taken_at(name)?.is_none() {
+            return Err(anyhow!("Unknown checkpoint: {}", name));
+        }
+        store.balance(name, &self.qualify(account))?.ok_or_else(|| {
+            ZikZakError::AccountNotFound {
+                account: account.to_string(),
+            }
+            .into()
+        })
+    }
+
+    /// Up to `limit` committed transfers whose metadata has `key=value`, newest first
+    ///
+    /// Answered from the metadata index, never by scanning the log.
+    pub async fn find_transfers_by_metadata(
+        &self,
+        key: &str,
+        value: &str,
+        limit: usize,
+    ) -> Result<Vec<Transfer>> {
+        if let Some(index) = &self.metadata_index {
+            return index.find(key, value, limit);
+        }
+
+        let positions = self
+            .by_metadata
+            .get(&(key.to_string(), value.to_string()))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        Ok(positions
+            .iter()
+            .rev()
+            .take(limit)
+            .map(|&i| self.transfers[i].clone())
+            .collect())
+    }
+
+    /// Whether TigerBeetle answered the last request
+    ///
+    /// While this is `false`, operations fail fast with [`ZikZakError::Backend`]
+    /// until a [`ping`](Self::ping) gets through again.
+    pub fn is_connected(&self) -> bool {
+        self.tigerbeetle.is_connected()
+    }
+
+    /// Check TigerBeetle with a cheap lookup and refresh `is_connected`
+    pub async fn ping(&self) -> bool {
+        self.tigerbeetle.ping().await
+    }
+
+    /// Ping TigerBeetle every `every` in the background, for as long as the engine lives
+    ///
+    /// The engine is read-locked only for the ping itself.
+    pub fn spawn_connection_monitor(
+        engine: Arc<tokio::sync::RwLock<Self>>,
+        every: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let engine = Arc::downgrade(&engine);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            loop {
+                ticker.tick().await;
+                let Some(engine) = engine.upgrade() else {
+                    break;
+                };
+                engine.read().await.ping().await;
+            }
+        })
+    }
+
+    pub async fn get_account_count(&self) -> Result<usize> {
+        self.tigerbeetle.get_account_count().await
+    }
+
+    pub async fn get_transfer_count(&self) -> Result<usize> {
+        Ok(self.transfers.len())
+    }
+
+    /// Transfers logged after the first `from`, oldest first
+    ///
+    /// Take [`get_transfer_count`](Self::get_transfer_count) before an operation
+    /// to see exactly the transfers it made.
+    pub fn transfers_since(&self, from: usize) -> &[Transfer] {
+        self.transfers.get(from..).unwrap_or_default()
+    }
+
+    /// Get account balance using TigerBeetle - returns net balance (ZAK - ZIK)
+    ///
+    /// Served from memory when [`with_balance_cache`](Self::with_balance_cache) is
+    /// set, like [`Consistency::Cached`]; otherwise always read live.
+    pub async fn get_balance(&self, account_id: impl Into<AccountId>) -> Result<i64> {
+        let consistency = if self.cache_balances {
+            Consistency::Cached
+        } else {
+            Consistency::Strong
+        };
+        self.get_balance_with(account_id, consistency).await
+    }
+
+    /// Balance of `account_id`, read only as fresh as `consistency` asks for
+    ///
+    /// Every live read refreshes the cached balance.
+    pub async fn get_balance_with(
+        &self,
+        account_id: impl Into<AccountId>,
+        consistency: Consistency,
+    ) -> Result<i64> {
+        let account_id = account_id.into();
+        let account = account_id.as_str();
+        let now = self.clock.now_millis();
+
+        let max_age = match consistency {
+            Consistency::Strong => None,
+            Consistency::Cached => Some(Some(self.balance_cache_ttl.as_millis() as u64)),
+            Consistency::Eventual => Some(None),
+        };
+        if let Some(max_age) = max_age {
+            if let Some(balance) = self.balance_cache.get(account, now, max_age) {
+                return Ok(balance);
+            }
+        }
+
+        let balance = self.read_balance(account).await?;
+        self.balance_cache.insert(account, balance, now);
+        Ok(balance)
+    }
+
+    /// Net balance straight from TigerBeetle
+    async fn read_balance(&self, account_id: &str) -> Result<i64> {
+        debug!("💰 Getting balance for account: {}", account_id);
+
+        match self
+            .tigerbeetle
+            .get_account_balance(&self.qualify(account_id))
+            .await
+        {
+            Ok((zik_balance, zak_balance)) => {
+                let net_balance = zak_balance as i64 - zik_balance as i64;
+                debug!(
+                    "💰 Balance for {}: ZIK={}, ZAK={}, Net={}",
+                    account_id, zik_balance, zak_balance, net_balance
+                );
+                Ok(net_balance)
+            }
+            Err(e) => {
+                error!("❌ Failed to get balance for {}: {}", account_id, e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Balance of a field account, or `None` once its entity has been deleted
+    ///
+    /// `product:123:price` belongs to `product:123`; when `product:123:existence`
+    /// exists but is no longer positive, the price is leftover data and reads as
+    /// `None`. Accounts without an existence account for their entity
+    /// (`merchant:revenue`, `system:*`) always read their balance, like
+    /// [`get_active_ledger_state`](Self::get_active_ledger_state).
+    pub async fn get_balance_live(&self, account: &str) -> Result<Option<i64>> {
+        if self
+            .belongs_to_deleted_entity(account, &mut HashMap::new())
+            .await?
+        {
+            return Ok(None);
+        }
+        self.get_balance(account).await.map(Some)
+    }
+
+    /// Check whether an entity exists (`{entity}:existence` balance > 0)
+    ///
+    /// Entities that were never created and entities whose existence was moved to
+    /// `system:deleted`/`system:void` both count as gone.
+    pub async fn exists(&self, entity: &str) -> Result<bool> {
+        match self.get_balance(format!("{}:existence", entity)).await {
+            Ok(balance) => Ok(balance > 0),
+            Err(e) if is_account_not_found(&e) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Like [`exists`](Self::exists), but fails with `AccountNotFound` for a missing entity
+    pub async fn ensure_exists(&self, entity: &str) -> Result<()> {
+        if self.exists(entity).await? {
+            Ok(())
+        } else {
+            Err(ZikZakError::AccountNotFound {
+                account: entity.to_string(),
+            }
+            .into())
+        }
+    }
+
+    /// Block every transfer into or out of `account` until it is unfrozen
+    ///
+    /// Sets the `{account}:frozen` flag balance to 1. Freezing a frozen account
+    /// does nothing.
+    pub async fn freeze_account(&mut self, account: &str) -> Result<()> {
+        if self.is_frozen(account).await? {
+            return Ok(());
+        }
+        warn!("🧊 Freezing {}", account);
+        let metadata = HashMap::from([("reason".to_string(), "freeze".to_string())]);
+        // Straight to TigerBeetle, so even a frozen genesis can set the flag
+        self.commit_transfer("system:genesis", &frozen_flag(account), 1, metadata)
+            .await?;
+        Ok(())
+    }
+
+    /// Let `account` transfer again; unfreezing an account that is not frozen does nothing
+    pub async fn unfreeze_account(&mut self, account: &str) -> Result<()> {
+        let flag = frozen_flag(account);
+        let balance = match self.get_balance(flag.as_str()).await {
+            Ok(balance) if balance > 0 => balance,
+            Ok(_) => return Ok(()),
+            Err(e) if is_account_not_found(&e) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        info!("🔥 Unfreezing {}", account);
+        let metadata = HashMap::from([("reason".to_string(), "unfreeze".to_string())]);
+        self.commit_transfer(&flag, "system:genesis", balance, metadata)
+            .await?;
+        Ok(())
+    }
+
+    /// Whether `account` is frozen (`{account}:frozen` balance > 0)
+    pub async fn is_frozen(&self, account: &str) -> Result<bool> {
+        match self.get_balance(frozen_flag(account)).await {
+            Ok(balance) => Ok(balance > 0),
+            Err(e) if is_account_not_found(&e) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Record that `from_entity` stands in `relation` to `to_entity`
+    ///
+    /// Sets the `{from}:rel:{relation}:{to}` link balance to 1, e.g.
+    /// `user:456:rel:orders:order:789`, so the relation is a transfer like any
+    /// other. Linking twice does nothing. `relation` must be a single
+    /// [segment](crate::accounting), or this fails with
+    /// [`ZikZakError::InvalidAccountName`].
+    pub async fn link(&mut self, from_entity: &str, relation: &str, to_entity: &str) -> Result<()> {
+        check_segment(relation)?;
+        if to_entity.is_empty() {
+            return Err(ZikZakError::InvalidAccountName {
+                segment: to_entity.to_string(),
+                reason: "empty segment".to_string(),
+            }
+            .into());
+        }
+        let link = link_account(from_entity, relation, to_entity);
+        match self.get_balance(link.as_str()).await {
+            Ok(balance) if balance > 0 => return Ok(()),
+            Ok(_) => {}
+            Err(e) if is_account_not_found(&e) => {}
+            Err(e) => return Err(e),
+        }
+
+        debug!("🔗 Linking {} -{}-> {}", from_entity, relation, to_entity);
+        let metadata = HashMap::from([("relation".to_string(), relation.to_string())]);
+        self.transfer("system:genesis", link.as_str(), 1, metadata)
+            .await?;
+        Ok(())
+    }
+
+    /// Every entity `from_entity` is [linked](Self::link) to by `relation`, sorted
+    ///
+    /// Scans the whole ledger like [`find_accounts`](Self::find_accounts).
+    pub async fn linked(&self, from_entity: &str, relation: &str) -> Result<Vec<String>> {
+        check_segment(relation)?;
+        let prefix = link_account(from_entity, relation, "");
+
+        let mut accounts = pin!(self.iter_accounts());
+        let mut targets = Vec::new();
+        while let Some(account) = accounts.try_next().await? {
+            let Some(target) = self
+                .unqualify(&account.name)
+                .and_then(|name| name.strip_prefix(prefix.as_str()))
+            else {
+                continue;
+            };
+            if !target.is_empty() && account.zak_balance > account.zik_balance {
+                targets.push(target.to_string());
+            }
+        }
+
+        targets.sort();
+        Ok(targets)
+    }
+
+    /// Execute transfer using TigerBeetle
+    ///
+    /// Fails with [`ZikZakError::AccountFrozen`] if either account is frozen, and
+    /// with [`ZikZakError::CurrencyMismatch`] between accounts of different
+    /// [currencies](Self::create_account_with_currency).
+    pub async fn transfer(
+        &mut self,
+        from_account: impl Into<AccountId>,
+        to_account: impl Into<AccountId>,
+        amount: i64,
+        metadata: HashMap<String, String>,
+    ) -> Result<String> {
+        let (from_account, to_account) = (from_account.into(), to_account.into());
+        let (from_account, to_account) = (from_account.as_str(), to_account.as_str());
+
+        self.precheck(from_account, to_account, amount, 0).await?;
+
+        let id = self
+            .commit_transfer(from_account, to_account, amount, metadata)
+            .await?;
+        self.count_outflow(from_account, amount);
+        Ok(id)
+    }
+
+    /// [`transfer`](Self::transfer) with a caller-chosen TigerBeetle id, safe to retry
+    ///
+    /// If a transfer with `transfer_id` was already made, nothing moves and nothing
+    /// is recorded again; the result is the same id either way, as a UUID string.
+    /// Retries must repeat the same accounts and amount, or TigerBeetle rejects them.
+    pub async fn transfer_with_id(
+        &mut self,
+        transfer_id: u128,
+        from_account: &str,
+        to_account: &str,
+        amount: i64,
+        metadata: HashMap<String, String>,
+    ) -> Result<String> {
+        let id = Uuid::from_u128(transfer_id).to_string();
+        // Retried by this engine: already recorded, and the limits must not count it twice
+        if self.transfers.iter().any(|t| t.id == id) {
+            debug!("♻️ Transfer {} is in the log, not repeating it", id);
+            return Ok(id);
+        }
+
+        self.precheck(from_account, to_account, amount, 0).await?;
+
+        let created = self
+            .tigerbeetle
+            .create_transfer_with_id(
+                transfer_id,
+                &self.qualify(from_account),
+                &self.qualify(to_account),
+                amount as u128,
+            )
+            .await?;
+        if !created {
+            info!("♻️ Transfer {} was already made, not repeating it", id);
+            return Ok(id);
+        }
+
+        self.record(Transfer {
+            id: id.clone(),
+            from_account: from_account.to_string(),
+            to_account: to_account.to_string(),
+            amount,
+            metadata,
+            timestamp: self.clock.now_secs(),
+        });
+        self.count_outflow(from_account, amount);
+        info!("✅ Transfer completed: {}", id);
+        Ok(id)
+    }
+
+    /// Send a validated transfer to TigerBeetle and record it, skipping the limits
+    async fn commit_transfer(
+        &mut self,
+        from_account: &str,
+        to_account: &str,
+        amount: i64,
+        metadata: HashMap<String, String>,
+    ) -> Result<String> {
+        info!(
+            "💸 Creating transfer: {} -> {} (amount: {})",
+            from_account, to_account, amount
+        );
+
+        // Execute transfer in TigerBeetle
+        match self
+            .tigerbeetle
+            .create_transfer(
+                &self.qualify(from_account),
+                &self.qualify(to_account),
+                amount as u128,
+                None,
+            )
+            .await
+        {
+            Ok(id) => {
+                // Same id as in TigerBeetle, so the record can be found from either side
+                let transfer_id = Uuid::from_u128(id).to_string();
+                let transfer = Transfer {
+                    id: transfer_id.clone(),
+                    from_account: from_account.to_string(),
+                    to_account: to_account.to_string(),
+                    amount,
+                    metadata,
+                    timestamp: self.clock.now_secs(),
+                };
+
+                self.record(transfer);
+
+                info!("✅ Transfer completed: {}", transfer_id);
+                Ok(transfer_id)
+            }
+            Err(e) => {
+                error!("❌ Transfer failed: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Fail with `LimitExceeded` if sending `amount` from `account` breaks a limit
+    ///
+    /// `queued` is what `account` sends earlier in the same batch, which the log
+    /// does not hold yet. System accounts are exempt.
+    fn check_limits(&self, account: &str, amount: i64, queued: i64) -> Result<()> {
+        if is_system_account(account) {
+            return Ok(());
+        }
+
+        if let Some(max) = self.limits.max_amount {
+            if amount > max {
+                warn!(
+                    "🚨 {} tried to send {} in one transfer (cap {})",
+                    account, amount, max
+                );
+                return Err(ZikZakError::LimitExceeded {
+                    account: account.to_string(),
+                    limit: "single transfer".to_string(),
+                    amount,
+                    max,
+                }
+                .into());
+            }
+        }
+
+        if let Some(max) = self.limits.daily_outflow {
+            let since = self.clock.now_secs().saturating_sub(DAY_SECS);
+            let sent = self
+                .recent_outflow
+                .get(account)
+                .map_or(0, |outflow| outflow.since(since))
+                + queued;
+            if sent + amount > max {
+                warn!(
+                    "🚨 {} would send {} within 24h (cap {})",
+                    account,
+                    sent + amount,
+                    max
+                );
+                return Err(ZikZakError::LimitExceeded {
+                    account: account.to_string(),
+                    limit: "daily outflow".to_string(),
+                    amount: sent + amount,
+                    max,
+                }
+                .into());
+            }
+        }
+
+        let day = self.clock.now_secs() / DAY_SECS;
+        for limit in self.daily_limits(account) {
+            let sent = self.daily_outflow_store()?.sent(account, day)? + queued;
+            if sent + amount > limit.max {
+                warn!(
+                    "🚨 {} would send {} today (cap {} for {})",
+                    account,
+                    sent + amount,
+                    limit.max,
+                    limit.pattern
+                );
+                return Err(ZikZakError::LimitExceeded {
+                    account: account.to_string(),
+                    limit: format!("daily {}", limit.pattern),
+                    amount: sent + amount,
+                    max: limit.max,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// [`TransferLimits::daily_limits`] that apply to `account`
+    fn daily_limits<'a>(&'a self, account: &'a str) -> impl Iterator<Item = &'a DailyLimit> {
+        self.limits
+            .daily_limits
+            .iter()
+            .filter(move |limit| limit.pattern.matches(account))
+    }
+
+    /// Count a committed transfer of `amount` from `account` towards its limits
+    fn count_outflow(&mut self, account: &str, amount: i64) {
+        if is_system_account(account) {
+            return;
+        }
+        let now = self.clock.now_secs();
+        self.recent_outflow
+            .entry(account.to_string())
+            .or_default()
+            .add(now, amount, now.saturating_sub(DAY_SECS));
+
+        if self.daily_limits(account).next().is_none() {
+            return;
+        }
+        let day = now / DAY_SECS;
+        // The transfer is committed either way; only the count would be short
+        let counted = self
+            .daily_outflow_store()
+            .and_then(|store| store.add(account, day, amount));
+        if let Err(e) = counted {
+            warn!("🚨 Failed to count daily outflow of {}: {}", account, e);
+        }
+    }
+
+    /// Fail with `AccountFrozen` for the first frozen account of `accounts`
+    async fn check_not_frozen<'a>(
+        &self,
+        accounts: impl IntoIterator<Item = &'a str>,
+    ) -> Result<()> {
+        for account in accounts {
+            if self.is_frozen(account).await? {
+                debug!("🧊 Refusing transfer touching frozen {}", account);
+                return Err(ZikZakError::AccountFrozen {
+                    account: account.to_string(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuse moving `amount` from `from` to `to` for whatever would refuse a transfer
+    ///
+    /// A non-positive amount, a [limit](TransferLimits), accounts of different
+    /// currencies or a frozen account. `queued` is what `from` sends earlier in the
+    /// same batch, see [`check_limits`](Self::check_limits).
+    async fn precheck(&self, from: &str, to: &str, amount: i64, queued: i64) -> Result<()> {
+        if amount <= 0 {
+            return Err(anyhow!("Transfer amount must be positive"));
+        }
+        self.check_limits(from, amount, queued)?;
+        self.check_currencies(from, to)?;
+        self.check_not_frozen([from, to]).await
+    }
+
+    /// Execute transfer with user_data for Sled reference
+    ///
+    /// Refused like a [`transfer`](Self::transfer) would be.
+    pub async fn transfer_with_user_data(
+        &mut self,
+        from_account: &str,
+        to_account: &str,
+        amount: i64,
+        user_data_128: u128,
+        metadata: HashMap<String, String>,
+    ) -> Result<String> {
+        self.precheck(from_account, to_account, amount, 0).await?;
+
+        info!(
+            "💸 Creating transfer with user_data: {} -> {} (amount: {}, user_data_128: {})",
+            from_account, to_account, amount, user_data_128
+        );
+
+        // For now, use the existing transfer method until TigerBeetle client is updated
+        // TODO: Update TigerBeetle client to accept user_data_128 parameter
+        match self
+            .tigerbeetle
+            .create_transfer(
+                &self.qualify(from_account),
+                &self.qualify(to_account),
+                amount as u128,
+                None,
+            )
+            .await
+        {
+            Ok(id) => {
+                let transfer_id = Uuid::from_u128(id).to_string();
+                // Store transfer record with user_data info in metadata
+                let mut enhanced_metadata = metadata;
+                enhanced_metadata.insert("user_data_128".to_string(), user_data_128.to_string());
+                enhanced_metadata.insert("sled_reference".to_string(), "true".to_string());
+
+                let transfer = Transfer {
+                    id: transfer_id.clone(),
+                    from_account: from_account.to_string(),
+                    to_account: to_account.to_string(),
+                    amount,
+                    metadata: enhanced_metadata,
+                    timestamp: self.clock.now_secs(),
+                };
+
+                self.record(transfer);
+                self.count_outflow(from_account, amount);
+
+                info!("✅ Transfer with user_data completed: {}", transfer_id);
+                Ok(transfer_id)
+            }
+            Err(e) => {
+                error!("❌ Transfer with user_data failed: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Execute many transfers as one linked TigerBeetle batch
+    ///
+    /// Either every transfer commits or none does. All recorded transfers share a
+    /// `batch_id` metadata entry. TigerBeetle caps a request at 8189 events, so very
+    /// large batches must be split by the caller. A frozen account anywhere in the
+    /// batch fails all of it with [`ZikZakError::AccountFrozen`], a leg between
+    /// different currencies with [`ZikZakError::CurrencyMismatch`], and a leg over
+    /// a limit with [`ZikZakError::LimitExceeded`]; limits count the legs before it
+    /// from the same account.
+    pub async fn transfer_batch(&mut self, transfers: Vec<BatchTransfer>) -> Result<Vec<String>> {
+        if transfers.is_empty() {
+            return Ok(Vec::new());
+        }
+        if let Some(bad) = transfers.iter().find(|t| t.amount <= 0) {
+            return Err(anyhow!(
+                "Transfer amount must be positive ({} -> {}: {})",
+                bad.from_account,
+                bad.to_account,
+                bad.amount
+            ));
+        }
+        let mut queued: HashMap<&str, i64> = HashMap::new();
+        for t in &transfers {
+            let sent = queued.get(t.from_account.as_str()).copied().unwrap_or(0);
+            self.precheck(&t.from_account, &t.to_account, t.amount, sent)
+                .await?;
+            *queued.entry(t.from_account.as_str()).or_default() += t.amount;
+        }
+
+        let batch_id = Uuid::new_v4().to_string();
+        info!(
+            "📦 Creating batch {} of {} linked transfers",
+            batch_id,
+            transfers.len()
+        );
+
+        let legs = transfers
+            .iter()
+            .map(|t| {
+                (
+                    self.qualify(&t.from_account),
+                    self.qualify(&t.to_account),
+                    t.amount as u128,
+                )
+            })
+            .collect();
+
+        let ids = match self.tigerbeetle.create_linked_transfers(legs).await {
+            Ok(ids) => ids,
+            Err(e) => {
+                error!("❌ Batch {} failed, nothing committed: {}", batch_id, e);
+                return Err(e);
+            }
+        };
+
+        let timestamp = self.clock.now_secs();
+
+        let mut transfer_ids = Vec::with_capacity(transfers.len());
+        for (t, id) in transfers.into_iter().zip(ids) {
+            let transfer_id = Uuid::from_u128(id).to_string();
+            let mut metadata = t.metadata;
+            metadata.insert("batch_id".to_string(), batch_id.clone());
+
+            self.count_outflow(&t.from_account, t.amount);
+            self.record(Transfer {
+                id: transfer_id.clone(),
+                from_account: t.from_account,
+                to_account: t.to_account,
+                amount: t.amount,
+                metadata,
+                timestamp,
+            });
+            transfer_ids.push(transfer_id);
+        }
+
+        info!("✅ Batch {} completed", batch_id);
+        Ok(transfer_ids)
+    }
+
+    /// Execute many independent transfers in one TigerBeetle request
+    ///
+    /// Unlike [`transfer_batch`](Self::transfer_batch) nothing is linked: each
+    /// transfer is checked and committed on its own, and the result holds its id or
+    /// why it was refused, in order. Limits count the transfers before it in the
+    /// same batch. Only failing to reach TigerBeetle fails the whole call. At most
+    /// 8189 transfers fit in one request.
+    pub async fn transfer_each(
+        &mut self,
+        transfers: Vec<BatchTransfer>,
+    ) -> Result<Vec<Result<String, String>>> {
+        let mut results = Vec::with_capacity(transfers.len());
+        let mut accepted = Vec::new();
+        let mut queued: HashMap<&str, i64> = HashMap::new();
+        for (i, t) in transfers.iter().enumerate() {
+            let sent = queued.get(t.from_account.as_str()).copied().unwrap_or(0);
+            let checked = self
+                .precheck(&t.from_account, &t.to_account, t.amount, sent)
+                .await;
+            match checked {
+                Ok(()) => {
+                    *queued.entry(t.from_account.as_str()).or_default() += t.amount;
+                    accepted.push(i);
+                    // Replaced by the id once TigerBeetle committed it
+                    results.push(Ok(String::new()));
+                }
+                Err(e) => results.push(Err(e.to_string())),
+            }
+        }
+        if accepted.is_empty() {
+            return Ok(results);
+        }
+
+        info!(
+            "📦 Creating {} independent transfers ({} refused before sending)",
+            accepted.len(),
+            transfers.len() - accepted.len()
+        );
+        let legs = accepted
+            .iter()
+            .map(|&i| {
+                let t = &transfers[i];
+                (
+                    self.qualify(&t.from_account),
+                    self.qualify(&t.to_account),
+                    t.amount as u128,
+                )
+            })
+            .collect();
+        let outcomes = self.tigerbeetle.create_independent_transfers(legs).await?;
+
+        let timestamp = self.clock.now_secs();
+        let mut outcomes = outcomes.into_iter();
+        for (t, result) in transfers.into_iter().zip(results.iter_mut()) {
+            // Refused before sending, so TigerBeetle has no outcome for it
+            if result.is_err() {
+                continue;
+            }
+            *result = match outcomes.next() {
+                Some(Ok(id)) => {
+                    let transfer_id = Uuid::from_u128(id).to_string();
+                    self.count_outflow(&t.from_account, t.amount);
+                    self.record(Transfer {
+                        id: transfer_id.clone(),
+                        from_account: t.from_account,
+                        to_account: t.to_account,
+                        amount: t.amount,
+                        metadata: t.metadata,
+                        timestamp,
+                    });
+                    Ok(transfer_id)
+                }
+                Some(Err(e)) => Err(format!(
+                    "Transfer {} -> {} refused by TigerBeetle: {}",
+                    t.from_account, t.to_account, e
+                )),
+                None => Err("TigerBeetle returned no result".to_string()),
+            };
+        }
+        Ok(results)
+    }
+
+    /// Credit every account in `accounts` with `amount` from genesis
+    ///
+    /// Sent as linked batches of up to 1000 transfers: each batch commits or fails
+    /// as a whole, and a failed batch does not stop the next one. Results come
+    /// back in the order of `accounts`.
+    pub async fn mint_to(
+        &mut self,
+        accounts: &[String],
+        amount: i64,
+        metadata: HashMap<String, String>,
+    ) -> Result<Vec<BulkResult>> {
+        if amount <= 0 {
+            return Err(anyhow!("Mint amount must be positive"));
+        }
+        let transfers = accounts
+            .iter()
+            .map(|account| BatchTransfer {
+                from_account: "system:genesis".to_string(),
+                to_account: account.clone(),
+                amount,
+                metadata: metadata.clone(),
+            })
+            .collect();
+        Ok(self.transfer_chunks(accounts, transfers).await)
+    }
+
+    /// Debit `amount` from every account in `accounts` into `to` (`system:void` by default)
+    ///
+    /// Accounts holding less than `amount` are skipped with an
+    /// [`InsufficientBalance`](ZikZakError::InsufficientBalance) result before
+    /// anything is sent; the rest go out in linked batches like
+    /// [`mint_to`](Self::mint_to).
+    pub async fn burn_from(
+        &mut self,
+        accounts: &[String],
+        amount: i64,
+        to: Option<&str>,
+    ) -> Result<Vec<BulkResult>> {
+        if amount <= 0 {
+            return Err(anyhow!("Burn amount must be positive"));
+        }
+        let to = to.unwrap_or("system:void");
+
+        let mut results = Vec::with_capacity(accounts.len());
+        let mut funded = Vec::new();
+        let mut transfers = Vec::new();
+        for account in accounts {
+            let balance = match self
+                .get_balance_with(account.as_str(), Consistency::Strong)
+                .await
+            {
+                Ok(balance) => balance,
+                Err(e) if is_account_not_found(&e) => 0,
+                Err(e) => return Err(e),
+            };
+            if balance < amount {
+                let error = ZikZakError::InsufficientBalance {
+                    account: account.clone(),
+                    balance,
+                    required: amount,
+                };
+                results.push(Some(BulkResult {
+                    account: account.clone(),
+                    result: Err(error.to_string()),
+                }));
+                continue;
+            }
+            results.push(None);
+            funded.push(account.clone());
+            transfers.push(BatchTransfer {
+                from_account: account.clone(),
+                to_account: to.to_string(),
+                amount,
+                metadata: HashMap::from([("reason".to_string(), "burn".to_string())]),
+            });
+        }
+
+        let mut sent = self.transfer_chunks(&funded, transfers).await.into_iter();
+        Ok(results
+            .into_iter()
+            .map(|result| result.or_else(|| sent.next()))
+            .collect::<Option<_>>()
+            .expect("one batch result per funded account"))
+    }
+
+    /// `transfers` (one per entry of `accounts`) in linked chunks, one result per account
+    async fn transfer_chunks(
+        &mut self,
+        accounts: &[String],
+        transfers: Vec<BatchTransfer>,
+    ) -> Vec<BulkResult> {
+        let mut results = Vec::with_capacity(accounts.len());
+        for (accounts, transfers) in accounts
+            .chunks(BULK_CHUNK_SIZE)
+            .zip(transfers.chunks(BULK_CHUNK_SIZE))
+        {
+            let outcome = self.transfer_batch(transfers.to_vec()).await;
+            for (i, account) in accounts.iter().enumerate() {
+                let result = match &outcome {
+                    Ok(ids) => Ok(ids[i].clone()),
+                    Err(e) => Err(e.to_string()),
+                };
+                results.push(BulkResult {
+                    account: account.clone(),
+                    result,
+                });
+            }
+        }
+        results
+    }
+
+    /// Check the source balance and transfer as one step
+    ///
+    /// Fails with [`ZikZakError::InsufficientBalance`] unless `from_account` holds at
+    /// least `min_from_balance`, and at least `amount`. TigerBeetle does the check,
+    /// like in [`reserve_stock`](Self::reserve_stock): the transfer is linked with a
+    /// hold of the rest of `min_from_balance` that is handed straight back, and the
+    /// source's [`AccountDirection::Zak`] flag refuses them all if it is short. So
+    /// no other engine or process can move the balance in between, and sources
+    /// whose debits TigerBeetle does not constrain are refused.
+    pub async fn check_and_transfer(
+        &mut self,
+        from_account: &str,
+        to_account: &str,
+        amount: i64,
+        min_from_balance: i64,
+    ) -> Result<String> {
+        self.check_and_transfer_with_metadata(
+            from_account,
+            to_account,
+            amount,
+            min_from_balance,
+            HashMap::new(),
+        )
+        .await
+    }
+
+    async fn check_and_transfer_with_metadata(
+        &mut self,
+        from_account: &str,
+        to_account: &str,
+        amount: i64,
+        min_from_balance: i64,
+        metadata: HashMap<String, String>,
+    ) -> Result<String> {
+        self.precheck(from_account, to_account, amount, 0).await?;
+        let required = min_from_balance.max(amount);
+        let short = |balance| ZikZakError::InsufficientBalance {
+            account: from_account.to_string(),
+            balance,
+            required,
+        };
+
+        let from = self.qualify(from_account);
+        let account = self
+            .tigerbeetle
+            .get_account_info(&from)
+            .await?
+            .ok_or_else(|| short(0))?;
+        if account.direction() != AccountDirection::Zak {
+            return Err(anyhow!(
+                "Cannot check the balance of {}: TigerBeetle does not keep it from going negative",
+                from_account
+            ));
+        }
+
+        let held = (required - amount) as u128;
+        let mut legs = vec![(from.clone(), self.qualify(to_account), amount as u128)];
+        if held > 0 {
+            legs.push((from.clone(), BALANCE_CHECK.to_string(), held));
+            legs.push((BALANCE_CHECK.to_string(), from, held));
+        }
+
+        let ids = match self.tigerbeetle.create_linked_transfers(legs).await {
+            Ok(ids) => ids,
+            Err(e) => {
+                let balance = self
+                    .get_balance_with(from_account, Consistency::Strong)
+                    .await;
+                return match balance {
+                    Ok(balance) if balance < required => {
+                        debug!(
+                            "🚫 Refusing transfer from {}: balance {} below required {}",
+                            from_account, balance, required
+                        );
+                        Err(short(balance).into())
+                    }
+                    _ => Err(e),
+                };
+            }
+        };
+
+        // Only the transfer itself is recorded; the hold left no trace on any balance
+        let transfer_id = Uuid::from_u128(ids[0]).to_string();
+        self.record(Transfer {
+            id: transfer_id.clone(),
+            from_account: from_account.to_string(),
+            to_account: to_account.to_string(),
+            amount,
+            metadata,
+            timestamp: self.clock.now_secs(),
+        });
+        self.count_outflow(from_account, amount);
+        info!("✅ Transfer completed: {}", transfer_id);
+        Ok(transfer_id)
+    }
+
+    /// Move `amount` out of `stock` to `to`, only if `stock` holds that much
+    ///
+    /// There is no separate balance read: the stock account's
+    /// [`AccountDirection::Zak`] flag makes TigerBeetle itself refuse a transfer
+    /// that would take it below zero, so two processes reserving the last unit at
+    /// once cannot both succeed. Fails with [`ZikZakError::InsufficientBalance`]
+    /// when the stock is short, and refuses stock accounts whose debits TigerBeetle
+    /// does not constrain.
+    ///
+    /// The units move for good; use [`reserve`](Self::reserve) for a hold that
+    /// expires.
+    pub async fn reserve_stock(
+        &mut self,
+        stock: &str,
+        to: &str,
+        amount: i64,
+        metadata: HashMap<String, String>,
+    ) -> Result<String> {
+        let short = |balance| ZikZakError::InsufficientBalance {
+            account: stock.to_string(),
+            balance,
+            required: amount,
+        };
+
+        let account = self
+            .tigerbeetle
+            .get_account_info(&self.qualify(stock))
+            .await?
+            .ok_or_else(|| short(0))?;
+        if account.direction() != AccountDirection::Zak {
+            return Err(anyhow!(
+                "Cannot reserve from {}: TigerBeetle does not keep it from going negative",
+                stock
+            ));
+        }
+
+        match self.transfer(stock, to, amount, metadata).await {
+            Ok(transfer_id) => Ok(transfer_id),
+            Err(e) => match self.get_balance(stock).await {
+                Ok(balance) if balance < amount => {
+                    debug!("🚫 Reservation of {} from {} refused", amount, stock);
+                    Err(short(balance).into())
+                }
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Create `account` with an explicit direction and history instead of the
+    /// name-based guess (see [`TigerBeetleClient::default_account_spec`])
+    ///
+    /// Accounts are otherwise created on first transfer, so call this before the
+    /// account is first used. An existing account with the same flags is fine.
+    pub async fn create_account(
+        &mut self,
+        account: impl Into<AccountId>,
+        spec: AccountSpec,
+    ) -> Result<()> {
+        let account = self.qualify(account.into().as_str());
+        self.tigerbeetle
+            .create_account_with_flags(&account, spec)
+            .await
+    }
+
+    /// Apply a signed `delta` to `account` against `system:genesis`
+    ///
+    /// Handy for counters and stock levels; see [`adjust_against`](Self::adjust_against).
+    pub async fn adjust(&mut self, account: &str, delta: i64) -> Result<String> {
+        self.adjust_against(account, delta, "system:genesis", HashMap::new())
+            .await
+    }
+
+    /// Apply a signed `delta` to `account`, with `counterparty` on the other side
+    ///
+    /// A positive delta credits the account (`counterparty -> account`); a negative
+    /// one debits it (`account -> counterparty`), and fails with
+    /// [`ZikZakError::InsufficientBalance`] rather than drive the account below
+    /// zero. The recorded transfer amount is always positive; the signed delta is
+    /// kept in its `delta` metadata next to `metadata`.
+    pub async fn adjust_against(
+        &mut self,
+        account: &str,
+        delta: i64,
+        counterparty: &str,
+        mut metadata: HashMap<String, String>,
+    ) -> Result<String> {
+        metadata.insert("delta".to_string(), delta.to_string());
+
+        match delta {
+            0 => Err(anyhow!("Adjustment delta must be non-zero")),
+            delta if delta > 0 => self.transfer(counterparty, account, delta, metadata).await,
+            delta => {
+                let amount = delta
+                    .checked_neg()
+                    .ok_or_else(|| anyhow!("Adjustment delta {} is out of range", delta))?;
+                self.check_and_transfer_with_metadata(
+                    account,
+                    counterparty,
+                    amount,
+                    amount,
+                    metadata,
+                )
+                .await
+            }
+        }
+    }
+
+    /// The named state `account` holds, `None` while it holds none
+    ///
+    /// Fails if the balance is not one of `S`'s values.
+    pub async fn get_state<S: StateEnum>(&self, account: &str) -> Result<Option<S>> {
+        let balance = match self.get_balance(account).await {
+            Ok(balance) => balance,
+            Err(e) if is_account_not_found(&e) => 0,
+            Err(e) => return Err(e),
+        };
+        state_of(account, balance)
+    }
+
+    /// Move `account` to `state`, crediting or debiting the difference
+    ///
+    /// Fails with [`ZikZakError::InvalidTransition`] when `S` does not allow the
+    /// move. Returns the transfer id, or `None` if the account was already there.
+    pub async fn set_state<S: StateEnum>(
+        &mut self,
+        account: &str,
+        state: S,
+    ) -> Result<Option<String>> {
+        let balance = match self.get_balance(account).await {
+            Ok(balance) => balance,
+            Err(e) if is_account_not_found(&e) => 0,
+            Err(e) => return Err(e),
+        };
+        let current = state_of::<S>(account, balance)?;
+
+        if !S::allows(current, state) {
+            return Err(ZikZakError::InvalidTransition {
+                account: account.to_string(),
+                from: current.map_or("no state", |s| s.name()).to_string(),
+                to: state.name().to_string(),
+            }
+            .into());
+        }
+        if current == Some(state) {
+            return Ok(None);
+        }
+
+        debug!("🚦 {} -> {}::{}", account, S::NAME, state.name());
+        self.adjust(account, state.value() - balance)
+            .await
+            .map(Some)
+    }
+
+    /// Set up many balances at once, e.g. for test fixtures
+    ///
+    /// Positive amounts are transferred from genesis, negative ones back to it, all
+    /// in one linked batch: either every balance is seeded or none is. Zero entries
+    /// are skipped. A negative seed still fails for an account that may not go
+    /// below zero, which is the default for new accounts.
+    pub async fn seed(&mut self, balances: &[(&str, i64)]) -> Result<()> {
+        let metadata = HashMap::from([("seed".to_string(), "true".to_string())]);
+
+        let mut transfers = Vec::with_capacity(balances.len());
+        for &(account, amount) in balances {
+            let (from_account, to_account) = match amount {
+                0 => continue,
+                amount if amount > 0 => ("system:genesis", account),
+                _ => (account, "system:genesis"),
+            };
+            let amount = amount
+                .checked_abs()
+                .ok_or_else(|| anyhow!("Seed amount {} for {} is out of range", amount, account))?;
+            transfers.push(BatchTransfer {
+                from_account: from_account.to_string(),
+                to_account: to_account.to_string(),
+                amount,
+                metadata: metadata.clone(),
+            });
+        }
+
+        info!("🌱 Seeding {} balances", transfers.len());
+        self.transfer_batch(transfers).await?;
+        Ok(())
+    }
+
+    /// [`seed`](Self::seed) from a JSON fixture: `{"user:1:balance": 100, ...}`
+    pub async fn seed_from_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read seed file {:?}: {}", path, e))?;
+        let balances: BTreeMap<String, i64> = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse seed file {:?}: {}", path, e))?;
+
+        let balances: Vec<(&str, i64)> = balances
+            .iter()
+            .map(|(account, amount)| (account.as_str(), *amount))
+            .collect();
+        self.seed(&balances).await
+    }
+
+    /// Reserve `amount` as a pending transfer that TigerBeetle releases after `timeout_secs`
+    ///
+    /// The timeout is whole seconds counted from the moment TigerBeetle accepts the
+    /// transfer, stored in TigerBeetle's `u32` timeout field; 0 means the hold
+    /// never expires, and anything above `u32::MAX` (about 136 years) is refused.
+    /// Returns the TigerBeetle id of the pending transfer.
+    ///
+    /// Refused like a [`transfer`](Self::transfer) would be; the hold counts
+    /// towards the sender's [limits](TransferLimits) even if it is later voided.
+    pub async fn reserve(
+        &mut self,
+        from_account: &str,
+        to_account: &str,
+        amount: i64,
+        timeout_secs: u64,
+    ) -> Result<u128> {
+        self.precheck(from_account, to_account, amount, 0).await?;
+        if timeout_secs > u32::MAX as u64 {
+            return Err(anyhow!(
+                "Pending transfer timeout {}s is longer than TigerBeetle allows ({}s)",
+                timeout_secs,
+                u32::MAX
+            ));
+        }
+
+        let id = self
+            .tigerbeetle
+            .create_pending_transfer(
+                &self.qualify(from_account),
+                &self.qualify(to_account),
+                amount as u128,
+                timeout_secs,
+            )
+            .await?;
+        self.count_outflow(from_account, amount);
+        Ok(id)
+    }
+
+    /// Pending transfers touching `account` that are still holding funds
+    ///
+    /// A pending transfer is outstanding until it is posted, voided, or its own
+    /// timeout elapses (TigerBeetle releases the funds itself at that point).
+    /// Only the account's most recent 8189 transfers are looked at.
+    pub async fn list_pending(&self, account: &str) -> Result<Vec<ZikZakTransfer>> {
+        let transfers = self
+            .tigerbeetle
+            .get_account_transfers(&self.qualify(account), TRANSFER_QUERY_LIMIT)
+            .await?;
+
+        Ok(outstanding_pending(transfers, self.now_nanos()))
+    }
+
+    /// Up to `limit` outstanding pending transfers touching `account`, newest first
+    ///
+    /// Like [`list_pending`](Self::list_pending), with engine account names and
+    /// unix-second times.
+    pub async fn list_pending_transfers(
+        &self,
+        account: &str,
+        limit: usize,
+    ) -> Result<Vec<PendingTransfer>> {
+        let transfers = self
+            .tigerbeetle
+            .get_account_transfers(&self.qualify(account), TRANSFER_QUERY_LIMIT)
+            .await?;
+
+        let mut pending = outstanding_pending(transfers, self.now_nanos());
+        pending.sort_by_key(|t| std::cmp::Reverse(t.timestamp));
+        Ok(pending
+            .iter()
+            .take(limit)
+            .map(|t| self.pending_transfer(t))
+            .collect())
+    }
+
+    /// Pending transfers whose timeout has elapsed without them being posted or voided
+    ///
+    /// TigerBeetle has already released their funds. Scans the most recent
+    /// transfers on the ledger, across all namespaces.
+    pub async fn expired_pending(&self) -> Result<Vec<PendingTransfer>> {
+        let transfers = self
+            .tigerbeetle
+            .get_ledger_transfers(TRANSFER_QUERY_LIMIT)
+            .await?;
+
+        Ok(expired_pending(transfers, self.now_nanos())
+            .iter()
+            .map(|t| self.pending_transfer(t))
+            .collect())
+    }
+
+    fn pending_transfer(&self, transfer: &ZikZakTransfer) -> PendingTransfer {
+        let created_at = transfer.timestamp / 1_000_000_000;
+        PendingTransfer {
+            id: transfer.id,
+            from_account: self.account_name(transfer.zik_account_id),
+            to_account: self.account_name(transfer.zak_account_id),
+            amount: transfer.amount as i64,
+            created_at,
+            expires_at: (transfer.timeout > 0).then(|| created_at + transfer.timeout as u64),
+        }
+    }
+
+    /// Send every pending transfer that expires unresolved to `events`, checking `every` period
+    ///
+    /// Each expiry is sent once per watcher. Stops once the engine is dropped.
+    pub fn spawn_pending_watcher(
+        engine: Arc<tokio::sync::RwLock<Self>>,
+        every: Duration,
+        events: broadcast::Sender<PendingTransfer>,
+    ) -> tokio::task::JoinHandle<()> {
+        let engine = Arc::downgrade(&engine);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            let mut announced = HashSet::new();
+            loop {
+                ticker.tick().await;
+                let Some(engine) = engine.upgrade() else {
+                    break;
+                };
+                let expired = match engine.read().await.expired_pending().await {
+                    Ok(expired) => expired,
+                    Err(e) => {
+                        warn!("🕒 Failed to check pending transfers: {}", e);
+                        continue;
+                    }
+                };
+                for pending in &expired {
+                    if !announced.contains(&pending.id) {
+                        info!("🕒 Pending transfer {} expired", pending.id);
+                        // Nobody listening is fine
+                        let _ = events.send(pending.clone());
+                    }
+                }
+                // Only remember what is still in the scanned window
+                announced = expired.iter().map(|p| p.id).collect();
+            }
+        })
+    }
+
+    /// Void every outstanding pending transfer created more than `older_than_secs` ago
+    ///
+    /// Scans the most recent transfers on the ledger, across all namespaces, and
+    /// returns the ids of the pending transfers it voided.
+    pub async fn expire_stale_pending(&mut self, older_than_secs: u64) -> Result<Vec<u128>> {
+        let now = self.now_nanos();
+        let cutoff = now.saturating_sub(older_than_secs.saturating_mul(1_000_000_000));
+        let transfers = self
+            .tigerbeetle
+            .get_ledger_transfers(TRANSFER_QUERY_LIMIT)
+            .await?;
+
+        let mut voided = Vec::new();
+        for pending in outstanding_pending(transfers, now) {
+            if pending.timestamp > cutoff {
+                continue;
+            }
+
+            self.tigerbeetle.void_pending_transfer(pending.id).await?;
+            voided.push(pending.id);
+        }
+
+        if !voided.is_empty() {
+            info!("🧹 Voided {} stale pending transfers", voided.len());
+        }
+        Ok(voided)
+    }
+
+    /// Every account in TigerBeetle, oldest first, one page at a time
+    ///
+    /// Pages of [`ACCOUNT_PAGE_SIZE`] are fetched as the stream is polled, using
+    /// the creation timestamp as cursor, so ledgers of any size come out complete.
+    /// Names are as stored (namespace-qualified); accounts this process never
+    /// touched are named `account:{id}`.
+    pub fn iter_accounts(&self) -> impl Stream<Item = Result<ZikZakAccount>> + '_ {
+        self.iter_accounts_after(0)
+    }
+
+    /// [`iter_accounts`](Self::iter_accounts) starting after creation timestamp `after`
+    fn iter_accounts_after(&self, after: u64) -> impl Stream<Item = Result<ZikZakAccount>> + '_ {
+        stream::try_unfold(Some(after), move |cursor| async move {
+            let Some(after) = cursor else {
+                return Ok::<_, anyhow::Error>(None);
+            };
+            let page = self
+                .tigerbeetle
+                .query_accounts_page(0, 0, after, ACCOUNT_PAGE_SIZE)
+                .await?;
+            Ok(Some((
+                stream::iter(page.accounts.into_iter().map(Ok::<_, anyhow::Error>)),
+                page.next,
+            )))
+        })
+        .try_flatten()
+    }
+
+    /// Accounts with a negative net balance, most negative first
+    ///
+    /// Pages through [`iter_accounts`](Self::iter_accounts), so it works on any
+    /// ledger size but reads all of it. Only accounts of this engine's namespace
+    /// are reported, and never `system:*` ones - genesis is negative by design,
+    /// including a tenant's own `tenant:{id}:system:genesis`. With the default
+    /// [`AccountDirection`] nothing else can go below zero, so any hit points at an
+    /// [`AccountDirection::Unconstrained`] account or a bug.
+    pub async fn find_overdrawn(&self) -> Result<Vec<(String, i64)>> {
+        let mut accounts = pin!(self.iter_accounts());
+        let mut overdrawn = Vec::new();
+
+        while let Some(account) = accounts.try_next().await? {
+            let Some(name) = self.unqualify(&account.name) else {
+                continue;
+            };
+            if name.starts_with("system:") || name.contains(":system:") {
+                continue;
+            }
+            let balance = account.zak_balance as i64 - account.zik_balance as i64;
+            if balance < 0 {
+                overdrawn.push((name.to_string(), balance));
+            }
+        }
+
+        if !overdrawn.is_empty() {
+            warn!("🚨 {} overdrawn accounts", overdrawn.len());
+        }
+        overdrawn.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(overdrawn)
+    }
+
+    /// Get current ledger state (all account balances)
+    pub async fn get_ledger_state(&self) -> Result<Value> {
+        debug!("📊 Getting ledger state...");
+
+        let mut accounts = pin!(self.iter_accounts());
+        let mut ledger = HashMap::new();
+
+        while let Some(account) = accounts.try_next().await? {
+            let balance = account.zak_balance as i64 - account.zik_balance as i64;
+            ledger.insert(account.id.to_string(), balance);
+        }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct Transfer {
-    pub id: String,
-    pub from_account: String,
-    pub to_account: String,
-    pub amount: i64,
-    pub metadata: HashMap<String, String>,
-    pub timestamp: u64,
-}
+        Ok(serde_json::to_value(ledger)?)
+    }
 
-pub struct ZikZakEngine {
-    tigerbeetle: TigerBeetleClient,
-    transfers: Vec<Transfer>,
-}
+    /// Ledger state without accounts of deleted entities
+    ///
+    /// A field account such as `product:123:price` belongs to the entity
+    /// `product:123`; when that entity's `:existence` account exists but is 0 the
+    /// field is leftover data and is skipped. Accounts of things that never had an
+    /// existence account (`merchant:revenue`, `system:*`) are always kept.
+    ///
+    /// Cost: one balance lookup per distinct owning entity, cached for the duration
+    /// of this call only. Keyed by account name rather than numeric id.
+    pub async fn get_active_ledger_state(&self) -> Result<Value> {
+        debug!("📊 Getting active ledger state...");
 
-// SAFETY: ZikZakEngine is used within a Mutex, ensuring exclusive access
-// The TigerBeetleClient contains raw pointers but is protected by mutex synchronization
-unsafe impl Send for ZikZakEngine {}
-unsafe impl Sync for ZikZakEngine {}
+        let mut accounts = pin!(self.iter_accounts());
+        let mut deleted_cache = HashMap::new();
+        let mut ledger = HashMap::new();
 
-impl ZikZakEngine {
-    pub async fn new() -> Result<Self> {
-        info!("🔌 Initializing TigerBeetle connection...");
-        let tigerbeetle = TigerBeetleClient::new().await?;
+        while let Some(account) = accounts.try_next().await? {
+            let Some(name) = self.unqualify(&account.name) else {
+                continue;
+            };
+            if self
+                .belongs_to_deleted_entity(name, &mut deleted_cache)
+                .await?
+            {
+                continue;
+            }
+            let balance = account.zak_balance as i64 - account.zik_balance as i64;
+            ledger.insert(name.to_string(), balance);
+        }
 
-        Ok(Self {
-            tigerbeetle,
-            transfers: Vec::new(),
-        })
+        Ok(serde_json::to_value(ledger)?)
     }
 
-    pub fn is_connected(&self) -> bool {
-        self.tigerbeetle.is_connected()
+    /// [`get_active_ledger_state`](Self::get_active_ledger_state) with each balance
+    /// also formatted in the ledger's currency
+    ///
+    /// `{"product:123:price": {"balance": 1000, "display": "¥1000"}}` on a JPY
+    /// ledger; the integer balance is always in minor units. With a
+    /// [tag store](Self::set_tag_store) set, each line also lists the account's
+    /// [`tags_of`](Self::tags_of) as `"tags"`.
+    pub async fn export_ledger(&self) -> Result<Value> {
+        let ledger: BTreeMap<String, i64> =
+            serde_json::from_value(self.get_active_ledger_state().await?)?;
+        let mut lines = BTreeMap::new();
+        for (name, balance) in ledger {
+            let mut line = json!({ "balance": balance, "display": self.format_amount(balance) });
+            if self.tags.is_some() {
+                line["tags"] = json!(self.tags_of(&name).await?);
+            }
+            lines.insert(name, line);
+        }
+        Ok(serde_json::to_value(lines)?)
     }
 
-    pub async fn get_account_count(&self) -> Result<usize> {
-        self.tigerbeetle.get_account_count().await
-    }
+    /// Accounts whose names match `pattern` (see [`crate::query`]), by name
+    ///
+    /// Fails with [`ZikZakError::InvalidPattern`] before touching TigerBeetle when
+    /// the pattern is malformed or too broad. Like [`iter_accounts`](Self::iter_accounts)
+    /// this only knows the names of accounts this process has touched.
+    pub async fn find_accounts(&self, pattern: &str) -> Result<Vec<AccountMatch>> {
+        let pattern = AccountPattern::parse(pattern)?;
 
-    pub async fn get_transfer_count(&self) -> Result<usize> {
-        Ok(self.transfers.len())
+        let mut accounts = pin!(self.iter_accounts());
+        let mut matches = Vec::new();
+        while let Some(account) = accounts.try_next().await? {
+            let Some(name) = self.unqualify(&account.name) else {
+                continue;
+            };
+            if !pattern.matches(name) {
+                continue;
+            }
+            matches.push(self.account_match(name, &account));
+        }
+
+        matches.sort_by(|a, b| a.account.cmp(&b.account));
+        Ok(matches)
     }
 
-    /// Get account balance using TigerBeetle - returns net balance (ZAK - ZIK)
-    pub async fn get_balance(&self, account_id: &str) -> Result<i64> {
-        debug!("💰 Getting balance for account: {}", account_id);
+    /// [`find_accounts`](Self::find_accounts) one page at a time
+    ///
+    /// Start with `cursor: None` and keep passing [`AccountMatchPage::next_cursor`]
+    /// back until it is `None`. The cursor is opaque to callers; it names the last
+    /// account returned (its id and TigerBeetle creation timestamp) and matches come
+    /// in creation order, so accounts created mid-walk show up on a later page
+    /// instead of shifting the ones already seen. A page is still a scan from the
+    /// cursor until `limit + 1` matches are found, so narrow patterns stay cheap.
+    pub async fn find_accounts_page(
+        &self,
+        pattern: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<AccountMatchPage> {
+        let pattern = AccountPattern::parse(pattern)?;
+        let limit = limit.max(1);
+        let after = match cursor {
+            Some(cursor) => decode_query_cursor(cursor)?.1,
+            None => 0,
+        };
 
-        match self.tigerbeetle.get_account_balance(account_id).await {
-            Ok((zik_balance, zak_balance)) => {
-                let net_balance = zak_balance as i64 - zik_balance as i64;
-                debug!(
-                    "💰 Balance for {}: ZIK={}, ZAK={}, Net={}",
-                    account_id, zik_balance, zak_balance, net_balance
-                );
-                Ok(net_balance)
+        let mut accounts = pin!(self.iter_accounts_after(after));
+        let mut results = Vec::new();
+        let mut last_seen = None;
+        while let Some(account) = accounts.try_next().await? {
+            let Some(name) = self.unqualify(&account.name) else {
+                continue;
+            };
+            if !pattern.matches(name) {
+                continue;
             }
-            Err(e) => {
-                error!("❌ Failed to get balance for {}: {}", account_id, e);
-                Err(e)
+            if results.len() == limit {
+                return Ok(AccountMatchPage {
+                    results,
+                    next_cursor: last_seen,
+                });
             }
+            results.push(self.account_match(name, &account));
+            last_seen = Some(encode_query_cursor(account.id, account.created_at));
         }
+
+        Ok(AccountMatchPage {
+            results,
+            next_cursor: None,
+        })
     }
 
-    /// Execute transfer using TigerBeetle
-    pub async fn transfer(
-        &mut self,
-        from_account: &str,
-        to_account: &str,
-        amount: i64,
-        metadata: HashMap<String, String>,
-    ) -> Result<String> {
-        if amount <= 0 {
-            return Err(anyhow!("Transfer amount must be positive"));
+    /// `account` as a query result, with the metadata of its latest local transfer
+    fn account_match(&self, name: &str, account: &ZikZakAccount) -> AccountMatch {
+        let metadata = self
+            .transfers
+            .iter()
+            .rev()
+            .find(|t| t.from_account == name || t.to_account == name)
+            .map(|t| t.metadata.clone())
+            .unwrap_or_default();
+        AccountMatch {
+            account: name.to_string(),
+            balance: account.zak_balance as i64 - account.zik_balance as i64,
+            metadata,
         }
+    }
 
-        let transfer_id = Uuid::new_v4().to_string();
+    /// Accounts matching `pattern` whose net balance is within `[min, max]`, lowest first
+    ///
+    /// For questions like "products priced between 1000 and 5000" with
+    /// `product:*:price`. This is a scan: every account is paged through
+    /// [`iter_accounts`](Self::iter_accounts) and filtered here, so keep the
+    /// pattern as narrow as the question allows - the cost is the ledger size
+    /// either way, but the result and the sort stay small. Ties are ordered by name.
+    pub async fn find_accounts_in_range(
+        &self,
+        pattern: &str,
+        min: i64,
+        max: i64,
+    ) -> Result<Vec<(String, i64)>> {
+        if min > max {
+            return Err(anyhow!("Empty balance range: {} > {}", min, max));
+        }
+        let pattern = AccountPattern::parse(pattern)?;
 
-        info!(
-            "💸 Creating transfer: {} -> {} (amount: {}, id: {})",
-            from_account, to_account, amount, transfer_id
-        );
+        let mut accounts = pin!(self.iter_accounts());
+        let mut in_range = Vec::new();
+        while let Some(account) = accounts.try_next().await? {
+            let Some(name) = self.unqualify(&account.name) else {
+                continue;
+            };
+            if !pattern.matches(name) {
+                continue;
+            }
+            let balance = account.zak_balance as i64 - account.zik_balance as i64;
+            if (min..=max).contains(&balance) {
+                in_range.push((name.to_string(), balance));
+            }
+        }
 
-        // Execute transfer in TigerBeetle
-        match self
-            .tigerbeetle
-            .create_transfer(from_account, to_account, amount as u128, None)
-            .await
-        {
-            Ok(_) => {
-                // Store transfer record
-                let transfer = Transfer {
-                    id: transfer_id.clone(),
-                    from_account: from_account.to_string(),
-                    to_account: to_account.to_string(),
-                    amount,
-                    metadata,
-                    timestamp: std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs(),
-                };
+        in_range.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(in_range)
+    }
 
-                self.transfers.push(transfer);
+    /// Check that the net balances of accounts matching `accounts_pattern` sum to `expected_total`
+    ///
+    /// Several [patterns](crate::query) may be joined with `,`, e.g.
+    /// `user:*:balance,merchant:*:revenue`; an account matching more than one
+    /// counts once. Returns `Ok(true)` when the invariant holds and fails with
+    /// [`ZikZakError::InvariantViolated`], carrying the actual sum, when it does
+    /// not. Cheap enough to run periodically as a conservation check, with the
+    /// same caveat as [`find_accounts`](Self::find_accounts): only accounts this
+    /// process has touched are seen.
+    pub async fn check_invariant(
+        &self,
+        accounts_pattern: &str,
+        expected_total: i64,
+    ) -> Result<bool> {
+        let patterns = accounts_pattern
+            .split(',')
+            .map(|pattern| AccountPattern::parse(pattern.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
 
-                info!("✅ Transfer completed: {}", transfer_id);
-                Ok(transfer_id)
+        let mut accounts = pin!(self.iter_accounts());
+        let mut actual: i128 = 0;
+        while let Some(account) = accounts.try_next().await? {
+            let Some(name) = self.unqualify(&account.name) else {
+                continue;
+            };
+            if patterns.iter().any(|pattern| pattern.matches(name)) {
+                actual += account.zak_balance as i128 - account.zik_balance as i128;
             }
-            Err(e) => {
-                error!("❌ Transfer failed: {}", e);
-                Err(e)
+        }
+
+        if actual != expected_total as i128 {
+            warn!(
+                "⚖️ Invariant {} broken: {} != {}",
+                accounts_pattern, actual, expected_total
+            );
+            return Err(ZikZakError::InvariantViolated {
+                pattern: accounts_pattern.to_string(),
+                expected: expected_total,
+                actual,
             }
+            .into());
         }
+        Ok(true)
     }
 
-    /// Execute transfer with user_data for Sled reference
-    pub async fn transfer_with_user_data(
-        &mut self,
-        from_account: &str,
-        to_account: &str,
-        amount: i64,
-        user_data_128: u128,
-        metadata: HashMap<String, String>,
-    ) -> Result<String> {
-        if amount <= 0 {
-            return Err(anyhow!("Transfer amount must be positive"));
+    /// Transfers touching `account`, newest first
+    ///
+    /// Once a [metadata index](Self::set_metadata_index) is set, this reads
+    /// TigerBeetle and takes each transfer's metadata from the index, so history
+    /// (up to the account's 8189 most recent transfers) survives restarts.
+    /// Pending transfers and their posts and voids are left out. Without an index
+    /// it reads this engine's local log.
+    ///
+    /// With `exclude_deleted`, transfers involving any account of a deleted entity
+    /// are dropped (same rule and per-call caching as
+    /// [`get_active_ledger_state`](Self::get_active_ledger_state)).
+    pub async fn account_transfers(
+        &self,
+        account: &str,
+        exclude_deleted: bool,
+    ) -> Result<Vec<Transfer>> {
+        let transfers = match &self.metadata_index {
+            Some(index) => self.persisted_account_transfers(account, index).await?,
+            None => self
+                .transfers
+                .iter()
+                .rev()
+                .filter(|t| t.from_account == account || t.to_account == account)
+                .cloned()
+                .collect(),
+        };
+        if !exclude_deleted {
+            return Ok(transfers);
         }
 
-        let transfer_id = Uuid::new_v4().to_string();
+        let mut deleted_cache = HashMap::new();
+        let mut result = Vec::new();
+        for transfer in transfers {
+            if self
+                .belongs_to_deleted_entity(&transfer.from_account, &mut deleted_cache)
+                .await?
+                || self
+                    .belongs_to_deleted_entity(&transfer.to_account, &mut deleted_cache)
+                    .await?
+            {
+                continue;
+            }
+            result.push(transfer);
+        }
 
-        info!(
-            "💸 Creating transfer with user_data: {} -> {} (amount: {}, user_data_128: {}, id: {})",
-            from_account, to_account, amount, user_data_128, transfer_id
-        );
+        Ok(result)
+    }
 
-        // For now, use the existing transfer method until TigerBeetle client is updated
-        // TODO: Update TigerBeetle client to accept user_data_128 parameter
-        match self
+    /// `account`'s transfers as TigerBeetle holds them, with metadata from `index`
+    async fn persisted_account_transfers(
+        &self,
+        account: &str,
+        index: &MetadataIndex,
+    ) -> Result<Vec<Transfer>> {
+        let transfers = self
             .tigerbeetle
-            .create_transfer(from_account, to_account, amount as u128, None)
-            .await
-        {
-            Ok(_) => {
-                // Store transfer record with user_data info in metadata
-                let mut enhanced_metadata = metadata;
-                enhanced_metadata.insert("user_data_128".to_string(), user_data_128.to_string());
-                enhanced_metadata.insert("sled_reference".to_string(), "true".to_string());
-
-                let transfer = Transfer {
-                    id: transfer_id.clone(),
-                    from_account: from_account.to_string(),
-                    to_account: to_account.to_string(),
-                    amount,
-                    metadata: enhanced_metadata,
-                    timestamp: std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs(),
-                };
+            .get_account_transfers(&self.qualify(account), TRANSFER_QUERY_LIMIT)
+            .await?;
 
-                self.transfers.push(transfer);
+        transfers
+            .iter()
+            .filter(|t| t.flags & (FLAG_PENDING | FLAG_POST_PENDING | FLAG_VOID_PENDING) == 0)
+            .map(|t| {
+                let id = Uuid::from_u128(t.id).to_string();
+                Ok(Transfer {
+                    metadata: index.metadata(&id)?.unwrap_or_default(),
+                    id,
+                    from_account: self.account_name(t.zik_account_id),
+                    to_account: self.account_name(t.zak_account_id),
+                    amount: t.amount as i64,
+                    timestamp: t.timestamp / 1_000_000_000,
+                })
+            })
+            .collect()
+    }
 
-                info!("✅ Transfer with user_data completed: {}", transfer_id);
-                Ok(transfer_id)
-            }
-            Err(e) => {
-                error!("❌ Transfer with user_data failed: {}", e);
-                Err(e)
-            }
+    /// Engine name of the account with TigerBeetle id `id`, `account:{id}` if unknown
+    fn account_name(&self, id: u128) -> String {
+        match self.tigerbeetle.resolve_name(id) {
+            Some(name) => self.unqualify(name).unwrap_or(name).to_string(),
+            None => format!("account:{}", id),
         }
     }
 
-    /// Get current ledger state (all account balances)
-    pub async fn get_ledger_state(&self) -> Result<Value> {
-        debug!("📊 Getting ledger state...");
-
-        let accounts = self.tigerbeetle.get_all_accounts().await?;
-        let mut ledger = HashMap::new();
-
-        for account in accounts {
-            let balance = account.zak_balance as i64 - account.zik_balance as i64;
-            ledger.insert(account.id.to_string(), balance);
+    /// Whether `account`'s owning entity exists but has been deleted
+    async fn belongs_to_deleted_entity(
+        &self,
+        account: &str,
+        cache: &mut HashMap<String, bool>,
+    ) -> Result<bool> {
+        let Some(entity) = owning_entity(account) else {
+            return Ok(false);
+        };
+        if let Some(&deleted) = cache.get(entity) {
+            return Ok(deleted);
         }
 
-        Ok(serde_json::to_value(ledger)?)
+        let deleted = match self.get_balance(format!("{}:existence", entity)).await {
+            Ok(balance) => balance <= 0,
+            Err(e) if is_account_not_found(&e) => false,
+            Err(e) => return Err(e),
+        };
+        cache.insert(entity.to_string(), deleted);
+        Ok(deleted)
+    }
+
+    /// Every transfer made through this engine, oldest first
+    pub(crate) fn transfer_log(&self) -> &[Transfer] {
+        &self.transfers
     }
 
     /// Get transaction history
@@ -255,6 +2716,171 @@ impl ZikZakEngine {
         Ok(serde_json::to_value(&self.transfers)?)
     }
 
+    /// One page of the transfer log, newest first
+    ///
+    /// Filters by account and time range, then skips `offset` and returns at most
+    /// `limit` (default 100, never more than 1000) transfers. Pair with
+    /// [`count_transfers`](Self::count_transfers) for the total.
+    pub async fn query_transfers(&self, filter: TransferFilter) -> Result<Vec<Transfer>> {
+        Ok(page(&self.transfers, &filter))
+    }
+
+    /// How many transfers match `filter`, ignoring `limit` and `offset`
+    pub fn count_transfers(&self, filter: &TransferFilter) -> usize {
+        self.transfers.iter().filter(|t| filter.matches(t)).count()
+    }
+
+    /// Statement of `account` between `since` and `until` (unix seconds, inclusive)
+    ///
+    /// Built from the local transfer log, so like [`reconcile`](Self::reconcile) it
+    /// only knows the transfers this engine made.
+    pub async fn generate_statement(
+        &self,
+        account: &str,
+        since: u64,
+        until: u64,
+    ) -> Result<Statement> {
+        if since > until {
+            return Err(anyhow!(
+                "Statement period starts after it ends: {} > {}",
+                since,
+                until
+            ));
+        }
+        Ok(statement(&self.transfers, account, since, until))
+    }
+
+    /// Up to `limit` transfers of `account` older than `cursor`, newest first
+    ///
+    /// Unlike [`generate_statement`](Self::generate_statement) this reads TigerBeetle,
+    /// so it sees every transfer, whichever process made it. Start with `cursor =
+    /// None` and keep passing [`StatementPage::next`] back until it is `None`; an
+    /// empty page always ends the walk. Pages hold at most 8189 entries.
+    pub async fn account_statement(
+        &self,
+        account: &str,
+        cursor: Option<u64>,
+        limit: u32,
+    ) -> Result<StatementPage> {
+        let account_id = self.tigerbeetle.hash_account_name(&self.qualify(account));
+        let page = self
+            .tigerbeetle
+            .get_account_transfers_paged(
+                &self.qualify(account),
+                cursor.unwrap_or(0),
+                limit.clamp(1, TRANSFER_QUERY_LIMIT),
+            )
+            .await?;
+
+        let entries = page
+            .transfers
+            .iter()
+            .map(|t| {
+                let (counterparty, amount) = if t.zak_account_id == account_id {
+                    (t.zik_account_id, t.amount as i64)
+                } else {
+                    (t.zak_account_id, -(t.amount as i64))
+                };
+                StatementEntry {
+                    id: t.id,
+                    timestamp: t.timestamp / 1_000_000_000,
+                    counterparty: self.account_name(counterparty),
+                    amount,
+                }
+            })
+            .collect();
+
+        Ok(StatementPage {
+            account: account.to_string(),
+            entries,
+            next: page.next,
+        })
+    }
+
+    /// Replay the local transfer log for `account` and compare with TigerBeetle
+    ///
+    /// A safety net for dual-write bugs: every transfer this engine committed is
+    /// both in TigerBeetle and in its log, so the two balances must agree. Only
+    /// meaningful for accounts this engine alone writes to - transfers made by other
+    /// processes (or before a restart) show up as a difference too.
+    pub async fn reconcile(&self, account: &str) -> Result<ReconResult> {
+        let local_balance = self
+            .transfers
+            .iter()
+            .map(|t| signed_amount(t, account))
+            .sum();
+
+        let tigerbeetle_balance = match self.get_balance_with(account, Consistency::Strong).await {
+            Ok(balance) => balance,
+            Err(e) if is_account_not_found(&e) => 0,
+            Err(e) => return Err(e),
+        };
+
+        let result = ReconResult {
+            account: account.to_string(),
+            local_balance,
+            tigerbeetle_balance,
+        };
+        if !result.is_consistent() {
+            warn!(
+                "⚖️ {} diverged: local log says {}, TigerBeetle says {}",
+                account, local_balance, tigerbeetle_balance
+            );
+        }
+        Ok(result)
+    }
+
+    /// Stream the transfer log one transfer at a time, oldest first
+    ///
+    /// Nothing is copied up front, so this suits exports of the whole log
+    /// (`/transfers/export` writes each item as one NDJSON line).
+    pub fn stream_transfers(&self) -> impl Stream<Item = Result<Transfer>> + '_ {
+        stream::iter(self.transfers.iter().cloned().map(Ok))
+    }
+
+    /// The whole transfer log, oldest first, in the compact binary format
+    ///
+    /// Holds the same records as [`get_transaction_history`](Self::get_transaction_history)
+    /// in a third or less of the JSON size; read it back with
+    /// [`import_transfers_binary`](Self::import_transfers_binary).
+    pub fn export_transfers_binary(&self) -> Result<Vec<u8>> {
+        binary::encode(&self.transfers)
+    }
+
+    /// Transfers from an [`export_transfers_binary`](Self::export_transfers_binary) payload
+    ///
+    /// Only parses; nothing is replayed into TigerBeetle or this engine's log.
+    pub fn import_transfers_binary(bytes: &[u8]) -> Result<Vec<Transfer>> {
+        binary::decode(bytes)
+    }
+
+    /// Write the transfer log as a tamper-evident hash chain, oldest first
+    ///
+    /// One JSON line per transfer carrying the SHA-256 of the record before it and
+    /// of itself; check an export with [`verify_audit`](Self::verify_audit). The
+    /// last record's hash is [`audit_head_hash`](Self::audit_head_hash) at the time
+    /// of the export.
+    pub fn export_audit<W: std::io::Write>(&self, writer: W) -> Result<()> {
+        audit::write(writer, &self.transfers)
+    }
+
+    /// Whether an [`export_audit`](Self::export_audit) chain is intact
+    ///
+    /// `Ok(false)` when any record was edited, dropped, reordered or garbled; read
+    /// errors fail. Records cut off the end leave a valid, shorter chain, so compare
+    /// the last hash against a checkpointed [`audit_head_hash`](Self::audit_head_hash).
+    pub fn verify_audit<R: std::io::BufRead>(reader: R) -> Result<bool> {
+        audit::verify(reader)
+    }
+
+    /// Hex hash of the newest audit chain record, all zeros for an empty log
+    ///
+    /// Kept up to date as transfers are recorded, so a monitor can checkpoint it
+    /// cheaply and later confirm an export ends on that hash or extends past it.
+    pub fn audit_head_hash(&self) -> String {
+        hex::encode(self.audit_head)
+    }
+
     /// Hash function for encoding string values as integers
     pub fn hash_string(input: &str) -> i64 {
         use sha2::{Digest, Sha256};
@@ -265,52 +2891,106 @@ impl ZikZakEngine {
 
         // Take first 8 bytes and convert to i64
         let bytes: [u8; 8] = result[0..8].try_into().unwrap();
-        i64::from_be_bytes(bytes).abs() // Use absolute value to ensure positive
+        // Mask the sign bit: `.abs()` maps x and -x together and overflows on i64::MIN
+        i64::from_be_bytes(bytes) & i64::MAX
     }
 
     /// Get current timestamp
     pub fn timestamp() -> i64 {
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64
+        SystemClock.now_millis() as i64
     }
 
     /// Special handling for system:genesis account (unlimited funds)
+    ///
+    /// A missing genesis is created with the opening balances of the
+    /// [configured set](Self::with_system_accounts). Also the depletion guard: once
+    /// [`genesis_remaining`](Self::genesis_remaining) has dropped below the
+    /// [threshold](Self::with_genesis_threshold) it is topped back up to
+    /// [`GENESIS_BALANCE`] from `system:treasury`, with a warning. The treasury may
+    /// go negative, so its balance is how much genesis has been refilled in total.
     pub async fn ensure_genesis_account(&mut self) -> Result<()> {
         let genesis_account = "system:genesis";
 
         // Check if genesis account exists
-        match self.get_balance(genesis_account).await {
-            Ok(_) => {
+        let remaining = match self.genesis_remaining().await {
+            Ok(remaining) => {
                 debug!("🌱 Genesis account already exists");
-                Ok(())
+                remaining
             }
-            Err(_) => {
+            Err(e) if is_account_not_found(&e) => {
                 info!("🌱 Creating genesis account with unlimited funds");
-                // Create genesis account with large initial balance
+                let (initial_zik, initial_zak) = self
+                    .system_accounts
+                    .get(genesis_account)
+                    .map_or((0, 0), |genesis| (genesis.initial_zik, genesis.initial_zak));
                 self.tigerbeetle
-                    .create_account(genesis_account, 0, (i64::MAX / 2) as u128)
+                    .create_account(genesis_account, initial_zik, initial_zak)
                     .await?;
-                Ok(())
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        if remaining >= self.genesis_threshold {
+            return Ok(());
+        }
+
+        let refill = GENESIS_BALANCE
+            .max(self.genesis_threshold)
+            .checked_sub(remaining)
+            .ok_or_else(|| anyhow!("Genesis refill overflows: {} remaining", remaining))?;
+        warn!(
+            "🌱 Genesis down to {} (threshold {}), refilling {} from system:treasury",
+            remaining, self.genesis_threshold, refill
+        );
+        // A set without a treasury gets one; its spec lets it go negative
+        if let Err(e) = self.get_balance(TREASURY).await {
+            if !is_account_not_found(&e) {
+                return Err(e);
             }
+            let spec = self.tigerbeetle.default_account_spec(TREASURY);
+            self.tigerbeetle
+                .create_account_with_flags(TREASURY, spec)
+                .await?;
         }
+        let metadata = HashMap::from([("reason".to_string(), "genesis_refill".to_string())]);
+        self.commit_transfer(TREASURY, genesis_account, refill, metadata)
+            .await?;
+        Ok(())
+    }
+
+    /// What `system:genesis` can still hand out
+    ///
+    /// Genesis is a ZIK account, so its net balance only falls as it hands value
+    /// out and TigerBeetle never refuses it. What runs out is the `i64` range that
+    /// balances are read in: this is how far the balance is from its bottom.
+    pub async fn genesis_remaining(&self) -> Result<i64> {
+        let balance = self.get_balance("system:genesis").await?;
+        Ok(balance.saturating_add(i64::MAX))
     }
 
     /// Ensure system accounts exist
+    ///
+    /// Genesis is created (or refilled) by [`ensure_genesis_account`](Self::ensure_genesis_account);
+    /// the rest of the [configured set](Self::with_system_accounts) is created with
+    /// its opening balances if missing.
     pub async fn ensure_system_accounts(&mut self) -> Result<()> {
         self.ensure_genesis_account().await?;
 
-        let system_accounts = vec!["system:deleted", "system:operations"];
-
-        for account in system_accounts {
-            match self.get_balance(account).await {
+        let system_accounts = self.system_accounts.clone();
+        for account in system_accounts.iter() {
+            if account.name == "system:genesis" {
+                continue;
+            }
+            match self.get_balance(account.name.as_str()).await {
                 Ok(_) => {
-                    debug!("🔧 System account {} already exists", account);
+                    debug!("🔧 System account {} already exists", account.name);
                 }
                 Err(_) => {
-                    info!("🔧 Creating system account: {}", account);
-                    self.tigerbeetle.create_account(account, 0, 0).await?;
+                    info!("🔧 Creating system account: {}", account.name);
+                    self.tigerbeetle
+                        .create_account(&account.name, account.initial_zik, account.initial_zak)
+                        .await?;
                 }
             }
         }
@@ -318,3 +2998,333 @@ impl ZikZakEngine {
         Ok(())
     }
 }
+
+/// Whether `account` can hold a currency, see [`NON_MONETARY_SEGMENTS`]
+fn is_monetary(account: &str) -> bool {
+    let last = account.rsplit(':').next().unwrap_or(account);
+    !NON_MONETARY_SEGMENTS.contains(&last)
+}
+
+/// Flag account holding whether `account` is frozen
+fn frozen_flag(account: &str) -> String {
+    format!("{}:frozen", account)
+}
+
+/// `{from}:rel:{relation}:{to}`, the account recording a [`ZikZakEngine::link`]
+fn link_account(from_entity: &str, relation: &str, to_entity: &str) -> String {
+    format!("{}:rel:{}:{}", from_entity, relation, to_entity)
+}
+
+/// Whether `account` is one of the shared `system:*` accounts
+fn is_system_account(account: &str) -> bool {
+    account.starts_with("system:")
+}
+
+/// Entity owning a field account: `product:123:price` -> `product:123`
+fn owning_entity(account: &str) -> Option<&str> {
+    if is_system_account(account) {
+        return None;
+    }
+    account.rsplit_once(':').map(|(entity, _)| entity)
+}
+
+/// Opaque [`AccountMatchPage`] cursor: account id then creation timestamp, in hex
+fn encode_query_cursor(id: u128, created_at: u64) -> String {
+    format!("{:032x}{:016x}", id, created_at)
+}
+
+/// `(id, created_at)` back out of [`encode_query_cursor`]
+fn decode_query_cursor(cursor: &str) -> Result<(u128, u64)> {
+    let invalid = || anyhow!("Invalid query cursor: {}", cursor);
+    if cursor.len() != 48 || !cursor.is_ascii() {
+        return Err(invalid());
+    }
+    let (id, created_at) = cursor.split_at(32);
+    let id = u128::from_str_radix(id, 16).map_err(|_| invalid())?;
+    let created_at = u64::from_str_radix(created_at, 16).map_err(|_| invalid())?;
+    Ok((id, created_at))
+}
+
+/// The `S` state `balance` stands for; 0 is no state, anything else unknown is an error
+fn state_of<S: StateEnum>(account: &str, balance: i64) -> Result<Option<S>> {
+    if balance == 0 {
+        return Ok(None);
+    }
+    S::from_value(balance).map(Some).ok_or_else(|| {
+        anyhow!(
+            "{} holds {}, which is not a {} state",
+            account,
+            balance,
+            S::NAME
+        )
+    })
+}
+
+/// Pending transfers in `transfers` that were neither resolved nor timed out by `now`
+fn outstanding_pending(transfers: Vec<ZikZakTransfer>, now: u64) -> Vec<ZikZakTransfer> {
+    unresolved_pending(transfers)
+        .filter(|t| !has_expired(t, now))
+        .collect()
+}
+
+/// Pending transfers among `transfers` that timed out before being posted or voided
+fn expired_pending(transfers: Vec<ZikZakTransfer>, now: u64) -> Vec<ZikZakTransfer> {
+    unresolved_pending(transfers)
+        .filter(|t| has_expired(t, now))
+        .collect()
+}
+
+/// Pending transfers among `transfers` that no other transfer in it posts or voids
+fn unresolved_pending(transfers: Vec<ZikZakTransfer>) -> impl Iterator<Item = ZikZakTransfer> {
+    let resolved: HashSet<u128> = transfers
+        .iter()
+        .filter(|t| t.flags & (FLAG_POST_PENDING | FLAG_VOID_PENDING) != 0)
+        .map(|t| t.pending_id)
+        .collect();
+
+    transfers
+        .into_iter()
+        .filter(move |t| t.flags & FLAG_PENDING != 0 && !resolved.contains(&t.id))
+}
+
+/// Whether the timeout of pending `transfer` has elapsed at `now` (nanoseconds)
+fn has_expired(transfer: &ZikZakTransfer, now: u64) -> bool {
+    transfer.timeout != 0 && transfer.timestamp + transfer.timeout as u64 * 1_000_000_000 <= now
+}
+
+/// `transfer.amount` as seen from `account`: credits positive, debits negative
+fn signed_amount(transfer: &Transfer, account: &str) -> i64 {
+    let credited = if transfer.to_account == account {
+        transfer.amount
+    } else {
+        0
+    };
+    let debited = if transfer.from_account == account {
+        transfer.amount
+    } else {
+        0
+    };
+    credited - debited
+}
+
+fn statement(transfers: &[Transfer], account: &str, since: u64, until: u64) -> Statement {
+    let touches = |t: &&Transfer| t.from_account == account || t.to_account == account;
+
+    let opening_balance = transfers
+        .iter()
+        .filter(touches)
+        .filter(|t| t.timestamp < since)
+        .map(|t| signed_amount(t, account))
+        .sum();
+
+    let mut balance = opening_balance;
+    let mut in_period: Vec<_> = transfers
+        .iter()
+        .filter(touches)
+        .filter(|t| (since..=until).contains(&t.timestamp))
+        .collect();
+    // The log is in commit order already; the stable sort only guards clock steps
+    in_period.sort_by_key(|t| t.timestamp);
+
+    let lines = in_period
+        .into_iter()
+        .map(|t| {
+            let amount = signed_amount(t, account);
+            balance += amount;
+            let counterparty = if t.to_account == account {
+                &t.from_account
+            } else {
+                &t.to_account
+            };
+            StatementLine {
+                transfer_id: t.id.clone(),
+                timestamp: t.timestamp,
+                counterparty: counterparty.clone(),
+                amount,
+                balance,
+                metadata: t.metadata.clone(),
+            }
+        })
+        .collect();
+
+    Statement {
+        account: account.to_string(),
+        since,
+        until,
+        opening_balance,
+        lines,
+        closing_balance: balance,
+    }
+}
+
+/// Newest-first page of `transfers` matching `filter`
+pub(crate) fn page(transfers: &[Transfer], filter: &TransferFilter) -> Vec<Transfer> {
+    transfers
+        .iter()
+        .rev()
+        .filter(|t| filter.matches(t))
+        .skip(filter.offset)
+        .take(filter.page_size())
+        .cloned()
+        .collect()
+}
+
+pub(crate) fn is_account_not_found(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ZikZakError>(),
+        Some(ZikZakError::AccountNotFound { .. })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tb_transfer(id: u128, flags: u16, pending_id: u128, timeout: u32) -> ZikZakTransfer {
+        ZikZakTransfer {
+            id,
+            zik_account_id: 1,
+            zak_account_id: 2,
+            amount: 10,
+            pending_id,
+            ledger: 1,
+            code: 7,
+            user_data_128: 0,
+            user_data_64: 0,
+            user_data_32: 0,
+            timeout,
+            flags,
+            timestamp: 1_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_outstanding_pending() {
+        let transfers = vec![
+            tb_transfer(1, FLAG_PENDING, 0, 0),      // open, no timeout
+            tb_transfer(2, FLAG_PENDING, 0, 60),     // open, times out at t=61s
+            tb_transfer(3, FLAG_PENDING, 0, 0),      // posted by 4
+            tb_transfer(4, FLAG_POST_PENDING, 3, 0), // posts 3
+            tb_transfer(5, FLAG_PENDING, 0, 0),      // voided by 6
+            tb_transfer(6, FLAG_VOID_PENDING, 5, 0), // voids 5
+            tb_transfer(7, 0, 0, 0),                 // plain transfer
+        ];
+
+        let ids = |now| -> Vec<u128> {
+            outstanding_pending(transfers.clone(), now)
+                .iter()
+                .map(|t| t.id)
+                .collect()
+        };
+        assert_eq!(ids(30_000_000_000), vec![1, 2]);
+        assert_eq!(ids(61_000_000_000), vec![1]);
+
+        let expired = |now| -> Vec<u128> {
+            expired_pending(transfers.clone(), now)
+                .iter()
+                .map(|t| t.id)
+                .collect()
+        };
+        assert!(expired(30_000_000_000).is_empty());
+        assert_eq!(expired(61_000_000_000), vec![2]);
+    }
+
+    #[test]
+    fn test_owning_entity() {
+        assert_eq!(owning_entity("product:123:price"), Some("product:123"));
+        assert_eq!(owning_entity("product:123:existence"), Some("product:123"));
+        assert_eq!(owning_entity("system:genesis"), None);
+        assert_eq!(owning_entity("orphan"), None);
+    }
+
+    #[test]
+    fn test_query_cursor_round_trip() {
+        let cursor = encode_query_cursor(u128::MAX - 7, 1_700_000_000_000_000_000);
+        assert_eq!(
+            decode_query_cursor(&cursor).unwrap(),
+            (u128::MAX - 7, 1_700_000_000_000_000_000)
+        );
+        assert!(decode_query_cursor("").is_err());
+        assert!(decode_query_cursor(&cursor[1..]).is_err());
+        assert!(decode_query_cursor(&format!("{}g", &cursor[1..])).is_err());
+    }
+
+    #[test]
+    fn test_statement_running_balance() {
+        let transfer = |id: &str, from: &str, to: &str, amount: i64, timestamp: u64| Transfer {
+            id: id.to_string(),
+            from_account: from.to_string(),
+            to_account: to.to_string(),
+            amount,
+            metadata: HashMap::new(),
+            timestamp,
+        };
+        let log = vec![
+            transfer("t1", "system:genesis", "user:1:balance", 100, 10),
+            transfer("t2", "user:1:balance", "shop:1:revenue", 30, 20),
+            transfer("t3", "system:genesis", "user:2:balance", 999, 25),
+            transfer("t4", "system:genesis", "user:1:balance", 50, 30),
+            transfer("t5", "user:1:balance", "shop:1:revenue", 70, 40),
+            transfer("t6", "system:genesis", "user:1:balance", 5, 50),
+        ];
+
+        let statement = statement(&log, "user:1:balance", 20, 40);
+        assert_eq!(statement.opening_balance, 100);
+
+        let lines: Vec<_> = statement
+            .lines
+            .iter()
+            .map(|l| {
+                (
+                    l.transfer_id.as_str(),
+                    l.counterparty.as_str(),
+                    l.amount,
+                    l.balance,
+                )
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("t2", "shop:1:revenue", -30, 70),
+                ("t4", "system:genesis", 50, 120),
+                ("t5", "shop:1:revenue", -70, 50),
+            ]
+        );
+        assert_eq!(statement.closing_balance, 50);
+    }
+
+    #[test]
+    fn test_page_filters_newest_first() {
+        let log: Vec<Transfer> = (0..1500u64)
+            .map(|i| Transfer {
+                id: i.to_string(),
+                from_account: "system:genesis".to_string(),
+                to_account: format!("user:{}:balance", i % 2),
+                amount: 1,
+                metadata: HashMap::new(),
+                timestamp: i,
+            })
+            .collect();
+        let ids = |filter: TransferFilter| -> Vec<String> {
+            page(&log, &filter).into_iter().map(|t| t.id).collect()
+        };
+
+        assert_eq!(page(&log, &TransferFilter::default()).len(), 100);
+        let huge = TransferFilter {
+            limit: Some(5000),
+            ..Default::default()
+        };
+        assert_eq!(page(&log, &huge).len(), 1000);
+
+        let filter = TransferFilter {
+            limit: Some(3),
+            offset: 1,
+            account: Some("user:1:balance".to_string()),
+            since: Some(10),
+            until: Some(20),
+            ..Default::default()
+        };
+        assert_eq!(ids(filter), vec!["17", "15", "13"]);
+    }
+}
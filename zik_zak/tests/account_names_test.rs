@@ -0,0 +1,35 @@
+//! Persisted account names against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use uuid::Uuid;
+use zik_zak::{AccountNameStore, TigerBeetleClient};
+
+#[tokio::test]
+async fn test_names_resolve_after_restart() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let path = temp_dir.path().join("names.db");
+    let name = format!("user:{}:balance", Uuid::new_v4().simple());
+
+    let id = {
+        let mut client = TigerBeetleClient::new().await?;
+        client.set_name_store(AccountNameStore::new(&path)?)?;
+        client.create_account(&name, 0, 0).await?;
+        client.resolve_id(&name).expect("created account is cached")
+    };
+
+    let mut client = TigerBeetleClient::new().await?;
+    assert_eq!(client.resolve_name(id), None);
+
+    client.set_name_store(AccountNameStore::new(&path)?)?;
+    assert_eq!(client.resolve_name(id), Some(name.as_str()));
+    assert_eq!(client.resolve_id(&name), Some(id));
+
+    // Listings name the account without it being touched in this process
+    let accounts = client.query_accounts(0, 0, 100).await?;
+    let listed = accounts.into_iter().find(|a| a.id == id);
+    assert_eq!(listed.map(|a| a.name), Some(name));
+
+    Ok(())
+}
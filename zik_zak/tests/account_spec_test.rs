@@ -0,0 +1,34 @@
+//! Explicit account creation against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{AccountDirection, AccountSpec, ZikZakEngine};
+
+#[tokio::test]
+async fn test_explicit_spec_overrides_name_heuristic() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    // `:inventory` would be guessed as a ZIK account and refuse credits
+    let inventory = format!("user:{}:inventory", Uuid::new_v4().simple());
+    engine
+        .create_account(
+            &inventory,
+            AccountSpec {
+                direction: AccountDirection::Zak,
+                history: true,
+                ..AccountSpec::default()
+            },
+        )
+        .await?;
+
+    engine
+        .transfer("system:genesis", &inventory, 12, HashMap::new())
+        .await?;
+    assert_eq!(engine.get_balance(&inventory).await?, 12);
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! Signed adjustment tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{ZikZakEngine, ZikZakError};
+
+#[tokio::test]
+async fn test_adjust_positive_and_negative_deltas() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    let stock = format!("product:{}:stock", Uuid::new_v4().simple());
+
+    engine.adjust(&stock, 10).await?;
+    assert_eq!(engine.get_balance(&stock).await?, 10);
+
+    engine.adjust(&stock, -3).await?;
+    assert_eq!(engine.get_balance(&stock).await?, 7);
+
+    let history = engine.account_transfers(&stock, false).await?;
+    assert_eq!(history[0].amount, 3);
+    assert_eq!(history[0].metadata["delta"], "-3");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_adjust_refuses_to_go_negative() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    let stock = format!("product:{}:stock", Uuid::new_v4().simple());
+    engine.adjust(&stock, 2).await?;
+
+    let err = engine.adjust(&stock, -5).await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ZikZakError>(),
+        Some(&ZikZakError::InsufficientBalance {
+            account: stock.clone(),
+            balance: 2,
+            required: 5,
+        })
+    );
+    assert_eq!(engine.get_balance(&stock).await?, 2);
+
+    assert!(engine.adjust(&stock, 0).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_adjust_against_counterparty_keeps_signed_intent() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    let id = Uuid::new_v4().simple().to_string();
+    let (wallet, shop) = (
+        format!("user:{}:wallet", id),
+        format!("shop:{}:revenue", id),
+    );
+    engine.adjust(&wallet, 100).await?;
+
+    let metadata = HashMap::from([("reason".to_string(), "purchase".to_string())]);
+    engine.adjust_against(&wallet, -40, &shop, metadata).await?;
+    assert_eq!(engine.get_balance(&wallet).await?, 60);
+    assert_eq!(engine.get_balance(&shop).await?, 40);
+
+    let debit = &engine.account_transfers(&wallet, false).await?[0];
+    assert_eq!(
+        (debit.from_account.as_str(), debit.to_account.as_str()),
+        (wallet.as_str(), shop.as_str())
+    );
+    assert_eq!(debit.amount, 40);
+    assert_eq!(debit.metadata["delta"], "-40");
+    assert_eq!(debit.metadata["reason"], "purchase");
+
+    // A positive delta flows the other way
+    engine
+        .adjust_against(&wallet, 15, &shop, HashMap::new())
+        .await?;
+    assert_eq!(engine.get_balance(&wallet).await?, 75);
+    assert_eq!(engine.get_balance(&shop).await?, 25);
+
+    Ok(())
+}
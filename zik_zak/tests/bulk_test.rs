@@ -0,0 +1,101 @@
+//! Bulk mint/burn tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{BatchTransfer, ZikZakEngine};
+
+#[tokio::test]
+async fn test_mint_then_burn_a_cohort() -> Result<()> {
+    let namespace = format!("bulk_{}", Uuid::new_v4().simple());
+    let mut engine = ZikZakEngine::new(&namespace).await?;
+    engine.ensure_system_accounts().await?;
+
+    let cohort: Vec<String> = (1..=3).map(|i| format!("user:{}:balance", i)).collect();
+    let metadata = HashMap::from([("promo".to_string(), "spring".to_string())]);
+    let minted = engine.mint_to(&cohort, 500, metadata).await?;
+    assert_eq!(minted.len(), 3);
+    for (result, account) in minted.iter().zip(&cohort) {
+        assert_eq!(&result.account, account);
+        assert!(result.result.is_ok());
+        assert_eq!(engine.get_balance(account.as_str()).await?, 500);
+    }
+
+    // user:4 was never minted to, so only it is refused
+    let mut burners = cohort.clone();
+    burners.insert(1, "user:4:balance".to_string());
+    let void_before = engine.get_balance("system:void").await?;
+    let burned = engine.burn_from(&burners, 200, None).await?;
+
+    let outcomes: Vec<(&str, bool)> = burned
+        .iter()
+        .map(|r| (r.account.as_str(), r.result.is_ok()))
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            ("user:1:balance", true),
+            ("user:4:balance", false),
+            ("user:2:balance", true),
+            ("user:3:balance", true),
+        ]
+    );
+    assert!(burned[1]
+        .result
+        .as_ref()
+        .unwrap_err()
+        .contains("Insufficient"));
+    assert_eq!(engine.get_balance("user:2:balance").await?, 300);
+    assert_eq!(engine.get_balance("system:void").await?, void_before + 600);
+
+    // A custom sink, and amounts must be positive
+    engine
+        .burn_from(&cohort[..1], 300, Some("system:deleted"))
+        .await?;
+    assert_eq!(engine.get_balance("user:1:balance").await?, 0);
+    assert!(engine.mint_to(&cohort, 0, HashMap::new()).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transfer_each_commits_transfers_independently() -> Result<()> {
+    let namespace = format!("bulk_{}", Uuid::new_v4().simple());
+    let mut engine = ZikZakEngine::new(&namespace).await?;
+    engine.ensure_system_accounts().await?;
+
+    let leg = |from: &str, to: &str, amount: i64| BatchTransfer {
+        from_account: from.to_string(),
+        to_account: to.to_string(),
+        amount,
+        metadata: HashMap::new(),
+    };
+    let results = engine
+        .transfer_each(vec![
+            leg("system:genesis", "user:1:balance", 100),
+            // Nothing to spend, refused by TigerBeetle
+            leg("user:2:balance", "user:1:balance", 50),
+            // Refused before it is sent
+            leg("system:genesis", "user:3:balance", 0),
+            leg("user:1:balance", "user:3:balance", 40),
+        ])
+        .await?;
+
+    let committed: Vec<bool> = results.iter().map(Result::is_ok).collect();
+    assert_eq!(committed, vec![true, false, false, true]);
+    assert!(results[2].as_ref().unwrap_err().contains("positive"));
+    assert_eq!(engine.get_balance("user:1:balance").await?, 60);
+    assert_eq!(engine.get_balance("user:3:balance").await?, 40);
+
+    let logged: Vec<&str> = engine
+        .transfers_since(0)
+        .iter()
+        .map(|t| t.id.as_str())
+        .collect();
+    assert!(logged.contains(&results[0].as_ref().unwrap().as_str()));
+    assert!(logged.contains(&results[3].as_ref().unwrap().as_str()));
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+//! Balance checkpoint tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{CheckpointStore, ZikZakEngine, ZikZakError};
+
+#[tokio::test]
+async fn test_checkpoint_is_unchanged_by_later_transfers() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.set_checkpoint_store(CheckpointStore::new(
+        temp_dir.path().join("checkpoints.db"),
+    )?);
+    engine.ensure_system_accounts().await?;
+
+    // Fresh accounts per run - TigerBeetle keeps state between runs
+    let run = Uuid::new_v4().simple().to_string();
+    let wallet = format!("user:{}:balance", run);
+    let shop = format!("shop:{}:revenue", run);
+    engine
+        .transfer("system:genesis", &wallet, 1000, HashMap::new())
+        .await?;
+    engine.transfer(&wallet, &shop, 300, HashMap::new()).await?;
+
+    let month_end = format!("month-end-{}", run);
+    engine.checkpoint(&month_end).await?;
+
+    engine.transfer(&wallet, &shop, 200, HashMap::new()).await?;
+    let later = format!("user:{}:savings", run);
+    engine
+        .transfer("system:genesis", &later, 50, HashMap::new())
+        .await?;
+
+    assert_eq!(engine.get_balance(&wallet).await?, 500);
+    assert_eq!(
+        engine.balance_at_checkpoint(&month_end, &wallet).await?,
+        700
+    );
+    assert_eq!(engine.balance_at_checkpoint(&month_end, &shop).await?, 300);
+
+    // Created after the checkpoint
+    let missing = engine
+        .balance_at_checkpoint(&month_end, &later)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        missing.downcast_ref::<ZikZakError>(),
+        Some(ZikZakError::AccountNotFound { .. })
+    ));
+
+    // Checkpoints are immutable
+    assert!(engine.checkpoint(&month_end).await.is_err());
+    assert_eq!(
+        engine.balance_at_checkpoint(&month_end, &wallet).await?,
+        700
+    );
+    assert!(engine
+        .balance_at_checkpoint(&format!("never-{}", run), &wallet)
+        .await
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+//! Concurrency tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+use zik_zak::{ZikZakEngine, ZikZakError};
+
+#[tokio::test]
+async fn test_check_and_transfer_never_overdraws() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    // Fresh accounts per run - TigerBeetle keeps state between runs
+    let run = Uuid::new_v4().simple().to_string();
+    let wallet = format!("wallet:{}:balance", run);
+    let shop = format!("shop:{}:revenue", run);
+
+    // Enough for exactly 5 purchases of 100
+    engine
+        .transfer("system:genesis", &wallet, 500, HashMap::new())
+        .await?;
+
+    let engine = Arc::new(RwLock::new(engine));
+    let mut handles = Vec::new();
+
+    for _ in 0..50 {
+        let engine = Arc::clone(&engine);
+        let wallet = wallet.clone();
+        let shop = shop.clone();
+
+        handles.push(tokio::spawn(async move {
+            let mut engine = engine.write().await;
+            engine.check_and_transfer(&wallet, &shop, 100, 100).await
+        }));
+    }
+
+    let mut succeeded = 0;
+    let mut rejected = 0;
+    for handle in handles {
+        match handle.await? {
+            Ok(_) => succeeded += 1,
+            Err(e) => {
+                assert!(
+                    matches!(
+                        e.downcast_ref::<ZikZakError>(),
+                        Some(ZikZakError::InsufficientBalance { .. })
+                    ),
+                    "unexpected error: {}",
+                    e
+                );
+                rejected += 1;
+            }
+        }
+    }
+
+    let engine = engine.read().await;
+    assert_eq!(succeeded, 5);
+    assert_eq!(rejected, 45);
+    assert_eq!(engine.get_balance(&wallet).await?, 0);
+    assert_eq!(engine.get_balance(&shop).await?, 500);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_balance_reads_share_the_lock() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    let run = Uuid::new_v4().simple().to_string();
+    let wallet = format!("wallet:{}:balance", run);
+    engine
+        .transfer("system:genesis", &wallet, 250, HashMap::new())
+        .await?;
+
+    let engine = Arc::new(RwLock::new(engine));
+    // Held for the whole test: readers must not wait for each other
+    let held = engine.read().await;
+
+    let mut handles = Vec::new();
+    for _ in 0..20 {
+        let engine = Arc::clone(&engine);
+        let wallet = wallet.clone();
+        handles.push(tokio::spawn(async move {
+            engine.read().await.get_balance(&wallet).await
+        }));
+    }
+    for handle in handles {
+        let balance = tokio::time::timeout(Duration::from_secs(5), handle).await???;
+        assert_eq!(balance, 250);
+    }
+    assert_eq!(held.get_balance(&wallet).await?, 250);
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+//! Connectivity tracking against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use zik_zak::ZikZakEngine;
+
+#[tokio::test]
+async fn test_ping_keeps_engine_connected() -> Result<()> {
+    let engine = ZikZakEngine::new("").await?;
+    assert!(engine.is_connected());
+    assert!(engine.ping().await);
+    assert!(engine.is_connected());
+
+    let engine = Arc::new(RwLock::new(engine));
+    let monitor =
+        ZikZakEngine::spawn_connection_monitor(Arc::clone(&engine), Duration::from_millis(50));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(engine.read().await.is_connected());
+
+    // The monitor stops on its own once the engine is dropped
+    drop(engine);
+    tokio::time::timeout(Duration::from_secs(1), monitor).await??;
+
+    Ok(())
+}
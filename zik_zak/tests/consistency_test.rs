@@ -0,0 +1,197 @@
+//! Balance read consistency tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+use zik_zak::testing::MockClock;
+use zik_zak::{BalanceCacheConfig, Consistency, ZikZakEngine};
+
+/// An engine on a mock clock and a wallet funded with 100
+async fn funded_wallet() -> Result<(ZikZakEngine, Arc<MockClock>, String)> {
+    let clock = Arc::new(MockClock::new(1_700_000_000_000));
+    let mut engine = ZikZakEngine::new("")
+        .await?
+        .with_clock(clock.clone())
+        .with_balance_cache_ttl(Duration::from_secs(10));
+    engine.ensure_system_accounts().await?;
+
+    // Fresh accounts per run - TigerBeetle keeps state between runs
+    let wallet = format!("wallet:{}:balance", Uuid::new_v4().simple());
+    engine
+        .transfer("system:genesis", &wallet, 100, HashMap::new())
+        .await?;
+    Ok((engine, clock, wallet))
+}
+
+/// Another engine on the same ledger, whose transfers the first cannot see
+async fn elsewhere() -> Result<ZikZakEngine> {
+    ZikZakEngine::new("").await
+}
+
+#[tokio::test]
+async fn test_strong_reads_see_every_transfer() -> Result<()> {
+    let (mut engine, _clock, wallet) = funded_wallet().await?;
+    assert_eq!(Consistency::default(), Consistency::Strong);
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Strong)
+            .await?,
+        100
+    );
+
+    engine
+        .transfer("system:genesis", &wallet, 50, HashMap::new())
+        .await?;
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Strong)
+            .await?,
+        150
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cached_reads_are_stale_until_the_ttl_passes() -> Result<()> {
+    let (engine, clock, wallet) = funded_wallet().await?;
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Cached)
+            .await?,
+        100
+    );
+
+    elsewhere()
+        .await?
+        .transfer("system:genesis", &wallet, 50, HashMap::new())
+        .await?;
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Cached)
+            .await?,
+        100
+    );
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Cached)
+            .await?,
+        150
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_eventual_reads_keep_the_last_known_balance() -> Result<()> {
+    let (engine, clock, wallet) = funded_wallet().await?;
+    // Nothing cached yet, so the first read is live
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Eventual)
+            .await?,
+        100
+    );
+
+    elsewhere()
+        .await?
+        .transfer("system:genesis", &wallet, 50, HashMap::new())
+        .await?;
+    clock.advance(Duration::from_secs(3600));
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Eventual)
+            .await?,
+        100
+    );
+
+    // A strong read refreshes what the weaker levels see
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Strong)
+            .await?,
+        150
+    );
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Eventual)
+            .await?,
+        150
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_own_transfers_invalidate_cached_balances() -> Result<()> {
+    let (mut engine, _clock, wallet) = funded_wallet().await?;
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Eventual)
+            .await?,
+        100
+    );
+
+    engine
+        .transfer(wallet.as_str(), "system:void", 30, HashMap::new())
+        .await?;
+    assert_eq!(
+        engine
+            .get_balance_with(&wallet, Consistency::Eventual)
+            .await?,
+        70
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_balance_cache_serves_get_balance_and_counts_lookups() -> Result<()> {
+    let clock = Arc::new(MockClock::new(1_700_000_000_000));
+    let mut engine = ZikZakEngine::new("")
+        .await?
+        .with_clock(clock.clone())
+        .with_balance_cache(BalanceCacheConfig {
+            ttl: Duration::from_secs(10),
+            max_entries: 2,
+        });
+    engine.ensure_system_accounts().await?;
+    let wallets: Vec<String> = (0..3)
+        .map(|_| format!("wallet:{}:balance", Uuid::new_v4().simple()))
+        .collect();
+    for wallet in &wallets {
+        engine
+            .transfer("system:genesis", wallet.as_str(), 100, HashMap::new())
+            .await?;
+    }
+    let before = engine.balance_cache_stats();
+
+    assert_eq!(engine.get_balance(wallets[0].as_str()).await?, 100);
+    assert_eq!(engine.get_balance(wallets[0].as_str()).await?, 100);
+    let stats = engine.balance_cache_stats();
+    assert_eq!(stats.misses - before.misses, 1);
+    assert_eq!(stats.hits - before.hits, 1);
+
+    // Served from memory until told about the change made elsewhere
+    elsewhere()
+        .await?
+        .transfer("system:genesis", wallets[0].as_str(), 50, HashMap::new())
+        .await?;
+    assert_eq!(engine.get_balance(wallets[0].as_str()).await?, 100);
+    engine.invalidate_balances([wallets[0].as_str()]);
+    assert_eq!(engine.get_balance(wallets[0].as_str()).await?, 150);
+
+    // Only the two most recently used accounts are kept
+    engine.get_balance(wallets[1].as_str()).await?;
+    engine.get_balance(wallets[2].as_str()).await?;
+    assert_eq!(engine.balance_cache_stats().entries, 2);
+
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//! Account currency tests against the in-memory backend
+
+use anyhow::Result;
+use std::collections::HashMap;
+use zik_zak::{BatchTransfer, CurrencyStore, MemoryBackend, ZikZakEngine, ZikZakError};
+
+async fn engine(temp_dir: &tempfile::TempDir) -> Result<ZikZakEngine> {
+    let mut engine = ZikZakEngine::with_backend("", MemoryBackend::new()).await?;
+    engine.set_currency_store(CurrencyStore::new(temp_dir.path().join("currencies.db"))?);
+    for (account, currency) in [
+        ("user:1:eur", "EUR"),
+        ("user:2:eur", "EUR"),
+        ("user:3:usd", "USD"),
+    ] {
+        engine
+            .create_account_with_currency(account, currency)
+            .await?;
+        engine
+            .transfer("system:genesis", account, 1000, HashMap::new())
+            .await?;
+    }
+    Ok(engine)
+}
+
+#[tokio::test]
+async fn test_same_currency_transfer_succeeds() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut engine = engine(&temp_dir).await?;
+
+    engine
+        .transfer("user:1:eur", "user:2:eur", 250, HashMap::new())
+        .await?;
+    assert_eq!(engine.get_balance("user:1:eur").await?, 750);
+    assert_eq!(engine.get_balance("user:2:eur").await?, 1250);
+
+    assert_eq!(engine.currency_of("user:1:eur")?, Some("EUR".to_string()));
+    assert_eq!(engine.currency_of("system:genesis")?, None);
+    // Creating it again with its own currency is fine, with another one is not
+    engine
+        .create_account_with_currency("user:1:eur", "EUR")
+        .await?;
+    assert!(engine
+        .create_account_with_currency("user:1:eur", "USD")
+        .await
+        .is_err());
+    // Flag accounts count things, not money
+    assert!(engine
+        .create_account_with_currency("product:1:existence", "EUR")
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cross_currency_transfer_fails() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut engine = engine(&temp_dir).await?;
+
+    let err = engine
+        .transfer("user:1:eur", "user:3:usd", 250, HashMap::new())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ZikZakError>(),
+        Some(&ZikZakError::CurrencyMismatch {
+            from: "user:1:eur".to_string(),
+            from_currency: "EUR".to_string(),
+            to: "user:3:usd".to_string(),
+            to_currency: "USD".to_string(),
+        })
+    );
+
+    // One mismatched leg fails the whole batch
+    let leg = |from: &str, to: &str| BatchTransfer {
+        from_account: from.to_string(),
+        to_account: to.to_string(),
+        amount: 100,
+        metadata: HashMap::new(),
+    };
+    assert!(engine
+        .transfer_batch(vec![
+            leg("user:1:eur", "user:2:eur"),
+            leg("user:3:usd", "user:2:eur"),
+        ])
+        .await
+        .is_err());
+
+    assert_eq!(engine.get_balance("user:1:eur").await?, 1000);
+    assert_eq!(engine.get_balance("user:3:usd").await?, 1000);
+    Ok(())
+}
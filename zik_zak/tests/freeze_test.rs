@@ -0,0 +1,81 @@
+//! Account freezing tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{BatchTransfer, ZikZakEngine, ZikZakError};
+
+fn frozen_account(error: &anyhow::Error) -> Option<&str> {
+    match error.downcast_ref::<ZikZakError>() {
+        Some(ZikZakError::AccountFrozen { account }) => Some(account),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn test_frozen_account_cannot_send_or_receive() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+    let id = Uuid::new_v4().simple().to_string();
+    let (user, shop) = (
+        format!("user:{}:balance", id),
+        format!("shop:{}:revenue", id),
+    );
+
+    engine
+        .transfer("system:genesis", user.as_str(), 1_000, HashMap::new())
+        .await?;
+    assert!(!engine.is_frozen(&user).await?);
+
+    engine.freeze_account(&user).await?;
+    engine.freeze_account(&user).await?;
+    assert!(engine.is_frozen(&user).await?);
+
+    let err = engine
+        .transfer(user.as_str(), shop.as_str(), 100, HashMap::new())
+        .await
+        .unwrap_err();
+    assert_eq!(frozen_account(&err), Some(user.as_str()));
+    let err = engine
+        .transfer("system:genesis", user.as_str(), 100, HashMap::new())
+        .await
+        .unwrap_err();
+    assert_eq!(frozen_account(&err), Some(user.as_str()));
+    let err = engine
+        .transfer_batch(vec![BatchTransfer {
+            from_account: user.clone(),
+            to_account: shop.clone(),
+            amount: 100,
+            metadata: HashMap::new(),
+        }])
+        .await
+        .unwrap_err();
+    assert_eq!(frozen_account(&err), Some(user.as_str()));
+    let err = engine
+        .transfer_with_user_data(user.as_str(), shop.as_str(), 100, 7, HashMap::new())
+        .await
+        .unwrap_err();
+    assert_eq!(frozen_account(&err), Some(user.as_str()));
+    let err = engine
+        .reserve(user.as_str(), shop.as_str(), 100, 0)
+        .await
+        .unwrap_err();
+    assert_eq!(frozen_account(&err), Some(user.as_str()));
+    assert_eq!(engine.get_balance(&user).await?, 1_000);
+
+    engine.unfreeze_account(&user).await?;
+    assert!(!engine.is_frozen(&user).await?);
+    engine
+        .transfer(user.as_str(), shop.as_str(), 100, HashMap::new())
+        .await?;
+    assert_eq!(engine.get_balance(&user).await?, 900);
+    assert_eq!(engine.get_balance(&shop).await?, 100);
+
+    // Unfreezing twice, or an account that was never frozen, is harmless
+    engine.unfreeze_account(&user).await?;
+    engine.unfreeze_account(&shop).await?;
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+//! Genesis depletion guard tests
+//!
+//! Tests on [`ZikZakEngine::new`] require TigerBeetle running as described in
+//! `tigerbeetle_integration_test.rs`; the others use a [`MemoryBackend`].
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{MemoryBackend, ZikZakEngine, DEFAULT_GENESIS_THRESHOLD};
+
+#[tokio::test]
+async fn test_genesis_is_refilled_below_threshold() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    // Anything genesis spends from here on puts it below the threshold
+    let threshold = engine.genesis_remaining().await?;
+    let mut engine = engine.with_genesis_threshold(threshold);
+
+    let sink = format!("sink:{}:balance", Uuid::new_v4().simple());
+    engine
+        .transfer("system:genesis", sink.as_str(), 5_000, HashMap::new())
+        .await?;
+    assert!(engine.genesis_remaining().await? < threshold);
+
+    engine.ensure_genesis_account().await?;
+    assert!(engine.genesis_remaining().await? >= threshold);
+
+    let refill = &engine.account_transfers("system:treasury", false).await?[0];
+    assert_eq!(refill.to_account, "system:genesis");
+    assert_eq!(refill.metadata["reason"], "genesis_refill");
+    assert!(refill.amount >= 5_000);
+
+    // Back above the threshold, nothing more happens
+    engine.ensure_genesis_account().await?;
+    assert_eq!(
+        engine
+            .account_transfers("system:treasury", false)
+            .await?
+            .len(),
+        1
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_system_accounts_start_above_the_threshold() -> Result<()> {
+    let mut engine = ZikZakEngine::with_backend("", MemoryBackend::new()).await?;
+    engine.ensure_system_accounts().await?;
+
+    // Genesis opens with debits, which is plenty of room, so nothing is refilled
+    let remaining = engine.genesis_remaining().await?;
+    assert!(remaining >= DEFAULT_GENESIS_THRESHOLD);
+    assert!(engine
+        .account_transfers("system:treasury", false)
+        .await?
+        .is_empty());
+
+    engine
+        .transfer("system:genesis", "user:1:balance", 500, HashMap::new())
+        .await?;
+    assert_eq!(engine.genesis_remaining().await?, remaining - 500);
+
+    // The default treasury can fund a refill
+    let mut engine = engine.with_genesis_threshold(remaining);
+    engine.ensure_system_accounts().await?;
+    assert_eq!(engine.genesis_remaining().await?, remaining);
+    let refill = &engine.account_transfers("system:treasury", false).await?[0];
+    assert_eq!(refill.amount, 500);
+    assert_eq!(refill.metadata["reason"], "genesis_refill");
+    Ok(())
+}
@@ -0,0 +1,66 @@
+//! Account hash collision detection against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use uuid::Uuid;
+use zik_zak::{
+    AccountHasher, AccountNameStore, Sha256AccountHasher, TigerBeetleClient, ZikZakError,
+};
+
+/// SHA-256, except every `twin:{run}:*` name hashes like `twin:{run}`
+struct TwinHasher;
+
+impl AccountHasher for TwinHasher {
+    fn hash_u128(&self, name: &str) -> u128 {
+        let name = match name.strip_prefix("twin:") {
+            Some(rest) => &name[..5 + rest.find(':').unwrap_or(rest.len())],
+            None => name,
+        };
+        Sha256AccountHasher.hash_u128(name)
+    }
+}
+
+fn is_collision(error: &anyhow::Error, a: &str, b: &str) -> bool {
+    error.downcast_ref::<ZikZakError>()
+        == Some(&ZikZakError::HashCollision {
+            a: a.to_string(),
+            b: b.to_string(),
+        })
+}
+
+#[tokio::test]
+async fn test_second_name_for_an_id_is_rejected() -> Result<()> {
+    let run = Uuid::new_v4().simple().to_string();
+    let (first, second) = (format!("twin:{}:a", run), format!("twin:{}:b", run));
+    let mut client = TigerBeetleClient::with_hasher(TwinHasher).await?;
+
+    client.create_account(&first, 0, 0).await?;
+    let err = client.create_account(&second, 0, 0).await.unwrap_err();
+    assert!(is_collision(&err, &first, &second), "{}", err);
+
+    // The first name keeps working
+    client.create_account(&first, 0, 0).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_collision_is_detected_across_restarts() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let path = temp_dir.path().join("names.db");
+    let run = Uuid::new_v4().simple().to_string();
+    let (first, second) = (format!("twin:{}:a", run), format!("twin:{}:b", run));
+
+    {
+        let mut client = TigerBeetleClient::with_hasher(TwinHasher).await?;
+        client.set_name_store(AccountNameStore::new(&path)?)?;
+        client.create_account(&first, 0, 0).await?;
+    }
+
+    // A fresh client has an empty cache; only the persisted names know about `first`
+    let mut client = TigerBeetleClient::with_hasher(TwinHasher).await?;
+    client.set_name_store(AccountNameStore::new(&path)?)?;
+    let err = client.create_account(&second, 0, 0).await.unwrap_err();
+    assert!(is_collision(&err, &first, &second), "{}", err);
+    Ok(())
+}
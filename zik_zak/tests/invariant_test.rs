@@ -0,0 +1,49 @@
+//! Cross-account invariant tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{ZikZakEngine, ZikZakError};
+
+#[tokio::test]
+async fn test_check_invariant_holds_and_breaks() -> Result<()> {
+    let namespace = format!("invariant_{}", Uuid::new_v4().simple());
+    let mut engine = ZikZakEngine::new(&namespace).await?;
+    engine.ensure_system_accounts().await?;
+
+    engine
+        .seed(&[
+            ("user:1:balance", 300),
+            ("user:2:balance", 200),
+            ("merchant:1:revenue", 0),
+            ("user:1:points", 999),
+        ])
+        .await?;
+    let pattern = "user:*:balance, merchant:*:revenue";
+    assert!(engine.check_invariant(pattern, 500).await?);
+
+    // Moving value between matching accounts conserves the total
+    engine
+        .transfer("user:1:balance", "merchant:1:revenue", 120, HashMap::new())
+        .await?;
+    assert!(engine.check_invariant(pattern, 500).await?);
+
+    // Issuing more from genesis breaks it, and the error says by how much
+    engine
+        .transfer("system:genesis", "user:2:balance", 7, HashMap::new())
+        .await?;
+    let error = engine.check_invariant(pattern, 500).await.unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<ZikZakError>(),
+        Some(&ZikZakError::InvariantViolated {
+            pattern: pattern.to_string(),
+            expected: 500,
+            actual: 507,
+        })
+    );
+
+    assert!(engine.check_invariant("*:balance", 0).await.is_err());
+    Ok(())
+}
@@ -0,0 +1,284 @@
+//! Transfer limit tests
+//!
+//! Tests on [`ZikZakEngine::new`] require TigerBeetle running as described in
+//! `tigerbeetle_integration_test.rs`; the others use a [`MemoryBackend`].
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+use zik_zak::testing::MockClock;
+use zik_zak::{
+    BatchTransfer, DailyLimit, DailyOutflowStore, MemoryBackend, TransferLimits, ZikZakEngine,
+    ZikZakError,
+};
+
+fn limit_exceeded(error: &anyhow::Error) -> Option<(String, i64, i64)> {
+    match error.downcast_ref::<ZikZakError>() {
+        Some(ZikZakError::LimitExceeded {
+            limit, amount, max, ..
+        }) => Some((limit.clone(), *amount, *max)),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn test_transfer_over_single_amount_cap_is_rejected() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?.with_limits(TransferLimits {
+        max_amount: Some(1_000),
+        ..Default::default()
+    });
+    let id = Uuid::new_v4().simple().to_string();
+    let (user, shop) = (
+        format!("user:{}:balance", id),
+        format!("shop:{}:revenue", id),
+    );
+
+    // Minting is not limited
+    engine
+        .transfer("system:genesis", user.as_str(), 5_000, HashMap::new())
+        .await?;
+    engine
+        .transfer(user.as_str(), shop.as_str(), 1_000, HashMap::new())
+        .await?;
+    let err = engine
+        .transfer(user.as_str(), shop.as_str(), 1_001, HashMap::new())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        limit_exceeded(&err),
+        Some(("single transfer".to_string(), 1_001, 1_000))
+    );
+
+    // Nothing was committed
+    assert_eq!(engine.get_balance(&user).await?, 4_000);
+
+    Ok(())
+}
+
+fn leg(from: &str, to: &str, amount: i64) -> BatchTransfer {
+    BatchTransfer {
+        from_account: from.to_string(),
+        to_account: to.to_string(),
+        amount,
+        metadata: HashMap::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_batch_legs_are_held_to_the_limits() -> Result<()> {
+    let mut engine = ZikZakEngine::with_backend("", MemoryBackend::new())
+        .await?
+        .with_limits(TransferLimits {
+            max_amount: Some(1_000),
+            daily_outflow: Some(1_500),
+            ..Default::default()
+        });
+    engine
+        .transfer("system:genesis", "user:1:balance", 5_000, HashMap::new())
+        .await?;
+
+    // A batch of one is still a transfer over the cap
+    let err = engine
+        .transfer_batch(vec![leg("user:1:balance", "shop:1:revenue", 1_001)])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        limit_exceeded(&err),
+        Some(("single transfer".to_string(), 1_001, 1_000))
+    );
+
+    // Each leg is under the cap, but together they send too much in a day
+    let err = engine
+        .transfer_batch(vec![
+            leg("user:1:balance", "shop:1:revenue", 800),
+            leg("user:1:balance", "shop:2:revenue", 800),
+        ])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        limit_exceeded(&err),
+        Some(("daily outflow".to_string(), 1_600, 1_500))
+    );
+
+    // Nothing was sent for either batch
+    assert_eq!(engine.get_balance("user:1:balance").await?, 5_000);
+    assert_eq!(engine.get_transfer_count().await?, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_daily_outflow_cap_is_cumulative_and_rolls() -> Result<()> {
+    let id = Uuid::new_v4().simple().to_string();
+    let (user, shop) = (
+        format!("user:{}:balance", id),
+        format!("shop:{}:revenue", id),
+    );
+
+    let clock = Arc::new(MockClock::new(1_700_000_000_000));
+    let mut engine = ZikZakEngine::new("")
+        .await?
+        .with_clock(clock.clone())
+        .with_limits(TransferLimits {
+            daily_outflow: Some(500),
+            ..Default::default()
+        });
+    // Genesis is not limited, however much it sends
+    engine
+        .transfer("system:genesis", user.as_str(), 1_000, HashMap::new())
+        .await?;
+
+    for _ in 0..4 {
+        engine
+            .transfer(user.as_str(), shop.as_str(), 100, HashMap::new())
+            .await?;
+        clock.advance(Duration::from_secs(60 * 60));
+    }
+
+    let err = engine
+        .transfer(user.as_str(), shop.as_str(), 150, HashMap::new())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        limit_exceeded(&err),
+        Some(("daily outflow".to_string(), 550, 500))
+    );
+    assert_eq!(engine.get_balance(&shop).await?, 400);
+
+    // Exactly up to the cap is fine
+    engine
+        .transfer(user.as_str(), shop.as_str(), 100, HashMap::new())
+        .await?;
+    assert!(engine
+        .transfer(user.as_str(), shop.as_str(), 1, HashMap::new())
+        .await
+        .is_err());
+
+    // Once the first transfer is over a day old, its 100 is spendable again
+    clock.advance(Duration::from_secs(20 * 60 * 60 + 1));
+    engine
+        .transfer(user.as_str(), shop.as_str(), 100, HashMap::new())
+        .await?;
+    assert_eq!(engine.get_balance(&shop).await?, 600);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pattern_daily_limit_resets_at_utc_midnight() -> Result<()> {
+    let id = Uuid::new_v4().simple().to_string();
+    let (user, shop) = (
+        format!("user:{}:balance", id),
+        format!("shop:{}:revenue", id),
+    );
+
+    // 23:00 UTC
+    let midnight_ms = 1_699_920_000_000;
+    let clock = Arc::new(MockClock::new(midnight_ms - 60 * 60 * 1000));
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut engine = ZikZakEngine::new("")
+        .await?
+        .with_clock(clock.clone())
+        .with_limits(TransferLimits {
+            daily_limits: vec![DailyLimit::new("user:*:balance", 300)?],
+            ..Default::default()
+        });
+    engine
+        .transfer("system:genesis", user.as_str(), 1_000, HashMap::new())
+        .await?;
+
+    // Covered accounts cannot send until there is somewhere to count
+    assert!(engine
+        .transfer(user.as_str(), shop.as_str(), 100, HashMap::new())
+        .await
+        .is_err());
+    engine.set_daily_outflow_store(DailyOutflowStore::new(temp_dir.path().join("outflow.db"))?);
+
+    engine
+        .transfer(user.as_str(), shop.as_str(), 200, HashMap::new())
+        .await?;
+    let err = engine
+        .transfer(user.as_str(), shop.as_str(), 101, HashMap::new())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        limit_exceeded(&err),
+        Some(("daily user:*:balance".to_string(), 301, 300))
+    );
+    assert!(err.to_string().contains("daily user:*:balance limit"));
+
+    // Accounts outside the pattern are not limited
+    engine
+        .transfer(shop.as_str(), user.as_str(), 150, HashMap::new())
+        .await?;
+
+    // Counting starts over at UTC midnight, not 24 hours after the first transfer
+    clock.advance(Duration::from_secs(60 * 60));
+    engine
+        .transfer(user.as_str(), shop.as_str(), 300, HashMap::new())
+        .await?;
+    assert_eq!(engine.get_balance(&user).await?, 650);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_and_user_data_transfers_count_towards_daily_limits() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut engine = ZikZakEngine::with_backend("", MemoryBackend::new())
+        .await?
+        .with_limits(TransferLimits {
+            daily_limits: vec![
+                DailyLimit::new("user:*:balance", 300)?,
+                DailyLimit::new("system:*", 1)?,
+            ],
+            ..Default::default()
+        });
+    engine.set_daily_outflow_store(DailyOutflowStore::new(temp_dir.path().join("outflow.db"))?);
+    // System accounts are exempt even from a pattern that names them
+    engine
+        .transfer("system:genesis", "user:1:balance", 1_000, HashMap::new())
+        .await?;
+
+    let err = engine
+        .transfer_batch(vec![
+            leg("user:1:balance", "shop:1:revenue", 200),
+            leg("user:1:balance", "shop:2:revenue", 101),
+        ])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        limit_exceeded(&err),
+        Some(("daily user:*:balance".to_string(), 301, 300))
+    );
+
+    engine
+        .transfer_batch(vec![
+            leg("user:1:balance", "shop:1:revenue", 100),
+            leg("user:1:balance", "shop:2:revenue", 100),
+        ])
+        .await?;
+    engine
+        .transfer_with_user_data("user:1:balance", "shop:1:revenue", 50, 7, HashMap::new())
+        .await?;
+
+    // The batch and the user_data transfer were counted, leaving 50 for today
+    let err = engine
+        .transfer("user:1:balance", "shop:1:revenue", 51, HashMap::new())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        limit_exceeded(&err),
+        Some(("daily user:*:balance".to_string(), 301, 300))
+    );
+    assert!(engine
+        .transfer_with_user_data("user:1:balance", "shop:1:revenue", 51, 8, HashMap::new())
+        .await
+        .is_err());
+    engine
+        .transfer("user:1:balance", "shop:1:revenue", 50, HashMap::new())
+        .await?;
+    assert_eq!(engine.get_balance("user:1:balance").await?, 700);
+    Ok(())
+}
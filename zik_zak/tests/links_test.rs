@@ -0,0 +1,47 @@
+//! Entity relationship link tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use uuid::Uuid;
+use zik_zak::{ZikZakEngine, ZikZakError};
+
+#[tokio::test]
+async fn test_user_linked_to_several_orders() -> Result<()> {
+    let namespace = format!("links_{}", Uuid::new_v4().simple());
+    let mut engine = ZikZakEngine::new(&namespace).await?;
+    engine.ensure_system_accounts().await?;
+
+    for order in ["order:789", "order:790", "order:791"] {
+        engine.link("user:456", "orders", order).await?;
+    }
+    // Linking again does not record a second link
+    engine.link("user:456", "orders", "order:789").await?;
+    engine.link("user:456", "reviews", "review:1").await?;
+    engine.link("user:457", "orders", "order:800").await?;
+
+    assert_eq!(
+        engine.linked("user:456", "orders").await?,
+        vec!["order:789", "order:790", "order:791"]
+    );
+    assert_eq!(
+        engine.get_balance("user:456:rel:orders:order:789").await?,
+        1
+    );
+    assert_eq!(
+        engine.linked("user:456", "reviews").await?,
+        vec!["review:1"]
+    );
+    assert!(engine.linked("user:458", "orders").await?.is_empty());
+
+    let err = engine
+        .link("user:456", "orders:open", "order:792")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ZikZakError>(),
+        Some(ZikZakError::InvalidAccountName { .. })
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,161 @@
+//! Engine scenarios against the in-memory backend
+//!
+//! The same flows the TigerBeetle tests cover, run on a [`MemoryBackend`] and a
+//! [`MockClock`] so they need no cluster and never sleep.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use zik_zak::testing::MockClock;
+use zik_zak::{
+    AccountDirection, AccountSpec, BatchTransfer, MemoryBackend, ZikZakEngine, ZikZakError,
+};
+
+async fn engine(clock: &Arc<MockClock>) -> Result<ZikZakEngine> {
+    let backend = MemoryBackend::new().with_clock(clock.clone());
+    Ok(ZikZakEngine::with_backend("", backend)
+        .await?
+        .with_clock(clock.clone()))
+}
+
+fn batch(from: &str, to: &str, amount: i64) -> BatchTransfer {
+    BatchTransfer {
+        from_account: from.to_string(),
+        to_account: to.to_string(),
+        amount,
+        metadata: HashMap::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_transfers_respect_balances() -> Result<()> {
+    let clock = Arc::new(MockClock::new(1_700_000_000_000));
+    let mut engine = engine(&clock).await?;
+
+    engine
+        .transfer("system:genesis", "user:1:balance", 500, HashMap::new())
+        .await?;
+    engine
+        .transfer("user:1:balance", "shop:1:balance", 200, HashMap::new())
+        .await?;
+    assert!(engine
+        .transfer("user:1:balance", "shop:1:balance", 400, HashMap::new())
+        .await
+        .is_err());
+
+    assert_eq!(engine.get_balance("user:1:balance").await?, 300);
+    assert_eq!(engine.get_balance("shop:1:balance").await?, 200);
+    assert_eq!(engine.get_transfer_count().await?, 2);
+
+    let shops = engine.find_accounts("shop:*:balance").await?;
+    assert_eq!(shops.len(), 1);
+    assert_eq!(shops[0].balance, 200);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_is_atomic_and_ids_are_idempotent() -> Result<()> {
+    let clock = Arc::new(MockClock::new(1_700_000_000_000));
+    let mut engine = engine(&clock).await?;
+
+    // The second leg overdraws, so the first is rolled back with it
+    let overdrawn = engine
+        .transfer_batch(vec![
+            batch("system:genesis", "user:2:balance", 100),
+            batch("user:2:balance", "user:3:balance", 150),
+        ])
+        .await;
+    assert!(overdrawn.is_err());
+    assert_eq!(engine.get_balance("user:2:balance").await?, 0);
+
+    for _ in 0..2 {
+        engine
+            .transfer_with_id(42, "system:genesis", "user:2:balance", 75, HashMap::new())
+            .await?;
+    }
+    assert_eq!(engine.get_balance("user:2:balance").await?, 75);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pending_transfers_expire_on_the_mock_clock() -> Result<()> {
+    let clock = Arc::new(MockClock::new(1_700_000_000_000));
+    let mut engine = engine(&clock).await?;
+
+    let wallet = "wallet:1:balance";
+    let hold = "hotel:1:holds";
+    engine
+        .transfer("system:genesis", wallet, 500, HashMap::new())
+        .await?;
+    // Unlike transfers, reservations do not create missing accounts
+    engine.create_account(hold, AccountSpec::default()).await?;
+
+    let expiring = engine.reserve(wallet, hold, 200, 30).await?;
+    assert!(engine.reserve(wallet, hold, 400, 0).await.is_err());
+    let stale = engine.reserve(wallet, hold, 100, 0).await?;
+    assert_eq!(engine.list_pending(wallet).await?.len(), 2);
+
+    clock.advance(Duration::from_secs(31));
+    let expired = engine.expired_pending().await?;
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].id, expiring);
+
+    assert_eq!(engine.expire_stale_pending(10).await?, vec![stale]);
+    assert!(engine.list_pending(wallet).await?.is_empty());
+    // Released holds leave room for the whole balance again
+    engine.reserve(wallet, hold, 500, 0).await?;
+    assert_eq!(engine.get_balance(wallet).await?, 500);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_check_and_transfer_is_checked_by_the_ledger() -> Result<()> {
+    let clock = Arc::new(MockClock::new(1_700_000_000_000));
+    let mut engine = engine(&clock).await?;
+
+    let (wallet, shop) = ("wallet:1:balance", "shop:1:revenue");
+    engine
+        .transfer("system:genesis", wallet, 150, HashMap::new())
+        .await?;
+
+    // The whole minimum must be there, not just the amount
+    let err = engine
+        .check_and_transfer(wallet, shop, 100, 200)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ZikZakError>(),
+        Some(&ZikZakError::InsufficientBalance {
+            account: wallet.to_string(),
+            balance: 150,
+            required: 200,
+        })
+    );
+    engine.check_and_transfer(wallet, shop, 100, 150).await?;
+    assert_eq!(engine.get_balance(wallet).await?, 50);
+    assert_eq!(engine.get_balance(shop).await?, 100);
+    // The funding and the transfer; the hold is not logged
+    assert_eq!(engine.get_transfer_count().await?, 2);
+
+    // TigerBeetle would let this one go negative, so it cannot do the check
+    let escrow = "escrow:1:balance";
+    engine
+        .create_account(
+            escrow,
+            AccountSpec {
+                direction: AccountDirection::Unconstrained,
+                ..Default::default()
+            },
+        )
+        .await?;
+    engine
+        .transfer("system:genesis", escrow, 500, HashMap::new())
+        .await?;
+    assert!(engine
+        .check_and_transfer(escrow, shop, 100, 100)
+        .await
+        .is_err());
+    assert_eq!(engine.get_balance(escrow).await?, 500);
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Transfer metadata lookup tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{AccountNameStore, MetadataIndex, ZikZakEngine};
+
+/// Five orders paid from one account: order ids `{run}-1`, `{run}-2`, `{run}-1`, `{run}-3`, `{run}-1`
+async fn pay_orders(engine: &mut ZikZakEngine, run: &str) -> Result<()> {
+    engine.ensure_system_accounts().await?;
+    let wallet = format!("user:{}:balance", run);
+    engine
+        .transfer("system:genesis", &wallet, 1000, HashMap::new())
+        .await?;
+
+    for (amount, order) in [(10, 1), (20, 2), (30, 1), (40, 3), (50, 1)] {
+        let metadata = HashMap::from([
+            ("order_id".to_string(), format!("{}-{}", run, order)),
+            ("channel".to_string(), "web".to_string()),
+        ]);
+        engine
+            .transfer(&wallet, "merchant:revenue", amount, metadata)
+            .await?;
+    }
+    Ok(())
+}
+
+fn amounts(transfers: &[zik_zak::Transfer]) -> Vec<i64> {
+    transfers.iter().map(|t| t.amount).collect()
+}
+
+#[tokio::test]
+async fn test_find_transfers_by_metadata_returns_matching_subset() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    let run = Uuid::new_v4().simple().to_string();
+    pay_orders(&mut engine, &run).await?;
+
+    let order_1 = format!("{}-1", run);
+    let found = engine
+        .find_transfers_by_metadata("order_id", &order_1, 10)
+        .await?;
+    assert_eq!(amounts(&found), vec![50, 30, 10]);
+    assert!(found.iter().all(|t| t.metadata["order_id"] == order_1));
+
+    let newest = engine
+        .find_transfers_by_metadata("order_id", &order_1, 2)
+        .await?;
+    assert_eq!(amounts(&newest), vec![50, 30]);
+
+    let order_2 = format!("{}-2", run);
+    let found = engine
+        .find_transfers_by_metadata("order_id", &order_2, 10)
+        .await?;
+    assert_eq!(amounts(&found), vec![20]);
+
+    assert!(engine
+        .find_transfers_by_metadata("order_id", &format!("{}-9", run), 10)
+        .await?
+        .is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sled_metadata_index_outlives_the_engine() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let path = temp_dir.path().join("metadata.db");
+    let run = Uuid::new_v4().simple().to_string();
+
+    {
+        let mut engine = ZikZakEngine::new("").await?;
+        engine.set_metadata_index(MetadataIndex::new(&path)?)?;
+        pay_orders(&mut engine, &run).await?;
+    }
+
+    let index = MetadataIndex::new(&path)?;
+    let found = index.find("order_id", &format!("{}-1", run), 10)?;
+    assert_eq!(amounts(&found), vec![50, 30, 10]);
+    assert_eq!(index.find("order_id", &format!("{}-3", run), 10)?.len(), 1);
+    // Every metadata entry is indexed, not just order_id
+    assert_eq!(index.find("channel", "web", 10)?.len(), 5);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_account_transfers_keep_metadata_across_restarts() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let metadata_path = temp_dir.path().join("metadata.db");
+    let names_path = temp_dir.path().join("names.db");
+    let run = Uuid::new_v4().simple().to_string();
+    let wallet = format!("user:{}:balance", run);
+
+    {
+        let mut engine = ZikZakEngine::new("").await?;
+        engine.set_account_name_store(AccountNameStore::new(&names_path)?)?;
+        engine.set_metadata_index(MetadataIndex::new(&metadata_path)?)?;
+        pay_orders(&mut engine, &run).await?;
+    }
+
+    // A fresh engine has an empty log; history comes from TigerBeetle and SLED
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.set_account_name_store(AccountNameStore::new(&names_path)?)?;
+    engine.set_metadata_index(MetadataIndex::new(&metadata_path)?)?;
+
+    let history = engine.account_transfers(&wallet, false).await?;
+    assert_eq!(amounts(&history), vec![50, 40, 30, 20, 10, 1000]);
+    assert_eq!(history[0].metadata["order_id"], format!("{}-1", run));
+    assert_eq!(history[0].from_account, wallet);
+    assert_eq!(history[0].to_account, "merchant:revenue");
+    // The funding transfer had no metadata
+    assert!(history[5].metadata.is_empty());
+    assert_eq!(history[5].from_account, "system:genesis");
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! Namespace isolation tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::ZikZakEngine;
+
+#[tokio::test]
+async fn test_namespaces_do_not_share_balances() -> Result<()> {
+    let run = Uuid::new_v4().simple().to_string();
+    let mut auth = ZikZakEngine::new(&format!("auth_{}", run)).await?;
+    let mut shop = ZikZakEngine::new(&format!("shop_{}", run)).await?;
+    auth.ensure_system_accounts().await?;
+
+    auth.transfer("system:genesis", "user:1:balance", 100, HashMap::new())
+        .await?;
+
+    // Same short name, different namespace: not even created there yet
+    assert_eq!(auth.get_balance("user:1:balance").await?, 100);
+    assert!(shop.get_balance("user:1:balance").await.is_err());
+    assert!(!shop.exists("user:1").await?);
+
+    shop.transfer("system:genesis", "user:1:balance", 40, HashMap::new())
+        .await?;
+
+    assert_eq!(auth.get_balance("user:1:balance").await?, 100);
+    assert_eq!(shop.get_balance("user:1:balance").await?, 40);
+
+    // Ledger dumps only show the namespace's own accounts, with short names
+    let ledger = shop.get_active_ledger_state().await?;
+    assert_eq!(ledger["user:1:balance"], 40);
+    assert!(ledger
+        .as_object()
+        .unwrap()
+        .keys()
+        .all(|name| !name.starts_with("auth_")));
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! Overdrawn account report against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{AccountDirection, AccountSpec, ZikZakEngine};
+
+#[tokio::test]
+async fn test_find_overdrawn_lists_negative_accounts_most_negative_first() -> Result<()> {
+    let namespace = format!("overdrawn{}", Uuid::new_v4().simple());
+    let mut engine = ZikZakEngine::new(&namespace).await?;
+    engine.ensure_system_accounts().await?;
+
+    let unconstrained = AccountSpec {
+        direction: AccountDirection::Unconstrained,
+        ..AccountSpec::default()
+    };
+    for (account, amount) in [("credit:1:line", 50), ("credit:2:line", 200)] {
+        engine.create_account(account, unconstrained).await?;
+        engine
+            .transfer(account, "shop:revenue", amount, HashMap::new())
+            .await?;
+    }
+    engine
+        .transfer("system:genesis", "user:1:balance", 10, HashMap::new())
+        .await?;
+
+    // Genesis is negative too, but by design
+    assert_eq!(
+        engine.find_overdrawn().await?,
+        vec![
+            ("credit:2:line".to_string(), -200),
+            ("credit:1:line".to_string(), -50),
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//! Pending transfer reaping against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
+use uuid::Uuid;
+use zik_zak::ZikZakEngine;
+
+#[tokio::test]
+async fn test_expire_stale_pending_voids_reservations() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    let run = Uuid::new_v4().simple().to_string();
+    let wallet = format!("wallet:{}:balance", run);
+    let hold = format!("hotel:{}:holds", run);
+
+    engine
+        .transfer("system:genesis", &wallet, 500, HashMap::new())
+        .await?;
+
+    let pending_id = engine.reserve(&wallet, &hold, 200, 30).await?;
+    let pending = engine.list_pending(&wallet).await?;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].id, pending_id);
+    assert_eq!(pending[0].timeout, 30);
+
+    // Too young to reap yet
+    assert!(engine.expire_stale_pending(60).await?.is_empty());
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let voided = engine.expire_stale_pending(1).await?;
+    assert!(voided.contains(&pending_id));
+
+    assert!(engine.list_pending(&wallet).await?.is_empty());
+    assert!(engine.list_pending(&hold).await?.is_empty());
+    assert_eq!(engine.get_balance(&wallet).await?, 500);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pending_watcher_reports_expiry() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    let run = Uuid::new_v4().simple().to_string();
+    let wallet = format!("wallet:{}:balance", run);
+    let hold = format!("hotel:{}:holds", run);
+
+    engine
+        .transfer("system:genesis", &wallet, 500, HashMap::new())
+        .await?;
+    let pending_id = engine.reserve(&wallet, &hold, 200, 1).await?;
+    assert!(engine.reserve(&wallet, &hold, 1, u64::MAX).await.is_err());
+
+    let pending = engine.list_pending_transfers(&wallet, 10).await?;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].id, pending_id);
+    assert_eq!(pending[0].from_account, wallet);
+    assert_eq!(pending[0].to_account, hold);
+    assert_eq!(pending[0].expires_at, Some(pending[0].created_at + 1));
+
+    let engine = Arc::new(RwLock::new(engine));
+    let (events, mut expiries) = broadcast::channel(16);
+    let watcher = ZikZakEngine::spawn_pending_watcher(
+        Arc::clone(&engine),
+        Duration::from_millis(100),
+        events,
+    );
+
+    let expired = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let pending = expiries.recv().await?;
+            if pending.id == pending_id {
+                return Ok::<_, anyhow::Error>(pending);
+            }
+        }
+    })
+    .await??;
+    assert_eq!(expired.amount, 200);
+    watcher.abort();
+
+    let engine = engine.read().await;
+    assert!(engine.list_pending_transfers(&wallet, 10).await?.is_empty());
+    assert_eq!(engine.get_balance(&wallet).await?, 500);
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! Reconciliation tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::ZikZakEngine;
+
+#[tokio::test]
+async fn test_reconcile_detects_out_of_band_transfer() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    let wallet = format!("wallet:{}:balance", Uuid::new_v4().simple());
+    engine
+        .transfer("system:genesis", &wallet, 100, HashMap::new())
+        .await?;
+    engine.adjust(&wallet, -30).await?;
+
+    let recon = engine.reconcile(&wallet).await?;
+    assert!(recon.is_consistent());
+    assert_eq!(recon.local_balance, 70);
+
+    // A second engine writes to TigerBeetle without touching the first one's log
+    let mut other = ZikZakEngine::new("").await?;
+    other
+        .transfer("system:genesis", &wallet, 5, HashMap::new())
+        .await?;
+
+    let recon = engine.reconcile(&wallet).await?;
+    assert!(!recon.is_consistent());
+    assert_eq!(recon.local_balance, 70);
+    assert_eq!(recon.tigerbeetle_balance, 75);
+    assert_eq!(recon.difference(), 5);
+
+    Ok(())
+}
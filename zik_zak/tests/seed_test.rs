@@ -0,0 +1,64 @@
+//! Seeding tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use uuid::Uuid;
+use zik_zak::ZikZakEngine;
+
+#[tokio::test]
+async fn test_seed_sets_every_balance() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    let run = Uuid::new_v4().simple().to_string();
+    let accounts: Vec<String> = ["alice", "bob", "carol", "dave", "erin"]
+        .iter()
+        .map(|name| format!("user:{}_{}:balance", name, run))
+        .collect();
+    let amounts = [100, 250, 1, 9999, 42];
+
+    let balances: Vec<(&str, i64)> = accounts.iter().map(String::as_str).zip(amounts).collect();
+    engine.seed(&balances).await?;
+
+    for (account, amount) in &balances {
+        assert_eq!(engine.get_balance(*account).await?, *amount);
+    }
+
+    // Negative seeds debit back toward genesis
+    engine.seed(&[(balances[0].0, -40)]).await?;
+    assert_eq!(engine.get_balance(balances[0].0).await?, 60);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seed_from_file() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    let run = Uuid::new_v4().simple().to_string();
+    let temp_dir = tempfile::TempDir::new()?;
+    let fixture = temp_dir.path().join("fixture.json");
+    std::fs::write(
+        &fixture,
+        serde_json::json!({
+            format!("product:{}:price", run): 2999,
+            format!("product:{}:stock", run): 12
+        })
+        .to_string(),
+    )?;
+
+    engine.seed_from_file(&fixture).await?;
+
+    assert_eq!(
+        engine.get_balance(format!("product:{}:price", run)).await?,
+        2999
+    );
+    assert_eq!(
+        engine.get_balance(format!("product:{}:stock", run)).await?,
+        12
+    );
+
+    Ok(())
+}
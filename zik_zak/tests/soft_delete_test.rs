@@ -0,0 +1,78 @@
+//! Soft-delete-aware read tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::ZikZakEngine;
+
+#[tokio::test]
+async fn test_get_balance_live_hides_fields_of_deleted_entity() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    let product = format!("product:{}", Uuid::new_v4().simple());
+    let price = format!("{}:price", product);
+    for (account, amount) in [(format!("{}:existence", product), 1), (price.clone(), 2999)] {
+        engine
+            .transfer("system:genesis", &account, amount, HashMap::new())
+            .await?;
+    }
+
+    assert_eq!(engine.get_balance_live(&price).await?, Some(2999));
+
+    // Soft delete: existence goes to system:deleted, the price account is untouched
+    engine
+        .transfer(
+            &format!("{}:existence", product),
+            "system:deleted",
+            1,
+            HashMap::new(),
+        )
+        .await?;
+
+    assert_eq!(engine.get_balance_live(&price).await?, None);
+    assert_eq!(engine.get_balance(price.as_str()).await?, 2999);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_balance_live_reads_accounts_without_existence() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+
+    // No `merchant:{run}:existence` account: nothing to be deleted
+    let revenue = format!("merchant:{}:revenue", Uuid::new_v4().simple());
+    engine
+        .transfer("system:genesis", &revenue, 500, HashMap::new())
+        .await?;
+
+    assert_eq!(engine.get_balance_live(&revenue).await?, Some(500));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_into_system_void() -> Result<()> {
+    // A namespace the client never touched: only ensure_system_accounts can create void
+    let namespace = format!("void_{}", Uuid::new_v4().simple());
+    let mut engine = ZikZakEngine::new(&namespace).await?;
+    engine.ensure_system_accounts().await?;
+    assert!(engine.get_balance("system:void").await.is_ok());
+
+    let comment = format!("comment:{}", Uuid::new_v4().simple());
+    let existence = format!("{}:existence", comment);
+    engine
+        .transfer("system:genesis", &existence, 1, HashMap::new())
+        .await?;
+    assert!(engine.exists(&comment).await?);
+
+    let void_before = engine.get_balance("system:void").await?;
+    engine
+        .transfer(&existence, "system:void", 1, HashMap::new())
+        .await?;
+
+    assert!(!engine.exists(&comment).await?);
+    assert_eq!(engine.get_balance("system:void").await?, void_before + 1);
+    Ok(())
+}
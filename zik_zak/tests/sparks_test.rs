@@ -0,0 +1,1452 @@
+//! Spark operation tests
+//!
+//! Tests through [`Genesis`] require TigerBeetle running as described in
+//! `tigerbeetle_integration_test.rs`; the others use a [`MemoryBackend`].
+
+use anyhow::Result;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+use zik_zak::{
+    zak, zik, Genesis, MemoryBackend, Spark, SparkEngine, TransferFilter, ZikZak, ZikZakEngine,
+    ZikZakError, RUN_ID_KEY,
+};
+
+fn spark(definition: serde_json::Value) -> Spark {
+    serde_json::from_value(definition).expect("valid spark definition")
+}
+
+#[tokio::test]
+async fn test_compute_feeds_later_transfer_amount() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("compute.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "set_price_with_tax".to_string(),
+        spark(json!({
+            "description": "Store price and the 7% tax derived from it",
+            "inputs": ["id", "price"],
+            "operations": [
+                {
+                    "type": "compute",
+                    "expression": "{price} * 7 / 100",
+                    "store_as": "tax"
+                },
+                {
+                    "type": "transfer",
+                    "zik": "system:genesis",
+                    "zak": "product:{id}:tax",
+                    "amount": "{tax}"
+                },
+                {
+                    "type": "transfer",
+                    "zik": "system:genesis",
+                    "zak": "product:{id}:gross",
+                    "amount": "{price} + {tax}"
+                }
+            ],
+            "return": { "tax": "{tax}", "tax_cents": "{tax}:number" },
+            "return_mode": "strings"
+        })),
+    );
+
+    let id = Uuid::new_v4().simple().to_string();
+    let result = genesis
+        .ignite_spark(
+            "set_price_with_tax",
+            ZikZak {
+                zik: zik! { id: id.clone(), price: 2999 },
+                zak: zak! {},
+            },
+        )
+        .await?;
+
+    assert_eq!(result.0["tax"], json!("209"));
+    assert_eq!(result.0["tax_cents"], json!(209));
+    assert_eq!(
+        genesis
+            .accounting
+            .get_balance(format!("product:{}:tax", id))
+            .await?,
+        209
+    );
+    assert_eq!(
+        genesis
+            .accounting
+            .get_balance(format!("product:{}:gross", id))
+            .await?,
+        3208
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_removes_existence_and_text() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("delete.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "create_note".to_string(),
+        spark(json!({
+            "description": "Note with a text title",
+            "inputs": ["id", "title"],
+            "operations": [
+                { "type": "transfer", "zik": "system:genesis", "zak": "note:{id}:existence", "amount": 1 },
+                { "type": "transfer", "zik": "system:genesis", "zak": "note:{id}:title", "amount": "{title}", "sled": true }
+            ]
+        })),
+    );
+    genesis.spark_engine.add_spark(
+        "delete_note".to_string(),
+        spark(json!({
+            "description": "Delete a note",
+            "inputs": ["id"],
+            "operations": [{ "type": "delete", "entity": "note:{id}" }]
+        })),
+    );
+    genesis.spark_engine.add_spark(
+        "delete_note_strict".to_string(),
+        spark(json!({
+            "description": "Delete a note, failing if it is gone",
+            "inputs": ["id"],
+            "operations": [{ "type": "delete", "entity": "note:{id}", "on_fail": "throw" }]
+        })),
+    );
+    genesis.spark_engine.add_spark(
+        "get_note_title".to_string(),
+        spark(json!({
+            "description": "Read a note's title",
+            "inputs": ["id"],
+            "operations": [{ "type": "balance", "account": "note:{id}:title", "sled": true }],
+            "return": { "title": "{op_0}" }
+        })),
+    );
+
+    let id = Uuid::new_v4().simple().to_string();
+    let args = || ZikZak {
+        zik: zik! { id: id.clone(), title: "hello" },
+        zak: zak! {},
+    };
+
+    genesis.ignite_spark("create_note", args()).await?;
+    let before = genesis.ignite_spark("get_note_title", args()).await?;
+    assert_ne!(before.0["title"], json!("null"));
+
+    genesis.ignite_spark("delete_note", args()).await?;
+    assert!(!genesis.accounting.exists(&format!("note:{}", id)).await?);
+    let after = genesis.ignite_spark("get_note_title", args()).await?;
+    assert_eq!(after.0["title"], json!("null"));
+
+    // Deleting again is a no-op unless the spark asks for a failure
+    genesis.ignite_spark("delete_note", args()).await?;
+    assert!(genesis
+        .ignite_spark("delete_note_strict", args())
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_text_interpolates_into_return() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("read_text.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "set_bio".to_string(),
+        spark(json!({
+            "description": "Store a user's bio as text",
+            "inputs": ["id", "bio"],
+            "operations": [
+                { "type": "transfer", "zik": "system:genesis", "zak": "user:{id}:bio", "amount": "{bio}", "sled": true }
+            ]
+        })),
+    );
+    genesis.spark_engine.add_spark(
+        "greet".to_string(),
+        spark(json!({
+            "description": "Read the bio back into a greeting",
+            "inputs": ["id"],
+            "operations": [{ "type": "read_text", "account": "user:{id}:bio", "store_as": "bio" }],
+            "return": { "greeting": "Hi, {bio}" }
+        })),
+    );
+    genesis.spark_engine.add_spark(
+        "greet_strict".to_string(),
+        spark(json!({
+            "description": "Read the bio, failing if there is none",
+            "inputs": ["id"],
+            "operations": [{ "type": "read_text", "account": "user:{id}:bio", "on_fail": "throw" }]
+        })),
+    );
+
+    let id = Uuid::new_v4().simple().to_string();
+    let args = || ZikZak {
+        zik: zik! { id: id.clone() },
+        zak: zak! {},
+    };
+
+    assert!(genesis.ignite_spark("greet_strict", args()).await.is_err());
+    let before = genesis.ignite_spark("greet", args()).await?;
+    assert_eq!(before.0["greeting"], json!("Hi, null"));
+
+    genesis
+        .ignite_spark(
+            "set_bio",
+            ZikZak {
+                zik: zik! { id: id.clone(), bio: "I like ledgers" },
+                zak: zak! {},
+            },
+        )
+        .await?;
+    let after = genesis.ignite_spark("greet", args()).await?;
+    assert_eq!(after.0["greeting"], json!("Hi, I like ledgers"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_text_feeds_later_operations() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("receipt.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "name_product".to_string(),
+        spark(json!({
+            "description": "Give a product a text name",
+            "inputs": ["id", "name"],
+            "operations": [
+                { "type": "transfer", "zik": "system:genesis", "zak": "product:{id}:name", "amount": "{name}", "sled": true }
+            ]
+        })),
+    );
+    genesis.spark_engine.add_spark(
+        "write_receipt".to_string(),
+        spark(json!({
+            "description": "Load the product name, then build a receipt message from it",
+            "inputs": ["id", "order"],
+            "operations": [
+                { "type": "read_text", "account": "product:{id}:name" },
+                { "type": "transfer", "zik": "system:genesis", "zak": "receipt:{order}:message", "amount": "Thanks for buying {op_0}!", "sled": true },
+                { "type": "balance", "account": "receipt:{order}:message", "sled": true }
+            ],
+            "return": { "message": "{op_2}" }
+        })),
+    );
+    genesis.spark_engine.add_spark(
+        "peek_text".to_string(),
+        spark(json!({
+            "description": "Text balance of an account that may not exist",
+            "inputs": ["id"],
+            "operations": [{ "type": "balance", "account": "product:{id}:name", "sled": true }],
+            "return": { "name": "{op_0}" }
+        })),
+    );
+
+    let (id, order) = (
+        Uuid::new_v4().simple().to_string(),
+        Uuid::new_v4().simple().to_string(),
+    );
+    // A text balance of an account never written is null, not an error
+    let missing = genesis
+        .ignite_spark(
+            "peek_text",
+            ZikZak {
+                zik: zik! { id: id.clone() },
+                zak: zak! {},
+            },
+        )
+        .await?;
+    assert_eq!(missing.0["name"], json!("null"));
+
+    genesis
+        .ignite_spark(
+            "name_product",
+            ZikZak {
+                zik: zik! { id: id.clone(), name: "Laptop" },
+                zak: zak! {},
+            },
+        )
+        .await?;
+    let receipt = genesis
+        .ignite_spark(
+            "write_receipt",
+            ZikZak {
+                zik: zik! { id: id.clone(), order: order.clone() },
+                zak: zak! {},
+            },
+        )
+        .await?;
+    assert_eq!(receipt.0["message"], json!("Thanks for buying Laptop!"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_counter_stops_at_its_bounds() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("counter.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "restock".to_string(),
+        spark(json!({
+            "description": "Add stock, never above 10",
+            "inputs": ["id", "quantity"],
+            "operations": [
+                { "type": "counter", "account": "product:{id}:stock", "op": "inc", "amount": "{quantity}", "max": 10 }
+            ],
+            "return": { "stock": "{op_0}:number" }
+        })),
+    );
+    genesis.spark_engine.add_spark(
+        "sell".to_string(),
+        spark(json!({
+            "description": "Take stock, never below the floor",
+            "inputs": ["id", "quantity"],
+            "operations": [
+                { "type": "counter", "account": "product:{id}:stock", "op": "dec", "amount": "{quantity}", "min": 0 }
+            ],
+            "return": { "stock": "{op_0}:number" }
+        })),
+    );
+
+    let id = Uuid::new_v4().simple().to_string();
+    let stock = format!("product:{}:stock", id);
+    let args = |quantity: i64| ZikZak {
+        zik: zik! { id: id.clone(), quantity: quantity },
+        zak: zak! {},
+    };
+
+    let restocked = genesis.ignite_spark("restock", args(5)).await?;
+    assert_eq!(restocked.0["stock"], json!(5));
+    let err = genesis.ignite_spark("restock", args(6)).await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ZikZakError>(),
+        Some(ZikZakError::LimitExceeded {
+            amount: 11,
+            max: 10,
+            ..
+        })
+    ));
+
+    genesis.ignite_spark("sell", args(2)).await?;
+    let sold_out = genesis.ignite_spark("sell", args(3)).await?;
+    assert_eq!(sold_out.0["stock"], json!(0));
+
+    // One past the floor is refused and moves nothing
+    let err = genesis.ignite_spark("sell", args(1)).await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ZikZakError>(),
+        Some(ZikZakError::InsufficientBalance {
+            balance: 0,
+            required: 1,
+            ..
+        })
+    ));
+    assert_eq!(genesis.accounting.get_balance(&stock).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upsert_creates_then_updates() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("upsert.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "record_visit".to_string(),
+        spark(json!({
+            "description": "Create the page on first visit, count visits after that",
+            "inputs": ["id", "weight"],
+            "operations": [
+                { "type": "compute", "expression": "{weight} * 2", "store_as": "points" },
+                {
+                    "type": "upsert",
+                    "entity": "page:{id}",
+                    "create": [
+                        { "type": "transfer", "zik": "system:genesis", "zak": "page:{id}:existence", "amount": 1 },
+                        { "type": "transfer", "zik": "system:genesis", "zak": "page:{id}:visits", "amount": 1 },
+                        { "type": "transfer", "zik": "system:genesis", "zak": "page:{id}:points", "amount": "{points}", "store_as": "points_transfer" }
+                    ],
+                    "update": [
+                        { "type": "transfer", "zik": "system:genesis", "zak": "page:{id}:visits", "amount": 1 },
+                        { "type": "transfer", "zik": "system:genesis", "zak": "page:{id}:points", "amount": "{points}", "store_as": "points_transfer" }
+                    ]
+                }
+            ],
+            "return": {
+                "outcome": "{op_1}",
+                "existed": "{op_1_exists}:bool",
+                "points_transfer": "{points_transfer}"
+            }
+        })),
+    );
+
+    let id = Uuid::new_v4().simple().to_string();
+    let args = || ZikZak {
+        zik: zik! { id: id.clone(), weight: 5 },
+        zak: zak! {},
+    };
+    let balance = |field: &str| format!("page:{}:{}", id, field);
+
+    let created = genesis.ignite_spark("record_visit", args()).await?;
+    assert_eq!(created.0["outcome"], json!("created"));
+    assert_eq!(created.0["existed"], json!(false));
+    assert_ne!(created.0["points_transfer"], json!("{points_transfer}"));
+    assert_eq!(
+        genesis.accounting.get_balance(balance("existence")).await?,
+        1
+    );
+    assert_eq!(genesis.accounting.get_balance(balance("visits")).await?, 1);
+    assert_eq!(genesis.accounting.get_balance(balance("points")).await?, 10);
+
+    // Same spark, same entity: now the update branch runs
+    let updated = genesis.ignite_spark("record_visit", args()).await?;
+    assert_eq!(updated.0["outcome"], json!("updated"));
+    assert_eq!(updated.0["existed"], json!(true));
+    assert_eq!(
+        genesis.accounting.get_balance(balance("existence")).await?,
+        1
+    );
+    assert_eq!(genesis.accounting.get_balance(balance("visits")).await?, 2);
+    assert_eq!(genesis.accounting.get_balance(balance("points")).await?, 20);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_call_spark_composes_sparks() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("call.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "ensure_user".to_string(),
+        spark(json!({
+            "description": "Create the user unless they exist",
+            "inputs": ["user_id"],
+            "operations": [{
+                "type": "upsert",
+                "entity": "user:{user_id}",
+                "create": [
+                    { "type": "transfer", "zik": "system:genesis", "zak": "user:{user_id}:existence", "amount": 1 }
+                ],
+                "update": []
+            }],
+            "return": { "account": "user:{user_id}", "outcome": "{op_0}" }
+        })),
+    );
+    genesis.spark_engine.add_spark(
+        "place_order".to_string(),
+        spark(json!({
+            "description": "Order for a user that may not exist yet",
+            "inputs": ["user_id", "order_id", "total"],
+            "operations": [
+                {
+                    "type": "call_spark",
+                    "spark": "ensure_user",
+                    "args": { "user_id": "{user_id}" },
+                    "store_as": "user"
+                },
+                { "type": "transfer", "zik": "system:genesis", "zak": "order:{order_id}:total", "amount": "{total}" },
+                { "type": "transfer", "zik": "system:genesis", "zak": "{user.account}:orders", "amount": 1 }
+            ],
+            "return": { "user": "{user.outcome}" }
+        })),
+    );
+
+    let user_id = Uuid::new_v4().simple().to_string();
+    let order = |order_id: &str| ZikZak {
+        zik: zik! { user_id: user_id.clone(), order_id: order_id.to_string(), total: 500 },
+        zak: zak! {},
+    };
+
+    let first = genesis.ignite_spark("place_order", order("a")).await?;
+    assert_eq!(first.0["user"], json!("created"));
+    let second = genesis.ignite_spark("place_order", order("b")).await?;
+    assert_eq!(second.0["user"], json!("updated"));
+
+    assert!(
+        genesis
+            .accounting
+            .exists(&format!("user:{}", user_id))
+            .await?
+    );
+    assert_eq!(
+        genesis
+            .accounting
+            .get_balance(format!("user:{}:orders", user_id))
+            .await?,
+        2
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_call_spark_recursion_is_limited() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("recursion.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "forever".to_string(),
+        spark(json!({
+            "description": "Calls itself",
+            "inputs": [],
+            "operations": [{ "type": "call_spark", "spark": "forever" }]
+        })),
+    );
+
+    let error = genesis
+        .ignite_spark(
+            "forever",
+            ZikZak {
+                zik: zik! {},
+                zak: zak! {},
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("depth limit"),
+        "unexpected error: {}",
+        error
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reserve_never_oversells_under_concurrency() -> Result<()> {
+    let id = Uuid::new_v4().simple().to_string();
+    let (stock, reserved) = (
+        format!("product:{}:stock", id),
+        format!("product:{}:reserved", id),
+    );
+
+    // One unit left
+    let mut engine = ZikZakEngine::new("").await?;
+    engine
+        .transfer("system:genesis", stock.as_str(), 1, HashMap::new())
+        .await?;
+
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut handles = Vec::new();
+
+    // Separate engines, so nothing but TigerBeetle serializes the orders
+    for order in 0..10 {
+        let db = temp_dir.path().join(format!("reserve_{}.db", order));
+        let id = id.clone();
+
+        handles.push(tokio::spawn(async move {
+            let mut genesis = Genesis::empty(db).await?;
+            genesis.spark_engine.add_spark(
+                "reserve_unit".to_string(),
+                spark(json!({
+                    "description": "Hold one unit for an order, or fail",
+                    "inputs": ["id"],
+                    "operations": [{
+                        "type": "reserve",
+                        "account": "product:{id}:stock",
+                        "to": "product:{id}:reserved",
+                        "amount": 1,
+                        "on_fail": "throw"
+                    }]
+                })),
+            );
+            genesis
+                .ignite_spark(
+                    "reserve_unit",
+                    ZikZak {
+                        zik: zik! { id: id },
+                        zak: zak! {},
+                    },
+                )
+                .await
+        }));
+    }
+
+    let mut succeeded = 0;
+    for handle in handles {
+        match handle.await? {
+            Ok(_) => succeeded += 1,
+            Err(e) => assert!(
+                matches!(
+                    e.downcast_ref::<ZikZakError>(),
+                    Some(ZikZakError::InsufficientBalance { .. })
+                ),
+                "unexpected error: {}",
+                e
+            ),
+        }
+    }
+
+    assert_eq!(succeeded, 1);
+    assert_eq!(engine.get_balance(&stock).await?, 0);
+    assert_eq!(engine.get_balance(&reserved).await?, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transfers_of_one_run_share_its_run_id() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("run_id.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "count_visit".to_string(),
+        spark(json!({
+            "description": "Count a visit",
+            "inputs": ["id"],
+            "operations": [
+                { "type": "transfer", "zik": "system:genesis", "zak": "page:{id}:visits", "amount": 1 }
+            ]
+        })),
+    );
+    genesis.spark_engine.add_spark(
+        "open_page".to_string(),
+        spark(json!({
+            "description": "Create a page and count the first visit",
+            "inputs": ["id"],
+            "operations": [
+                { "type": "transfer", "zik": "system:genesis", "zak": "page:{id}:existence", "amount": 1 },
+                {
+                    "type": "transfer",
+                    "zik": "system:genesis",
+                    "zak": "page:{id}:views",
+                    "amount": 1,
+                    "metadata": { "source": "open_page" }
+                },
+                { "type": "call_spark", "spark": "count_visit", "args": { "id": "{id}" } }
+            ],
+            "return": { "run": "{recipe_run_id}" }
+        })),
+    );
+
+    let id = Uuid::new_v4().simple().to_string();
+    let args = || ZikZak {
+        zik: zik! { id: id.clone() },
+        zak: zak! {},
+    };
+    let first = genesis.ignite_spark("open_page", args()).await?;
+    let second = genesis.ignite_spark("open_page", args()).await?;
+
+    let run_id = first.0["run"].as_str().unwrap().to_string();
+    assert_ne!(second.0["run"], first.0["run"]);
+
+    let transfers = genesis
+        .accounting
+        .query_transfers(TransferFilter {
+            recipe_run_id: Some(run_id.clone()),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(transfers.len(), 3);
+    for transfer in &transfers {
+        assert_eq!(transfer.metadata[RUN_ID_KEY], run_id);
+    }
+    assert!(transfers
+        .iter()
+        .any(|t| t.metadata.get("source").map(String::as_str) == Some("open_page")));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_profiled_ignition_times_every_operation() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("profile.db")).await?;
+
+    let operations = json!([
+        { "type": "compute", "expression": "{price} * 7 / 100", "store_as": "tax" },
+        { "type": "transfer", "zik": "system:genesis", "zak": "product:{id}:price", "amount": "{price}" },
+        { "type": "balance", "account": "product:{id}:price" }
+    ]);
+    genesis.spark_engine.add_spark(
+        "set_price".to_string(),
+        spark(json!({
+            "description": "Price a product and read it back",
+            "inputs": ["id", "price"],
+            "operations": operations,
+            "return": { "tax": "{tax}:number" }
+        })),
+    );
+
+    let id = Uuid::new_v4().simple().to_string();
+    let zikzak = || ZikZak {
+        zik: zik! { id: id.clone(), price: 2999 },
+        zak: zak! {},
+    };
+    let result = genesis.ignite_spark_profiled("set_price", zikzak()).await?;
+
+    assert_eq!(result.0.get("tax"), Some(&json!(209)));
+    let steps = result.0.get("steps").and_then(|s| s.as_array()).unwrap();
+    assert_eq!(steps.len(), operations.as_array().unwrap().len());
+    for (step, operation) in steps.iter().zip(operations.as_array().unwrap()) {
+        assert_eq!(step["op_type"], operation["type"]);
+        assert!(step["ms"].as_f64().unwrap() >= 0.0);
+    }
+
+    // Unprofiled ignitions are unchanged
+    let result = genesis.ignite_spark("set_price", zikzak()).await?;
+    assert!(!result.0.contains_key("steps"));
+
+    Ok(())
+}
+
+fn order_status_spark() -> Spark {
+    spark(json!({
+        "description": "Move an order along pending(1) -> shipped(2) -> delivered(3)",
+        "inputs": ["id", "status"],
+        "operations": [
+            {
+                "type": "set_status",
+                "account": "order:{id}:status",
+                "amount": "{status}",
+                "transitions": { "0": [1], "1": [2], "2": [3] },
+                "store_as": "change"
+            }
+        ],
+        "return": { "status": "{change.status}:number", "at": "{change.at}:number" }
+    }))
+}
+
+#[tokio::test]
+async fn test_set_status_stamps_each_transition() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("status.db")).await?;
+    genesis
+        .spark_engine
+        .add_spark("set_order_status".to_string(), order_status_spark());
+
+    let id = Uuid::new_v4().simple().to_string();
+    for status in [1, 2] {
+        let result = genesis
+            .ignite_spark(
+                "set_order_status",
+                ZikZak {
+                    zik: zik! { id: id.clone(), status: status },
+                    zak: zak! {},
+                },
+            )
+            .await?;
+        assert_eq!(result.0["status"], json!(status));
+    }
+
+    let engine = &genesis.accounting;
+    let status = format!("order:{}:status", id);
+    assert_eq!(engine.get_balance(status.as_str()).await?, 2);
+    let pending_at = engine.get_balance(format!("{}:1:at", status)).await?;
+    let shipped_at = engine.get_balance(format!("{}:2:at", status)).await?;
+    assert!(pending_at > 0);
+    assert!(shipped_at >= pending_at);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_status_refuses_illegal_transition() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("status.db")).await?;
+    genesis
+        .spark_engine
+        .add_spark("set_order_status".to_string(), order_status_spark());
+
+    let id = Uuid::new_v4().simple().to_string();
+    let ignite = |status: i64| ZikZak {
+        zik: zik! { id: id.clone(), status: status },
+        zak: zak! {},
+    };
+    genesis.ignite_spark("set_order_status", ignite(1)).await?;
+
+    // Pending straight to delivered skips shipping
+    let error = genesis
+        .ignite_spark("set_order_status", ignite(3))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ZikZakError>(),
+        Some(ZikZakError::InvalidTransition { from, to, .. }) if from == "1" && to == "3"
+    ));
+
+    let status = format!("order:{}:status", id);
+    assert_eq!(genesis.accounting.get_balance(status.as_str()).await?, 1);
+    assert!(genesis
+        .accounting
+        .get_balance(format!("{}:3:at", status))
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+fn atomic_checkout_spark() -> Spark {
+    spark(json!({
+        "description": "Charge an order and take payment, all or nothing",
+        "inputs": ["id", "price", "qty"],
+        "atomic": true,
+        "operations": [
+            {
+                "type": "transfer",
+                "zik": "system:genesis",
+                "zak": "order:{id}:total",
+                "amount": "{price}",
+                "store_as": "charge"
+            },
+            {
+                "type": "transfer",
+                "zik": "system:genesis",
+                "zak": "order:{id}:items",
+                "amount": "{qty}"
+            },
+            {
+                "type": "balance",
+                "account": "customer:{id}:wallet",
+                "condition": "> 0"
+            },
+            {
+                "type": "transfer",
+                "zik": "customer:{id}:wallet",
+                "zak": "order:{id}:paid",
+                "amount": "{price}"
+            }
+        ],
+        "return": { "charge": "{charge}" }
+    }))
+}
+
+#[tokio::test]
+async fn test_atomic_spark_commits_all_or_nothing() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("atomic.db")).await?;
+    genesis
+        .spark_engine
+        .add_spark("checkout".to_string(), atomic_checkout_spark());
+
+    let id = Uuid::new_v4().simple().to_string();
+    let checkout = || ZikZak {
+        zik: zik! { id: id.clone(), price: 2999, qty: 2 },
+        zak: zak! {},
+    };
+
+    // The wallet check fails after two transfers were planned; neither is sent
+    assert!(genesis.ignite_spark("checkout", checkout()).await.is_err());
+    let total = format!("order:{}:total", id);
+    assert!(genesis
+        .accounting
+        .get_balance(total.as_str())
+        .await
+        .is_err());
+    assert!(genesis
+        .accounting
+        .get_balance(format!("order:{}:items", id))
+        .await
+        .is_err());
+
+    genesis
+        .accounting
+        .transfer(
+            "system:genesis",
+            &format!("customer:{}:wallet", id),
+            5000,
+            HashMap::new(),
+        )
+        .await?;
+    let result = genesis.ignite_spark("checkout", checkout()).await?;
+
+    // Transfer ids are filled in once the batch commits
+    let charge = result.0["charge"].as_str().unwrap();
+    assert!(Uuid::parse_str(charge).is_ok());
+
+    let engine = &genesis.accounting;
+    assert_eq!(engine.get_balance(total.as_str()).await?, 2999);
+    assert_eq!(engine.get_balance(format!("order:{}:items", id)).await?, 2);
+    assert_eq!(
+        engine.get_balance(format!("order:{}:paid", id)).await?,
+        2999
+    );
+    assert_eq!(
+        engine
+            .get_balance(format!("customer:{}:wallet", id))
+            .await?,
+        5000 - 2999
+    );
+
+    let batch = engine
+        .query_transfers(TransferFilter {
+            account: Some(total),
+            ..Default::default()
+        })
+        .await?;
+    assert!(batch[0].metadata.contains_key("batch_id"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_atomic_spark_rejects_unbatchable_operations() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("atomic.db")).await?;
+
+    let mut checkout = atomic_checkout_spark();
+    checkout.operations[1] = serde_json::from_value(json!({
+        "type": "reserve",
+        "account": "product:{id}:stock",
+        "to": "order:{id}:reserved",
+        "amount": "{qty}"
+    }))?;
+    genesis
+        .spark_engine
+        .add_spark("checkout".to_string(), checkout);
+
+    let id = Uuid::new_v4().simple().to_string();
+    let error = genesis
+        .ignite_spark(
+            "checkout",
+            ZikZak {
+                zik: zik! { id: id.clone(), price: 2999, qty: 2 },
+                zak: zak! {},
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("'reserve' cannot be batched"));
+    assert!(genesis
+        .accounting
+        .get_balance(format!("order:{}:total", id))
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_idempotent_ignition_applies_transfers_once() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("idempotent.db")).await?;
+    genesis.spark_engine.add_spark(
+        "pay".to_string(),
+        spark(json!({
+            "description": "Grant a bonus, then charge a card that must be funded",
+            "inputs": ["id"],
+            "operations": [
+                {
+                    "type": "transfer",
+                    "zik": "system:genesis",
+                    "zak": "user:{id}:bonus",
+                    "amount": 10
+                },
+                {
+                    "type": "balance",
+                    "account": "user:{id}:card",
+                    "condition": "> 0"
+                },
+                {
+                    "type": "transfer",
+                    "zik": "user:{id}:card",
+                    "zak": "shop:{id}:revenue",
+                    "amount": 100
+                }
+            ],
+            "return": { "bonus": "{op_0}", "payment": "{op_2}" }
+        })),
+    );
+
+    let id = Uuid::new_v4().simple().to_string();
+    let key = format!("checkout-{}", id);
+    let pay = || ZikZak {
+        zik: zik! { id: id.clone() },
+        zak: zak! {},
+    };
+
+    // The card is empty: the bonus goes through, then the spark fails
+    assert!(genesis
+        .ignite_spark_idempotent("pay", pay(), &key)
+        .await
+        .is_err());
+    let bonus = format!("user:{}:bonus", id);
+    assert_eq!(genesis.accounting.get_balance(bonus.as_str()).await?, 10);
+
+    // Retry from a fresh engine, as after a restart, once the card is funded
+    genesis.accounting = ZikZakEngine::new("").await?;
+    genesis
+        .accounting
+        .transfer(
+            "system:genesis",
+            &format!("user:{}:card", id),
+            500,
+            HashMap::new(),
+        )
+        .await?;
+    let first = genesis.ignite_spark_idempotent("pay", pay(), &key).await?;
+    assert_eq!(genesis.accounting.get_balance(bonus.as_str()).await?, 10);
+
+    // A completed key returns the stored result without running anything
+    let again = genesis.ignite_spark_idempotent("pay", pay(), &key).await?;
+    assert_eq!(again.0, first.0);
+    assert_eq!(genesis.accounting.get_balance(bonus.as_str()).await?, 10);
+    assert_eq!(
+        genesis
+            .accounting
+            .get_balance(format!("shop:{}:revenue", id))
+            .await?,
+        100
+    );
+
+    // Another key is another checkout
+    genesis
+        .ignite_spark_idempotent("pay", pay(), &format!("{}-2", key))
+        .await?;
+    assert_eq!(genesis.accounting.get_balance(bonus.as_str()).await?, 20);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_divine_entity_assembles_fields() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("entity.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "create_user".to_string(),
+        spark(json!({
+            "description": "User with a balance and a text name",
+            "inputs": ["id", "name"],
+            "operations": [
+                { "type": "transfer", "zik": "system:genesis", "zak": "user:{id}:existence", "amount": 1 },
+                { "type": "transfer", "zik": "system:genesis", "zak": "user:{id}:balance", "amount": 5000 },
+                { "type": "transfer", "zik": "system:genesis", "zak": "user:{id}:name", "amount": "{name}", "sled": true }
+            ]
+        })),
+    );
+
+    let id = Uuid::new_v4().simple().to_string();
+    genesis
+        .ignite_spark(
+            "create_user",
+            ZikZak {
+                zik: zik! { id: id.clone(), name: "alice" },
+                zak: zak! {},
+            },
+        )
+        .await?;
+
+    let user = genesis.divine_entity(&format!("user:{}", id)).await?;
+    assert_eq!(
+        user,
+        json!({ "existence": 1, "balance": 5000, "name": "alice" })
+    );
+
+    let missing = genesis
+        .divine_entity(&format!("user:{}", Uuid::new_v4().simple()))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        missing.downcast_ref::<ZikZakError>(),
+        Some(ZikZakError::AccountNotFound { .. })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_metadata_merges_under_operation_metadata() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("defaults.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "top_up".to_string(),
+        spark(json!({
+            "description": "Top up a wallet, tagged for the audit trail",
+            "inputs": ["user_id", "amount"],
+            "default_metadata": { "source": "api", "user": "{user_id}" },
+            "operations": [
+                { "type": "transfer", "zik": "system:genesis", "zak": "user:{user_id}:balance",
+                  "amount": "{amount}", "metadata": { "source": "promo", "campaign": "spring" } }
+            ]
+        })),
+    );
+
+    let user_id = Uuid::new_v4().simple().to_string();
+    let result = genesis
+        .ignite_spark(
+            "top_up",
+            ZikZak {
+                zik: zik! { user_id: user_id.clone(), amount: 50 },
+                zak: zak! {},
+            },
+        )
+        .await?;
+
+    let transfers = genesis
+        .accounting
+        .query_transfers(TransferFilter {
+            recipe_run_id: Some(result.0[RUN_ID_KEY].as_str().unwrap().to_string()),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(transfers.len(), 1);
+    let metadata = &transfers[0].metadata;
+    assert_eq!(metadata["user"], user_id);
+    assert_eq!(metadata["campaign"], "spring");
+    // The operation's own key wins
+    assert_eq!(metadata["source"], "promo");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_balance_condition_uses_custom_error() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("condition.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "purchase".to_string(),
+        spark(json!({
+            "description": "Refuse purchases the wallet cannot cover",
+            "inputs": ["user_id", "price"],
+            "operations": [
+                { "type": "balance", "account": "user:{user_id}:balance", "condition": ">= 500",
+                  "error": "Insufficient funds to complete purchase: {account} has {balance}",
+                  "error_code": 402 }
+            ]
+        })),
+    );
+
+    let user_id = Uuid::new_v4().simple().to_string();
+    let wallet = format!("user:{}:balance", user_id);
+    genesis
+        .accounting
+        .transfer("system:genesis", &wallet, 120, HashMap::new())
+        .await?;
+
+    let error = genesis
+        .ignite_spark(
+            "purchase",
+            ZikZak {
+                zik: zik! { user_id: user_id.clone(), price: 500 },
+                zak: zak! {},
+            },
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Insufficient funds to complete purchase: {} has 120",
+            wallet
+        )
+    );
+    assert!(matches!(
+        error.downcast_ref::<ZikZakError>(),
+        Some(ZikZakError::ConditionFailed {
+            balance: 120,
+            error_code: Some(402),
+            ..
+        })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_divine_query_pages_with_a_cursor() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("query.db")).await?;
+
+    let shop = format!("shop{}", Uuid::new_v4().simple());
+    for i in 0..5 {
+        genesis
+            .accounting
+            .transfer(
+                "system:genesis",
+                &format!("{}:{}:price", shop, i),
+                100 + i,
+                HashMap::new(),
+            )
+            .await?;
+    }
+
+    let pattern = format!("{}:*:price", shop);
+    let mut seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    let mut pages = 0;
+    loop {
+        let page = genesis.divine_query(&pattern, 2, cursor.as_deref()).await?;
+        let results = page["results"].as_array().expect("results");
+        assert!(results.len() <= 2);
+        seen.extend(
+            results
+                .iter()
+                .map(|m| m["account"].as_str().unwrap().to_string()),
+        );
+        pages += 1;
+
+        // Created mid-walk: lands after the cursor instead of shifting earlier pages
+        if pages == 1 {
+            genesis
+                .accounting
+                .transfer(
+                    "system:genesis",
+                    &format!("{}:5:price", shop),
+                    105,
+                    HashMap::new(),
+                )
+                .await?;
+        }
+
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+
+    let expected: Vec<String> = (0..6).map(|i| format!("{}:{}:price", shop, i)).collect();
+    assert_eq!(seen, expected);
+    assert_eq!(pages, 3);
+
+    assert!(genesis
+        .divine_query(&pattern, 2, Some("not-a-cursor"))
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subscribe_sees_ignitions_and_transfers() -> Result<()> {
+    use futures::StreamExt;
+    use std::time::Duration;
+    use zik_zak::realtime::SparkEvent;
+
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut genesis = Genesis::empty(temp_dir.path().join("subscribe.db")).await?;
+
+    genesis.spark_engine.add_spark(
+        "create_user".to_string(),
+        spark(json!({
+            "description": "Bring a user into existence",
+            "inputs": ["id"],
+            "operations": [
+                { "type": "transfer", "zik": "system:genesis", "zak": "user:{id}:existence", "amount": 1 }
+            ],
+            "return": { "id": "{id}" }
+        })),
+    );
+
+    let id = Uuid::new_v4().simple().to_string();
+    let mut events = Box::pin(genesis.subscribe(&format!("user:{}:*", id))?);
+    assert!(genesis.subscribe("*:1").is_err());
+
+    // Another entity's transfers are filtered out
+    genesis
+        .accounting
+        .transfer(
+            "system:genesis",
+            format!("shop:{}:revenue", id).as_str(),
+            5,
+            HashMap::new(),
+        )
+        .await?;
+    genesis
+        .ignite_spark(
+            "create_user",
+            ZikZak {
+                zik: zik! { id: id.clone() },
+                zak: zak! {},
+            },
+        )
+        .await?;
+
+    let mut ignited = None;
+    let mut transfers = Vec::new();
+    while ignited.is_none() || transfers.is_empty() {
+        let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await?
+            .expect("stream open while genesis lives");
+        match event {
+            SparkEvent::Ignited {
+                spark,
+                inputs,
+                transfers,
+                result,
+            } => {
+                assert_eq!(inputs["id"], json!(id));
+                assert_eq!(transfers.len(), 1);
+                assert_eq!(result.0["id"], json!(id));
+                ignited = Some(spark);
+            }
+            SparkEvent::Transfer(transfer) => transfers.push(transfer.to_account),
+            SparkEvent::Lagged(missed) => panic!("missed {} events", missed),
+        }
+    }
+    assert_eq!(ignited.as_deref(), Some("create_user"));
+    assert_eq!(transfers, vec![format!("user:{}:existence", id)]);
+
+    Ok(())
+}
+
+async fn store_document(
+    sparks: &SparkEngine,
+    engine: &mut ZikZakEngine,
+    id: &str,
+    body: &str,
+) -> Result<HashMap<String, serde_json::Value>> {
+    let input = ZikZak {
+        zik: zik! { id: id, body: body },
+        zak: zak! {},
+    };
+    Ok(sparks
+        .ignite_spark("store_document", input, engine)
+        .await?
+        .0)
+}
+
+#[tokio::test]
+async fn test_hash_field_matches_for_identical_content() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut sparks = SparkEngine::empty(temp_dir.path().join("hash.db"))?;
+    let mut engine = ZikZakEngine::with_backend("", MemoryBackend::new()).await?;
+    sparks.add_spark(
+        "store_document".to_string(),
+        spark(json!({
+            "description": "Store a document addressed by its content hash",
+            "inputs": ["id", "body"],
+            "operations": [
+                {
+                    "type": "hash_field",
+                    "account": "document:{id}:content_hash",
+                    "amount": "{body}",
+                    "sled": true,
+                    "store_as": "hash"
+                },
+                {
+                    "type": "read_text",
+                    "account": "document:{id}:content_hash",
+                    "store_as": "stored"
+                }
+            ],
+            "return": { "hash": "{hash}", "body": "{stored}" }
+        })),
+    );
+
+    let first = store_document(&sparks, &mut engine, "1", "hello world").await?;
+    let second = store_document(&sparks, &mut engine, "2", "hello world").await?;
+    let other = store_document(&sparks, &mut engine, "3", "goodbye").await?;
+    assert_eq!(first["hash"], second["hash"]);
+    assert_ne!(first["hash"], other["hash"]);
+    assert_eq!(first["body"], json!("hello world"));
+
+    let hash = ZikZakEngine::hash_string("hello world");
+    assert_eq!(first["hash"], json!(hash));
+    for id in [1, 2] {
+        let account = format!("document:{}:content_hash", id);
+        assert_eq!(engine.get_balance(account.as_str()).await?, hash);
+    }
+    Ok(())
+}
+
+fn await_goal_spark(timeout: u64) -> Spark {
+    spark(json!({
+        "description": "Close a campaign once it has raised its goal",
+        "inputs": ["id"],
+        "operations": [
+            {
+                "type": "await_balance",
+                "account": "campaign:{id}:raised",
+                "condition": ">= 1000",
+                "timeout": timeout,
+                "poll_interval": 10,
+                "error": "Campaign {id} did not reach its goal",
+                "store_as": "raised"
+            },
+            {
+                "type": "transfer",
+                "zik": "system:genesis",
+                "zak": "campaign:{id}:closed",
+                "amount": "1"
+            }
+        ],
+        "return": { "raised": "{raised}" }
+    }))
+}
+
+#[tokio::test]
+async fn test_await_balance_resumes_once_the_condition_holds() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut sparks = SparkEngine::empty(temp_dir.path().join("await.db"))?;
+    sparks.add_spark("await_goal".to_string(), await_goal_spark(5000));
+    let engine = Arc::new(RwLock::new(
+        ZikZakEngine::with_backend("", MemoryBackend::new()).await?,
+    ));
+
+    // The pledge can only land if the waiting spark lets the lock go
+    let pledge = tokio::spawn({
+        let engine = engine.clone();
+        async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            engine
+                .write()
+                .await
+                .transfer("system:genesis", "campaign:1:raised", 1000, HashMap::new())
+                .await
+        }
+    });
+    let input = ZikZak {
+        zik: zik! { id: "1" },
+        zak: zak! {},
+    };
+    let (result, run_id) = sparks
+        .ignite_spark_locked("await_goal", input, &engine)
+        .await?;
+    pledge.await??;
+
+    assert_eq!(result.0["raised"], json!(1000));
+    let engine = engine.read().await;
+    assert_eq!(engine.get_balance("campaign:1:closed").await?, 1);
+    let ours = engine
+        .transfers_since(0)
+        .iter()
+        .filter(|transfer| transfer.metadata.get(RUN_ID_KEY) == Some(&run_id))
+        .count();
+    assert_eq!(ours, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_await_balance_times_out_and_releases_the_lock() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut sparks = SparkEngine::empty(temp_dir.path().join("await.db"))?;
+    sparks.add_spark("await_goal".to_string(), await_goal_spark(50));
+    sparks.add_spark("await_forever".to_string(), await_goal_spark(60_000));
+    let engine = RwLock::new(ZikZakEngine::with_backend("", MemoryBackend::new()).await?);
+    let input = || ZikZak {
+        zik: zik! { id: "2" },
+        zak: zak! {},
+    };
+
+    let err = sparks
+        .ignite_spark_locked("await_goal", input(), &engine)
+        .await
+        .unwrap_err();
+    match err.downcast_ref::<ZikZakError>() {
+        Some(ZikZakError::ConditionFailed { message, .. }) => {
+            assert_eq!(message, "Campaign 2 did not reach its goal")
+        }
+        other => panic!("expected ConditionFailed, got {:?}", other),
+    }
+
+    // Abandoning a wait part way through leaves the engine free for others
+    let abandoned = tokio::time::timeout(
+        Duration::from_millis(50),
+        sparks.ignite_spark_locked("await_forever", input(), &engine),
+    )
+    .await;
+    assert!(abandoned.is_err());
+    let engine = engine.try_write()?;
+    assert_eq!(engine.get_transfer_count().await?, 0);
+    Ok(())
+}
@@ -0,0 +1,58 @@
+//! Account statement paging tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::ZikZakEngine;
+
+#[tokio::test]
+async fn test_account_statement_pages_backward() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+    let id = Uuid::new_v4().simple().to_string();
+    let (user, shop) = (
+        format!("user:{}:balance", id),
+        format!("shop:{}:revenue", id),
+    );
+
+    for amount in 1..=4 {
+        engine
+            .transfer("system:genesis", user.as_str(), amount, HashMap::new())
+            .await?;
+    }
+    engine
+        .transfer(user.as_str(), shop.as_str(), 5, HashMap::new())
+        .await?;
+
+    let mut amounts = Vec::new();
+    let mut cursor = None;
+    let mut pages = 0;
+    loop {
+        let page = engine.account_statement(&user, cursor, 2).await?;
+        assert_eq!(page.account, user);
+        amounts.extend(page.entries.iter().map(|e| e.amount));
+        pages += 1;
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    // Newest first, debits negative; the third page is short and ends the walk
+    assert_eq!(amounts, vec![-5, 4, 3, 2, 1]);
+    assert_eq!(pages, 3);
+
+    let newest = engine.account_statement(&user, None, 1).await?;
+    assert_eq!(newest.entries[0].counterparty, shop);
+    assert!(newest.entries[0].timestamp > 0);
+
+    // A full last page is followed by an empty, terminal one
+    let all = engine.account_statement(&user, None, 5).await?;
+    assert_eq!(all.entries.len(), 5);
+    let rest = engine.account_statement(&user, all.next, 5).await?;
+    assert!(rest.entries.is_empty());
+    assert_eq!(rest.next, None);
+
+    Ok(())
+}
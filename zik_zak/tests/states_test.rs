@@ -0,0 +1,75 @@
+//! Named state tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{state_enum, StateEnum, ZikZakEngine, ZikZakError};
+
+state_enum! {
+    enum OrderStatus {
+        Pending = 1,
+        Shipped = 2,
+        Delivered = 3,
+    }
+    transitions {
+        Pending => Shipped,
+        Shipped => Delivered,
+    }
+}
+
+#[tokio::test]
+async fn test_set_state_moves_balance_and_reads_back_names() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+    let status = format!("order:{}:status", Uuid::new_v4().simple());
+
+    assert_eq!(engine.get_state::<OrderStatus>(&status).await?, None);
+
+    for state in [
+        OrderStatus::Pending,
+        OrderStatus::Shipped,
+        OrderStatus::Delivered,
+    ] {
+        assert!(engine.set_state(&status, state).await?.is_some());
+        assert_eq!(engine.get_balance(status.as_str()).await?, state.value());
+        assert_eq!(engine.get_state(&status).await?, Some(state));
+    }
+
+    // Already there: no transfer
+    assert!(engine
+        .set_state(&status, OrderStatus::Delivered)
+        .await?
+        .is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_state_rejects_undeclared_transition() -> Result<()> {
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+    let status = format!("order:{}:status", Uuid::new_v4().simple());
+
+    engine.set_state(&status, OrderStatus::Shipped).await?;
+    let err = engine
+        .set_state(&status, OrderStatus::Pending)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ZikZakError>(),
+        Some(&ZikZakError::InvalidTransition {
+            account: status.clone(),
+            from: "Shipped".to_string(),
+            to: "Pending".to_string(),
+        })
+    );
+    assert_eq!(engine.get_state(&status).await?, Some(OrderStatus::Shipped));
+
+    // A balance outside the enum is reported, not guessed at
+    engine
+        .transfer("system:genesis", &status, 40, HashMap::new())
+        .await?;
+    assert!(engine.get_state::<OrderStatus>(&status).await.is_err());
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Account tagging tests
+//!
+//! Tests on [`ZikZakEngine::new`] require TigerBeetle running as described in
+//! `tigerbeetle_integration_test.rs`; the others use a [`MemoryBackend`].
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use uuid::Uuid;
+use zik_zak::{MemoryBackend, TagStore, ZikZakEngine};
+
+#[tokio::test]
+async fn test_sum_by_tag_covers_only_tagged_accounts() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut engine = ZikZakEngine::new("").await?;
+    engine.ensure_system_accounts().await?;
+    engine.set_tag_store(TagStore::new(temp_dir.path().join("tags.db"))?);
+
+    let run = Uuid::new_v4().simple().to_string();
+    let stock = |n: i32| format!("product:{}_{}:stock_value", run, n);
+
+    for (n, value) in [(1, 100), (2, 250), (3, 40)] {
+        engine
+            .transfer("system:genesis", &stock(n), value, HashMap::new())
+            .await?;
+    }
+
+    engine
+        .tag_account(&stock(1), "category", "electronics")
+        .await?;
+    engine
+        .tag_account(&stock(2), "category", "electronics")
+        .await?;
+    engine.tag_account(&stock(3), "category", "books").await?;
+
+    assert_eq!(engine.sum_by_tag("category", "electronics").await?, 350);
+    assert_eq!(engine.sum_by_tag("category", "books").await?, 40);
+
+    assert!(engine.untag_account(&stock(2), "category").await?);
+    assert_eq!(engine.sum_by_tag("category", "electronics").await?, 100);
+
+    Ok(())
+}
+
+/// Open the tag store at `path` once its last owner has let it go
+///
+/// SLED's flusher thread holds the file lock a moment after the final handle is
+/// dropped, so the first attempts may find it still locked.
+async fn reopen(path: &Path) -> Result<TagStore> {
+    for _ in 0..50 {
+        match TagStore::new(path) {
+            Ok(tags) => return Ok(tags),
+            Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+        }
+    }
+    TagStore::new(path)
+}
+
+#[tokio::test]
+async fn test_tag_groups_span_prefixes_and_survive_engine_restarts() -> Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let path = temp_dir.path().join("tags.db");
+    let mut engine = ZikZakEngine::with_backend("", MemoryBackend::new()).await?;
+    engine.set_tag_store(TagStore::new(&path)?);
+
+    for (account, amount) in [
+        ("merchant:fr_1:revenue", 300),
+        ("shop:de_7:balance", 120),
+        ("merchant:us_2:revenue", 999),
+    ] {
+        engine
+            .transfer("system:genesis", account, amount, HashMap::new())
+            .await?;
+    }
+    engine
+        .add_tag("merchant:fr_1:revenue", "eu_merchants")
+        .await?;
+    engine.add_tag("shop:de_7:balance", "eu_merchants").await?;
+    engine
+        .tag_account("shop:de_7:balance", "country", "de")
+        .await?;
+    assert!(engine.add_tag("shop:de_7:balance", "").await.is_err());
+
+    assert_eq!(engine.sum_tagged("eu_merchants").await?, 420);
+    assert_eq!(
+        engine.tags_of("shop:de_7:balance").await?,
+        vec!["country=de", "eu_merchants"]
+    );
+    let ledger = engine.export_ledger().await?;
+    assert_eq!(
+        ledger["shop:de_7:balance"]["tags"],
+        serde_json::json!(["country=de", "eu_merchants"])
+    );
+    assert_eq!(
+        ledger["merchant:us_2:revenue"]["tags"],
+        serde_json::json!([])
+    );
+
+    // Only bare tags are removed by name
+    assert!(!engine.remove_tag("shop:de_7:balance", "country").await?);
+    assert!(
+        engine
+            .remove_tag("shop:de_7:balance", "eu_merchants")
+            .await?
+    );
+    assert_eq!(
+        engine.accounts_tagged("eu_merchants").await?,
+        vec!["merchant:fr_1:revenue"]
+    );
+
+    // Tags live in SLED, not in the engine: a new engine on a reopened store sees them
+    drop(engine);
+    let mut engine = ZikZakEngine::with_backend("", MemoryBackend::new()).await?;
+    engine.set_tag_store(reopen(&path).await?);
+    assert_eq!(
+        engine.accounts_tagged("eu_merchants").await?,
+        vec!["merchant:fr_1:revenue"]
+    );
+    assert_eq!(
+        engine.tags_of("shop:de_7:balance").await?,
+        vec!["country=de"]
+    );
+    Ok(())
+}
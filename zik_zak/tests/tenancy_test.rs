@@ -0,0 +1,78 @@
+//! Tenant isolation tests against a real TigerBeetle
+//!
+//! Requires TigerBeetle running as described in `tigerbeetle_integration_test.rs`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+use zik_zak::{TransferFilter, ZikZakEngine};
+
+#[tokio::test]
+async fn test_cross_tenant_reads_return_zero() -> Result<()> {
+    let run = Uuid::new_v4().simple().to_string();
+    let mut engine = ZikZakEngine::new(&format!("tenancy_{}", run)).await?;
+    engine.ensure_system_accounts().await?;
+
+    let mut acme = engine.scoped("acme");
+    acme.transfer("system:genesis", "user:1:balance", 100, HashMap::new())
+        .await?;
+    acme.transfer("system:genesis", "user:1:existence", 1, HashMap::new())
+        .await?;
+    assert_eq!(acme.get_balance("user:1:balance").await?, 100);
+    assert!(acme.exists("user:1").await?);
+
+    let globex = engine.scoped("globex");
+    assert_eq!(globex.get_balance("user:1:balance").await?, 0);
+    assert!(!globex.exists("user:1").await?);
+    assert!(globex
+        .query_transfers(TransferFilter::default())
+        .await?
+        .is_empty());
+    assert!(globex.find_accounts("user:*:balance").await?.is_empty());
+    let ledger = globex.get_ledger_state().await?;
+    assert_eq!(ledger.as_object().map(|l| l.len()), Some(0));
+
+    // The owning tenant sees its own accounts under their short names
+    let acme = engine.scoped("acme");
+    let transfers = acme
+        .query_transfers(TransferFilter {
+            account: Some("user:1:balance".to_string()),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(transfers[0].from_account, "system:genesis");
+    let ledger = acme.get_ledger_state().await?;
+    assert_eq!(ledger["user:1:balance"], 100);
+    assert!(ledger.get("system:genesis").is_none());
+
+    // The engine itself stores the prefixed name
+    assert_eq!(engine.get_balance("tenant:acme:user:1:balance").await?, 100);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_per_tenant_system_accounts() -> Result<()> {
+    let run = Uuid::new_v4().simple().to_string();
+    let mut engine = ZikZakEngine::new(&format!("tenancy_{}", run))
+        .await?
+        .with_tenant_system_accounts(true);
+
+    let mut acme = engine.scoped("acme");
+    acme.ensure_system_accounts().await?;
+    acme.transfer("system:genesis", "user:1:balance", 30, HashMap::new())
+        .await?;
+
+    assert_eq!(acme.get_balance("system:genesis").await?, -30);
+    assert_eq!(acme.qualify("system:genesis"), "tenant:acme:system:genesis");
+    assert_eq!(
+        engine
+            .scoped("globex")
+            .get_balance("system:genesis")
+            .await?,
+        0
+    );
+
+    Ok(())
+}